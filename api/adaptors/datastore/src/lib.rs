@@ -1,8 +1,12 @@
-use std::{env, error::Error, fmt::Display};
+use std::{cmp::Reverse, env, error::Error, fmt::Display};
 
 use async_trait::async_trait;
-use chrono::{DateTime, NaiveDateTime, Utc};
-use common::{Adaptor, Event, Person, Stats};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use common::{
+    Activity, Adaptor, CleanupPreview, Comment, DailyStats, Event, EventQuery, EventViews,
+    IdempotencyKey, Page, PageRange, Person, PersonInsert, PersonUpdate, RemovedPerson, Stats,
+    Template,
+};
 use google_cloud::{
     authorize::ApplicationCredentials,
     datastore::{Client, Filter, FromValue, IntoValue, Key, KeyID, Query},
@@ -15,8 +19,14 @@ pub struct DatastoreAdaptor {
 
 // Keys
 const STATS_KIND: &str = "Stats";
+const DAILY_STATS_KIND: &str = "DailyStats";
 const EVENT_KIND: &str = "Event";
 const PERSON_KIND: &str = "Person";
+const COMMENT_KIND: &str = "Comment";
+const ACTIVITY_KIND: &str = "Activity";
+const EVENT_VIEWS_KIND: &str = "EventViews";
+const IDEMPOTENCY_KEY_KIND: &str = "IdempotencyKey";
+const TEMPLATE_KIND: &str = "Template";
 const STATS_EVENTS_ID: &str = "eventCount";
 const STATS_PEOPLE_ID: &str = "personCount";
 
@@ -61,6 +71,64 @@ impl Adaptor for DatastoreAdaptor {
         Ok(person_stats.value)
     }
 
+    async fn add_stats(&self, stats: Stats) -> Result<Stats, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let event_key = Key::new(STATS_KIND).id(STATS_EVENTS_ID);
+        let mut event_stats: DatastoreStats =
+            client.get(event_key.clone()).await?.unwrap_or_default();
+        event_stats.value += stats.event_count;
+        client.put((event_key, event_stats.clone())).await?;
+
+        let person_key = Key::new(STATS_KIND).id(STATS_PEOPLE_ID);
+        let mut person_stats: DatastoreStats =
+            client.get(person_key.clone()).await?.unwrap_or_default();
+        person_stats.value += stats.person_count;
+        client.put((person_key, person_stats.clone())).await?;
+
+        Ok(Stats {
+            event_count: event_stats.value,
+            person_count: person_stats.value,
+        })
+    }
+
+    async fn add_daily_stats(&self, date: NaiveDate, stats: Stats) -> Result<(), Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let key = Key::new(DAILY_STATS_KIND).id(date.to_string());
+        let mut day = client
+            .get::<DatastoreDailyStats, _>(key.clone())
+            .await?
+            .unwrap_or(DatastoreDailyStats {
+                date: date_to_unix(date),
+                eventCount: 0,
+                personCount: 0,
+            });
+        day.eventCount += stats.event_count;
+        day.personCount += stats.person_count;
+        client.put((key, day)).await?;
+
+        Ok(())
+    }
+
+    async fn get_daily_stats(&self, since: NaiveDate) -> Result<Vec<DailyStats>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let mut days: Vec<DailyStats> = client
+            .query(Query::new(DAILY_STATS_KIND).filter(Filter::GreaterThan(
+                "date".into(),
+                (date_to_unix(since) - 1).into_value(),
+            )))
+            .await?
+            .into_iter()
+            .filter_map(|entity| DatastoreDailyStats::from_value(entity.properties().clone()).ok())
+            .map(|day| day.into())
+            .collect();
+        days.sort_by_key(|day| day.date);
+
+        Ok(days)
+    }
+
     async fn get_people(&self, event_id: String) -> Result<Option<Vec<Person>>, Self::Error> {
         let mut client = self.client.lock().await;
 
@@ -133,165 +201,1174 @@ impl Adaptor for DatastoreAdaptor {
         Ok(Some(person))
     }
 
-    async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+    async fn insert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<PersonInsert>, Self::Error> {
+        // Datastore transactions aren't supported by the client, so holding its lock while
+        // counting and inserting only keeps this atomic within one instance of the API
         let mut client = self.client.lock().await;
 
-        let key = Key::new(EVENT_KIND).id(id.clone());
-        let existing_event = client.get::<DatastoreEvent, _>(key.clone()).await?;
+        let Some(event) = client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
+            .await?
+        else {
+            return Ok(None);
+        };
 
-        // Mark as visited if it exists
-        if let Some(mut event) = existing_event.clone() {
-            event.visited = Utc::now().timestamp();
-            client.put((key, event)).await?;
+        let people = client
+            .query(Query::new(PERSON_KIND).filter(Filter::Equal(
+                "eventId".into(),
+                event_id.clone().into_value(),
+            )))
+            .await?;
+        let existing = people.iter().find(|entity| {
+            DatastorePerson::from_value(entity.properties().clone())
+                .is_ok_and(|p| p.name == person.name)
+        });
+
+        let key = match existing {
+            Some(entity) => entity.key().clone(),
+            None => {
+                if event
+                    .maxPeople
+                    .is_some_and(|max| people.len() as i64 >= max)
+                {
+                    return Ok(Some(PersonInsert::Full));
+                }
+                Key::new(PERSON_KIND)
+            }
+        };
+
+        client
+            .put((key, DatastorePerson::from_person(person.clone(), event_id)))
+            .await?;
+
+        Ok(Some(PersonInsert::Inserted(Box::new(person))))
+    }
+
+    async fn update_person(
+        &self,
+        event_id: String,
+        person: Person,
+        expected_updated_at: DateTime<Utc>,
+    ) -> Result<Option<PersonUpdate>, Self::Error> {
+        // As with inserting, holding the client's lock only keeps this atomic within one
+        // instance of the API
+        let mut client = self.client.lock().await;
+
+        if client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
+            .await?
+            .is_none()
+        {
+            return Ok(None);
         }
 
-        Ok(existing_event.map(|e| e.to_event(id)))
+        let existing = client
+            .query(
+                Query::new(PERSON_KIND)
+                    .filter(Filter::Equal(
+                        "eventId".into(),
+                        event_id.clone().into_value(),
+                    ))
+                    .filter(Filter::Equal(
+                        "name".into(),
+                        person.name.clone().into_value(),
+                    )),
+            )
+            .await?;
+        let Some(entity) = existing.into_iter().find(|entity| {
+            DatastorePerson::from_value(entity.properties().clone()).is_ok_and(|existing| {
+                Person::from(existing).updated_at.timestamp_millis()
+                    == expected_updated_at.timestamp_millis()
+            })
+        }) else {
+            return Ok(Some(PersonUpdate::Changed));
+        };
+
+        client
+            .put((
+                entity.key().clone(),
+                DatastorePerson::from_person(person.clone(), event_id),
+            ))
+            .await?;
+
+        Ok(Some(PersonUpdate::Updated(Box::new(person))))
     }
 
-    async fn create_event(&self, event: Event) -> Result<Event, Self::Error> {
+    async fn delete_person(
+        &self,
+        event_id: String,
+        person_name: String,
+    ) -> Result<Option<Person>, Self::Error> {
         let mut client = self.client.lock().await;
 
-        let key = Key::new(EVENT_KIND).id(event.id.clone());
+        let existing_person = client
+            .query(
+                Query::new(PERSON_KIND)
+                    .filter(Filter::Equal("eventId".into(), event_id.into_value()))
+                    .filter(Filter::Equal("name".into(), person_name.into_value())),
+            )
+            .await?;
 
-        let ds_event: DatastoreEvent = event.clone().into();
-        client.put((key, ds_event)).await?;
+        let Some(entity) = existing_person.first() else {
+            return Ok(None);
+        };
+        let person = DatastorePerson::from_value(entity.properties().clone())?;
+        client.delete_all(vec![entity.key().clone()]).await?;
 
-        Ok(event)
+        Ok(Some(person.into()))
     }
 
-    async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error> {
+    async fn get_comments(&self, event_id: String) -> Result<Option<Vec<Comment>>, Self::Error> {
         let mut client = self.client.lock().await;
 
-        let mut keys_to_delete: Vec<Key> = client
-            .query(Query::new(EVENT_KIND).filter(Filter::LesserThan(
-                "visited".into(),
-                cutoff.timestamp().into_value(),
-            )))
+        // Check the event exists
+        if client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
             .await?
-            .iter()
-            .map(|entity| entity.key().clone())
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut comments: Vec<DatastoreComment> = client
+            .query(
+                Query::new(COMMENT_KIND)
+                    .filter(Filter::Equal("eventId".into(), event_id.into_value())),
+            )
+            .await?
+            .into_iter()
+            .filter_map(|entity| DatastoreComment::from_value(entity.properties().clone()).ok())
             .collect();
+        comments.sort_by_key(|c| c.created);
 
-        let event_count = keys_to_delete.len() as i64;
+        Ok(Some(comments.into_iter().map(|c| c.into()).collect()))
+    }
 
-        let events_to_delete = keys_to_delete.clone();
-        for e in events_to_delete.iter() {
-            if let KeyID::StringID(id) = e.get_id() {
-                let mut event_people_to_delete: Vec<Key> = client
-                    .query(
-                        Query::new(PERSON_KIND)
-                            .filter(Filter::Equal("eventId".into(), id.clone().into_value())),
-                    )
-                    .await?
-                    .iter()
-                    .map(|entity| entity.key().clone())
-                    .collect();
-                keys_to_delete.append(&mut event_people_to_delete);
-            }
-        }
+    async fn create_comment(
+        &self,
+        event_id: String,
+        comment: Comment,
+    ) -> Result<Option<Comment>, Self::Error> {
+        let mut client = self.client.lock().await;
 
-        let person_count = keys_to_delete.len() as i64 - event_count;
+        // Check the event exists
+        if client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
 
-        client.delete_all(keys_to_delete).await?;
+        client
+            .put((
+                Key::new(COMMENT_KIND),
+                DatastoreComment::from_comment(comment.clone(), event_id),
+            ))
+            .await?;
 
-        Ok(Stats {
-            event_count,
-            person_count,
-        })
+        Ok(Some(comment))
     }
-}
 
-impl DatastoreAdaptor {
-    pub async fn new() -> Self {
-        // Load credentials
-        let credentials: ApplicationCredentials = serde_json::from_str(
-            &env::var("GCP_CREDENTIALS").expect("Expected GCP_CREDENTIALS environment variable"),
-        )
-        .expect("GCP_CREDENTIALS environment variable is not valid JSON");
+    async fn get_activity(&self, event_id: String) -> Result<Option<Vec<Activity>>, Self::Error> {
+        let mut client = self.client.lock().await;
 
-        // Connect to datastore
-        let client = Client::from_credentials(credentials.project_id.clone(), credentials.clone())
-            .await
-            .expect("Failed to setup datastore client");
-        let client = Mutex::new(client);
+        // Check the event exists
+        if client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
 
-        println!(
-            "🎛️  Connected to datastore in project {}",
-            credentials.project_id
-        );
+        let mut activity: Vec<DatastoreActivity> = client
+            .query(
+                Query::new(ACTIVITY_KIND)
+                    .filter(Filter::Equal("eventId".into(), event_id.into_value())),
+            )
+            .await?
+            .into_iter()
+            .filter_map(|entity| DatastoreActivity::from_value(entity.properties().clone()).ok())
+            .collect();
+        activity.sort_by_key(|a| std::cmp::Reverse(a.created));
 
-        Self { client }
+        Ok(Some(activity.into_iter().map(|a| a.into()).collect()))
     }
-}
 
-#[derive(FromValue, IntoValue, Default, Clone)]
-struct DatastoreStats {
-    value: i64,
-}
+    async fn create_activity(
+        &self,
+        event_id: String,
+        activity: Activity,
+    ) -> Result<Option<Activity>, Self::Error> {
+        let mut client = self.client.lock().await;
 
-#[derive(FromValue, IntoValue, Clone)]
-struct DatastoreEvent {
-    name: String,
-    created: i64,
-    visited: i64,
-    times: Vec<String>,
-    timezone: String,
-}
+        // Check the event exists
+        if client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
 
-#[derive(FromValue, IntoValue)]
-#[allow(non_snake_case)]
-struct DatastorePerson {
-    name: String,
-    password: Option<String>,
-    created: i64,
-    eventId: String,
-    availability: Vec<String>,
-}
+        client
+            .put((
+                Key::new(ACTIVITY_KIND),
+                DatastoreActivity::from_activity(activity.clone(), event_id),
+            ))
+            .await?;
 
-impl From<DatastorePerson> for Person {
-    fn from(value: DatastorePerson) -> Self {
-        Self {
-            name: value.name,
-            password_hash: value.password,
-            created_at: unix_to_date(value.created),
-            availability: value.availability,
-        }
+        Ok(Some(activity))
     }
-}
 
-impl DatastorePerson {
-    fn from_person(person: Person, event_id: String) -> Self {
-        Self {
-            name: person.name,
-            password: person.password_hash,
-            created: person.created_at.timestamp(),
-            eventId: event_id,
-            availability: person.availability,
-        }
-    }
-}
+    async fn get_event_views(&self, event_id: String) -> Result<Option<EventViews>, Self::Error> {
+        let mut client = self.client.lock().await;
 
-impl From<Event> for DatastoreEvent {
-    fn from(value: Event) -> Self {
-        Self {
-            name: value.name,
-            created: value.created_at.timestamp(),
-            visited: value.visited_at.timestamp(),
-            times: value.times,
-            timezone: value.timezone,
+        // Check the event exists
+        if client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
+            .await?
+            .is_none()
+        {
+            return Ok(None);
         }
+
+        Ok(Some(
+            client
+                .get::<DatastoreEventViews, _>(Key::new(EVENT_VIEWS_KIND).id(event_id))
+                .await?
+                .map(|ds_views| ds_views.into())
+                .unwrap_or_default(),
+        ))
     }
-}
 
-impl DatastoreEvent {
-    fn to_event(&self, event_id: String) -> Event {
-        Event {
+    async fn record_event_view(
+        &self,
+        event_id: String,
+        register: usize,
+        rank: u8,
+    ) -> Result<Option<EventViews>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        // Check the event exists
+        if client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(event_id.clone()))
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let key = Key::new(EVENT_VIEWS_KIND).id(event_id.clone());
+        let mut views: EventViews = client
+            .get::<DatastoreEventViews, _>(key.clone())
+            .await?
+            .map(|ds_views| ds_views.into())
+            .unwrap_or_default();
+        views.record(register, rank);
+        client
+            .put((
+                key,
+                DatastoreEventViews::from_views(views.clone(), event_id),
+            ))
+            .await?;
+
+        Ok(Some(views))
+    }
+
+    async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let key = Key::new(EVENT_KIND).id(id.clone());
+        let existing_event = client
+            .get::<DatastoreEvent, _>(key.clone())
+            .await?
+            .filter(|event| event.expired.is_none());
+
+        // Mark as visited if it exists
+        if let Some(mut event) = existing_event.clone() {
+            event.visited = Utc::now().timestamp();
+            client.put((key, event)).await?;
+        }
+
+        Ok(existing_event.map(|e| e.to_event(id)))
+    }
+
+    async fn create_event(&self, event: Event) -> Result<Event, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let key = Key::new(EVENT_KIND).id(event.id.clone());
+
+        let ds_event: DatastoreEvent = event.clone().into();
+        client.put((key, ds_event)).await?;
+
+        Ok(event)
+    }
+
+    async fn update_event(&self, event: Event) -> Result<Option<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let key = Key::new(EVENT_KIND).id(event.id.clone());
+        let Some(existing_event) = client.get::<DatastoreEvent, _>(key.clone()).await? else {
+            return Ok(None);
+        };
+
+        let mut ds_event: DatastoreEvent = event.clone().into();
+        ds_event.visited = existing_event.visited;
+        ds_event.expired = existing_event.expired;
+        ds_event.updated = Some(Utc::now().timestamp());
+        client.put((key, ds_event.clone())).await?;
+
+        Ok(Some(ds_event.to_event(event.id)))
+    }
+
+    async fn get_listed_events(&self, tag: Option<String>) -> Result<Vec<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        Ok(client
+            .query(Query::new(EVENT_KIND).filter(Filter::Equal("listed".into(), true.into_value())))
+            .await?
+            .into_iter()
+            .filter_map(|entity| {
+                let id = match entity.key().get_id() {
+                    KeyID::StringID(id) => id.clone(),
+                    _ => return None,
+                };
+                DatastoreEvent::from_value(entity.properties().clone())
+                    .ok()
+                    .filter(|ds_event| ds_event.expired.is_none())
+                    .map(|ds_event| ds_event.to_event(id))
+            })
+            .filter(|e| tag.as_ref().is_none_or(|tag| e.tags.contains(tag)))
+            .collect())
+    }
+
+    async fn get_group_events(&self, group_id: String) -> Result<Vec<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let mut events: Vec<Event> = client
+            .query(
+                Query::new(EVENT_KIND)
+                    .filter(Filter::Equal("groupId".into(), group_id.into_value())),
+            )
+            .await?
+            .into_iter()
+            .filter_map(|entity| {
+                let KeyID::StringID(id) = entity.key().get_id() else {
+                    return None;
+                };
+                DatastoreEvent::from_value(entity.properties().clone())
+                    .ok()
+                    .filter(|ds_event| ds_event.expired.is_none())
+                    .map(|ds_event| ds_event.to_event(id.clone()))
+            })
+            .collect();
+        events.sort_by_key(|e| e.created_at);
+
+        Ok(events)
+    }
+
+    async fn get_creator_events(&self, creator_id: String) -> Result<Vec<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let mut events: Vec<Event> = client
+            .query(
+                Query::new(EVENT_KIND)
+                    .filter(Filter::Equal("creatorId".into(), creator_id.into_value())),
+            )
+            .await?
+            .into_iter()
+            .filter_map(|entity| {
+                let KeyID::StringID(id) = entity.key().get_id() else {
+                    return None;
+                };
+                DatastoreEvent::from_value(entity.properties().clone())
+                    .ok()
+                    .filter(|ds_event| ds_event.expired.is_none())
+                    .map(|ds_event| ds_event.to_event(id.clone()))
+            })
+            .collect();
+        events.sort_by_key(|e| Reverse(e.created_at));
+
+        Ok(events)
+    }
+
+    async fn query_events(
+        &self,
+        query: EventQuery,
+        range: PageRange,
+    ) -> Result<Page<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        // Datastore can only filter on one range per query, so filter names in memory
+        let mut ds_query = Query::new(EVENT_KIND);
+        if let Some(after) = query.created_after {
+            ds_query = ds_query.filter(Filter::GreaterThan(
+                "created".into(),
+                after.timestamp().into_value(),
+            ));
+        }
+        if let Some(before) = query.created_before {
+            ds_query = ds_query.filter(Filter::LesserThan(
+                "created".into(),
+                before.timestamp().into_value(),
+            ));
+        }
+
+        let mut events: Vec<Event> = client
+            .query(ds_query)
+            .await?
+            .into_iter()
+            .filter_map(|entity| {
+                let id = match entity.key().get_id() {
+                    KeyID::StringID(id) => id.clone(),
+                    _ => return None,
+                };
+                DatastoreEvent::from_value(entity.properties().clone())
+                    .ok()
+                    .map(|ds_event| ds_event.to_event(id))
+            })
+            .filter(|e| query.matches(e))
+            .collect();
+        events.sort_by_key(|e| Reverse(e.created_at));
+
+        Ok(range.apply(events))
+    }
+
+    async fn peek_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        Ok(client
+            .get::<DatastoreEvent, _>(Key::new(EVENT_KIND).id(id.clone()))
+            .await?
+            .map(|ds_event| ds_event.to_event(id)))
+    }
+
+    async fn get_idempotency_key(
+        &self,
+        key: String,
+    ) -> Result<Option<IdempotencyKey>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        Ok(client
+            .get::<DatastoreIdempotencyKey, _>(Key::new(IDEMPOTENCY_KEY_KIND).id(key.clone()))
+            .await?
+            .map(|ds_key| ds_key.to_idempotency_key(key)))
+    }
+
+    async fn create_idempotency_key(&self, key: IdempotencyKey) -> Result<bool, Self::Error> {
+        // As with inserting people, holding the client's lock only keeps this atomic within
+        // one instance of the API
+        let mut client = self.client.lock().await;
+
+        let datastore_key = Key::new(IDEMPOTENCY_KEY_KIND).id(key.key.clone());
+        if client
+            .get::<DatastoreIdempotencyKey, _>(datastore_key.clone())
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+        client
+            .put((datastore_key, DatastoreIdempotencyKey::from(key)))
+            .await?;
+
+        Ok(true)
+    }
+
+    async fn set_idempotency_key_event(
+        &self,
+        key: String,
+        event_id: String,
+    ) -> Result<(), Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let datastore_key = Key::new(IDEMPOTENCY_KEY_KIND).id(key);
+        if let Some(mut existing) = client
+            .get::<DatastoreIdempotencyKey, _>(datastore_key.clone())
+            .await?
+        {
+            existing.eventId = Some(event_id);
+            client.put((datastore_key, existing)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_idempotency_key(&self, key: String) -> Result<(), Self::Error> {
+        let mut client = self.client.lock().await;
+
+        client
+            .delete_all(vec![Key::new(IDEMPOTENCY_KEY_KIND).id(key)])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let keys_to_delete: Vec<Key> = client
+            .query(Query::new(IDEMPOTENCY_KEY_KIND).filter(Filter::LesserThan(
+                "created".into(),
+                cutoff.timestamp().into_value(),
+            )))
+            .await?
+            .iter()
+            .map(|entity| entity.key().clone())
+            .collect();
+        let count = keys_to_delete.len() as i64;
+
+        client.delete_all(keys_to_delete).await?;
+
+        Ok(count)
+    }
+
+    async fn get_template(&self, id: String) -> Result<Option<Template>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let key = Key::new(TEMPLATE_KIND).id(id.clone());
+        let existing_template = client.get::<DatastoreTemplate, _>(key.clone()).await?;
+
+        // Mark as used if it exists
+        if let Some(mut template) = existing_template.clone() {
+            template.used = Utc::now().timestamp();
+            client.put((key, template)).await?;
+        }
+
+        Ok(existing_template.map(|t| t.to_template(id)))
+    }
+
+    async fn create_template(&self, template: Template) -> Result<Template, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        client
+            .put((
+                Key::new(TEMPLATE_KIND).id(template.id.clone()),
+                DatastoreTemplate::from(template.clone()),
+            ))
+            .await?;
+
+        Ok(template)
+    }
+
+    async fn delete_templates(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let keys_to_delete: Vec<Key> = client
+            .query(Query::new(TEMPLATE_KIND).filter(Filter::LesserThan(
+                "used".into(),
+                cutoff.timestamp().into_value(),
+            )))
+            .await?
+            .iter()
+            .map(|entity| entity.key().clone())
+            .collect();
+        let count = keys_to_delete.len() as i64;
+
+        client.delete_all(keys_to_delete).await?;
+
+        Ok(count)
+    }
+
+    async fn expire_events(&self, default_retention_days: i64) -> Result<i64, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        // Events are kept for at least a day, so only those can be stale
+        let now = Utc::now();
+        let stale_events: Vec<(Key, DatastoreEvent)> = client
+            .query(Query::new(EVENT_KIND).filter(Filter::LesserThan(
+                "visited".into(),
+                (now - Duration::days(1)).timestamp().into_value(),
+            )))
+            .await?
+            .into_iter()
+            .filter_map(|entity| {
+                let KeyID::StringID(id) = entity.key().get_id() else {
+                    return None;
+                };
+                let ds_event = DatastoreEvent::from_value(entity.properties().clone()).ok()?;
+                (ds_event.expired.is_none()
+                    && ds_event
+                        .to_event(id.clone())
+                        .is_stale(default_retention_days, now))
+                .then(|| (entity.key().clone(), ds_event))
+            })
+            .collect();
+        let count = stale_events.len() as i64;
+
+        for (key, mut ds_event) in stale_events {
+            ds_event.expired = Some(now.timestamp());
+            client.put((key, ds_event)).await?;
+        }
+
+        Ok(count)
+    }
+
+    async fn restore_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let key = Key::new(EVENT_KIND).id(id.clone());
+        let Some(mut ds_event) = client.get::<DatastoreEvent, _>(key.clone()).await? else {
+            return Ok(None);
+        };
+
+        ds_event.expired = None;
+        ds_event.visited = Utc::now().timestamp();
+        client.put((key, ds_event.clone())).await?;
+
+        Ok(Some(ds_event.to_event(id)))
+    }
+
+    async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let mut keys_to_delete: Vec<Key> = client
+            .query(Query::new(EVENT_KIND).filter(Filter::LesserThan(
+                "expired".into(),
+                cutoff.timestamp().into_value(),
+            )))
+            .await?
+            .iter()
+            .map(|entity| entity.key().clone())
+            .collect();
+
+        let event_count = keys_to_delete.len() as i64;
+
+        let events_to_delete = keys_to_delete.clone();
+        for e in events_to_delete.iter() {
+            if let KeyID::StringID(id) = e.get_id() {
+                let mut event_people_to_delete: Vec<Key> = client
+                    .query(
+                        Query::new(PERSON_KIND)
+                            .filter(Filter::Equal("eventId".into(), id.clone().into_value())),
+                    )
+                    .await?
+                    .iter()
+                    .map(|entity| entity.key().clone())
+                    .collect();
+                keys_to_delete.append(&mut event_people_to_delete);
+            }
+        }
+
+        let person_count = keys_to_delete.len() as i64 - event_count;
+
+        for e in events_to_delete.iter() {
+            if let KeyID::StringID(id) = e.get_id() {
+                for kind in [COMMENT_KIND, ACTIVITY_KIND, EVENT_VIEWS_KIND] {
+                    let mut event_keys_to_delete: Vec<Key> = client
+                        .query(
+                            Query::new(kind)
+                                .filter(Filter::Equal("eventId".into(), id.clone().into_value())),
+                        )
+                        .await?
+                        .iter()
+                        .map(|entity| entity.key().clone())
+                        .collect();
+                    keys_to_delete.append(&mut event_keys_to_delete);
+                }
+            }
+        }
+
+        client.delete_all(keys_to_delete).await?;
+
+        Ok(Stats {
+            event_count,
+            person_count,
+        })
+    }
+
+    async fn preview_cleanup(
+        &self,
+        default_retention_days: i64,
+        cutoff: DateTime<Utc>,
+    ) -> Result<CleanupPreview, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        // Matches `expire_events`, events are kept for at least a day
+        let now = Utc::now();
+        let stale_events: Vec<Event> = client
+            .query(Query::new(EVENT_KIND).filter(Filter::LesserThan(
+                "visited".into(),
+                (now - Duration::days(1)).timestamp().into_value(),
+            )))
+            .await?
+            .into_iter()
+            .filter_map(|entity| {
+                let KeyID::StringID(id) = entity.key().get_id() else {
+                    return None;
+                };
+                let ds_event = DatastoreEvent::from_value(entity.properties().clone()).ok()?;
+                let event = ds_event.to_event(id.clone());
+                (ds_event.expired.is_none() && event.is_stale(default_retention_days, now))
+                    .then_some(event)
+            })
+            .collect();
+
+        let expired_events: Vec<Event> = client
+            .query(Query::new(EVENT_KIND).filter(Filter::LesserThan(
+                "expired".into(),
+                cutoff.timestamp().into_value(),
+            )))
+            .await?
+            .into_iter()
+            .filter_map(|entity| {
+                let KeyID::StringID(id) = entity.key().get_id() else {
+                    return None;
+                };
+                let ds_event = DatastoreEvent::from_value(entity.properties().clone()).ok()?;
+                Some(ds_event.to_event(id.clone()))
+            })
+            .collect();
+
+        let mut person_count = 0;
+        for event in expired_events.iter() {
+            person_count += client
+                .query(Query::new(PERSON_KIND).filter(Filter::Equal(
+                    "eventId".into(),
+                    event.id.clone().into_value(),
+                )))
+                .await?
+                .len() as i64;
+        }
+
+        Ok(CleanupPreview {
+            stale_events,
+            expired_events,
+            person_count,
+        })
+    }
+
+    async fn delete_event(&self, id: String) -> Result<Option<Stats>, Self::Error> {
+        let mut client = self.client.lock().await;
+
+        let key = Key::new(EVENT_KIND).id(id.clone());
+        if client
+            .get::<DatastoreEvent, _>(key.clone())
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut keys_to_delete: Vec<Key> = client
+            .query(
+                Query::new(PERSON_KIND)
+                    .filter(Filter::Equal("eventId".into(), id.clone().into_value())),
+            )
+            .await?
+            .iter()
+            .map(|entity| entity.key().clone())
+            .collect();
+        let person_count = keys_to_delete.len() as i64;
+        for kind in [COMMENT_KIND, ACTIVITY_KIND, EVENT_VIEWS_KIND] {
+            keys_to_delete.extend(
+                client
+                    .query(
+                        Query::new(kind)
+                            .filter(Filter::Equal("eventId".into(), id.clone().into_value())),
+                    )
+                    .await?
+                    .iter()
+                    .map(|entity| entity.key().clone()),
+            );
+        }
+        keys_to_delete.push(key);
+
+        client.delete_all(keys_to_delete).await?;
+
+        Ok(Some(Stats {
+            event_count: 1,
+            person_count,
+        }))
+    }
+}
+
+impl DatastoreAdaptor {
+    /// Connect with the service account credentials in `GCP_CREDENTIALS`
+    pub async fn new() -> Self {
+        Self::from_credentials(
+            &env::var("GCP_CREDENTIALS").expect("Expected GCP_CREDENTIALS environment variable"),
+        )
+        .await
+    }
+
+    /// Connect with a service account's JSON credentials
+    pub async fn from_credentials(credentials: &str) -> Self {
+        // Load credentials
+        let credentials: ApplicationCredentials =
+            serde_json::from_str(credentials).expect("GCP credentials are not valid JSON");
+
+        // Connect to datastore
+        let client = Client::from_credentials(credentials.project_id.clone(), credentials.clone())
+            .await
+            .expect("Failed to setup datastore client");
+        let client = Mutex::new(client);
+
+        println!(
+            "🎛️  Connected to datastore in project {}",
+            credentials.project_id
+        );
+
+        Self { client }
+    }
+}
+
+#[derive(FromValue, IntoValue, Default, Clone)]
+struct DatastoreStats {
+    value: i64,
+}
+
+#[derive(FromValue, IntoValue)]
+#[allow(non_snake_case)]
+struct DatastoreEventViews {
+    eventId: String,
+    viewCount: i64,
+    // JSON list of the sketch's registers
+    visitorRegisters: Option<String>,
+}
+
+impl From<DatastoreEventViews> for EventViews {
+    fn from(value: DatastoreEventViews) -> Self {
+        Self {
+            view_count: value.viewCount,
+            visitor_registers: value
+                .visitorRegisters
+                .and_then(|registers| serde_json::from_str(&registers).ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl DatastoreEventViews {
+    fn from_views(views: EventViews, event_id: String) -> Self {
+        Self {
+            eventId: event_id,
+            viewCount: views.view_count,
+            visitorRegisters: serde_json::to_string(&views.visitor_registers).ok(),
+        }
+    }
+}
+
+#[derive(FromValue, IntoValue)]
+#[allow(non_snake_case)]
+struct DatastoreDailyStats {
+    // Unix time of the start of the day, so days can be filtered by range
+    date: i64,
+    eventCount: i64,
+    personCount: i64,
+}
+
+impl From<DatastoreDailyStats> for DailyStats {
+    fn from(value: DatastoreDailyStats) -> Self {
+        Self {
+            date: unix_to_date(value.date).date_naive(),
+            event_count: value.eventCount,
+            person_count: value.personCount,
+        }
+    }
+}
+
+#[derive(FromValue, IntoValue, Clone)]
+#[allow(non_snake_case)]
+struct DatastoreEvent {
+    name: String,
+    created: i64,
+    visited: i64,
+    times: Vec<String>,
+    timezone: String,
+    editToken: Option<String>,
+    scoring: Option<String>,
+    listed: Option<bool>,
+    tags: Option<Vec<String>>,
+    finalizedTime: Option<String>,
+    password: Option<String>,
+    invitees: Option<Vec<String>>,
+    responsesClosed: Option<bool>,
+    webhookUrl: Option<String>,
+    webhookSecret: Option<String>,
+    updated: Option<i64>,
+    // JSON list of names and the unix time in milliseconds they were removed
+    removedPeople: Option<String>,
+    retentionDays: Option<i64>,
+    expired: Option<i64>,
+    groupId: Option<String>,
+    creatorId: Option<String>,
+    maxPeople: Option<i64>,
+    chatWebhookUrl: Option<String>,
+}
+
+#[derive(FromValue, IntoValue)]
+#[allow(non_snake_case)]
+struct DatastorePerson {
+    name: String,
+    password: Option<String>,
+    created: i64,
+    updated: Option<i64>,
+    eventId: String,
+    availability: Vec<String>,
+    editToken: Option<String>,
+    editTokenExpires: Option<i64>,
+    ifNeeded: Option<Vec<String>>,
+    undecided: Option<Vec<String>>,
+    subjectId: Option<String>,
+    avatarColor: Option<String>,
+    avatarEmoji: Option<String>,
+    timezone: Option<String>,
+    googleRefreshToken: Option<String>,
+}
+
+impl From<DatastorePerson> for Person {
+    fn from(value: DatastorePerson) -> Self {
+        Self {
+            name: value.name,
+            password_hash: value.password,
+            created_at: unix_to_date(value.created),
+            updated_at: unix_to_date(value.updated.unwrap_or(value.created)),
+            availability: value.availability,
+            edit_token_hash: value.editToken,
+            edit_token_expires_at: value.editTokenExpires.map(unix_to_date),
+            if_needed: value.ifNeeded.unwrap_or_default(),
+            undecided: value.undecided.unwrap_or_default(),
+            subject_id: value.subjectId,
+            avatar_color: value.avatarColor,
+            avatar_emoji: value.avatarEmoji,
+            timezone: value.timezone,
+            google_refresh_token: value.googleRefreshToken,
+        }
+    }
+}
+
+impl DatastorePerson {
+    fn from_person(person: Person, event_id: String) -> Self {
+        Self {
+            name: person.name,
+            password: person.password_hash,
+            created: person.created_at.timestamp(),
+            updated: Some(person.updated_at.timestamp()),
+            eventId: event_id,
+            availability: person.availability,
+            editToken: person.edit_token_hash,
+            editTokenExpires: person.edit_token_expires_at.map(|t| t.timestamp()),
+            ifNeeded: Some(person.if_needed),
+            undecided: Some(person.undecided),
+            subjectId: person.subject_id,
+            avatarColor: person.avatar_color,
+            avatarEmoji: person.avatar_emoji,
+            timezone: person.timezone,
+            googleRefreshToken: person.google_refresh_token,
+        }
+    }
+}
+
+impl From<Event> for DatastoreEvent {
+    fn from(value: Event) -> Self {
+        Self {
+            name: value.name,
+            created: value.created_at.timestamp(),
+            visited: value.visited_at.timestamp(),
+            times: value.times,
+            timezone: value.timezone,
+            editToken: value.edit_token_hash,
+            scoring: value.scoring,
+            listed: Some(value.listed),
+            tags: Some(value.tags),
+            finalizedTime: value.finalized_time,
+            password: value.password_hash,
+            invitees: Some(value.invitees),
+            responsesClosed: Some(value.responses_closed),
+            webhookUrl: value.webhook_url,
+            webhookSecret: value.webhook_secret,
+            updated: Some(value.updated_at.timestamp()),
+            removedPeople: serde_json::to_string(
+                &value
+                    .removed_people
+                    .into_iter()
+                    .map(|removed| (removed.name, removed.removed_at.timestamp_millis()))
+                    .collect::<Vec<_>>(),
+            )
+            .ok(),
+            retentionDays: value.retention_days,
+            expired: value.expired_at.map(|t| t.timestamp()),
+            groupId: value.group_id,
+            creatorId: value.creator_id,
+            maxPeople: value.max_people,
+            chatWebhookUrl: value.chat_webhook_url,
+        }
+    }
+}
+
+impl DatastoreEvent {
+    fn to_event(&self, event_id: String) -> Event {
+        Event {
             id: event_id,
             name: self.name.clone(),
             created_at: unix_to_date(self.created),
             visited_at: unix_to_date(self.visited),
             times: self.times.clone(),
             timezone: self.timezone.clone(),
+            edit_token_hash: self.editToken.clone(),
+            scoring: self.scoring.clone(),
+            listed: self.listed.unwrap_or(false),
+            tags: self.tags.clone().unwrap_or_default(),
+            finalized_time: self.finalizedTime.clone(),
+            password_hash: self.password.clone(),
+            invitees: self.invitees.clone().unwrap_or_default(),
+            responses_closed: self.responsesClosed.unwrap_or(false),
+            webhook_url: self.webhookUrl.clone(),
+            webhook_secret: self.webhookSecret.clone(),
+            updated_at: unix_to_date(self.updated.unwrap_or(self.created)),
+            removed_people: self
+                .removedPeople
+                .as_ref()
+                .and_then(|removed| serde_json::from_str::<Vec<(String, i64)>>(removed).ok())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(name, removed_at)| {
+                    Some(RemovedPerson {
+                        name,
+                        removed_at: Utc.timestamp_millis_opt(removed_at).single()?,
+                    })
+                })
+                .collect(),
+            retention_days: self.retentionDays,
+            expired_at: self.expired.map(unix_to_date),
+            group_id: self.groupId.clone(),
+            creator_id: self.creatorId.clone(),
+            max_people: self.maxPeople,
+            chat_webhook_url: self.chatWebhookUrl.clone(),
+        }
+    }
+}
+
+#[derive(FromValue, IntoValue)]
+#[allow(non_snake_case)]
+struct DatastoreComment {
+    eventId: String,
+    author: String,
+    body: String,
+    created: i64,
+}
+
+impl From<DatastoreComment> for Comment {
+    fn from(value: DatastoreComment) -> Self {
+        Self {
+            author: value.author,
+            body: value.body,
+            created_at: unix_to_date(value.created),
+        }
+    }
+}
+
+impl DatastoreComment {
+    fn from_comment(comment: Comment, event_id: String) -> Self {
+        Self {
+            eventId: event_id,
+            author: comment.author,
+            body: comment.body,
+            created: comment.created_at.timestamp(),
+        }
+    }
+}
+
+#[derive(FromValue, IntoValue)]
+#[allow(non_snake_case)]
+struct DatastoreActivity {
+    eventId: String,
+    kind: String,
+    person: Option<String>,
+    created: i64,
+}
+
+impl From<DatastoreActivity> for Activity {
+    fn from(value: DatastoreActivity) -> Self {
+        Self {
+            kind: value.kind,
+            person: value.person,
+            created_at: unix_to_date(value.created),
+        }
+    }
+}
+
+impl DatastoreActivity {
+    fn from_activity(activity: Activity, event_id: String) -> Self {
+        Self {
+            eventId: event_id,
+            kind: activity.kind,
+            person: activity.person,
+            created: activity.created_at.timestamp(),
+        }
+    }
+}
+
+#[derive(FromValue, IntoValue)]
+#[allow(non_snake_case)]
+struct DatastoreIdempotencyKey {
+    requestHash: String,
+    eventId: Option<String>,
+    created: i64,
+}
+
+impl From<IdempotencyKey> for DatastoreIdempotencyKey {
+    fn from(value: IdempotencyKey) -> Self {
+        Self {
+            requestHash: value.request_hash,
+            eventId: value.event_id,
+            created: value.created_at.timestamp(),
+        }
+    }
+}
+
+impl DatastoreIdempotencyKey {
+    fn to_idempotency_key(&self, key: String) -> IdempotencyKey {
+        IdempotencyKey {
+            key,
+            request_hash: self.requestHash.clone(),
+            event_id: self.eventId.clone(),
+            created_at: unix_to_date(self.created),
+        }
+    }
+}
+
+#[derive(FromValue, IntoValue, Clone)]
+#[allow(non_snake_case)]
+struct DatastoreTemplate {
+    name: String,
+    times: Vec<String>,
+    timezone: String,
+    editToken: Option<String>,
+    created: i64,
+    used: i64,
+}
+
+impl From<Template> for DatastoreTemplate {
+    fn from(value: Template) -> Self {
+        Self {
+            name: value.name,
+            times: value.times,
+            timezone: value.timezone,
+            editToken: value.edit_token_hash,
+            created: value.created_at.timestamp(),
+            used: value.used_at.timestamp(),
+        }
+    }
+}
+
+impl DatastoreTemplate {
+    fn to_template(&self, id: String) -> Template {
+        Template {
+            id,
+            name: self.name.clone(),
+            times: self.times.clone(),
+            timezone: self.timezone.clone(),
+            edit_token_hash: self.editToken.clone(),
+            created_at: unix_to_date(self.created),
+            used_at: unix_to_date(self.used),
         }
     }
 }
@@ -300,6 +1377,10 @@ fn unix_to_date(unix: i64) -> DateTime<Utc> {
     DateTime::from_utc(NaiveDateTime::from_timestamp_opt(unix, 0).unwrap(), Utc)
 }
 
+fn date_to_unix(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0).unwrap().timestamp()
+}
+
 #[derive(Debug)]
 pub enum DatastoreAdaptorError {
     DatastoreError(google_cloud::error::Error),
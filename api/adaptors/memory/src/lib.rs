@@ -1,14 +1,28 @@
-use std::{collections::HashMap, error::Error, fmt::Display};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Display,
+};
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use common::{Adaptor, Event, Person, Stats};
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    Activity, Adaptor, CleanupPreview, Comment, DailyStats, Event, EventQuery, EventViews,
+    IdempotencyKey, Page, PageRange, Person, PersonInsert, PersonUpdate, Stats, Template,
+};
 use tokio::sync::Mutex;
 
 struct State {
     stats: Stats,
+    daily_stats: BTreeMap<NaiveDate, Stats>,
     events: HashMap<String, Event>,
     people: HashMap<(String, String), Person>,
+    comments: HashMap<String, Vec<Comment>>,
+    activity: HashMap<String, Vec<Activity>>,
+    views: HashMap<String, EventViews>,
+    idempotency_keys: HashMap<String, IdempotencyKey>,
+    templates: HashMap<String, Template>,
 }
 
 pub struct MemoryAdaptor {
@@ -39,11 +53,45 @@ impl Adaptor for MemoryAdaptor {
         Ok(state.stats.person_count)
     }
 
+    async fn add_stats(&self, stats: Stats) -> Result<Stats, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        state.stats.event_count += stats.event_count;
+        state.stats.person_count += stats.person_count;
+        Ok(state.stats.clone())
+    }
+
+    async fn add_daily_stats(&self, date: NaiveDate, stats: Stats) -> Result<(), Self::Error> {
+        let mut state = self.state.lock().await;
+
+        let day = state.daily_stats.entry(date).or_insert(Stats {
+            event_count: 0,
+            person_count: 0,
+        });
+        day.event_count += stats.event_count;
+        day.person_count += stats.person_count;
+        Ok(())
+    }
+
+    async fn get_daily_stats(&self, since: NaiveDate) -> Result<Vec<DailyStats>, Self::Error> {
+        let state = self.state.lock().await;
+
+        Ok(state
+            .daily_stats
+            .range(since..)
+            .map(|(date, stats)| DailyStats {
+                date: *date,
+                event_count: stats.event_count,
+                person_count: stats.person_count,
+            })
+            .collect())
+    }
+
     async fn get_people(&self, event_id: String) -> Result<Option<Vec<Person>>, Self::Error> {
         let state = self.state.lock().await;
 
         // Event doesn't exist
-        if state.events.get(&event_id).is_none() {
+        if !state.events.contains_key(&event_id) {
             return Ok(None);
         }
 
@@ -71,7 +119,7 @@ impl Adaptor for MemoryAdaptor {
         let mut state = self.state.lock().await;
 
         // Check event exists
-        if state.events.get(&event_id).is_none() {
+        if !state.events.contains_key(&event_id) {
             return Ok(None);
         }
 
@@ -82,10 +130,169 @@ impl Adaptor for MemoryAdaptor {
         Ok(Some(person))
     }
 
+    async fn insert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<PersonInsert>, Self::Error> {
+        // Counting and inserting while holding the lock keeps it atomic
+        let mut state = self.state.lock().await;
+
+        let Some(event) = state.events.get(&event_id) else {
+            return Ok(None);
+        };
+        let key = (event_id, person.name.clone());
+        if let Some(max_people) = event.max_people {
+            let count = state.people.keys().filter(|(id, _)| *id == key.0).count();
+            if !state.people.contains_key(&key) && count as i64 >= max_people {
+                return Ok(Some(PersonInsert::Full));
+            }
+        }
+
+        state.people.insert(key, person.clone());
+        Ok(Some(PersonInsert::Inserted(Box::new(person))))
+    }
+
+    async fn update_person(
+        &self,
+        event_id: String,
+        person: Person,
+        expected_updated_at: DateTime<Utc>,
+    ) -> Result<Option<PersonUpdate>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        if !state.events.contains_key(&event_id) {
+            return Ok(None);
+        }
+        let key = (event_id, person.name.clone());
+        if !state.people.get(&key).is_some_and(|existing| {
+            existing.updated_at.timestamp_millis() == expected_updated_at.timestamp_millis()
+        }) {
+            return Ok(Some(PersonUpdate::Changed));
+        }
+
+        state.people.insert(key, person.clone());
+        Ok(Some(PersonUpdate::Updated(Box::new(person))))
+    }
+
+    async fn delete_person(
+        &self,
+        event_id: String,
+        person_name: String,
+    ) -> Result<Option<Person>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        Ok(state.people.remove(&(event_id, person_name)))
+    }
+
+    async fn get_comments(&self, event_id: String) -> Result<Option<Vec<Comment>>, Self::Error> {
+        let state = self.state.lock().await;
+
+        // Event doesn't exist
+        if !state.events.contains_key(&event_id) {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            state.comments.get(&event_id).cloned().unwrap_or_default(),
+        ))
+    }
+
+    async fn create_comment(
+        &self,
+        event_id: String,
+        comment: Comment,
+    ) -> Result<Option<Comment>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        // Check event exists
+        if !state.events.contains_key(&event_id) {
+            return Ok(None);
+        }
+
+        state
+            .comments
+            .entry(event_id)
+            .or_default()
+            .push(comment.clone());
+
+        Ok(Some(comment))
+    }
+
+    async fn get_activity(&self, event_id: String) -> Result<Option<Vec<Activity>>, Self::Error> {
+        let state = self.state.lock().await;
+
+        // Event doesn't exist
+        if !state.events.contains_key(&event_id) {
+            return Ok(None);
+        }
+
+        let mut activity = state.activity.get(&event_id).cloned().unwrap_or_default();
+        activity.reverse();
+        Ok(Some(activity))
+    }
+
+    async fn create_activity(
+        &self,
+        event_id: String,
+        activity: Activity,
+    ) -> Result<Option<Activity>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        // Check event exists
+        if !state.events.contains_key(&event_id) {
+            return Ok(None);
+        }
+
+        state
+            .activity
+            .entry(event_id)
+            .or_default()
+            .push(activity.clone());
+
+        Ok(Some(activity))
+    }
+
+    async fn get_event_views(&self, event_id: String) -> Result<Option<EventViews>, Self::Error> {
+        let state = self.state.lock().await;
+
+        // Event doesn't exist
+        if !state.events.contains_key(&event_id) {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            state.views.get(&event_id).cloned().unwrap_or_default(),
+        ))
+    }
+
+    async fn record_event_view(
+        &self,
+        event_id: String,
+        register: usize,
+        rank: u8,
+    ) -> Result<Option<EventViews>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        // Check event exists
+        if !state.events.contains_key(&event_id) {
+            return Ok(None);
+        }
+
+        let views = state.views.entry(event_id).or_default();
+        views.record(register, rank);
+
+        Ok(Some(views.clone()))
+    }
+
     async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
         let mut state = self.state.lock().await;
 
-        let event = state.events.get(&id).cloned();
+        let event = state
+            .events
+            .get(&id)
+            .filter(|event| event.expired_at.is_none())
+            .cloned();
         if let Some(mut event) = event.clone() {
             event.visited_at = Utc::now();
             state.events.insert(id, event);
@@ -102,17 +309,211 @@ impl Adaptor for MemoryAdaptor {
         Ok(event)
     }
 
+    async fn update_event(&self, event: Event) -> Result<Option<Event>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        let Some(existing_event) = state.events.get_mut(&event.id) else {
+            return Ok(None);
+        };
+        *existing_event = Event {
+            visited_at: existing_event.visited_at,
+            expired_at: existing_event.expired_at,
+            updated_at: Utc::now(),
+            ..event
+        };
+
+        Ok(Some(existing_event.clone()))
+    }
+
+    async fn get_listed_events(&self, tag: Option<String>) -> Result<Vec<Event>, Self::Error> {
+        let state = self.state.lock().await;
+
+        Ok(state
+            .events
+            .values()
+            .filter(|e| {
+                e.listed
+                    && e.expired_at.is_none()
+                    && tag.as_ref().is_none_or(|tag| e.tags.contains(tag))
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn get_idempotency_key(
+        &self,
+        key: String,
+    ) -> Result<Option<IdempotencyKey>, Self::Error> {
+        let state = self.state.lock().await;
+
+        Ok(state.idempotency_keys.get(&key).cloned())
+    }
+
+    async fn create_idempotency_key(&self, key: IdempotencyKey) -> Result<bool, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        if state.idempotency_keys.contains_key(&key.key) {
+            return Ok(false);
+        }
+        state.idempotency_keys.insert(key.key.clone(), key);
+
+        Ok(true)
+    }
+
+    async fn set_idempotency_key_event(
+        &self,
+        key: String,
+        event_id: String,
+    ) -> Result<(), Self::Error> {
+        let mut state = self.state.lock().await;
+
+        if let Some(key) = state.idempotency_keys.get_mut(&key) {
+            key.event_id = Some(event_id);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_idempotency_key(&self, key: String) -> Result<(), Self::Error> {
+        let mut state = self.state.lock().await;
+
+        state.idempotency_keys.remove(&key);
+
+        Ok(())
+    }
+
+    async fn delete_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        let count = state.idempotency_keys.len();
+        state
+            .idempotency_keys
+            .retain(|_, key| key.created_at >= cutoff);
+
+        Ok((count - state.idempotency_keys.len()) as i64)
+    }
+
+    async fn get_template(&self, id: String) -> Result<Option<Template>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        Ok(state.templates.get_mut(&id).map(|template| {
+            template.used_at = Utc::now();
+            template.clone()
+        }))
+    }
+
+    async fn create_template(&self, template: Template) -> Result<Template, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        state
+            .templates
+            .insert(template.id.clone(), template.clone());
+
+        Ok(template)
+    }
+
+    async fn delete_templates(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        let count = state.templates.len();
+        state
+            .templates
+            .retain(|_, template| template.used_at >= cutoff);
+
+        Ok((count - state.templates.len()) as i64)
+    }
+
+    async fn expire_events(&self, default_retention_days: i64) -> Result<i64, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        let now = Utc::now();
+        let mut expired_count = 0;
+        for event in state.events.values_mut() {
+            if event.expired_at.is_none() && event.is_stale(default_retention_days, now) {
+                event.expired_at = Some(now);
+                expired_count += 1;
+            }
+        }
+
+        Ok(expired_count)
+    }
+
+    async fn restore_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        Ok(state.events.get_mut(&id).map(|event| {
+            event.expired_at = None;
+            event.visited_at = Utc::now();
+            event.clone()
+        }))
+    }
+
+    async fn get_group_events(&self, group_id: String) -> Result<Vec<Event>, Self::Error> {
+        let state = self.state.lock().await;
+
+        let mut events: Vec<Event> = state
+            .events
+            .values()
+            .filter(|e| e.expired_at.is_none() && e.group_id.as_ref() == Some(&group_id))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| e.created_at);
+
+        Ok(events)
+    }
+
+    async fn get_creator_events(&self, creator_id: String) -> Result<Vec<Event>, Self::Error> {
+        let state = self.state.lock().await;
+
+        let mut events: Vec<Event> = state
+            .events
+            .values()
+            .filter(|e| e.expired_at.is_none() && e.creator_id.as_ref() == Some(&creator_id))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| Reverse(e.created_at));
+
+        Ok(events)
+    }
+
+    async fn query_events(
+        &self,
+        query: EventQuery,
+        range: PageRange,
+    ) -> Result<Page<Event>, Self::Error> {
+        let state = self.state.lock().await;
+
+        let mut events: Vec<Event> = state
+            .events
+            .values()
+            .filter(|e| query.matches(e))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| Reverse(e.created_at));
+
+        Ok(range.apply(events))
+    }
+
+    async fn peek_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        let state = self.state.lock().await;
+
+        Ok(state.events.get(&id).cloned())
+    }
+
     async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error> {
         let mut state = self.state.lock().await;
 
-        // Delete events older than cutoff date
+        // Delete events that expired before the cutoff date
         let mut deleted_event_ids: Vec<String> = Vec::new();
         state.events = state
             .events
             .clone()
             .into_iter()
             .filter(|(id, event)| {
-                if event.visited_at >= cutoff {
+                if event
+                    .expired_at
+                    .is_none_or(|expired_at| expired_at >= cutoff)
+                {
                     true
                 } else {
                     deleted_event_ids.push(id.into());
@@ -126,15 +527,79 @@ impl Adaptor for MemoryAdaptor {
             .people
             .clone()
             .into_iter()
-            .filter(|((event_id, _), _)| deleted_event_ids.contains(event_id))
+            .filter(|((event_id, _), _)| !deleted_event_ids.contains(event_id))
             .collect();
         person_count -= state.people.len() as i64;
 
+        state
+            .comments
+            .retain(|event_id, _| !deleted_event_ids.contains(event_id));
+        state
+            .activity
+            .retain(|event_id, _| !deleted_event_ids.contains(event_id));
+        state
+            .views
+            .retain(|event_id, _| !deleted_event_ids.contains(event_id));
+
         Ok(Stats {
             event_count: deleted_event_ids.len() as i64,
             person_count,
         })
     }
+
+    async fn preview_cleanup(
+        &self,
+        default_retention_days: i64,
+        cutoff: DateTime<Utc>,
+    ) -> Result<CleanupPreview, Self::Error> {
+        let state = self.state.lock().await;
+
+        let now = Utc::now();
+        let stale_events: Vec<Event> = state
+            .events
+            .values()
+            .filter(|e| e.expired_at.is_none() && e.is_stale(default_retention_days, now))
+            .cloned()
+            .collect();
+        let expired_events: Vec<Event> = state
+            .events
+            .values()
+            .filter(|e| e.expired_at.is_some_and(|expired_at| expired_at < cutoff))
+            .cloned()
+            .collect();
+        let person_count = state
+            .people
+            .keys()
+            .filter(|(event_id, _)| expired_events.iter().any(|e| &e.id == event_id))
+            .count() as i64;
+
+        Ok(CleanupPreview {
+            stale_events,
+            expired_events,
+            person_count,
+        })
+    }
+
+    async fn delete_event(&self, id: String) -> Result<Option<Stats>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        if state.events.remove(&id).is_none() {
+            return Ok(None);
+        }
+
+        let mut person_count = state.people.len() as i64;
+        state.people.retain(|(event_id, _), _| *event_id != id);
+        person_count -= state.people.len() as i64;
+
+        state.comments.remove(&id);
+        state.activity.remove(&id);
+        state.views.remove(&id);
+
+        Ok(Some(Stats {
+            event_count: 1,
+            person_count,
+        }))
+    }
 }
 
 impl MemoryAdaptor {
@@ -147,8 +612,14 @@ impl MemoryAdaptor {
                 event_count: 0,
                 person_count: 0,
             },
+            daily_stats: BTreeMap::new(),
             events: HashMap::new(),
             people: HashMap::new(),
+            comments: HashMap::new(),
+            activity: HashMap::new(),
+            views: HashMap::new(),
+            idempotency_keys: HashMap::new(),
+            templates: HashMap::new(),
         });
 
         Self { state }
@@ -12,14 +12,56 @@ pub struct Model {
     pub visited_at: DateTime,
     pub times: Json,
     pub timezone: String,
+    pub edit_token_hash: Option<String>,
+    pub scoring: Option<String>,
+    pub listed: bool,
+    pub tags: Option<Json>,
+    pub finalized_time: Option<String>,
+    pub password_hash: Option<String>,
+    pub invitees: Option<Json>,
+    pub responses_closed: bool,
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub updated_at: Option<DateTime>,
+    pub removed_people: Option<Json>,
+    pub retention_days: Option<i64>,
+    pub expired_at: Option<DateTime>,
+    pub group_id: Option<String>,
+    pub creator_id: Option<String>,
+    pub max_people: Option<i64>,
+    pub chat_webhook_url: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::activity::Entity")]
+    Activity,
+    #[sea_orm(has_many = "super::comment::Entity")]
+    Comment,
+    #[sea_orm(has_one = "super::event_views::Entity")]
+    EventViews,
     #[sea_orm(has_many = "super::person::Entity")]
     Person,
 }
 
+impl Related<super::activity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Activity.def()
+    }
+}
+
+impl Related<super::comment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comment.def()
+    }
+}
+
+impl Related<super::event_views::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::EventViews.def()
+    }
+}
+
 impl Related<super::person::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Person.def()
@@ -1,7 +1,14 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+#[allow(unused_imports)]
 pub mod prelude;
 
+pub mod activity;
+pub mod comment;
+pub mod daily_stats;
 pub mod event;
+pub mod event_views;
+pub mod idempotency_key;
 pub mod person;
 pub mod stats;
+pub mod template;
@@ -12,6 +12,16 @@ pub struct Model {
     pub availability: Json,
     #[sea_orm(primary_key, auto_increment = false)]
     pub event_id: String,
+    pub updated_at: Option<DateTime>,
+    pub edit_token_hash: Option<String>,
+    pub edit_token_expires_at: Option<DateTime>,
+    pub if_needed: Option<Json>,
+    pub undecided: Option<Json>,
+    pub subject_id: Option<String>,
+    pub avatar_color: Option<String>,
+    pub avatar_emoji: Option<String>,
+    pub timezone: Option<String>,
+    pub google_refresh_token: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
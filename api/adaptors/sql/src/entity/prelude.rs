@@ -1,5 +1,11 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+pub use super::activity::Entity as Activity;
+pub use super::comment::Entity as Comment;
+pub use super::daily_stats::Entity as DailyStats;
 pub use super::event::Entity as Event;
+pub use super::event_views::Entity as EventViews;
+pub use super::idempotency_key::Entity as IdempotencyKey;
 pub use super::person::Entity as Person;
 pub use super::stats::Entity as Stats;
+pub use super::template::Entity as Template;
@@ -1,17 +1,26 @@
 use std::{env, error::Error};
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use common::{Adaptor, Event, Person, Stats};
-use entity::{event, person, stats};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use common::{
+    Activity, Adaptor, CleanupPreview, Comment, DailyStats, Event, EventQuery, EventViews,
+    IdempotencyKey, Page, PageRange, PeopleQuery, PeopleVersion, Person, PersonInsert,
+    PersonUpdate, RemovedPerson, SchemaVersion, Stats, Template,
+};
+use entity::{
+    activity, comment, daily_stats, event, event_views, idempotency_key, person, stats, template,
+};
 use migration::{Migrator, MigratorTrait};
 use sea_orm::{
+    sea_query::{Expr, Func, LikeExpr, OnConflict},
     strum::Display,
     ActiveModelTrait,
     ActiveValue::{NotSet, Set},
-    ColumnTrait, Database, DatabaseConnection, DbErr, EntityTrait, ModelTrait, QueryFilter,
+    ColumnTrait, Condition, ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, DbErr,
+    EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Statement,
     TransactionError, TransactionTrait, TryIntoModel,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 mod entity;
@@ -25,6 +34,38 @@ pub struct SqlAdaptor {
 impl Adaptor for SqlAdaptor {
     type Error = SqlAdaptorError;
 
+    async fn ping(&self) -> Result<(), Self::Error> {
+        let backend = self.db.get_database_backend();
+        self.db
+            .execute(Statement::from_string(backend, "SELECT 1".to_owned()))
+            .await?;
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        // Clones share the pool, so this closes it for every handle
+        self.db.clone().close().await?;
+        Ok(())
+    }
+
+    async fn get_schema_version(&self) -> Result<SchemaVersion, Self::Error> {
+        // Each migration is a version, so a database with migrations this build doesn't know
+        // about is from a newer version
+        Ok(SchemaVersion {
+            current: Migrator::get_migration_models(&self.db).await?.len() as i64,
+            expected: Migrator::migrations().len() as i64,
+        })
+    }
+
+    async fn migrate(&self) -> Result<SchemaVersion, Self::Error> {
+        let version = self.get_schema_version().await?;
+        // Migrating a newer database would fail on the migrations it has that this build doesn't
+        if version.current < version.expected {
+            Migrator::up(&self.db, None).await?;
+        }
+        self.get_schema_version().await
+    }
+
     async fn get_stats(&self) -> Result<Stats, Self::Error> {
         let stats_row = get_stats_row(&self.db).await?;
         Ok(Stats {
@@ -47,6 +88,60 @@ impl Adaptor for SqlAdaptor {
         Ok(current_stats.save(&self.db).await?.person_count.unwrap() as i64)
     }
 
+    async fn add_stats(&self, stats: Stats) -> Result<Stats, Self::Error> {
+        let mut current_stats = get_stats_row(&self.db).await?;
+        current_stats.event_count =
+            Set(current_stats.event_count.unwrap() + stats.event_count as i32);
+        current_stats.person_count =
+            Set(current_stats.person_count.unwrap() + stats.person_count as i32);
+
+        let saved = current_stats.save(&self.db).await?;
+        Ok(Stats {
+            event_count: saved.event_count.unwrap() as i64,
+            person_count: saved.person_count.unwrap() as i64,
+        })
+    }
+
+    async fn add_daily_stats(&self, date: NaiveDate, stats: Stats) -> Result<(), Self::Error> {
+        self.db
+            .transaction::<_, (), DbErr>(|t| {
+                Box::pin(async move {
+                    match daily_stats::Entity::find_by_id(date).one(t).await? {
+                        Some(existing) => {
+                            let mut day: daily_stats::ActiveModel = existing.clone().into();
+                            day.event_count = Set(existing.event_count + stats.event_count);
+                            day.person_count = Set(existing.person_count + stats.person_count);
+                            day.update(t).await?;
+                        }
+                        None => {
+                            daily_stats::ActiveModel {
+                                date: Set(date),
+                                event_count: Set(stats.event_count),
+                                person_count: Set(stats.person_count),
+                            }
+                            .insert(t)
+                            .await?;
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_daily_stats(&self, since: NaiveDate) -> Result<Vec<DailyStats>, Self::Error> {
+        Ok(daily_stats::Entity::find()
+            .filter(daily_stats::Column::Date.gte(since))
+            .order_by_asc(daily_stats::Column::Date)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|model| model.into())
+            .collect())
+    }
+
     async fn get_people(&self, event_id: String) -> Result<Option<Vec<Person>>, Self::Error> {
         // TODO: optimize into one query
         let event_row = event::Entity::find_by_id(event_id).one(&self.db).await?;
@@ -65,18 +160,84 @@ impl Adaptor for SqlAdaptor {
         })
     }
 
+    async fn query_people(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+    ) -> Result<Option<Vec<Person>>, Self::Error> {
+        if event::Entity::find_by_id(event_id.clone())
+            .one(&self.db)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut select = person::Entity::find().filter(person::Column::EventId.eq(event_id));
+        if let Some(since) = query.updated_since {
+            // People who have never been updated fall back to their creation date
+            select = select.filter(
+                Condition::any()
+                    .add(person::Column::UpdatedAt.gte(since.naive_utc()))
+                    .add(
+                        Condition::all()
+                            .add(person::Column::UpdatedAt.is_null())
+                            .add(person::Column::CreatedAt.gte(since.naive_utc())),
+                    ),
+            );
+        }
+
+        // Sorting and the responded filter depend on the availability json, so finish up in memory
+        Ok(Some(
+            query.apply(
+                select
+                    .all(&self.db)
+                    .await?
+                    .into_iter()
+                    .map(|model| model.into())
+                    .collect(),
+            ),
+        ))
+    }
+
+    async fn get_people_version(
+        &self,
+        event_id: String,
+    ) -> Result<Option<PeopleVersion>, Self::Error> {
+        if event::Entity::find_by_id(event_id.clone())
+            .one(&self.db)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        // People who have never been updated fall back to their creation date
+        let (count, updated_at): (i64, Option<NaiveDateTime>) = person::Entity::find()
+            .select_only()
+            .column_as(Expr::cust("COUNT(*)"), "count")
+            .column_as(
+                Expr::cust("MAX(COALESCE(updated_at, created_at))"),
+                "updated_at",
+            )
+            .filter(person::Column::EventId.eq(event_id))
+            .into_tuple()
+            .one(&self.db)
+            .await?
+            .unwrap_or((0, None));
+
+        Ok(Some(PeopleVersion {
+            count: count as usize,
+            updated_at: updated_at.map(|t| DateTime::<Utc>::from_utc(t, Utc)),
+        }))
+    }
+
     async fn upsert_person(
         &self,
         event_id: String,
         person: Person,
     ) -> Result<Option<Person>, Self::Error> {
-        let data = person::ActiveModel {
-            name: Set(person.name.clone()),
-            password_hash: Set(person.password_hash),
-            created_at: Set(person.created_at.naive_utc()),
-            availability: Set(serde_json::to_value(person.availability).unwrap_or(json!([]))),
-            event_id: Set(event_id.clone()),
-        };
+        let data = person_model(event_id.clone(), person.clone());
 
         // Check if the event exists
         if event::Entity::find_by_id(event_id.clone())
@@ -98,8 +259,270 @@ impl Adaptor for SqlAdaptor {
         ))
     }
 
+    async fn insert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<PersonInsert>, Self::Error> {
+        Ok(self
+            .db
+            .transaction::<_, Option<PersonInsert>, DbErr>(|t| {
+                Box::pin(async move {
+                    // Lock the event, so anyone else joining waits until this person is counted
+                    let Some(event) = event::Entity::find_by_id(event_id.clone())
+                        .lock_exclusive()
+                        .one(t)
+                        .await?
+                    else {
+                        return Ok(None);
+                    };
+
+                    let data = person_model(event_id.clone(), person.clone());
+                    if person::Entity::find_by_id((person.name, event_id.clone()))
+                        .one(t)
+                        .await?
+                        .is_some()
+                    {
+                        let updated = data.update(t).await?.try_into_model()?;
+                        return Ok(Some(PersonInsert::Inserted(Box::new(updated.into()))));
+                    }
+
+                    if let Some(max_people) = event.max_people {
+                        let count = person::Entity::find()
+                            .filter(person::Column::EventId.eq(event_id))
+                            .count(t)
+                            .await?;
+                        if count as i64 >= max_people {
+                            return Ok(Some(PersonInsert::Full));
+                        }
+                    }
+
+                    let inserted = data.insert(t).await?.try_into_model()?;
+                    Ok(Some(PersonInsert::Inserted(Box::new(inserted.into()))))
+                })
+            })
+            .await?)
+    }
+
+    async fn update_person(
+        &self,
+        event_id: String,
+        person: Person,
+        expected_updated_at: DateTime<Utc>,
+    ) -> Result<Option<PersonUpdate>, Self::Error> {
+        Ok(self
+            .db
+            .transaction::<_, Option<PersonUpdate>, DbErr>(|t| {
+                Box::pin(async move {
+                    if event::Entity::find_by_id(event_id.clone())
+                        .one(t)
+                        .await?
+                        .is_none()
+                    {
+                        return Ok(None);
+                    }
+
+                    // Lock the person, so anyone else updating them waits to see this change
+                    let existing =
+                        person::Entity::find_by_id((person.name.clone(), event_id.clone()))
+                            .lock_exclusive()
+                            .one(t)
+                            .await?
+                            .map(Person::from);
+                    if !existing.is_some_and(|existing| {
+                        existing.updated_at.timestamp_millis()
+                            == expected_updated_at.timestamp_millis()
+                    }) {
+                        return Ok(Some(PersonUpdate::Changed));
+                    }
+
+                    let updated = person_model(event_id, person)
+                        .update(t)
+                        .await?
+                        .try_into_model()?;
+                    Ok(Some(PersonUpdate::Updated(Box::new(updated.into()))))
+                })
+            })
+            .await?)
+    }
+
+    async fn delete_person(
+        &self,
+        event_id: String,
+        person_name: String,
+    ) -> Result<Option<Person>, Self::Error> {
+        let existing_person = person::Entity::find_by_id((person_name, event_id))
+            .one(&self.db)
+            .await?;
+
+        if let Some(person) = existing_person.clone() {
+            person.delete(&self.db).await?;
+        }
+
+        Ok(existing_person.map(|model| model.into()))
+    }
+
+    async fn get_comments(&self, event_id: String) -> Result<Option<Vec<Comment>>, Self::Error> {
+        let event_row = event::Entity::find_by_id(event_id).one(&self.db).await?;
+
+        Ok(match event_row {
+            Some(event) => Some(
+                event
+                    .find_related(comment::Entity)
+                    .order_by_asc(comment::Column::CreatedAt)
+                    .all(&self.db)
+                    .await?
+                    .into_iter()
+                    .map(|model| model.into())
+                    .collect(),
+            ),
+            None => None,
+        })
+    }
+
+    async fn create_comment(
+        &self,
+        event_id: String,
+        comment: Comment,
+    ) -> Result<Option<Comment>, Self::Error> {
+        // Check if the event exists
+        if event::Entity::find_by_id(event_id.clone())
+            .one(&self.db)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            comment::ActiveModel {
+                id: NotSet,
+                event_id: Set(event_id),
+                author: Set(comment.author),
+                body: Set(comment.body),
+                created_at: Set(comment.created_at.naive_utc()),
+            }
+            .insert(&self.db)
+            .await?
+            .into(),
+        ))
+    }
+
+    async fn get_activity(&self, event_id: String) -> Result<Option<Vec<Activity>>, Self::Error> {
+        let event_row = event::Entity::find_by_id(event_id).one(&self.db).await?;
+
+        Ok(match event_row {
+            Some(event) => Some(
+                event
+                    .find_related(activity::Entity)
+                    .order_by_desc(activity::Column::CreatedAt)
+                    .order_by_desc(activity::Column::Id)
+                    .all(&self.db)
+                    .await?
+                    .into_iter()
+                    .map(|model| model.into())
+                    .collect(),
+            ),
+            None => None,
+        })
+    }
+
+    async fn create_activity(
+        &self,
+        event_id: String,
+        activity: Activity,
+    ) -> Result<Option<Activity>, Self::Error> {
+        // Check if the event exists
+        if event::Entity::find_by_id(event_id.clone())
+            .one(&self.db)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            activity::ActiveModel {
+                id: NotSet,
+                event_id: Set(event_id),
+                kind: Set(activity.kind),
+                person: Set(activity.person),
+                created_at: Set(activity.created_at.naive_utc()),
+            }
+            .insert(&self.db)
+            .await?
+            .into(),
+        ))
+    }
+
+    async fn get_event_views(&self, event_id: String) -> Result<Option<EventViews>, Self::Error> {
+        if event::Entity::find_by_id(event_id.clone())
+            .one(&self.db)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            event_views::Entity::find_by_id(event_id)
+                .one(&self.db)
+                .await?
+                .map(|model| model.into())
+                .unwrap_or_default(),
+        ))
+    }
+
+    async fn record_event_view(
+        &self,
+        event_id: String,
+        register: usize,
+        rank: u8,
+    ) -> Result<Option<EventViews>, Self::Error> {
+        Ok(self
+            .db
+            .transaction::<_, Option<EventViews>, DbErr>(|t| {
+                Box::pin(async move {
+                    if event::Entity::find_by_id(event_id.clone())
+                        .one(t)
+                        .await?
+                        .is_none()
+                    {
+                        return Ok(None);
+                    }
+
+                    let existing = event_views::Entity::find_by_id(event_id.clone())
+                        .one(t)
+                        .await?;
+                    let mut views: EventViews = existing
+                        .clone()
+                        .map(|model| model.into())
+                        .unwrap_or_default();
+                    views.record(register, rank);
+
+                    let model = event_views::ActiveModel {
+                        event_id: Set(event_id),
+                        view_count: Set(views.view_count),
+                        visitor_registers: Set(
+                            serde_json::to_value(&views.visitor_registers).unwrap_or(json!([]))
+                        ),
+                    };
+                    match existing {
+                        Some(_) => model.update(t).await?,
+                        None => model.insert(t).await?,
+                    };
+
+                    Ok(Some(views))
+                })
+            })
+            .await?)
+    }
+
     async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
-        let existing_event = event::Entity::find_by_id(id).one(&self.db).await?;
+        let existing_event = event::Entity::find_by_id(id)
+            .filter(event::Column::ExpiredAt.is_null())
+            .one(&self.db)
+            .await?;
 
         // Mark as visited
         if let Some(event) = existing_event.clone() {
@@ -111,6 +534,28 @@ impl Adaptor for SqlAdaptor {
         Ok(existing_event.map(|model| model.into()))
     }
 
+    async fn get_events(&self, ids: Vec<String>) -> Result<Vec<Event>, Self::Error> {
+        let events = event::Entity::find()
+            .filter(event::Column::Id.is_in(ids))
+            .filter(event::Column::ExpiredAt.is_null())
+            .all(&self.db)
+            .await?;
+
+        // Mark as visited
+        if !events.is_empty() {
+            event::Entity::update_many()
+                .col_expr(
+                    event::Column::VisitedAt,
+                    Expr::value(Utc::now().naive_utc()),
+                )
+                .filter(event::Column::Id.is_in(events.iter().map(|event| event.id.clone())))
+                .exec(&self.db)
+                .await?;
+        }
+
+        Ok(events.into_iter().map(|model| model.into()).collect())
+    }
+
     async fn create_event(&self, event: Event) -> Result<Event, Self::Error> {
         Ok(event::ActiveModel {
             id: Set(event.id),
@@ -119,6 +564,26 @@ impl Adaptor for SqlAdaptor {
             visited_at: Set(event.visited_at.naive_utc()),
             times: Set(serde_json::to_value(event.times).unwrap_or(json!([]))),
             timezone: Set(event.timezone),
+            edit_token_hash: Set(event.edit_token_hash),
+            scoring: Set(event.scoring),
+            listed: Set(event.listed),
+            tags: Set(Some(serde_json::to_value(event.tags).unwrap_or(json!([])))),
+            finalized_time: Set(event.finalized_time),
+            password_hash: Set(event.password_hash),
+            invitees: Set(Some(
+                serde_json::to_value(event.invitees).unwrap_or(json!([])),
+            )),
+            responses_closed: Set(event.responses_closed),
+            webhook_url: Set(event.webhook_url),
+            webhook_secret: Set(event.webhook_secret),
+            updated_at: Set(Some(event.updated_at.naive_utc())),
+            removed_people: Set(Some(removed_people_to_json(event.removed_people))),
+            retention_days: Set(event.retention_days),
+            expired_at: Set(event.expired_at.map(|expired_at| expired_at.naive_utc())),
+            group_id: Set(event.group_id),
+            creator_id: Set(event.creator_id),
+            max_people: Set(event.max_people),
+            chat_webhook_url: Set(event.chat_webhook_url),
         }
         .insert(&self.db)
         .await?
@@ -126,18 +591,294 @@ impl Adaptor for SqlAdaptor {
         .into())
     }
 
+    async fn update_event(&self, event: Event) -> Result<Option<Event>, Self::Error> {
+        let Some(existing_event) = event::Entity::find_by_id(event.id.clone())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let mut model: event::ActiveModel = existing_event.into();
+        model.name = Set(event.name);
+        model.times = Set(serde_json::to_value(event.times).unwrap_or(json!([])));
+        model.timezone = Set(event.timezone);
+        model.edit_token_hash = Set(event.edit_token_hash);
+        model.scoring = Set(event.scoring);
+        model.listed = Set(event.listed);
+        model.tags = Set(Some(serde_json::to_value(event.tags).unwrap_or(json!([]))));
+        model.finalized_time = Set(event.finalized_time);
+        model.password_hash = Set(event.password_hash);
+        model.invitees = Set(Some(
+            serde_json::to_value(event.invitees).unwrap_or(json!([])),
+        ));
+        model.responses_closed = Set(event.responses_closed);
+        model.webhook_url = Set(event.webhook_url);
+        model.webhook_secret = Set(event.webhook_secret);
+        model.updated_at = Set(Some(Utc::now().naive_utc()));
+        model.removed_people = Set(Some(removed_people_to_json(event.removed_people)));
+        model.retention_days = Set(event.retention_days);
+        model.group_id = Set(event.group_id);
+        model.max_people = Set(event.max_people);
+        model.chat_webhook_url = Set(event.chat_webhook_url);
+
+        Ok(Some(model.update(&self.db).await?.into()))
+    }
+
+    async fn get_listed_events(&self, tag: Option<String>) -> Result<Vec<Event>, Self::Error> {
+        // Tags are stored as json, so filter them in memory
+        Ok(event::Entity::find()
+            .filter(event::Column::Listed.eq(true))
+            .filter(event::Column::ExpiredAt.is_null())
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Event::from)
+            .filter(|e| tag.as_ref().is_none_or(|tag| e.tags.contains(tag)))
+            .collect())
+    }
+
+    async fn get_group_events(&self, group_id: String) -> Result<Vec<Event>, Self::Error> {
+        Ok(event::Entity::find()
+            .filter(event::Column::GroupId.eq(group_id))
+            .filter(event::Column::ExpiredAt.is_null())
+            .order_by_asc(event::Column::CreatedAt)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Event::from)
+            .collect())
+    }
+
+    async fn get_creator_events(&self, creator_id: String) -> Result<Vec<Event>, Self::Error> {
+        Ok(event::Entity::find()
+            .filter(event::Column::CreatorId.eq(creator_id))
+            .filter(event::Column::ExpiredAt.is_null())
+            .order_by_desc(event::Column::CreatedAt)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Event::from)
+            .collect())
+    }
+
+    async fn query_events(
+        &self,
+        query: EventQuery,
+        range: PageRange,
+    ) -> Result<Page<Event>, Self::Error> {
+        let mut select = event::Entity::find();
+        if let Some(after) = query.created_after {
+            select = select.filter(event::Column::CreatedAt.gt(after.naive_utc()));
+        }
+        if let Some(before) = query.created_before {
+            select = select.filter(event::Column::CreatedAt.lt(before.naive_utc()));
+        }
+        if let Some(name) = query.name_contains {
+            // Escape wildcards so they're matched literally
+            let pattern = format!(
+                "%{}%",
+                name.to_lowercase()
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+            );
+            select = select.filter(
+                Expr::expr(Func::lower(Expr::col(event::Column::Name)))
+                    .like(LikeExpr::new(pattern).escape('\\')),
+            );
+        }
+
+        let total = select.clone().count(&self.db).await? as usize;
+        let mut select = select
+            .order_by_desc(event::Column::CreatedAt)
+            .offset(range.offset as u64);
+        if let Some(limit) = range.limit {
+            select = select.limit(limit as u64);
+        }
+
+        Ok(Page {
+            items: select
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(Event::from)
+                .collect(),
+            total,
+        })
+    }
+
+    async fn peek_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        Ok(event::Entity::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .map(|model| model.into()))
+    }
+
+    async fn get_idempotency_key(
+        &self,
+        key: String,
+    ) -> Result<Option<IdempotencyKey>, Self::Error> {
+        Ok(idempotency_key::Entity::find_by_id(key)
+            .one(&self.db)
+            .await?
+            .map(|model| model.into()))
+    }
+
+    async fn create_idempotency_key(&self, key: IdempotencyKey) -> Result<bool, Self::Error> {
+        // The primary key makes this atomic, whoever inserts first gets the key
+        let inserted = idempotency_key::Entity::insert(idempotency_key::ActiveModel {
+            key: Set(key.key),
+            request_hash: Set(key.request_hash),
+            event_id: Set(key.event_id),
+            created_at: Set(key.created_at.naive_utc()),
+        })
+        .on_conflict(
+            OnConflict::column(idempotency_key::Column::Key)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec(&self.db)
+        .await;
+
+        match inserted {
+            Ok(_) => Ok(true),
+            Err(DbErr::RecordNotInserted) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn set_idempotency_key_event(
+        &self,
+        key: String,
+        event_id: String,
+    ) -> Result<(), Self::Error> {
+        idempotency_key::Entity::update_many()
+            .col_expr(idempotency_key::Column::EventId, Expr::value(event_id))
+            .filter(idempotency_key::Column::Key.eq(key))
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_idempotency_key(&self, key: String) -> Result<(), Self::Error> {
+        idempotency_key::Entity::delete_by_id(key)
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        Ok(idempotency_key::Entity::delete_many()
+            .filter(idempotency_key::Column::CreatedAt.lt(cutoff.naive_utc()))
+            .exec(&self.db)
+            .await?
+            .rows_affected as i64)
+    }
+
+    async fn get_template(&self, id: String) -> Result<Option<Template>, Self::Error> {
+        let existing_template = template::Entity::find_by_id(id).one(&self.db).await?;
+
+        // Mark as used
+        if let Some(template) = existing_template.clone() {
+            let mut template: template::ActiveModel = template.into();
+            template.used_at = Set(Utc::now().naive_utc());
+            template.save(&self.db).await?;
+        }
+
+        Ok(existing_template.map(|model| model.into()))
+    }
+
+    async fn create_template(&self, template: Template) -> Result<Template, Self::Error> {
+        Ok(template::ActiveModel {
+            id: Set(template.id),
+            name: Set(template.name),
+            times: Set(serde_json::to_value(template.times).unwrap_or(json!([]))),
+            timezone: Set(template.timezone),
+            edit_token_hash: Set(template.edit_token_hash),
+            created_at: Set(template.created_at.naive_utc()),
+            used_at: Set(template.used_at.naive_utc()),
+        }
+        .insert(&self.db)
+        .await?
+        .into())
+    }
+
+    async fn delete_templates(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        Ok(template::Entity::delete_many()
+            .filter(template::Column::UsedAt.lt(cutoff.naive_utc()))
+            .exec(&self.db)
+            .await?
+            .rows_affected as i64)
+    }
+
+    async fn expire_events(&self, default_retention_days: i64) -> Result<i64, Self::Error> {
+        let now = Utc::now();
+
+        // Events using the default retention can be expired in one go
+        let default_result = event::Entity::update_many()
+            .col_expr(event::Column::ExpiredAt, Expr::value(now.naive_utc()))
+            .filter(event::Column::ExpiredAt.is_null())
+            .filter(event::Column::RetentionDays.is_null())
+            .filter(
+                event::Column::VisitedAt
+                    .lt((now - Duration::days(default_retention_days)).naive_utc()),
+            )
+            .exec(&self.db)
+            .await?;
+
+        // Others have their own retention, so check them in memory
+        let stale_ids: Vec<String> = event::Entity::find()
+            .filter(event::Column::ExpiredAt.is_null())
+            .filter(event::Column::RetentionDays.is_not_null())
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Event::from)
+            .filter(|e| e.is_stale(default_retention_days, now))
+            .map(|e| e.id)
+            .collect();
+        let custom_count = match stale_ids.is_empty() {
+            true => 0,
+            false => {
+                event::Entity::update_many()
+                    .col_expr(event::Column::ExpiredAt, Expr::value(now.naive_utc()))
+                    .filter(event::Column::Id.is_in(stale_ids))
+                    .exec(&self.db)
+                    .await?
+                    .rows_affected
+            }
+        };
+
+        Ok((default_result.rows_affected + custom_count) as i64)
+    }
+
+    async fn restore_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        let Some(existing_event) = event::Entity::find_by_id(id).one(&self.db).await? else {
+            return Ok(None);
+        };
+
+        let mut model: event::ActiveModel = existing_event.into();
+        model.expired_at = Set(None);
+        model.visited_at = Set(Utc::now().naive_utc());
+
+        Ok(Some(model.update(&self.db).await?.into()))
+    }
+
     async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error> {
         let (event_count, person_count) = self
             .db
             .transaction::<_, (i64, i64), DbErr>(|t| {
                 Box::pin(async move {
-                    // Get events older than the cutoff date
+                    // Get events that expired before the cutoff date
                     let old_events = event::Entity::find()
-                        .filter(event::Column::VisitedAt.lt(cutoff.naive_utc()))
+                        .filter(event::Column::ExpiredAt.lt(cutoff.naive_utc()))
                         .all(t)
                         .await?;
 
-                    // Delete people
+                    // Delete people, comments and activity
                     let mut people_deleted: i64 = 0;
                     // TODO: run concurrently
                     for e in old_events.iter() {
@@ -146,11 +887,23 @@ impl Adaptor for SqlAdaptor {
                             .exec(t)
                             .await?;
                         people_deleted += people_delete_result.rows_affected as i64;
+                        comment::Entity::delete_many()
+                            .filter(comment::Column::EventId.eq(&e.id))
+                            .exec(t)
+                            .await?;
+                        activity::Entity::delete_many()
+                            .filter(activity::Column::EventId.eq(&e.id))
+                            .exec(t)
+                            .await?;
+                        event_views::Entity::delete_many()
+                            .filter(event_views::Column::EventId.eq(&e.id))
+                            .exec(t)
+                            .await?;
                     }
 
                     // Delete events
                     let event_delete_result = event::Entity::delete_many()
-                        .filter(event::Column::VisitedAt.lt(cutoff.naive_utc()))
+                        .filter(event::Column::ExpiredAt.lt(cutoff.naive_utc()))
                         .exec(t)
                         .await?;
 
@@ -164,6 +917,102 @@ impl Adaptor for SqlAdaptor {
             person_count,
         })
     }
+
+    async fn preview_cleanup(
+        &self,
+        default_retention_days: i64,
+        cutoff: DateTime<Utc>,
+    ) -> Result<CleanupPreview, Self::Error> {
+        let now = Utc::now();
+
+        // Matches `expire_events`, but both kinds of retention are checked in memory
+        let stale_events: Vec<Event> = event::Entity::find()
+            .filter(event::Column::ExpiredAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(event::Column::RetentionDays.is_not_null())
+                    .add(
+                        event::Column::VisitedAt
+                            .lt((now - Duration::days(default_retention_days)).naive_utc()),
+                    ),
+            )
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Event::from)
+            .filter(|e| e.is_stale(default_retention_days, now))
+            .collect();
+
+        let expired_events: Vec<Event> = event::Entity::find()
+            .filter(event::Column::ExpiredAt.lt(cutoff.naive_utc()))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Event::from)
+            .collect();
+        let person_count = match expired_events.is_empty() {
+            true => 0,
+            false => {
+                person::Entity::find()
+                    .filter(
+                        person::Column::EventId.is_in(expired_events.iter().map(|e| e.id.clone())),
+                    )
+                    .count(&self.db)
+                    .await? as i64
+            }
+        };
+
+        Ok(CleanupPreview {
+            stale_events,
+            expired_events,
+            person_count,
+        })
+    }
+
+    async fn delete_event(&self, id: String) -> Result<Option<Stats>, Self::Error> {
+        let deleted = self
+            .db
+            .transaction::<_, Option<(i64, i64)>, DbErr>(|t| {
+                Box::pin(async move {
+                    if event::Entity::find_by_id(id.clone())
+                        .one(t)
+                        .await?
+                        .is_none()
+                    {
+                        return Ok(None);
+                    }
+
+                    let people_delete_result = person::Entity::delete_many()
+                        .filter(person::Column::EventId.eq(&id))
+                        .exec(t)
+                        .await?;
+                    comment::Entity::delete_many()
+                        .filter(comment::Column::EventId.eq(&id))
+                        .exec(t)
+                        .await?;
+                    activity::Entity::delete_many()
+                        .filter(activity::Column::EventId.eq(&id))
+                        .exec(t)
+                        .await?;
+                    event_views::Entity::delete_many()
+                        .filter(event_views::Column::EventId.eq(&id))
+                        .exec(t)
+                        .await?;
+                    let event_delete_result = event::Entity::delete_by_id(id).exec(t).await?;
+
+                    Ok(Some((
+                        event_delete_result.rows_affected as i64,
+                        people_delete_result.rows_affected as i64,
+                    )))
+                })
+            })
+            .await?;
+
+        Ok(deleted.map(|(event_count, person_count)| Stats {
+            event_count,
+            person_count,
+        }))
+    }
 }
 
 // Get the current stats as an ActiveModel
@@ -181,12 +1030,27 @@ async fn get_stats_row(db: &DatabaseConnection) -> Result<stats::ActiveModel, Db
 }
 
 impl SqlAdaptor {
+    /// Connect to `DATABASE_URL`, or the SQLite file at `SQLITE_PATH`
     pub async fn new() -> Self {
-        let connection_string =
-            env::var("DATABASE_URL").expect("Expected DATABASE_URL environment variable");
+        let connection_string = match env::var("DATABASE_URL") {
+            Ok(url) => url,
+            Err(_) => Self::sqlite_url(
+                &env::var("SQLITE_PATH")
+                    .expect("Expected DATABASE_URL or SQLITE_PATH environment variable"),
+            ),
+        };
+        Self::connect(&connection_string).await
+    }
 
+    /// Small instances can point straight at a SQLite file instead of a database server,
+    /// which is created if it doesn't exist
+    pub fn sqlite_url(path: &str) -> String {
+        format!("sqlite://{}?mode=rwc", path)
+    }
+
+    pub async fn connect(connection_string: &str) -> Self {
         // Connect to the database
-        let db = Database::connect(&connection_string)
+        let db = Database::connect(connection_string)
             .await
             .expect("Failed to connect to SQL database");
         println!(
@@ -200,11 +1064,18 @@ impl SqlAdaptor {
             connection_string
         );
 
-        // Setup tables
-        Migrator::up(&db, None)
+        // Write-ahead logging lets reads continue while a write is in progress,
+        // and is persisted in the database file once set
+        if db.get_database_backend() == DatabaseBackend::Sqlite {
+            db.execute(Statement::from_string(
+                DatabaseBackend::Sqlite,
+                "PRAGMA journal_mode=WAL;".to_owned(),
+            ))
             .await
-            .expect("Failed to set up tables in the database");
+            .expect("Failed to enable WAL mode for SQLite database");
+        }
 
+        // Tables are set up by `migrate`, which the API runs on startup unless told not to
         Self { db }
     }
 }
@@ -218,6 +1089,154 @@ impl From<event::Model> for Event {
             visited_at: DateTime::<Utc>::from_utc(value.visited_at, Utc),
             times: serde_json::from_value(value.times).unwrap_or(vec![]),
             timezone: value.timezone,
+            edit_token_hash: value.edit_token_hash,
+            scoring: value.scoring,
+            listed: value.listed,
+            tags: value
+                .tags
+                .and_then(|tags| serde_json::from_value(tags).ok())
+                .unwrap_or(vec![]),
+            finalized_time: value.finalized_time,
+            password_hash: value.password_hash,
+            invitees: value
+                .invitees
+                .and_then(|invitees| serde_json::from_value(invitees).ok())
+                .unwrap_or(vec![]),
+            responses_closed: value.responses_closed,
+            webhook_url: value.webhook_url,
+            webhook_secret: value.webhook_secret,
+            updated_at: DateTime::<Utc>::from_utc(
+                value.updated_at.unwrap_or(value.created_at),
+                Utc,
+            ),
+            removed_people: value
+                .removed_people
+                .and_then(|removed| serde_json::from_value::<Vec<RemovedPersonJson>>(removed).ok())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|removed| {
+                    Some(RemovedPerson {
+                        name: removed.name,
+                        removed_at: Utc.timestamp_millis_opt(removed.removed_at).single()?,
+                    })
+                })
+                .collect(),
+            retention_days: value.retention_days,
+            expired_at: value
+                .expired_at
+                .map(|expired_at| DateTime::<Utc>::from_utc(expired_at, Utc)),
+            group_id: value.group_id,
+            creator_id: value.creator_id,
+            max_people: value.max_people,
+            chat_webhook_url: value.chat_webhook_url,
+        }
+    }
+}
+
+// How removed people are stored in the event's json column
+#[derive(Serialize, Deserialize)]
+struct RemovedPersonJson {
+    name: String,
+    removed_at: i64,
+}
+
+fn person_model(event_id: String, person: Person) -> person::ActiveModel {
+    person::ActiveModel {
+        name: Set(person.name),
+        password_hash: Set(person.password_hash),
+        created_at: Set(person.created_at.naive_utc()),
+        availability: Set(serde_json::to_value(person.availability).unwrap_or(json!([]))),
+        event_id: Set(event_id),
+        updated_at: Set(Some(person.updated_at.naive_utc())),
+        edit_token_hash: Set(person.edit_token_hash),
+        edit_token_expires_at: Set(person.edit_token_expires_at.map(|t| t.naive_utc())),
+        if_needed: Set(Some(
+            serde_json::to_value(person.if_needed).unwrap_or(json!([])),
+        )),
+        undecided: Set(Some(
+            serde_json::to_value(person.undecided).unwrap_or(json!([])),
+        )),
+        subject_id: Set(person.subject_id),
+        avatar_color: Set(person.avatar_color),
+        avatar_emoji: Set(person.avatar_emoji),
+        timezone: Set(person.timezone),
+        google_refresh_token: Set(person.google_refresh_token),
+    }
+}
+
+fn removed_people_to_json(removed_people: Vec<RemovedPerson>) -> serde_json::Value {
+    serde_json::to_value(
+        removed_people
+            .into_iter()
+            .map(|removed| RemovedPersonJson {
+                name: removed.name,
+                removed_at: removed.removed_at.timestamp_millis(),
+            })
+            .collect::<Vec<_>>(),
+    )
+    .unwrap_or(json!([]))
+}
+
+impl From<event_views::Model> for EventViews {
+    fn from(value: event_views::Model) -> Self {
+        Self {
+            view_count: value.view_count,
+            visitor_registers: serde_json::from_value(value.visitor_registers).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<daily_stats::Model> for DailyStats {
+    fn from(value: daily_stats::Model) -> Self {
+        Self {
+            date: value.date,
+            event_count: value.event_count,
+            person_count: value.person_count,
+        }
+    }
+}
+
+impl From<idempotency_key::Model> for IdempotencyKey {
+    fn from(value: idempotency_key::Model) -> Self {
+        Self {
+            key: value.key,
+            request_hash: value.request_hash,
+            event_id: value.event_id,
+            created_at: DateTime::<Utc>::from_utc(value.created_at, Utc),
+        }
+    }
+}
+
+impl From<template::Model> for Template {
+    fn from(value: template::Model) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            times: serde_json::from_value(value.times).unwrap_or(vec![]),
+            timezone: value.timezone,
+            edit_token_hash: value.edit_token_hash,
+            created_at: DateTime::<Utc>::from_utc(value.created_at, Utc),
+            used_at: DateTime::<Utc>::from_utc(value.used_at, Utc),
+        }
+    }
+}
+
+impl From<activity::Model> for Activity {
+    fn from(value: activity::Model) -> Self {
+        Self {
+            kind: value.kind,
+            person: value.person,
+            created_at: DateTime::<Utc>::from_utc(value.created_at, Utc),
+        }
+    }
+}
+
+impl From<comment::Model> for Comment {
+    fn from(value: comment::Model) -> Self {
+        Self {
+            author: value.author,
+            body: value.body,
+            created_at: DateTime::<Utc>::from_utc(value.created_at, Utc),
         }
     }
 }
@@ -228,7 +1247,28 @@ impl From<person::Model> for Person {
             name: value.name,
             password_hash: value.password_hash,
             created_at: DateTime::<Utc>::from_utc(value.created_at, Utc),
+            updated_at: DateTime::<Utc>::from_utc(
+                value.updated_at.unwrap_or(value.created_at),
+                Utc,
+            ),
             availability: serde_json::from_value(value.availability).unwrap_or(vec![]),
+            if_needed: value
+                .if_needed
+                .and_then(|times| serde_json::from_value(times).ok())
+                .unwrap_or(vec![]),
+            undecided: value
+                .undecided
+                .and_then(|times| serde_json::from_value(times).ok())
+                .unwrap_or(vec![]),
+            edit_token_hash: value.edit_token_hash,
+            edit_token_expires_at: value
+                .edit_token_expires_at
+                .map(|t| DateTime::<Utc>::from_utc(t, Utc)),
+            subject_id: value.subject_id,
+            avatar_color: value.avatar_color,
+            avatar_emoji: value.avatar_emoji,
+            timezone: value.timezone,
+            google_refresh_token: value.google_refresh_token,
         }
     }
 }
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Activity::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Activity::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Activity::EventId).string().not_null())
+                    .col(ColumnDef::new(Activity::Kind).string().not_null())
+                    .col(ColumnDef::new(Activity::Person).string())
+                    .col(ColumnDef::new(Activity::CreatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_activity_event")
+                            .from(Activity::Table, Activity::EventId)
+                            .to(Event::Table, Event::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Activity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Activity {
+    Table,
+    Id,
+    EventId,
+    Kind,
+    Person,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Event {
+    Table,
+    Id,
+}
@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Template::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Template::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Template::Name).string().not_null())
+                    .col(ColumnDef::new(Template::Times).json().not_null())
+                    .col(ColumnDef::new(Template::Timezone).string().not_null())
+                    .col(ColumnDef::new(Template::EditTokenHash).string())
+                    .col(ColumnDef::new(Template::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(Template::UsedAt).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Template::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Template {
+    Table,
+    Id,
+    Name,
+    Times,
+    Timezone,
+    EditTokenHash,
+    CreatedAt,
+    UsedAt,
+}
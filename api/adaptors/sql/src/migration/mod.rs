@@ -1,12 +1,70 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m01_setup_tables;
+mod m02_person_updated_at;
+mod m03_event_edit_token;
+mod m04_event_scoring;
+mod m05_person_edit_token;
+mod m06_event_directory;
+mod m07_event_finalized_time;
+mod m08_event_password;
+mod m09_person_if_needed;
+mod m10_comments;
+mod m11_event_invitees;
+mod m12_event_webhook;
+mod m13_event_sync;
+mod m14_person_undecided;
+mod m15_idempotency_keys;
+mod m16_activity;
+mod m17_event_retention;
+mod m18_daily_stats;
+mod m19_event_views;
+mod m20_templates;
+mod m21_event_group;
+mod m22_event_creator;
+mod m23_person_subject;
+mod m24_person_avatar;
+mod m25_person_timezone;
+mod m26_event_max_people;
+mod m27_event_chat_webhook;
+mod m28_person_google;
+mod m29_idempotency_key_scope;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m01_setup_tables::Migration)]
+        vec![
+            Box::new(m01_setup_tables::Migration),
+            Box::new(m02_person_updated_at::Migration),
+            Box::new(m03_event_edit_token::Migration),
+            Box::new(m04_event_scoring::Migration),
+            Box::new(m05_person_edit_token::Migration),
+            Box::new(m06_event_directory::Migration),
+            Box::new(m07_event_finalized_time::Migration),
+            Box::new(m08_event_password::Migration),
+            Box::new(m09_person_if_needed::Migration),
+            Box::new(m10_comments::Migration),
+            Box::new(m11_event_invitees::Migration),
+            Box::new(m12_event_webhook::Migration),
+            Box::new(m13_event_sync::Migration),
+            Box::new(m14_person_undecided::Migration),
+            Box::new(m15_idempotency_keys::Migration),
+            Box::new(m16_activity::Migration),
+            Box::new(m17_event_retention::Migration),
+            Box::new(m18_daily_stats::Migration),
+            Box::new(m19_event_views::Migration),
+            Box::new(m20_templates::Migration),
+            Box::new(m21_event_group::Migration),
+            Box::new(m22_event_creator::Migration),
+            Box::new(m23_person_subject::Migration),
+            Box::new(m24_person_avatar::Migration),
+            Box::new(m25_person_timezone::Migration),
+            Box::new(m26_event_max_people::Migration),
+            Box::new(m27_event_chat_webhook::Migration),
+            Box::new(m28_person_google::Migration),
+            Box::new(m29_idempotency_key_scope::Migration),
+        ]
     }
 }
@@ -0,0 +1,9 @@
+fn main() {
+    // The gRPC service is generated from its protobuf definitions, using a bundled `protoc`
+    // so building doesn't need one installed
+    #[cfg(feature = "grpc")]
+    {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+        tonic_build::compile_protos("proto/jellifit.proto").unwrap();
+    }
+}
@@ -0,0 +1,474 @@
+//! Checks that an adaptor behaves the way the API expects, for adaptor authors to run against
+//! their implementation. Turn on the `conformance` feature and call [`run`] from a test with
+//! an adaptor connected to a store that's only used for testing, as the cleanup checks expire
+//! and delete any stale events in it.
+//!
+//! Each check panics with what went wrong, and uses its own event IDs so they can share a store.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::{Duration, Utc};
+
+use crate::{Adaptor, Event, IdempotencyKey, Person, PersonInsert, PersonUpdate};
+
+/// Run every check against an adaptor
+pub async fn run<A: Adaptor>(adaptor: &A) {
+    check_events(adaptor).await;
+    check_people(adaptor).await;
+    check_max_people(adaptor).await;
+    check_update_person(adaptor).await;
+    check_idempotency_keys(adaptor).await;
+    check_stats(adaptor).await;
+    check_cleanup(adaptor).await;
+}
+
+/// An event that's just been created, with no optional details set
+pub fn event(id: &str) -> Event {
+    let now = Utc::now();
+    Event {
+        id: id.to_owned(),
+        name: "Conformance test".to_owned(),
+        created_at: now,
+        visited_at: now,
+        updated_at: now,
+        times: vec!["0900-01022023".to_owned(), "0915-01022023".to_owned()],
+        timezone: "UTC".to_owned(),
+        edit_token_hash: None,
+        scoring: None,
+        listed: false,
+        tags: Vec::new(),
+        finalized_time: None,
+        password_hash: None,
+        invitees: Vec::new(),
+        responses_closed: false,
+        webhook_url: None,
+        webhook_secret: None,
+        removed_people: Vec::new(),
+        retention_days: None,
+        expired_at: None,
+        group_id: None,
+        creator_id: None,
+        max_people: None,
+        chat_webhook_url: None,
+    }
+}
+
+/// Someone who's just joined an event, available at its first time
+pub fn person(name: &str) -> Person {
+    let now = Utc::now();
+    Person {
+        name: name.to_owned(),
+        password_hash: None,
+        created_at: now,
+        updated_at: now,
+        availability: vec!["0900-01022023".to_owned()],
+        if_needed: Vec::new(),
+        undecided: Vec::new(),
+        edit_token_hash: None,
+        edit_token_expires_at: None,
+        subject_id: None,
+        avatar_color: None,
+        avatar_emoji: None,
+        timezone: None,
+        google_refresh_token: None,
+    }
+}
+
+/// An ID no other check or run has used
+pub fn unique_id(prefix: &str) -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "{}-{}-{}",
+        prefix,
+        Utc::now().timestamp_micros(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Events can be created, fetched, updated and deleted
+pub async fn check_events<A: Adaptor>(adaptor: &A) {
+    let id = unique_id("conformance-event");
+    assert!(
+        adaptor.get_event(id.clone()).await.unwrap().is_none(),
+        "get_event should return None for an event that doesn't exist"
+    );
+
+    adaptor.create_event(event(&id)).await.unwrap();
+    let created = adaptor
+        .get_event(id.clone())
+        .await
+        .unwrap()
+        .expect("get_event should return a created event");
+    assert_eq!(created.name, "Conformance test");
+    assert_eq!(created.times.len(), 2, "the event's times should be stored");
+
+    let updated = adaptor
+        .update_event(Event {
+            name: "Renamed".to_owned(),
+            tags: vec!["tag".to_owned()],
+            max_people: Some(10),
+            ..created
+        })
+        .await
+        .unwrap()
+        .expect("update_event should return the updated event");
+    assert_eq!(updated.name, "Renamed");
+    let peeked = adaptor
+        .peek_event(id.clone())
+        .await
+        .unwrap()
+        .expect("peek_event should return an existing event");
+    assert_eq!(
+        peeked.name, "Renamed",
+        "update_event should store the changes"
+    );
+    assert_eq!(peeked.tags, vec!["tag".to_owned()]);
+    assert_eq!(peeked.max_people, Some(10));
+    assert!(
+        adaptor
+            .update_event(event(&unique_id("conformance-missing")))
+            .await
+            .unwrap()
+            .is_none(),
+        "update_event should return None for an event that doesn't exist"
+    );
+
+    let deleted = adaptor
+        .delete_event(id.clone())
+        .await
+        .unwrap()
+        .expect("delete_event should return what it deleted");
+    assert_eq!(deleted.event_count, 1);
+    assert!(
+        adaptor.peek_event(id).await.unwrap().is_none(),
+        "a deleted event shouldn't be returned"
+    );
+}
+
+/// People can be added to an event, updated and removed
+pub async fn check_people<A: Adaptor>(adaptor: &A) {
+    let id = unique_id("conformance-people");
+    assert!(
+        adaptor
+            .upsert_person(id.clone(), person("Ana"))
+            .await
+            .unwrap()
+            .is_none(),
+        "upsert_person should return None for an event that doesn't exist"
+    );
+
+    adaptor.create_event(event(&id)).await.unwrap();
+    adaptor
+        .upsert_person(id.clone(), person("Ana"))
+        .await
+        .unwrap()
+        .expect("upsert_person should add someone to an event");
+    adaptor
+        .upsert_person(id.clone(), person("Ben"))
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person(
+            id.clone(),
+            Person {
+                availability: vec!["0915-01022023".to_owned()],
+                ..person("Ana")
+            },
+        )
+        .await
+        .unwrap();
+
+    let people = adaptor
+        .get_people(id.clone())
+        .await
+        .unwrap()
+        .expect("get_people should return an existing event's people");
+    assert_eq!(
+        people.len(),
+        2,
+        "upserting someone again shouldn't add them twice"
+    );
+    let ana = people.iter().find(|p| p.name == "Ana").unwrap();
+    assert_eq!(
+        ana.availability,
+        vec!["0915-01022023".to_owned()],
+        "upserting someone again should update them"
+    );
+
+    adaptor
+        .delete_person(id.clone(), "Ana".to_owned())
+        .await
+        .unwrap()
+        .expect("delete_person should return who it deleted");
+    let people = adaptor.get_people(id.clone()).await.unwrap().unwrap();
+    assert_eq!(people.len(), 1);
+
+    let deleted = adaptor.delete_event(id).await.unwrap().unwrap();
+    assert_eq!(
+        deleted.person_count, 1,
+        "delete_event should delete the event's people"
+    );
+}
+
+/// Nobody new can be inserted once an event has its `max_people`, but people already on it
+/// can still be updated
+pub async fn check_max_people<A: Adaptor>(adaptor: &A) {
+    let id = unique_id("conformance-max-people");
+    adaptor
+        .create_event(Event {
+            max_people: Some(1),
+            ..event(&id)
+        })
+        .await
+        .unwrap();
+
+    let first = adaptor
+        .insert_person(id.clone(), person("Ana"))
+        .await
+        .unwrap();
+    assert!(
+        matches!(first, Some(PersonInsert::Inserted(_))),
+        "insert_person should add someone to an event with room"
+    );
+    let second = adaptor
+        .insert_person(id.clone(), person("Ben"))
+        .await
+        .unwrap();
+    assert!(
+        matches!(second, Some(PersonInsert::Full)),
+        "insert_person should refuse someone new once the event is full"
+    );
+    let again = adaptor
+        .insert_person(id.clone(), person("Ana"))
+        .await
+        .unwrap();
+    assert!(
+        matches!(again, Some(PersonInsert::Inserted(_))),
+        "insert_person should update someone already on a full event"
+    );
+
+    adaptor.delete_event(id).await.unwrap();
+}
+
+/// People are only updated if they haven't changed since the version the update was made from
+pub async fn check_update_person<A: Adaptor>(adaptor: &A) {
+    let id = unique_id("conformance-update-person");
+    adaptor.create_event(event(&id)).await.unwrap();
+    let ana = adaptor
+        .upsert_person(id.clone(), person("Ana"))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let first = adaptor
+        .update_person(
+            id.clone(),
+            Person {
+                updated_at: ana.updated_at + Duration::seconds(1),
+                ..ana.clone()
+            },
+            ana.updated_at,
+        )
+        .await
+        .unwrap();
+    assert!(
+        matches!(first, Some(PersonUpdate::Updated(_))),
+        "update_person should update someone at the expected version"
+    );
+    let second = adaptor
+        .update_person(
+            id.clone(),
+            Person {
+                availability: Vec::new(),
+                ..ana.clone()
+            },
+            ana.updated_at,
+        )
+        .await
+        .unwrap();
+    assert!(
+        matches!(second, Some(PersonUpdate::Changed)),
+        "update_person should refuse an update made from an old version"
+    );
+    let people = adaptor.get_people(id.clone()).await.unwrap().unwrap();
+    assert_eq!(people[0].availability, ana.availability);
+
+    let missing = adaptor
+        .update_person(id.clone(), person("Ben"), Utc::now())
+        .await
+        .unwrap();
+    assert!(
+        matches!(missing, Some(PersonUpdate::Changed)),
+        "update_person should refuse someone who isn't on the event"
+    );
+
+    adaptor.delete_event(id).await.unwrap();
+}
+
+/// Idempotency keys can only be reserved once, until they're released
+pub async fn check_idempotency_keys<A: Adaptor>(adaptor: &A) {
+    let key = IdempotencyKey {
+        key: unique_id("conformance-idempotency-key"),
+        request_hash: "request".to_owned(),
+        event_id: None,
+        created_at: Utc::now(),
+    };
+
+    assert!(adaptor.create_idempotency_key(key.clone()).await.unwrap());
+    assert!(
+        !adaptor.create_idempotency_key(key.clone()).await.unwrap(),
+        "create_idempotency_key should refuse a key that's already reserved"
+    );
+
+    adaptor
+        .set_idempotency_key_event(key.key.clone(), "conformance-event".to_owned())
+        .await
+        .unwrap();
+    let stored = adaptor
+        .get_idempotency_key(key.key.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.event_id.as_deref(), Some("conformance-event"));
+    assert_eq!(stored.request_hash, "request");
+
+    adaptor
+        .delete_idempotency_key(key.key.clone())
+        .await
+        .unwrap();
+    assert!(adaptor
+        .get_idempotency_key(key.key.clone())
+        .await
+        .unwrap()
+        .is_none());
+    assert!(adaptor.create_idempotency_key(key.clone()).await.unwrap());
+    adaptor.delete_idempotency_key(key.key).await.unwrap();
+}
+
+/// Stats count up, from wherever they are in a shared store
+pub async fn check_stats<A: Adaptor>(adaptor: &A) {
+    let before = adaptor.get_stats().await.unwrap();
+    let event_count = adaptor.increment_stat_event_count().await.unwrap();
+    let person_count = adaptor.increment_stat_person_count().await.unwrap();
+    assert!(event_count > before.event_count);
+    assert!(person_count > before.person_count);
+
+    let after = adaptor.get_stats().await.unwrap();
+    assert!(
+        after.event_count >= event_count && after.person_count >= person_count,
+        "get_stats should include increments"
+    );
+}
+
+/// Stale events are expired, can be restored until they're deleted, and are deleted along
+/// with their people once they've been expired long enough
+pub async fn check_cleanup<A: Adaptor>(adaptor: &A) {
+    // Long enough that nothing but these events is stale
+    let default_retention_days = 36_500;
+    let stale_id = unique_id("conformance-stale");
+    let fresh_id = unique_id("conformance-fresh");
+    adaptor
+        .create_event(Event {
+            visited_at: Utc::now() - Duration::days(10),
+            retention_days: Some(1),
+            ..event(&stale_id)
+        })
+        .await
+        .unwrap();
+    adaptor
+        .create_event(Event {
+            retention_days: Some(1),
+            ..event(&fresh_id)
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person(stale_id.clone(), person("Ana"))
+        .await
+        .unwrap();
+
+    let preview = adaptor
+        .preview_cleanup(default_retention_days, Utc::now())
+        .await
+        .unwrap();
+    assert!(
+        preview.stale_events.iter().any(|e| e.id == stale_id),
+        "preview_cleanup should include events that haven't been visited within their retention period"
+    );
+    assert!(
+        !preview.stale_events.iter().any(|e| e.id == fresh_id),
+        "preview_cleanup shouldn't include events that have been visited recently"
+    );
+
+    assert!(adaptor.expire_events(default_retention_days).await.unwrap() >= 1);
+    assert!(
+        adaptor.get_event(stale_id.clone()).await.unwrap().is_none(),
+        "get_event shouldn't return expired events"
+    );
+    assert!(
+        adaptor
+            .peek_event(stale_id.clone())
+            .await
+            .unwrap()
+            .and_then(|e| e.expired_at)
+            .is_some(),
+        "expire_events should set when the event expired"
+    );
+    assert!(adaptor.get_event(fresh_id.clone()).await.unwrap().is_some());
+
+    adaptor
+        .restore_event(stale_id.clone())
+        .await
+        .unwrap()
+        .expect("restore_event should return the restored event");
+    assert!(
+        adaptor.get_event(stale_id.clone()).await.unwrap().is_some(),
+        "a restored event should be returned again"
+    );
+
+    // Expire it again, by making it stale without visiting it
+    let stale = adaptor.peek_event(stale_id.clone()).await.unwrap().unwrap();
+    adaptor
+        .update_event(Event {
+            retention_days: Some(0),
+            ..stale
+        })
+        .await
+        .unwrap();
+    adaptor.expire_events(default_retention_days).await.unwrap();
+
+    let cutoff = Utc::now() + Duration::seconds(1);
+    let preview = adaptor
+        .preview_cleanup(default_retention_days, cutoff)
+        .await
+        .unwrap();
+    assert!(
+        preview.expired_events.iter().any(|e| e.id == stale_id),
+        "preview_cleanup should include events that expired before the cutoff"
+    );
+
+    let deleted = adaptor.delete_events(cutoff).await.unwrap();
+    assert!(deleted.event_count >= 1 && deleted.person_count >= 1);
+    assert!(
+        adaptor
+            .peek_event(stale_id.clone())
+            .await
+            .unwrap()
+            .is_none(),
+        "delete_events should delete events that expired before the cutoff"
+    );
+    assert!(
+        adaptor.get_people(stale_id).await.unwrap().is_none(),
+        "delete_events should delete the event's people"
+    );
+    assert!(
+        adaptor
+            .peek_event(fresh_id.clone())
+            .await
+            .unwrap()
+            .is_some(),
+        "delete_events shouldn't delete events that haven't expired"
+    );
+
+    adaptor.delete_event(fresh_id).await.unwrap();
+}
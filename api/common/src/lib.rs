@@ -1,7 +1,10 @@
 use std::error::Error;
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+
+#[cfg(feature = "conformance")]
+pub mod conformance;
 
 /// Data storage adaptor, all methods on an adaptor can return an error if
 /// something goes wrong, or potentially None if the data requested was not found.
@@ -9,24 +12,247 @@ use chrono::{DateTime, Utc};
 pub trait Adaptor: Send + Sync {
     type Error: Error;
 
+    /// Check the storage backend can be reached. By default this fetches the stats,
+    /// adaptors with a cheaper way to check should override it.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.get_stats().await.map(|_| ())
+    }
+
+    /// Flush anything buffered and release connections before the server exits. Adaptors
+    /// that don't hold on to anything can rely on the default, which does nothing.
+    async fn close(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Get which version of the schema the stored data is at, and which this build of the
+    /// adaptor needs. Adaptors without a schema to migrate can rely on the default, which is
+    /// always up to date.
+    async fn get_schema_version(&self) -> Result<SchemaVersion, Self::Error> {
+        Ok(SchemaVersion {
+            current: 0,
+            expected: 0,
+        })
+    }
+
+    /// Run any migrations needed to bring the stored data up to the expected schema version,
+    /// returning the version it's at afterwards. Adaptors without a schema can rely on the
+    /// default, which does nothing.
+    async fn migrate(&self) -> Result<SchemaVersion, Self::Error> {
+        self.get_schema_version().await
+    }
+
     async fn get_stats(&self) -> Result<Stats, Self::Error>;
     async fn increment_stat_event_count(&self) -> Result<i64, Self::Error>;
     async fn increment_stat_person_count(&self) -> Result<i64, Self::Error>;
+    /// Add to both stat counts at once, used to write increments that were buffered
+    /// in memory. Returns the new totals.
+    async fn add_stats(&self, stats: Stats) -> Result<Stats, Self::Error>;
+    /// Add to the counts of events and people created on a day
+    async fn add_daily_stats(&self, date: NaiveDate, stats: Stats) -> Result<(), Self::Error>;
+    /// Get the counts of events and people created each day since a date, oldest first
+    /// Days nothing was created on may be left out
+    async fn get_daily_stats(&self, since: NaiveDate) -> Result<Vec<DailyStats>, Self::Error>;
 
     async fn get_people(&self, event_id: String) -> Result<Option<Vec<Person>>, Self::Error>;
+    /// Get the people for an event, filtered and sorted. By default this fetches
+    /// every person and applies the query in memory, adaptors that can filter in
+    /// the database should override it.
+    async fn query_people(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+    ) -> Result<Option<Vec<Person>>, Self::Error> {
+        Ok(self
+            .get_people(event_id)
+            .await?
+            .map(|people| query.apply(people)))
+    }
+    /// Get one page of the people matched by a query, along with how many matched in
+    /// total. By default this slices the result of `query_people`, adaptors that can
+    /// paginate in the database should override it.
+    async fn query_people_page(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+        range: PageRange,
+    ) -> Result<Option<Page<Person>>, Self::Error> {
+        Ok(self
+            .query_people(event_id, query)
+            .await?
+            .map(|people| range.apply(people)))
+    }
+    /// Get how many people are on an event and when they last changed, to tell whether
+    /// a copy of them is still up to date without fetching them all. By default this
+    /// fetches every person, adaptors that can aggregate in the database should override it.
+    /// Returns None if the event doesn't exist
+    async fn get_people_version(
+        &self,
+        event_id: String,
+    ) -> Result<Option<PeopleVersion>, Self::Error> {
+        Ok(self
+            .get_people(event_id)
+            .await?
+            .map(|people| PeopleVersion {
+                count: people.len(),
+                updated_at: people.iter().map(|p| p.updated_at).max(),
+            }))
+    }
     async fn upsert_person(
         &self,
         event_id: String,
         person: Person,
     ) -> Result<Option<Person>, Self::Error>;
+    /// Add someone new to an event, unless it already has its `max_people`. The people are
+    /// counted and the person added in one step, so people joining at the same time can't
+    /// take the event over its limit. Someone already on the event is updated instead.
+    /// Returns None if the event doesn't exist
+    async fn insert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<PersonInsert>, Self::Error>;
+    /// Update someone already on an event, as long as they haven't changed since
+    /// `expected_updated_at`, compared to the millisecond as that's the version clients are
+    /// given. Checking and writing happen in one step, so two updates made from the same
+    /// version can't both succeed.
+    /// Returns None if the event doesn't exist
+    async fn update_person(
+        &self,
+        event_id: String,
+        person: Person,
+        expected_updated_at: DateTime<Utc>,
+    ) -> Result<Option<PersonUpdate>, Self::Error>;
+    /// Delete a person from an event
+    /// Returns the deleted person, or None if the event or person doesn't exist
+    async fn delete_person(
+        &self,
+        event_id: String,
+        person_name: String,
+    ) -> Result<Option<Person>, Self::Error>;
+
+    /// Get the comments left on an event, oldest first
+    /// Returns None if the event doesn't exist
+    async fn get_comments(&self, event_id: String) -> Result<Option<Vec<Comment>>, Self::Error>;
+    /// Add a comment to an event
+    /// Returns None if the event doesn't exist
+    async fn create_comment(
+        &self,
+        event_id: String,
+        comment: Comment,
+    ) -> Result<Option<Comment>, Self::Error>;
+
+    /// Get the log of changes made to an event, newest first
+    /// Returns None if the event doesn't exist
+    async fn get_activity(&self, event_id: String) -> Result<Option<Vec<Activity>>, Self::Error>;
+    /// Add a change to an event's log
+    /// Returns None if the event doesn't exist
+    async fn create_activity(
+        &self,
+        event_id: String,
+        activity: Activity,
+    ) -> Result<Option<Activity>, Self::Error>;
+
+    /// Get how often an event has been viewed, empty if it hasn't been yet
+    /// Returns None if the event doesn't exist
+    async fn get_event_views(&self, event_id: String) -> Result<Option<EventViews>, Self::Error>;
+    /// Count a view of an event, and raise one register of its visitor sketch to `rank`
+    /// if it's lower, adding registers as needed
+    /// Returns None if the event doesn't exist
+    async fn record_event_view(
+        &self,
+        event_id: String,
+        register: usize,
+        rank: u8,
+    ) -> Result<Option<EventViews>, Self::Error>;
 
     /// Get an event and update visited date to current time
+    /// Expired events aren't returned, so they can't be visited until they're restored
     async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error>;
+    /// Get several events at once and update their visited dates, in any order, leaving out
+    /// any that don't exist or are expired. By default this gets each event in turn, adaptors
+    /// that can fetch them in one query should override it.
+    async fn get_events(&self, ids: Vec<String>) -> Result<Vec<Event>, Self::Error> {
+        let mut events = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(event) = self.get_event(id).await? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
     async fn create_event(&self, event: Event) -> Result<Event, Self::Error>;
+    /// Replace the details of an existing event and set its updated date to the current
+    /// time, without changing its visited date
+    /// Returns None if the event doesn't exist
+    async fn update_event(&self, event: Event) -> Result<Option<Event>, Self::Error>;
+    /// Get events that are listed in the public directory, optionally only those with a tag
+    /// Expired events aren't included
+    async fn get_listed_events(&self, tag: Option<String>) -> Result<Vec<Event>, Self::Error>;
+    /// Get the events in a group, oldest first, without updating their visited dates.
+    /// Expired events aren't included.
+    async fn get_group_events(&self, group_id: String) -> Result<Vec<Event>, Self::Error>;
+    /// Get the events created with a creator token, newest first, without updating their
+    /// visited dates. Expired events aren't included.
+    async fn get_creator_events(&self, creator_id: String) -> Result<Vec<Event>, Self::Error>;
+    /// Get one page of the events matched by a query, newest first, along with how many
+    /// matched in total. Expired events are included, and visited dates aren't updated.
+    async fn query_events(
+        &self,
+        query: EventQuery,
+        range: PageRange,
+    ) -> Result<Page<Event>, Self::Error>;
+    /// Get an event without updating its visited date, even if it's expired
+    /// Returns None if the event doesn't exist
+    async fn peek_event(&self, id: String) -> Result<Option<Event>, Self::Error>;
 
-    /// Delete events older than a cutoff date, as well as any associated people
+    /// Get the record of an event created with an idempotency key
+    async fn get_idempotency_key(&self, key: String)
+        -> Result<Option<IdempotencyKey>, Self::Error>;
+    /// Reserve an idempotency key before its event is created, atomically so concurrent
+    /// retries can't both create one. Returns false if the key is already taken.
+    async fn create_idempotency_key(&self, key: IdempotencyKey) -> Result<bool, Self::Error>;
+    /// Remember which event was created with a reserved idempotency key
+    async fn set_idempotency_key_event(
+        &self,
+        key: String,
+        event_id: String,
+    ) -> Result<(), Self::Error>;
+    /// Release an idempotency key, if its event couldn't be created or it has expired
+    async fn delete_idempotency_key(&self, key: String) -> Result<(), Self::Error>;
+    /// Delete idempotency keys created before a cutoff date
+    /// Returns the amount of keys deleted
+    async fn delete_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error>;
+
+    /// Get a template and update its used date to the current time
+    /// Returns None if the template doesn't exist
+    async fn get_template(&self, id: String) -> Result<Option<Template>, Self::Error>;
+    async fn create_template(&self, template: Template) -> Result<Template, Self::Error>;
+    /// Delete templates that haven't been used since a cutoff date
+    /// Returns the amount of templates deleted
+    async fn delete_templates(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error>;
+
+    /// Mark events that haven't been visited within their retention period as expired, using
+    /// the default for events that don't have their own
+    /// Returns the amount of events expired
+    async fn expire_events(&self, default_retention_days: i64) -> Result<i64, Self::Error>;
+    /// Undo an event's expiry, as long as it hasn't been deleted yet, and update its visited
+    /// date to the current time
+    /// Returns None if the event doesn't exist
+    async fn restore_event(&self, id: String) -> Result<Option<Event>, Self::Error>;
+    /// Delete events that expired before a cutoff date, as well as any associated people,
+    /// comments, activity and views
     /// Returns the amount of events and people deleted
     async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error>;
+    /// Find the events `expire_events` and `delete_events` would change with the same
+    /// arguments, without changing anything
+    async fn preview_cleanup(
+        &self,
+        default_retention_days: i64,
+        cutoff: DateTime<Utc>,
+    ) -> Result<CleanupPreview, Self::Error>;
+    /// Delete a single event, as well as any associated people, comments, activity and views
+    /// Returns the amount of events and people deleted, or None if the event doesn't exist
+    async fn delete_event(&self, id: String) -> Result<Option<Stats>, Self::Error>;
 }
 
 #[derive(Clone)]
@@ -35,14 +261,134 @@ pub struct Stats {
     pub person_count: i64,
 }
 
+/// How many times an event has been viewed, and a HyperLogLog sketch of who viewed it
+#[derive(Clone, Default)]
+pub struct EventViews {
+    pub view_count: i64,
+    /// The highest rank seen in each register of the sketch
+    pub visitor_registers: Vec<u8>,
+}
+
+impl EventViews {
+    /// Count a view, raising a register of the sketch to `rank` if it's lower
+    pub fn record(&mut self, register: usize, rank: u8) {
+        self.view_count += 1;
+        if self.visitor_registers.len() <= register {
+            self.visitor_registers.resize(register + 1, 0);
+        }
+        self.visitor_registers[register] = self.visitor_registers[register].max(rank);
+    }
+}
+
+#[derive(Clone)]
+pub struct DailyStats {
+    pub date: NaiveDate,
+    pub event_count: i64,
+    pub person_count: i64,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PeopleVersion {
+    pub count: usize,
+    /// When someone on the event last changed, None if nobody has joined
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Clone)]
 pub struct Event {
     pub id: String,
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub visited_at: DateTime<Utc>,
+    /// When the event's details last changed, events from before this was tracked use
+    /// their creation date
+    pub updated_at: DateTime<Utc>,
     pub times: Vec<String>,
     pub timezone: String,
+    /// Hash of the token needed to edit or delete the event, events
+    /// created before edit tokens existed don't have one
+    pub edit_token_hash: Option<String>,
+    /// How to rank the event's times, serialized as JSON
+    pub scoring: Option<String>,
+    /// Whether the event appears in the public directory
+    pub listed: bool,
+    pub tags: Vec<String>,
+    /// The time chosen by the organizer, after which availability can't be changed
+    pub finalized_time: Option<String>,
+    /// Hash of the password needed to view a private event
+    pub password_hash: Option<String>,
+    /// Names of the people expected to respond, once they all have responses are closed
+    pub invitees: Vec<String>,
+    /// Whether people can no longer change their availability
+    pub responses_closed: bool,
+    /// Where to send changes to the event, registered by its creator
+    pub webhook_url: Option<String>,
+    /// Key used to sign webhook payloads, so the receiver can check they came from the API
+    pub webhook_secret: Option<String>,
+    /// People who have been removed from the event, so syncing clients can remove them too
+    pub removed_people: Vec<RemovedPerson>,
+    /// How many days the event is kept after it was last visited, None to use the
+    /// instance's default
+    pub retention_days: Option<i64>,
+    /// When the event expired, it's hidden from then on until it's restored or deleted
+    pub expired_at: Option<DateTime<Utc>>,
+    /// Shared by related events, like each week of a series, so they can be fetched together
+    pub group_id: Option<String>,
+    /// Hash of the creator token the event was created with, so someone's events can be
+    /// listed without storing their token, or the subject ID of who created it if they
+    /// were logged in
+    pub creator_id: Option<String>,
+    /// The most people that can join the event, None for no limit
+    pub max_people: Option<i64>,
+    /// Slack or Discord incoming webhook to post a message to when the event is created,
+    /// someone responds, or a time is chosen
+    pub chat_webhook_url: Option<String>,
+}
+
+impl Event {
+    /// Whether the event has gone unvisited for longer than its retention period
+    pub fn is_stale(&self, default_retention_days: i64, now: DateTime<Utc>) -> bool {
+        let retention_days = self.retention_days.unwrap_or(default_retention_days);
+        self.visited_at < now - chrono::Duration::days(retention_days)
+    }
+}
+
+/// Which version of its schema an adaptor's stored data is at, versions count up from 1 with
+/// each migration
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SchemaVersion {
+    /// The version the stored data has been migrated to
+    pub current: i64,
+    /// The version this build of the adaptor needs
+    pub expected: i64,
+}
+
+/// What a cleanup would do, for checking before running it
+pub struct CleanupPreview {
+    /// Events that haven't been visited within their retention period, so would be expired
+    pub stale_events: Vec<Event>,
+    /// Events that expired before the cutoff, so would be deleted
+    pub expired_events: Vec<Event>,
+    /// How many people are on the events that would be deleted
+    pub person_count: i64,
+}
+
+pub enum PersonInsert {
+    Inserted(Box<Person>),
+    /// The event already has as many people as it allows
+    Full,
+}
+
+pub enum PersonUpdate {
+    Updated(Box<Person>),
+    /// The person was changed or removed since the expected version
+    Changed,
+}
+
+#[derive(Clone)]
+pub struct RemovedPerson {
+    pub name: String,
+    pub removed_at: DateTime<Utc>,
 }
 
 #[derive(Clone)]
@@ -50,5 +396,158 @@ pub struct Person {
     pub name: String,
     pub password_hash: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     pub availability: Vec<String>,
+    /// Times in `availability` the person can only make if needed
+    pub if_needed: Vec<String>,
+    /// Times the person hasn't decided on yet, any time that isn't in this or
+    /// `availability` is one they can't make
+    pub undecided: Vec<String>,
+    /// Hash of a token that lets the person edit their availability without a password
+    pub edit_token_hash: Option<String>,
+    pub edit_token_expires_at: Option<DateTime<Utc>>,
+    /// The stable ID of whoever added the person if they were logged in, which lets them
+    /// edit it without a password from any device
+    pub subject_id: Option<String>,
+    /// Hex color like `#ff8800` the person is shown in, so they look the same everywhere
+    pub avatar_color: Option<String>,
+    /// A single emoji shown next to the person's name
+    pub avatar_emoji: Option<String>,
+    /// The person's own IANA timezone, which can be different to the event's
+    pub timezone: Option<String>,
+    /// OAuth refresh token for reading the person's Google Calendar free/busy, which is never
+    /// sent to clients
+    pub google_refresh_token: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Comment {
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct Activity {
+    /// What changed, stored as the API's name for it
+    pub kind: String,
+    /// Who made the change, None for changes to the event itself
+    pub person: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct IdempotencyKey {
+    /// Hashed along with who sent it, so one client's key can't be used by another
+    pub key: String,
+    /// Hash of the request that reserved the key, so it can't be reused for another event
+    pub request_hash: String,
+    /// None while the event is still being created
+    pub event_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A reusable layout for events that happen every week
+#[derive(Clone)]
+pub struct Template {
+    pub id: String,
+    /// Name for events created from the template, where `{date}` is replaced with the
+    /// first day of the event's week
+    pub name: String,
+    /// Times in the `HHmm-d` days of the week format
+    pub times: Vec<String>,
+    pub timezone: String,
+    pub edit_token_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// When an event was last created from the template
+    pub used_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Default)]
+pub struct EventQuery {
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only include events with this in their name, ignoring case
+    pub name_contains: Option<String>,
+}
+
+impl EventQuery {
+    /// Whether an event matches the query, for filtering in memory
+    pub fn matches(&self, event: &Event) -> bool {
+        self.created_after
+            .is_none_or(|after| event.created_at > after)
+            && self
+                .created_before
+                .is_none_or(|before| event.created_at < before)
+            && self
+                .name_contains
+                .as_ref()
+                .is_none_or(|name| event.name.to_lowercase().contains(&name.to_lowercase()))
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PeopleQuery {
+    pub sort: Option<PeopleSort>,
+    pub descending: bool,
+    /// Only include people who have (or haven't) filled in their availability
+    pub responded: Option<bool>,
+    pub updated_since: Option<DateTime<Utc>>,
+}
+
+/// Which part of a list to return, everything after `offset` if there's no `limit`
+#[derive(Clone, Copy, Default)]
+pub struct PageRange {
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl PageRange {
+    /// Slice a list in memory
+    pub fn apply<T>(&self, items: Vec<T>) -> Page<T> {
+        let total = items.len();
+        let items = items
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        Page { items, total }
+    }
+}
+
+/// Part of a list, along with how long the whole list is
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+}
+
+#[derive(Clone, Copy)]
+pub enum PeopleSort {
+    Name,
+    CreatedAt,
+    SlotsCount,
+}
+
+impl PeopleQuery {
+    /// Filter and sort a list of people in memory
+    pub fn apply(&self, mut people: Vec<Person>) -> Vec<Person> {
+        people.retain(|p| {
+            self.responded
+                .is_none_or(|responded| responded != p.availability.is_empty())
+                && self.updated_since.is_none_or(|since| p.updated_at >= since)
+        });
+
+        if let Some(sort) = self.sort {
+            match sort {
+                PeopleSort::Name => people.sort_by_key(|p| p.name.to_lowercase()),
+                PeopleSort::CreatedAt => people.sort_by_key(|p| p.created_at),
+                PeopleSort::SlotsCount => people.sort_by_key(|p| p.availability.len()),
+            }
+            if self.descending {
+                people.reverse();
+            }
+        }
+
+        people
+    }
 }
@@ -1,15 +1,45 @@
 #[cfg(feature = "sql-adaptor")]
-pub async fn create_adaptor() -> sql_adaptor::SqlAdaptor {
-    sql_adaptor::SqlAdaptor::new().await
+use std::time::Duration;
+
+use crate::config::AdaptorConfig;
+#[cfg(feature = "sql-adaptor")]
+use crate::replicas::ReplicatedAdaptor;
+
+/// Reads are spread across any read replicas, which does nothing if there aren't any
+#[cfg(feature = "sql-adaptor")]
+pub async fn create_adaptor(config: &AdaptorConfig) -> ReplicatedAdaptor<sql_adaptor::SqlAdaptor> {
+    let connection_string = match (&config.database_url, &config.sqlite_path) {
+        (Some(url), _) => url.clone(),
+        (None, Some(path)) => sql_adaptor::SqlAdaptor::sqlite_url(&path.to_string_lossy()),
+        (None, None) => unreachable!("The config is checked for a database when it's loaded"),
+    };
+    let primary = sql_adaptor::SqlAdaptor::connect(&connection_string).await;
+
+    let mut replicas = Vec::with_capacity(config.replica_urls.len());
+    for url in &config.replica_urls {
+        replicas.push(sql_adaptor::SqlAdaptor::connect(url).await);
+    }
+    ReplicatedAdaptor::new(
+        primary,
+        replicas,
+        Duration::from_secs(config.replica_lag_secs),
+        Duration::from_secs(config.replica_health_check_secs),
+    )
 }
 
 #[cfg(feature = "datastore-adaptor")]
-pub async fn create_adaptor() -> datastore_adaptor::DatastoreAdaptor {
-    datastore_adaptor::DatastoreAdaptor::new().await
+pub async fn create_adaptor(config: &AdaptorConfig) -> datastore_adaptor::DatastoreAdaptor {
+    datastore_adaptor::DatastoreAdaptor::from_credentials(
+        config
+            .gcp_credentials
+            .as_deref()
+            .expect("The config is checked for credentials when it's loaded"),
+    )
+    .await
 }
 
 #[cfg(not(feature = "sql-adaptor"))]
 #[cfg(not(feature = "datastore-adaptor"))]
-pub async fn create_adaptor() -> memory_adaptor::MemoryAdaptor {
+pub async fn create_adaptor(_config: &AdaptorConfig) -> memory_adaptor::MemoryAdaptor {
     memory_adaptor::MemoryAdaptor::new().await
 }
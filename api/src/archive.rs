@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use axum::http::{header::CONTENT_TYPE, Request, Uri};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use hyper::{client::HttpConnector, Body, Client};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use sha2::{Digest, Sha256};
+
+use crate::config::ArchiveConfig;
+
+// Archives can be large, but a bucket that hasn't answered by now isn't going to
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Uploads archives of events to an S3-compatible bucket, set with `archive.bucket` and a key
+/// in `archive.access_key_id` and `archive.secret_access_key`
+pub struct Archive {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    prefix: String,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl Archive {
+    /// Events aren't archived unless there's a bucket
+    pub fn from_config(config: &ArchiveConfig) -> Option<Self> {
+        let bucket = config.bucket.clone()?;
+        let endpoint = config
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", config.region));
+
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Some(Self {
+            endpoint: endpoint.trim_end_matches('/').to_owned(),
+            bucket,
+            region: config.region.clone(),
+            access_key_id: config.access_key_id.clone().unwrap_or_default(),
+            secret_access_key: config.secret_access_key.clone().unwrap_or_default(),
+            prefix: config.prefix.clone(),
+            client: Client::builder().build(connector),
+        })
+    }
+
+    /// The key for an archive made at `time`, which sort in the order they were made
+    pub fn key(&self, time: DateTime<Utc>) -> String {
+        format!("{}{}.ndjson", self.prefix, time.format("%Y-%m-%dT%H%M%SZ"))
+    }
+
+    /// Upload an archive to the bucket, replacing anything already at `key`
+    pub async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), String> {
+        // Path style URLs work with every S3-compatible service, not just AWS
+        let path = format!("/{}/{}", uri_encode(&self.bucket), uri_encode(key));
+        let uri = format!("{}{}", self.endpoint, path)
+            .parse::<Uri>()
+            .map_err(|e| e.to_string())?;
+        let host = match (uri.host(), uri.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => return Err(format!("No host in {}", self.endpoint)),
+        };
+
+        let now = Utc::now();
+        let content_sha256 = hex(&Sha256::digest(&body));
+        let authorization = self.authorization(&path, &host, &content_sha256, now);
+
+        let request = Request::put(uri)
+            .header("host", host)
+            .header("x-amz-content-sha256", content_sha256)
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header("authorization", authorization)
+            .header(CONTENT_TYPE, "application/x-ndjson")
+            .body(Body::from(body))
+            .map_err(|e| e.to_string())?;
+
+        let response = tokio::time::timeout(UPLOAD_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| "Timed out".to_owned())?
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Responded with {}", response.status()));
+        }
+
+        Ok(())
+    }
+
+    // An AWS Signature Version 4 for a PUT, signing the headers that are always sent
+    fn authorization(
+        &self,
+        path: &str,
+        host: &str,
+        content_sha256: &str,
+        now: DateTime<Utc>,
+    ) -> String {
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            path, host, content_sha256, timestamp, signed_headers, content_sha256
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = [date.as_str(), &self.region, "s3", "aws4_request"]
+            .into_iter()
+            .fold(
+                format!("AWS4{}", self.secret_access_key).into_bytes(),
+                |key, part| hmac(&key, part.as_bytes()),
+            );
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        )
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Percent-encode everything but unreserved characters and slashes, the way S3 expects paths
+// to be signed
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
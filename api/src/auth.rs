@@ -0,0 +1,178 @@
+use std::time::{Duration, Instant};
+
+use hyper::{body, client::HttpConnector, Client, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use jsonwebtoken::{
+    decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, TokenData, Validation,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::{config::OidcConfig, tokens::lookup_hash};
+
+pub const ID_TOKEN_HEADER: &str = "x-id-token";
+
+// Issuers rotate their keys every few days, with the old ones kept around for a while
+const KEYS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+// Tokens signed with a key we don't know trigger a refetch, but not more often than this
+const KEYS_MIN_AGE: Duration = Duration::from_secs(60);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Someone logged in with the configured OIDC issuer, from a verified ID token
+#[derive(Clone, Debug)]
+pub struct Identity {
+    /// Stable across devices and sessions, as it's based on the issuer's ID for them
+    pub subject_id: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    name: Option<String>,
+    preferred_username: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+struct Keys {
+    set: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Verifies ID tokens from an OpenID Connect issuer like Google, Auth0 or a self-hosted
+/// Keycloak, set with `oidc.issuer` and the client ID tokens are issued for in
+/// `oidc.audience`. The signing keys are found with the issuer's discovery document, or
+/// can be set directly with `oidc.jwks_url`.
+pub struct Oidc {
+    issuer: String,
+    audience: String,
+    jwks_url: OnceCell<String>,
+    keys: RwLock<Option<Keys>>,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl Oidc {
+    /// Logging in is turned off unless there's an issuer, and the config has already checked
+    /// there's an audience with it
+    pub fn from_config(config: &OidcConfig) -> Option<Self> {
+        let issuer = config.issuer.clone()?;
+        let audience = config.audience.clone()?;
+
+        let jwks_url = OnceCell::new();
+        if let Some(url) = config.jwks_url.clone() {
+            jwks_url
+                .set(url)
+                .expect("The JWKS URL can't have been set yet");
+        }
+
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Some(Self {
+            issuer,
+            audience,
+            jwks_url,
+            keys: RwLock::new(None),
+            client: Client::builder().build(connector),
+        })
+    }
+
+    /// Check an ID token was signed by the issuer for this API, and hasn't expired
+    pub async fn verify(&self, token: &str) -> Result<Identity, String> {
+        let header = decode_header(token).map_err(|e| e.to_string())?;
+        // Only keys published by the issuer are trusted, so symmetric algorithms never are
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(format!("{:?} isn't supported", header.alg));
+        }
+        let kid = header.kid.ok_or("The token has no key ID")?;
+        let key = self.key(&kid).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        let TokenData { claims, .. } =
+            decode::<Claims>(token, &key, &validation).map_err(|e| e.to_string())?;
+
+        Ok(Identity {
+            // Namespaced by the issuer, in case it's ever changed to one with overlapping IDs
+            subject_id: lookup_hash(&format!("{} {}", self.issuer, claims.sub)),
+            name: claims.name.or(claims.preferred_username),
+            email: claims.email,
+        })
+    }
+
+    async fn key(&self, kid: &str) -> Result<DecodingKey, String> {
+        let can_refresh = {
+            let keys = self.keys.read().await;
+            match keys.as_ref() {
+                Some(keys) if keys.fetched_at.elapsed() < KEYS_MAX_AGE => {
+                    if let Some(jwk) = keys.set.find(kid) {
+                        return DecodingKey::from_jwk(jwk).map_err(|e| e.to_string());
+                    }
+                    keys.fetched_at.elapsed() >= KEYS_MIN_AGE
+                }
+                _ => true,
+            }
+        };
+        if !can_refresh {
+            return Err(format!("Unknown key {}", kid));
+        }
+
+        let mut keys = self.keys.write().await;
+        // Another request might have refreshed them while this one was waiting
+        if keys
+            .as_ref()
+            .is_none_or(|keys| keys.fetched_at.elapsed() >= KEYS_MIN_AGE)
+        {
+            let jwks_url = self
+                .jwks_url
+                .get_or_try_init(|| async {
+                    let url = format!(
+                        "{}/.well-known/openid-configuration",
+                        self.issuer.trim_end_matches('/')
+                    );
+                    self.fetch::<Discovery>(&url).await.map(|d| d.jwks_uri)
+                })
+                .await?;
+            *keys = Some(Keys {
+                set: self.fetch(jwks_url).await?,
+                fetched_at: Instant::now(),
+            });
+        }
+
+        let jwk = keys
+            .as_ref()
+            .and_then(|keys| keys.set.find(kid))
+            .ok_or_else(|| format!("Unknown key {}", kid))?;
+        DecodingKey::from_jwk(jwk).map_err(|e| e.to_string())
+    }
+
+    async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        let response = tokio::time::timeout(FETCH_TIMEOUT, self.client.get(uri))
+            .await
+            .map_err(|_| format!("Timed out fetching {}", url))?
+            .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to fetch {}: {}", url, response.status()));
+        }
+        let bytes = body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| e.to_string())?;
+        serde_json::from_slice(&bytes).map_err(|e| format!("Invalid response from {}: {}", url, e))
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    Activity, Adaptor, CleanupPreview, Comment, DailyStats, Event, EventQuery, EventViews,
+    IdempotencyKey, Page, PageRange, PeopleQuery, PeopleVersion, Person, PersonInsert,
+    PersonUpdate, SchemaVersion, Stats, Template,
+};
+use moka::future::Cache;
+
+use crate::config::CacheConfig;
+
+/// Recently fetched events and their people, kept in memory so clients polling the same
+/// event don't each cost a read from storage. Configured with `cache.ttl_secs`, where 0
+/// turns the cache off, and `cache.capacity`.
+///
+/// Clones share the same cache, so the state can keep one to report hit rates.
+#[derive(Clone)]
+pub struct EventCache {
+    events: Cache<String, Event>,
+    people: Cache<String, Vec<Person>>,
+    enabled: bool,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    // Bumped on every invalidation, so a read that raced a write doesn't put the old
+    // value back in the cache
+    generation: Arc<AtomicU64>,
+}
+
+pub struct CacheStats {
+    pub enabled: bool,
+    pub events: u64,
+    pub people: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl EventCache {
+    pub fn from_config(config: &CacheConfig) -> Self {
+        let ttl = Duration::from_secs(config.ttl_secs);
+        let capacity = config.capacity;
+
+        Self {
+            events: Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl)
+                .build(),
+            people: Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl)
+                .build(),
+            enabled: !ttl.is_zero() && capacity > 0,
+            hits: Arc::default(),
+            misses: Arc::default(),
+            generation: Arc::default(),
+        }
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        // Entry counts only catch up with recent inserts once pending work is done
+        self.events.run_pending_tasks().await;
+        self.people.run_pending_tasks().await;
+        CacheStats {
+            enabled: self.enabled,
+            events: self.events.entry_count(),
+            people: self.people.entry_count(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn get<V: Clone + Send + Sync + 'static, E>(
+        &self,
+        cache: &Cache<String, V>,
+        key: String,
+        fetch: impl std::future::Future<Output = Result<Option<V>, E>>,
+    ) -> Result<Option<V>, E> {
+        if !self.enabled {
+            return fetch.await;
+        }
+        if let Some(value) = cache.get(&key).await {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(value));
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let value = fetch.await?;
+        // Missing events aren't cached, so one that's created straight after shows up
+        if let Some(value) = &value {
+            cache.insert(key.clone(), value.clone()).await;
+            if self.generation.load(Ordering::Acquire) != generation {
+                cache.invalidate(&key).await;
+            }
+        }
+        Ok(value)
+    }
+
+    async fn invalidate(&self, event_id: &str) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.events.invalidate(event_id).await;
+        self.people.invalidate(event_id).await;
+    }
+
+    async fn invalidate_people(&self, event_id: &str) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.people.invalidate(event_id).await;
+    }
+
+    fn invalidate_all(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.events.invalidate_all();
+        self.people.invalidate_all();
+    }
+}
+
+/// Wraps an adaptor to read events and people through an [`EventCache`], dropping them from
+/// the cache whenever they're changed through this adaptor.
+///
+/// Cached reads of an event don't update its visited date, but the cache only keeps events
+/// for a few seconds, so visits are still recorded far more often than retention needs.
+pub struct CachedAdaptor<A> {
+    inner: A,
+    cache: EventCache,
+}
+
+impl<A> CachedAdaptor<A> {
+    pub fn new(inner: A, cache: EventCache) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl<A: Adaptor> Adaptor for CachedAdaptor<A>
+where
+    A::Error: Send,
+{
+    type Error = A::Error;
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.inner.ping().await
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        self.inner.close().await
+    }
+
+    async fn get_schema_version(&self) -> Result<SchemaVersion, Self::Error> {
+        self.inner.get_schema_version().await
+    }
+
+    async fn migrate(&self) -> Result<SchemaVersion, Self::Error> {
+        self.inner.migrate().await
+    }
+
+    async fn get_stats(&self) -> Result<Stats, Self::Error> {
+        self.inner.get_stats().await
+    }
+
+    async fn increment_stat_event_count(&self) -> Result<i64, Self::Error> {
+        self.inner.increment_stat_event_count().await
+    }
+
+    async fn increment_stat_person_count(&self) -> Result<i64, Self::Error> {
+        self.inner.increment_stat_person_count().await
+    }
+
+    async fn add_stats(&self, stats: Stats) -> Result<Stats, Self::Error> {
+        self.inner.add_stats(stats).await
+    }
+
+    async fn add_daily_stats(&self, date: NaiveDate, stats: Stats) -> Result<(), Self::Error> {
+        self.inner.add_daily_stats(date, stats).await
+    }
+
+    async fn get_daily_stats(&self, since: NaiveDate) -> Result<Vec<DailyStats>, Self::Error> {
+        self.inner.get_daily_stats(since).await
+    }
+
+    async fn get_people(&self, event_id: String) -> Result<Option<Vec<Person>>, Self::Error> {
+        self.cache
+            .get(
+                &self.cache.people,
+                event_id.clone(),
+                self.inner.get_people(event_id),
+            )
+            .await
+    }
+
+    async fn query_people(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+    ) -> Result<Option<Vec<Person>>, Self::Error> {
+        // Filtering the cached people is cheaper than a query, even for adaptors that can
+        // filter in the database
+        if !self.cache.enabled {
+            return self.inner.query_people(event_id, query).await;
+        }
+        Ok(self
+            .get_people(event_id)
+            .await?
+            .map(|people| query.apply(people)))
+    }
+
+    async fn query_people_page(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+        range: PageRange,
+    ) -> Result<Option<Page<Person>>, Self::Error> {
+        if !self.cache.enabled {
+            return self.inner.query_people_page(event_id, query, range).await;
+        }
+        Ok(self
+            .query_people(event_id, query)
+            .await?
+            .map(|people| range.apply(people)))
+    }
+
+    async fn get_people_version(
+        &self,
+        event_id: String,
+    ) -> Result<Option<PeopleVersion>, Self::Error> {
+        if !self.cache.enabled {
+            return self.inner.get_people_version(event_id).await;
+        }
+        Ok(self
+            .get_people(event_id)
+            .await?
+            .map(|people| PeopleVersion {
+                count: people.len(),
+                updated_at: people.iter().map(|p| p.updated_at).max(),
+            }))
+    }
+
+    async fn upsert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<Person>, Self::Error> {
+        let result = self.inner.upsert_person(event_id.clone(), person).await;
+        self.cache.invalidate_people(&event_id).await;
+        result
+    }
+
+    async fn insert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<PersonInsert>, Self::Error> {
+        let result = self.inner.insert_person(event_id.clone(), person).await;
+        self.cache.invalidate_people(&event_id).await;
+        result
+    }
+
+    async fn update_person(
+        &self,
+        event_id: String,
+        person: Person,
+        expected_updated_at: DateTime<Utc>,
+    ) -> Result<Option<PersonUpdate>, Self::Error> {
+        let result = self
+            .inner
+            .update_person(event_id.clone(), person, expected_updated_at)
+            .await;
+        self.cache.invalidate_people(&event_id).await;
+        result
+    }
+
+    async fn delete_person(
+        &self,
+        event_id: String,
+        person_name: String,
+    ) -> Result<Option<Person>, Self::Error> {
+        let result = self
+            .inner
+            .delete_person(event_id.clone(), person_name)
+            .await;
+        self.cache.invalidate_people(&event_id).await;
+        result
+    }
+
+    async fn get_comments(&self, event_id: String) -> Result<Option<Vec<Comment>>, Self::Error> {
+        self.inner.get_comments(event_id).await
+    }
+
+    async fn create_comment(
+        &self,
+        event_id: String,
+        comment: Comment,
+    ) -> Result<Option<Comment>, Self::Error> {
+        self.inner.create_comment(event_id, comment).await
+    }
+
+    async fn get_activity(&self, event_id: String) -> Result<Option<Vec<Activity>>, Self::Error> {
+        self.inner.get_activity(event_id).await
+    }
+
+    async fn create_activity(
+        &self,
+        event_id: String,
+        activity: Activity,
+    ) -> Result<Option<Activity>, Self::Error> {
+        self.inner.create_activity(event_id, activity).await
+    }
+
+    async fn get_event_views(&self, event_id: String) -> Result<Option<EventViews>, Self::Error> {
+        self.inner.get_event_views(event_id).await
+    }
+
+    async fn record_event_view(
+        &self,
+        event_id: String,
+        register: usize,
+        rank: u8,
+    ) -> Result<Option<EventViews>, Self::Error> {
+        self.inner.record_event_view(event_id, register, rank).await
+    }
+
+    async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        self.cache
+            .get(&self.cache.events, id.clone(), self.inner.get_event(id))
+            .await
+    }
+
+    async fn get_events(&self, ids: Vec<String>) -> Result<Vec<Event>, Self::Error> {
+        self.inner.get_events(ids).await
+    }
+
+    async fn create_event(&self, event: Event) -> Result<Event, Self::Error> {
+        let id = event.id.clone();
+        let result = self.inner.create_event(event).await;
+        self.cache.invalidate(&id).await;
+        result
+    }
+
+    async fn update_event(&self, event: Event) -> Result<Option<Event>, Self::Error> {
+        let id = event.id.clone();
+        let result = self.inner.update_event(event).await;
+        self.cache.invalidate(&id).await;
+        result
+    }
+
+    async fn get_listed_events(&self, tag: Option<String>) -> Result<Vec<Event>, Self::Error> {
+        self.inner.get_listed_events(tag).await
+    }
+
+    async fn get_group_events(&self, group_id: String) -> Result<Vec<Event>, Self::Error> {
+        self.inner.get_group_events(group_id).await
+    }
+
+    async fn get_creator_events(&self, creator_id: String) -> Result<Vec<Event>, Self::Error> {
+        self.inner.get_creator_events(creator_id).await
+    }
+
+    async fn query_events(
+        &self,
+        query: EventQuery,
+        range: PageRange,
+    ) -> Result<Page<Event>, Self::Error> {
+        self.inner.query_events(query, range).await
+    }
+
+    async fn peek_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        self.inner.peek_event(id).await
+    }
+
+    async fn get_idempotency_key(
+        &self,
+        key: String,
+    ) -> Result<Option<IdempotencyKey>, Self::Error> {
+        self.inner.get_idempotency_key(key).await
+    }
+
+    async fn create_idempotency_key(&self, key: IdempotencyKey) -> Result<bool, Self::Error> {
+        self.inner.create_idempotency_key(key).await
+    }
+
+    async fn set_idempotency_key_event(
+        &self,
+        key: String,
+        event_id: String,
+    ) -> Result<(), Self::Error> {
+        self.inner.set_idempotency_key_event(key, event_id).await
+    }
+
+    async fn delete_idempotency_key(&self, key: String) -> Result<(), Self::Error> {
+        self.inner.delete_idempotency_key(key).await
+    }
+
+    async fn delete_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        self.inner.delete_idempotency_keys(cutoff).await
+    }
+
+    async fn get_template(&self, id: String) -> Result<Option<Template>, Self::Error> {
+        self.inner.get_template(id).await
+    }
+
+    async fn create_template(&self, template: Template) -> Result<Template, Self::Error> {
+        self.inner.create_template(template).await
+    }
+
+    async fn delete_templates(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        self.inner.delete_templates(cutoff).await
+    }
+
+    async fn expire_events(&self, default_retention_days: i64) -> Result<i64, Self::Error> {
+        let result = self.inner.expire_events(default_retention_days).await;
+        self.cache.invalidate_all();
+        result
+    }
+
+    async fn restore_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        let result = self.inner.restore_event(id.clone()).await;
+        self.cache.invalidate(&id).await;
+        result
+    }
+
+    async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error> {
+        let result = self.inner.delete_events(cutoff).await;
+        self.cache.invalidate_all();
+        result
+    }
+
+    async fn preview_cleanup(
+        &self,
+        default_retention_days: i64,
+        cutoff: DateTime<Utc>,
+    ) -> Result<CleanupPreview, Self::Error> {
+        self.inner
+            .preview_cleanup(default_retention_days, cutoff)
+            .await
+    }
+
+    async fn delete_event(&self, id: String) -> Result<Option<Stats>, Self::Error> {
+        let result = self.inner.delete_event(id.clone()).await;
+        self.cache.invalidate(&id).await;
+        result
+    }
+}
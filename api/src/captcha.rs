@@ -0,0 +1,134 @@
+use std::{net::IpAddr, time::Duration};
+
+use axum::http::{header::CONTENT_TYPE, HeaderMap, Request};
+use common::Adaptor;
+use hyper::{body, client::HttpConnector, Body, Client};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{CaptchaConfig, CaptchaProvider},
+    errors::ApiError,
+};
+
+pub const CAPTCHA_TOKEN_HEADER: &str = "x-captcha-token";
+
+const TURNSTILE_VERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+const HCAPTCHA_VERIFY_URL: &str = "https://api.hcaptcha.com/siteverify";
+// How long to wait for the provider before letting the request through
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct VerifyRequest<'a> {
+    secret: &'a str,
+    response: &'a str,
+    remoteip: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VerifyResponse {
+    success: bool,
+    #[serde(default, rename = "error-codes")]
+    error_codes: Vec<String>,
+}
+
+/// Checks the token from a Cloudflare Turnstile or hCaptcha widget, set with the site's
+/// secret in `captcha.secret` and `captcha.provider` as `turnstile` (the default) or
+/// `hcaptcha`. Both verify tokens the same way, so `captcha.verify_url` can point
+/// anywhere else that does too.
+pub struct Captcha {
+    secret: String,
+    verify_url: String,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl Captcha {
+    /// CAPTCHAs aren't checked unless there's a secret
+    pub fn from_config(config: &CaptchaConfig) -> Option<Self> {
+        let secret = config.secret.clone()?;
+        let verify_url = config.verify_url.clone().unwrap_or_else(|| {
+            match config.provider {
+                CaptchaProvider::Turnstile => TURNSTILE_VERIFY_URL,
+                CaptchaProvider::Hcaptcha => HCAPTCHA_VERIFY_URL,
+            }
+            .to_owned()
+        });
+
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Some(Self {
+            secret,
+            verify_url,
+            client: Client::builder().build(connector),
+        })
+    }
+
+    /// Ask the provider whether a token was given to someone who solved the challenge
+    pub async fn verify(&self, token: &str, client_ip: Option<IpAddr>) -> Result<bool, String> {
+        let form = serde_urlencoded::to_string(VerifyRequest {
+            secret: &self.secret,
+            response: token,
+            remoteip: client_ip.map(|ip| ip.to_string()),
+        })
+        .map_err(|e| e.to_string())?;
+        let request = Request::post(&self.verify_url)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(form))
+            .map_err(|e| e.to_string())?;
+
+        let response = tokio::time::timeout(VERIFY_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| "Timed out".to_owned())?
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Responded with {}", response.status()));
+        }
+
+        let bytes = body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| e.to_string())?;
+        let VerifyResponse {
+            success,
+            error_codes,
+        } = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+
+        // A wrong secret fails every token, which is worth knowing about
+        if error_codes.iter().any(|code| code.contains("secret")) {
+            tracing::error!("CAPTCHA_SECRET was rejected: {}", error_codes.join(", "));
+        }
+
+        Ok(success)
+    }
+}
+
+/// Reject requests without a valid token in the `X-Captcha-Token` header, if CAPTCHAs are
+/// configured. If the provider fails, the request is allowed so an outage doesn't stop
+/// events being created.
+pub async fn check_captcha<A: Adaptor>(
+    captcha: Option<&Captcha>,
+    headers: &HeaderMap,
+    client_ip: Option<IpAddr>,
+) -> Result<(), ApiError<A>> {
+    let Some(captcha) = captcha else {
+        return Ok(());
+    };
+    let token = headers
+        .get(CAPTCHA_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(ApiError::CaptchaFailed)?;
+
+    match captcha.verify(token, client_ip).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ApiError::CaptchaFailed),
+        Err(e) => {
+            tracing::warn!("CAPTCHA verification failed: {}", e);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use axum::http::{header::CONTENT_TYPE, Request, Uri};
+use chrono_tz::Tz;
+use common::Event;
+use hyper::{client::HttpConnector, Body, Client};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::Serialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::{
+    config::config,
+    cors,
+    jobs::{Job, Jobs},
+    routes::meta::branding,
+    slots::Slot,
+};
+
+pub const INVALID_URL_MESSAGE: &str =
+    "Chat webhooks must be Slack or Discord incoming webhook URLs, like https://hooks.slack.com/services/...";
+
+// How long to wait for Slack or Discord to respond before counting the attempt as failed
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where a chat webhook posts to, worked out from its URL
+#[derive(Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatPlatform {
+    Slack,
+    Discord,
+}
+
+impl ChatPlatform {
+    /// The platform an incoming webhook URL belongs to, or None if it isn't one
+    pub fn from_url(url: &str) -> Option<Self> {
+        let uri = url.parse::<Uri>().ok()?;
+        if uri.scheme_str() != Some("https") {
+            return None;
+        }
+        match (uri.host()?, uri.path()) {
+            ("hooks.slack.com", path) if path.starts_with("/services/") => Some(Self::Slack),
+            ("discord.com" | "discordapp.com" | "ptb.discord.com" | "canary.discord.com", path)
+                if path.starts_with("/api/webhooks/") =>
+            {
+                Some(Self::Discord)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Slack => "Slack",
+            Self::Discord => "Discord",
+        }
+    }
+}
+
+/// Something that happened to an event that's worth posting about
+pub enum ChatNotification<'a> {
+    Created,
+    /// Someone filled in their availability for the first time
+    Responded(&'a str),
+    /// The organizer chose one of the event's times
+    Finalized(&'a str),
+}
+
+/// A formatted message waiting to be posted
+pub struct ChatMessage {
+    pub platform: ChatPlatform,
+    // Incoming webhook URLs are secret, so they aren't logged
+    url: String,
+    body: Vec<u8>,
+}
+
+/// Posts updates about events to the Slack or Discord channels their creators connected, as
+/// background jobs
+pub struct Chat {
+    jobs: Jobs,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl Chat {
+    pub fn new(jobs: Jobs) -> Self {
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_only()
+            .enable_http1()
+            .build();
+
+        Self {
+            jobs,
+            client: Client::builder().build(connector),
+        }
+    }
+
+    /// Queue a message to the event's chat webhook, if it has one
+    pub fn send(&self, event: &Event, notification: ChatNotification) {
+        let Some(url) = &event.chat_webhook_url else {
+            return;
+        };
+        let Some(platform) = ChatPlatform::from_url(url) else {
+            return;
+        };
+
+        let body = match serde_json::to_vec(&format_message(platform, event, notification)) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("Failed to serialize chat message: {}", e);
+                return;
+            }
+        };
+        self.jobs.push(Job::Chat(ChatMessage {
+            platform,
+            url: url.clone(),
+            body,
+        }));
+    }
+
+    /// Make one attempt at posting a message, the job queue retries it if this fails
+    pub async fn deliver(&self, message: &ChatMessage) -> Result<(), String> {
+        let request = Request::post(&message.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(message.body.clone()))
+            .map_err(|e| e.to_string())?;
+
+        let response = tokio::time::timeout(DELIVERY_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| "Timed out".to_owned())?
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Responded with {}", response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+// The JSON body for a platform's incoming webhook, linking to the event on the frontend
+fn format_message(platform: ChatPlatform, event: &Event, notification: ChatNotification) -> Value {
+    let url = format!("{}/{}", cors::frontend_url(&config().cors), event.id);
+    let text = match notification {
+        ChatNotification::Created => "Event created, add your availability".to_owned(),
+        ChatNotification::Responded(name) => format!("{} added their availability", name),
+        ChatNotification::Finalized(time) => {
+            let timezone = event.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+            let time = time
+                .parse::<Slot>()
+                .map(|slot| slot.format_in(timezone))
+                .unwrap_or_else(|_| time.to_owned());
+            format!("The time has been set for {} ({})", time, timezone.name())
+        }
+    };
+
+    match platform {
+        ChatPlatform::Slack => {
+            let name = escape_slack(&event.name);
+            let text = escape_slack(&text);
+            json!({
+                "text": format!("{}: {}", name, text),
+                "blocks": [{
+                    "type": "section",
+                    "text": {
+                        "type": "mrkdwn",
+                        "text": format!("*<{}|{}>*\n{}", url, name, text),
+                    },
+                }],
+            })
+        }
+        ChatPlatform::Discord => json!({
+            "username": branding().name,
+            "embeds": [{
+                "title": event.name,
+                "url": url,
+                "description": text,
+                "color": parse_color(&branding().color),
+            }],
+            // Names are chosen by whoever responds, so they can't ping the channel
+            "allowed_mentions": { "parse": [] },
+        }),
+    }
+}
+
+// Slack treats these as control characters in mrkdwn, everything else is shown as is
+fn escape_slack(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Discord embed colors are a single integer, branding colors that aren't `#rrggbb` are left
+// out
+fn parse_color(color: &str) -> Option<u32> {
+    color
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+}
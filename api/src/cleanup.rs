@@ -0,0 +1,185 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration as StdDuration,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use common::{Adaptor, CleanupPreview, Stats};
+use rand::Rng;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use crate::{
+    archive::Archive,
+    config::{config, RetentionConfig},
+    export, AppState,
+};
+
+/// Longest retention an event can ask for
+pub const MAX_EVENT_RETENTION_DAYS: i64 = 365;
+/// How many hours an idempotency key is remembered for after its event was created
+pub const IDEMPOTENCY_KEY_TTL_HOURS: i64 = 24;
+
+// Set while a cleanup is running, whether it was scheduled or requested over HTTP
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// Clears the running flag when the run finishes, even if it fails
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// What a cleanup removed
+pub struct CleanupReport {
+    pub expired_count: i64,
+    pub deleted: Stats,
+    pub keys_deleted: i64,
+    pub templates_deleted: i64,
+    /// Where the deleted events were archived to, if they were
+    pub archive_key: Option<String>,
+}
+
+/// Expire stale events, then delete events whose grace period has passed, old idempotency
+/// keys and unused templates
+///
+/// With an archive, the events are uploaded to it along with their people before they're
+/// deleted, and if that fails they're kept until the next run.
+///
+/// Returns None without doing anything if another cleanup is still running.
+pub async fn run<A: Adaptor>(
+    adaptor: &A,
+    archive: Option<&Archive>,
+) -> Result<Option<CleanupReport>, A::Error> {
+    if RUNNING.swap(true, Ordering::Acquire) {
+        return Ok(None);
+    }
+    let _guard = RunningGuard;
+
+    info!("Running cleanup task");
+
+    let expired_count = adaptor.expire_events(event_retention_days()).await?;
+    let cutoff = Utc::now() - Duration::days(event_grace_days());
+    let archive_key = match archive {
+        Some(archive) => archive_events(adaptor, archive, cutoff).await,
+        None => Ok(None),
+    };
+    let (deleted, archive_key) = match archive_key {
+        Ok(archive_key) => (adaptor.delete_events(cutoff).await?, archive_key),
+        Err(e) => {
+            warn!("Not deleting events, as archiving them failed: {}", e);
+            let deleted = Stats {
+                event_count: 0,
+                person_count: 0,
+            };
+            (deleted, None)
+        }
+    };
+    let keys_deleted = adaptor
+        .delete_idempotency_keys(Utc::now() - Duration::hours(IDEMPOTENCY_KEY_TTL_HOURS))
+        .await?;
+    let templates_deleted = adaptor
+        .delete_templates(Utc::now() - Duration::days(template_retention_days()))
+        .await?;
+
+    info!(
+        "Cleanup successful: {} events expired, {} events, {} people, {} idempotency keys and {} templates removed",
+        expired_count, deleted.event_count, deleted.person_count, keys_deleted, templates_deleted
+    );
+
+    Ok(Some(CleanupReport {
+        expired_count,
+        deleted,
+        keys_deleted,
+        templates_deleted,
+        archive_key,
+    }))
+}
+
+// Upload the events that are about to be deleted, returning the key they were archived at, or
+// None if there weren't any
+async fn archive_events<A: Adaptor>(
+    adaptor: &A,
+    archive: &Archive,
+    cutoff: DateTime<Utc>,
+) -> Result<Option<String>, String> {
+    let events = adaptor
+        .preview_cleanup(event_retention_days(), cutoff)
+        .await
+        .map_err(|e| e.to_string())?
+        .expired_events;
+    if events.is_empty() {
+        return Ok(None);
+    }
+
+    let mut body = Vec::new();
+    let event_ids = export::export_lines(adaptor, events, &mut body).await?;
+    let key = archive.key(Utc::now());
+    archive.put(&key, body).await?;
+
+    // Logged so any one event can be found again to restore it
+    for event_id in event_ids {
+        info!("Archived event {} to {}", event_id, key);
+    }
+    Ok(Some(key))
+}
+
+/// Find the events a cleanup would expire and delete right now, without changing anything
+pub async fn preview<A: Adaptor>(adaptor: &A) -> Result<CleanupPreview, A::Error> {
+    adaptor
+        .preview_cleanup(
+            event_retention_days(),
+            Utc::now() - Duration::days(event_grace_days()),
+        )
+        .await
+}
+
+/// How often to run the cleanup in the background, from `retention.cleanup_interval_minutes`,
+/// or `None` if it isn't set
+pub fn interval(config: &RetentionConfig) -> Option<StdDuration> {
+    config
+        .cleanup_interval_minutes
+        .map(|minutes| StdDuration::from_secs(minutes * 60))
+}
+
+/// Run the cleanup on an interval, for as long as the API is running
+///
+/// The first run waits for a random part of the interval, so instances that start together
+/// don't all clean up at once.
+pub async fn run_periodically<A: Adaptor>(state: AppState<A>, period: StdDuration) {
+    let jitter = rand::thread_rng().gen_range(StdDuration::ZERO..period);
+    info!(
+        "Scheduled cleanup every {} minutes, starting in {} seconds",
+        period.as_secs() / 60,
+        jitter.as_secs()
+    );
+
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + jitter, period);
+    // A slow run shouldn't cause a burst of catch-up runs
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        match run(&state.adaptor, state.archive.as_ref()).await {
+            Ok(Some(_)) => {}
+            Ok(None) => info!("Skipping scheduled cleanup, as one is already running"),
+            Err(e) => warn!("Scheduled cleanup failed: {}", e),
+        }
+    }
+}
+
+/// How many days an event is kept after it was last visited, unless it has its own retention
+pub fn event_retention_days() -> i64 {
+    config().retention.event_days
+}
+
+/// How many days an expired event can be restored for before it's deleted
+pub fn event_grace_days() -> i64 {
+    config().retention.event_grace_days
+}
+
+/// How many days a template is kept after an event was last created from it
+pub fn template_retention_days() -> i64 {
+    config().retention.template_days
+}
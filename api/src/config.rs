@@ -0,0 +1,799 @@
+use std::{
+    env, fmt, fs,
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use axum::http::{HeaderValue, Uri};
+use serde::Deserialize;
+
+use crate::middleware::client_ip::parse_range;
+
+// Looked for in the working directory, in this order, if no file is given
+const DEFAULT_PATHS: [&str; 3] = ["jelli.toml", "jelli.yaml", "jelli.yml"];
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Everything an instance can be configured with. Settings are read from a `jelli.toml` or
+/// `jelli.yaml` file if there is one, then any environment variables that are set override
+/// them, and anything set in neither place uses its default.
+///
+/// The whole thing is checked when it's loaded, so a mistake stops the API from starting
+/// instead of turning up later.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub cors: CorsConfig,
+    pub rate_limits: RateLimitsConfig,
+    pub adaptor: AdaptorConfig,
+    pub retention: RetentionConfig,
+    pub archive: ArchiveConfig,
+    pub cache: CacheConfig,
+    pub admin: AdminConfig,
+    pub signatures: SignaturesConfig,
+    pub events: EventsConfig,
+    pub oidc: OidcConfig,
+    pub google: GoogleConfig,
+    pub captcha: CaptchaConfig,
+    pub spam: SpamConfig,
+    pub branding: BrandingConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// `HOST`
+    pub host: IpAddr,
+    /// `PORT`
+    pub port: u16,
+    /// `UNIX_SOCKET_PATH`, listened on instead of the host and port if it's set
+    pub unix_socket_path: Option<PathBuf>,
+    /// `GRPC_PORT`
+    pub grpc_port: u16,
+    /// `SHUTDOWN_TIMEOUT_SECS`
+    pub shutdown_timeout_secs: u64,
+    /// `TLS_CERT_PATH`
+    pub tls_cert_path: Option<PathBuf>,
+    /// `TLS_KEY_PATH`
+    pub tls_key_path: Option<PathBuf>,
+    /// `TRUSTED_PROXIES`, as addresses or CIDR ranges
+    pub trusted_proxies: Vec<String>,
+    /// `TRUSTED_PROXY_HEADER`
+    pub trusted_proxy_header: ProxyHeader,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// `FRONTEND_URL`, required in release builds
+    pub origins: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitsConfig {
+    /// `RATE_LIMIT_STANDARD_BURST` and `RATE_LIMIT_STANDARD_PERIOD_MS`
+    pub standard: RateLimitConfig,
+    /// `RATE_LIMIT_STRICT_BURST` and `RATE_LIMIT_STRICT_PERIOD_MS`
+    pub strict: RateLimitConfig,
+}
+
+/// Allows bursts of up to `burst_size` requests, and replenishes one every `period_ms`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub burst_size: u32,
+    pub period_ms: u64,
+}
+
+/// Only the settings for the adaptor the API was built with are used
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptorConfig {
+    /// `DATABASE_URL`, for `sql-adaptor`
+    pub database_url: Option<String>,
+    /// `SQLITE_PATH`, for `sql-adaptor` if there's no database URL
+    pub sqlite_path: Option<PathBuf>,
+    /// `DATABASE_REPLICA_URLS`, read replicas of the database for `sql-adaptor`
+    pub replica_urls: Vec<String>,
+    /// `REPLICA_LAG_SECS`, how long an event is read from the primary after it's written
+    pub replica_lag_secs: u64,
+    /// `REPLICA_HEALTH_CHECK_SECS`, how often replicas are pinged
+    pub replica_health_check_secs: u64,
+    /// `GCP_CREDENTIALS`, for `datastore-adaptor`
+    pub gcp_credentials: Option<String>,
+    /// `AUTO_MIGRATE`
+    pub auto_migrate: bool,
+}
+
+/// An S3-compatible bucket that events are archived to before the cleanup deletes them
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// `ARCHIVE_BUCKET`, events are deleted without being archived if it isn't set
+    pub bucket: Option<String>,
+    /// `ARCHIVE_ENDPOINT`, like `https://<account>.r2.cloudflarestorage.com`, defaults to AWS
+    /// in the region
+    pub endpoint: Option<String>,
+    /// `ARCHIVE_REGION`
+    pub region: String,
+    /// `ARCHIVE_ACCESS_KEY_ID`
+    pub access_key_id: Option<String>,
+    /// `ARCHIVE_SECRET_ACCESS_KEY`
+    pub secret_access_key: Option<String>,
+    /// `ARCHIVE_PREFIX`, added to the start of each archive's key, like `jellifit/`
+    pub prefix: String,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// `EVENT_RETENTION_DAYS`
+    pub event_days: i64,
+    /// `EVENT_GRACE_DAYS`
+    pub event_grace_days: i64,
+    /// `TEMPLATE_RETENTION_DAYS`
+    pub template_days: i64,
+    /// `CLEANUP_INTERVAL_MINUTES`, the cleanup only runs when asked to if it isn't set
+    pub cleanup_interval_minutes: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// `EVENT_CACHE_TTL_SECS`, 0 turns the cache off
+    pub ttl_secs: u64,
+    /// `EVENT_CACHE_CAPACITY`
+    pub capacity: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// `ADMIN_KEY`, or `CRON_KEY` from older versions
+    pub key: Option<String>,
+    /// `ALLOW_SEEDING`, seeding is always allowed in debug builds
+    pub allow_seeding: bool,
+}
+
+/// Keys that server-to-server clients can sign write requests with
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignaturesConfig {
+    /// `SIGNATURE_KEYS`, as `key-id:secret`, signatures aren't checked if there aren't any
+    pub keys: Vec<String>,
+    /// `SIGNATURE_MAX_AGE_SECS`, how far a signature's `created` time can be from now
+    pub max_age_secs: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventsConfig {
+    /// `TERMS_VERSION`
+    pub terms_version: Option<String>,
+    /// `EXTEND_REQUIRES_PERSON`
+    pub extend_requires_person: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcConfig {
+    /// `OIDC_ISSUER`, logging in is turned off if it isn't set
+    pub issuer: Option<String>,
+    /// `OIDC_AUDIENCE`, required with an issuer
+    pub audience: Option<String>,
+    /// `OIDC_JWKS_URL`, found from the issuer if it isn't set
+    pub jwks_url: Option<String>,
+}
+
+/// A Google OAuth client that people can connect their calendars with
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct GoogleConfig {
+    /// `GOOGLE_CLIENT_ID`, calendars can't be connected if it isn't set
+    pub client_id: Option<String>,
+    /// `GOOGLE_CLIENT_SECRET`, required with a client ID
+    pub client_secret: Option<String>,
+    /// `GOOGLE_REDIRECT_URL`, this API's `/google/callback` as registered with the client,
+    /// required with a client ID
+    pub redirect_url: Option<String>,
+    /// `GOOGLE_TOKEN_URL`, defaults to Google's
+    pub token_url: Option<String>,
+    /// `GOOGLE_CALENDAR_URL`, the Calendar API to ask for free/busy times, defaults to
+    /// Google's
+    pub calendar_url: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptchaConfig {
+    /// `CAPTCHA_SECRET`, CAPTCHAs aren't checked if it isn't set
+    pub secret: Option<String>,
+    /// `CAPTCHA_PROVIDER`
+    pub provider: CaptchaProvider,
+    /// `CAPTCHA_VERIFY_URL`, overrides the provider's
+    pub verify_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpamConfig {
+    /// `SPAM_SCORER_URL`, content isn't checked if it isn't set
+    pub scorer_url: Option<String>,
+    /// `SPAM_THRESHOLD`
+    pub threshold: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrandingConfig {
+    /// `BRAND_NAME`
+    pub name: String,
+    /// `BRAND_LOGO_URL`
+    pub logo_url: Option<String>,
+    /// `BRAND_COLOR`
+    pub color: String,
+    /// `BRAND_SUPPORT_CONTACT`
+    pub support_contact: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// `LOG_FORMAT`
+    pub format: LogFormat,
+}
+
+/// Which header trusted proxies say who they're forwarding for in
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProxyHeader {
+    #[default]
+    XForwardedFor,
+    Forwarded,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptchaProvider {
+    #[default]
+    Turnstile,
+    Hcaptcha,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 3000,
+            unix_socket_path: None,
+            grpc_port: 50051,
+            // Fly and Kubernetes both wait 30 seconds by default before killing the process
+            shutdown_timeout_secs: 25,
+            tls_cert_path: None,
+            tls_key_path: None,
+            trusted_proxies: Vec::new(),
+            trusted_proxy_header: ProxyHeader::default(),
+        }
+    }
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            standard: RateLimitConfig {
+                burst_size: 20,
+                period_ms: 500,
+            },
+            strict: RateLimitConfig {
+                burst_size: 5,
+                period_ms: 5000,
+            },
+        }
+    }
+}
+
+impl Default for AdaptorConfig {
+    fn default() -> Self {
+        Self {
+            database_url: None,
+            sqlite_path: None,
+            replica_urls: Vec::new(),
+            replica_lag_secs: 5,
+            replica_health_check_secs: 10,
+            gcp_credentials: None,
+            auto_migrate: true,
+        }
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            event_days: 90,
+            event_grace_days: 7,
+            template_days: 365,
+            cleanup_interval_minutes: None,
+        }
+    }
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            bucket: None,
+            endpoint: None,
+            region: "us-east-1".to_owned(),
+            access_key_id: None,
+            secret_access_key: None,
+            prefix: String::new(),
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            // Short enough that other instances' changes show up quickly, long enough that
+            // clients polling a popular event mostly hit the cache
+            ttl_secs: 10,
+            capacity: 10_000,
+        }
+    }
+}
+
+impl Default for SignaturesConfig {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            max_age_secs: 300,
+        }
+    }
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        Self {
+            scorer_url: None,
+            threshold: 0.9,
+        }
+    }
+}
+
+impl Default for BrandingConfig {
+    fn default() -> Self {
+        Self {
+            name: "Jelli Fit".to_owned(),
+            logo_url: None,
+            color: "#0eaac5".to_owned(),
+            support_contact: None,
+        }
+    }
+}
+
+impl FromStr for ProxyHeader {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "x-forwarded-for" => Ok(Self::XForwardedFor),
+            "forwarded" => Ok(Self::Forwarded),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for CaptchaProvider {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "turnstile" => Ok(Self::Turnstile),
+            "hcaptcha" => Ok(Self::Hcaptcha),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Load the configuration, from a file if one is given, otherwise from `JELLI_CONFIG` or the
+/// first of `jelli.toml`, `jelli.yaml` or `jelli.yml` that exists. Panics with everything
+/// that's wrong with it.
+///
+/// Only the first call loads anything, later calls return what it loaded.
+pub fn init(path: Option<&Path>) -> &'static Config {
+    CONFIG.get_or_init(|| {
+        Config::load(path).unwrap_or_else(|errors| {
+            panic!("Invalid configuration:\n  {}", errors.join("\n  "));
+        })
+    })
+}
+
+/// The configuration, loaded the first time it's needed if [`init`] wasn't called first
+pub fn config() -> &'static Config {
+    init(None)
+}
+
+impl Config {
+    /// Read the file and environment, and check the result
+    pub fn load(path: Option<&Path>) -> Result<Self, Vec<String>> {
+        let mut config = match config_path(path) {
+            Some(path) => Self::from_file(&path).map_err(|e| vec![e])?,
+            None => Self::default(),
+        };
+
+        let mut env = EnvOverrides::default();
+        config.apply_env(&mut env);
+        let mut errors = env.errors;
+        errors.extend(config.validate());
+
+        match errors.is_empty() {
+            true => Ok(config),
+            false => Err(errors),
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|e| e.to_string()),
+            Some("yaml" | "yml") => serde_yaml::from_str(&contents).map_err(|e| e.to_string()),
+            _ => Err("The config file has to end in .toml, .yaml or .yml".to_owned()),
+        }
+        .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self, env: &mut EnvOverrides) {
+        let server = &mut self.server;
+        env.value("HOST", &mut server.host);
+        env.value("PORT", &mut server.port);
+        env.option("UNIX_SOCKET_PATH", &mut server.unix_socket_path);
+        env.value("GRPC_PORT", &mut server.grpc_port);
+        env.value("SHUTDOWN_TIMEOUT_SECS", &mut server.shutdown_timeout_secs);
+        env.option("TLS_CERT_PATH", &mut server.tls_cert_path);
+        env.option("TLS_KEY_PATH", &mut server.tls_key_path);
+        env.list("TRUSTED_PROXIES", &mut server.trusted_proxies);
+        env.value("TRUSTED_PROXY_HEADER", &mut server.trusted_proxy_header);
+
+        env.list("FRONTEND_URL", &mut self.cors.origins);
+
+        let rate_limits = &mut self.rate_limits;
+        env.value(
+            "RATE_LIMIT_STANDARD_BURST",
+            &mut rate_limits.standard.burst_size,
+        );
+        env.value(
+            "RATE_LIMIT_STANDARD_PERIOD_MS",
+            &mut rate_limits.standard.period_ms,
+        );
+        env.value(
+            "RATE_LIMIT_STRICT_BURST",
+            &mut rate_limits.strict.burst_size,
+        );
+        env.value(
+            "RATE_LIMIT_STRICT_PERIOD_MS",
+            &mut rate_limits.strict.period_ms,
+        );
+
+        let adaptor = &mut self.adaptor;
+        env.option("DATABASE_URL", &mut adaptor.database_url);
+        env.option("SQLITE_PATH", &mut adaptor.sqlite_path);
+        env.list("DATABASE_REPLICA_URLS", &mut adaptor.replica_urls);
+        env.value("REPLICA_LAG_SECS", &mut adaptor.replica_lag_secs);
+        env.value(
+            "REPLICA_HEALTH_CHECK_SECS",
+            &mut adaptor.replica_health_check_secs,
+        );
+        env.option("GCP_CREDENTIALS", &mut adaptor.gcp_credentials);
+        env.flag("AUTO_MIGRATE", &mut adaptor.auto_migrate);
+
+        let retention = &mut self.retention;
+        env.value("EVENT_RETENTION_DAYS", &mut retention.event_days);
+        env.value("EVENT_GRACE_DAYS", &mut retention.event_grace_days);
+        env.value("TEMPLATE_RETENTION_DAYS", &mut retention.template_days);
+        env.option(
+            "CLEANUP_INTERVAL_MINUTES",
+            &mut retention.cleanup_interval_minutes,
+        );
+
+        let archive = &mut self.archive;
+        env.option("ARCHIVE_BUCKET", &mut archive.bucket);
+        env.option("ARCHIVE_ENDPOINT", &mut archive.endpoint);
+        env.value("ARCHIVE_REGION", &mut archive.region);
+        env.option("ARCHIVE_ACCESS_KEY_ID", &mut archive.access_key_id);
+        env.option("ARCHIVE_SECRET_ACCESS_KEY", &mut archive.secret_access_key);
+        env.value("ARCHIVE_PREFIX", &mut archive.prefix);
+
+        env.value("EVENT_CACHE_TTL_SECS", &mut self.cache.ttl_secs);
+        env.value("EVENT_CACHE_CAPACITY", &mut self.cache.capacity);
+
+        // The admin key used to be called the cron key, when it was only for the cleanup
+        env.option("CRON_KEY", &mut self.admin.key);
+        env.option("ADMIN_KEY", &mut self.admin.key);
+        env.flag("ALLOW_SEEDING", &mut self.admin.allow_seeding);
+
+        env.list("SIGNATURE_KEYS", &mut self.signatures.keys);
+        env.value("SIGNATURE_MAX_AGE_SECS", &mut self.signatures.max_age_secs);
+
+        env.option("TERMS_VERSION", &mut self.events.terms_version);
+        env.flag(
+            "EXTEND_REQUIRES_PERSON",
+            &mut self.events.extend_requires_person,
+        );
+
+        env.option("OIDC_ISSUER", &mut self.oidc.issuer);
+        env.option("OIDC_AUDIENCE", &mut self.oidc.audience);
+        env.option("OIDC_JWKS_URL", &mut self.oidc.jwks_url);
+
+        env.option("GOOGLE_CLIENT_ID", &mut self.google.client_id);
+        env.option("GOOGLE_CLIENT_SECRET", &mut self.google.client_secret);
+        env.option("GOOGLE_REDIRECT_URL", &mut self.google.redirect_url);
+        env.option("GOOGLE_TOKEN_URL", &mut self.google.token_url);
+        env.option("GOOGLE_CALENDAR_URL", &mut self.google.calendar_url);
+
+        env.option("CAPTCHA_SECRET", &mut self.captcha.secret);
+        env.value("CAPTCHA_PROVIDER", &mut self.captcha.provider);
+        env.option("CAPTCHA_VERIFY_URL", &mut self.captcha.verify_url);
+
+        env.option("SPAM_SCORER_URL", &mut self.spam.scorer_url);
+        env.value("SPAM_THRESHOLD", &mut self.spam.threshold);
+
+        env.value("BRAND_NAME", &mut self.branding.name);
+        env.option("BRAND_LOGO_URL", &mut self.branding.logo_url);
+        env.value("BRAND_COLOR", &mut self.branding.color);
+        env.option("BRAND_SUPPORT_CONTACT", &mut self.branding.support_contact);
+
+        env.value("LOG_FORMAT", &mut self.logging.format);
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut check = |valid: bool, message: &str| {
+            if !valid {
+                errors.push(message.to_owned());
+            }
+        };
+
+        let server = &self.server;
+        check(
+            server.tls_cert_path.is_some() == server.tls_key_path.is_some(),
+            "server.tls_cert_path (TLS_CERT_PATH) and server.tls_key_path (TLS_KEY_PATH) must be set together",
+        );
+        check(
+            server.unix_socket_path.is_none() || server.tls_cert_path.is_none(),
+            "TLS can't be used with a Unix socket, the proxy in front of it should handle HTTPS",
+        );
+        for proxy in &server.trusted_proxies {
+            check(
+                parse_range(proxy).is_some(),
+                &format!(
+                    "\"{}\" in server.trusted_proxies (TRUSTED_PROXIES) isn't an address or CIDR range",
+                    proxy
+                ),
+            );
+        }
+
+        // The frontend's dev server is allowed instead in debug builds
+        check(
+            cfg!(debug_assertions) || !self.cors.origins.is_empty(),
+            "cors.origins (FRONTEND_URL) must be set to the frontend's URL",
+        );
+        for origin in &self.cors.origins {
+            check(
+                origin.parse::<HeaderValue>().is_ok(),
+                &format!(
+                    "\"{}\" in cors.origins (FRONTEND_URL) isn't a valid origin",
+                    origin
+                ),
+            );
+        }
+
+        for (group, limit) in [
+            ("standard", &self.rate_limits.standard),
+            ("strict", &self.rate_limits.strict),
+        ] {
+            check(
+                limit.burst_size > 0 && limit.period_ms > 0,
+                &format!(
+                    "rate_limits.{0}.burst_size (RATE_LIMIT_{1}_BURST) and rate_limits.{0}.period_ms (RATE_LIMIT_{1}_PERIOD_MS) must be more than 0",
+                    group,
+                    group.to_uppercase()
+                ),
+            );
+        }
+
+        #[cfg(feature = "sql-adaptor")]
+        check(
+            self.adaptor.database_url.is_some() || self.adaptor.sqlite_path.is_some(),
+            "adaptor.database_url (DATABASE_URL) or adaptor.sqlite_path (SQLITE_PATH) must be set",
+        );
+        #[cfg(not(feature = "sql-adaptor"))]
+        check(
+            self.adaptor.replica_urls.is_empty(),
+            "adaptor.replica_urls (DATABASE_REPLICA_URLS) can only be used with sql-adaptor",
+        );
+        check(
+            self.adaptor.replica_health_check_secs > 0,
+            "adaptor.replica_health_check_secs (REPLICA_HEALTH_CHECK_SECS) must be more than 0",
+        );
+        #[cfg(feature = "datastore-adaptor")]
+        check(
+            self.adaptor.gcp_credentials.is_some(),
+            "adaptor.gcp_credentials (GCP_CREDENTIALS) must be set",
+        );
+
+        let retention = &self.retention;
+        for (key, env, days) in [
+            ("event_days", "EVENT_RETENTION_DAYS", retention.event_days),
+            (
+                "event_grace_days",
+                "EVENT_GRACE_DAYS",
+                retention.event_grace_days,
+            ),
+            (
+                "template_days",
+                "TEMPLATE_RETENTION_DAYS",
+                retention.template_days,
+            ),
+        ] {
+            check(
+                days >= 1,
+                &format!("retention.{} ({}) must be at least 1 day", key, env),
+            );
+        }
+        check(
+            retention.cleanup_interval_minutes != Some(0),
+            "retention.cleanup_interval_minutes (CLEANUP_INTERVAL_MINUTES) must be more than 0, or unset to turn it off",
+        );
+
+        let archive = &self.archive;
+        check(
+            archive.bucket.is_none()
+                || (archive.access_key_id.is_some() && archive.secret_access_key.is_some()),
+            "archive.access_key_id (ARCHIVE_ACCESS_KEY_ID) and archive.secret_access_key (ARCHIVE_SECRET_ACCESS_KEY) must be set when there's a bucket",
+        );
+        if let Some(endpoint) = &archive.endpoint {
+            check(
+                endpoint.parse::<Uri>().is_ok_and(|uri| {
+                    matches!(uri.scheme_str(), Some("http" | "https")) && uri.host().is_some()
+                }),
+                &format!(
+                    "\"{}\" in archive.endpoint (ARCHIVE_ENDPOINT) isn't an http:// or https:// URL",
+                    endpoint
+                ),
+            );
+        }
+
+        for key in &self.signatures.keys {
+            check(
+                key.split_once(':')
+                    .is_some_and(|(id, secret)| !id.is_empty() && !secret.is_empty()),
+                "Keys in signatures.keys (SIGNATURE_KEYS) must look like key-id:secret",
+            );
+        }
+        check(
+            self.signatures.max_age_secs > 0,
+            "signatures.max_age_secs (SIGNATURE_MAX_AGE_SECS) must be more than 0",
+        );
+
+        check(
+            self.oidc.issuer.is_none() || self.oidc.audience.is_some(),
+            "oidc.audience (OIDC_AUDIENCE) must be set to the client ID when there's an issuer",
+        );
+        check(
+            self.google.client_id.is_none()
+                || (self.google.client_secret.is_some() && self.google.redirect_url.is_some()),
+            "google.client_secret (GOOGLE_CLIENT_SECRET) and google.redirect_url (GOOGLE_REDIRECT_URL) must be set when there's a client ID",
+        );
+        check(
+            (0.0..=1.0).contains(&self.spam.threshold),
+            "spam.threshold (SPAM_THRESHOLD) must be between 0 and 1",
+        );
+
+        errors
+    }
+}
+
+fn config_path(path: Option<&Path>) -> Option<PathBuf> {
+    path.map(Path::to_path_buf)
+        .or_else(|| env::var_os("JELLI_CONFIG").map(PathBuf::from))
+        .or_else(|| {
+            DEFAULT_PATHS
+                .iter()
+                .map(PathBuf::from)
+                .find(|path| path.exists())
+        })
+}
+
+// Environment variables that are set, and not empty, replace what's in the file
+#[derive(Default)]
+struct EnvOverrides {
+    errors: Vec<String>,
+}
+
+impl EnvOverrides {
+    fn get(name: &str) -> Option<String> {
+        env::var(name)
+            .ok()
+            .map(|value| value.trim().to_owned())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, value: &str) -> Option<T> {
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            self.errors
+                .push(format!("{} has an invalid value \"{}\"", name, value));
+        }
+        parsed
+    }
+
+    fn value<T: FromStr>(&mut self, name: &str, target: &mut T) {
+        if let Some(value) = Self::get(name).and_then(|value| self.parse(name, &value)) {
+            *target = value;
+        }
+    }
+
+    fn option<T: FromStr>(&mut self, name: &str, target: &mut Option<T>) {
+        if let Some(value) = Self::get(name).and_then(|value| self.parse(name, &value)) {
+            *target = Some(value);
+        }
+    }
+
+    fn flag(&mut self, name: &str, target: &mut bool) {
+        if let Some(value) = Self::get(name) {
+            match value.to_lowercase().as_str() {
+                "true" => *target = true,
+                "false" => *target = false,
+                _ => self.errors.push(format!(
+                    "{} has to be true or false, not \"{}\"",
+                    name, value
+                )),
+            }
+        }
+    }
+
+    // Comma separated, like `FRONTEND_URL=https://jelli.fit,https://staging.jelli.fit`
+    fn list(&mut self, name: &str, target: &mut Vec<String>) {
+        if let Some(value) = Self::get(name) {
+            *target = value
+                .split(',')
+                .map(|item| item.trim().to_owned())
+                .filter(|item| !item.is_empty())
+                .collect();
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
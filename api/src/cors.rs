@@ -0,0 +1,64 @@
+use axum::http::HeaderValue;
+use tower_http::cors::AllowOrigin;
+
+use crate::config::CorsConfig;
+
+// The frontend's dev server
+const DEBUG_ORIGIN: &str = "http://localhost:1234";
+
+/// Origins allowed to make cross-origin requests, from `cors.origins`, or the frontend's dev
+/// server in debug builds if there aren't any. Origins can use a wildcard for one level of
+/// subdomains, like `https://*.jelli.fit`, for preview deployments.
+pub fn allowed_origins(config: &CorsConfig) -> AllowOrigin {
+    let origins: Vec<String> = match config.origins.is_empty() {
+        true => vec![DEBUG_ORIGIN.to_owned()],
+        false => config
+            .origins
+            .iter()
+            .map(|origin| origin.trim_end_matches('/').to_owned())
+            .collect(),
+    };
+
+    if origins.iter().any(|origin| origin.contains('*')) {
+        return AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            origin.to_str().is_ok_and(|origin| {
+                origins
+                    .iter()
+                    .any(|allowed| origin_matches(allowed, origin))
+            })
+        });
+    }
+
+    AllowOrigin::list(origins.iter().map(|origin| {
+        origin
+            .parse::<HeaderValue>()
+            .expect("Origins are checked when the config is loaded")
+    }))
+}
+
+/// Where links to the frontend point, the first of `cors.origins` without a wildcard, or the
+/// frontend's dev server if there isn't one
+pub fn frontend_url(config: &CorsConfig) -> String {
+    config
+        .origins
+        .iter()
+        .find(|origin| !origin.contains('*'))
+        .map(|origin| origin.trim_end_matches('/').to_owned())
+        .unwrap_or_else(|| DEBUG_ORIGIN.to_owned())
+}
+
+fn origin_matches(allowed: &str, origin: &str) -> bool {
+    match allowed.split_once("*.") {
+        Some((scheme, domain)) => origin
+            .strip_prefix(scheme)
+            .and_then(|rest| rest.strip_suffix(domain))
+            .and_then(|rest| rest.strip_suffix('.'))
+            .is_some_and(|subdomain| {
+                !subdomain.is_empty()
+                    && subdomain
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            }),
+        None => allowed == origin,
+    }
+}
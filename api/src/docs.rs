@@ -1,5 +1,7 @@
+use crate::chat;
 use crate::payloads;
 use crate::routes;
+use crate::scoring;
 
 use utoipa::openapi::security::ApiKey;
 use utoipa::openapi::security::ApiKeyValue;
@@ -14,25 +16,164 @@ use utoipa::{
     info(title = "Jelli Fit API"),
     paths(
         routes::stats::get_stats,
+        routes::stats::get_stats_timeseries,
+        routes::graphql::get_graphql,
+        routes::graphql::post_graphql,
+        routes::health::get_health,
+        routes::health::get_ready,
+        routes::meta::get_meta,
+        routes::meta::get_branding,
+        routes::meta::get_changelog,
         routes::event::create_event,
         routes::event::get_event,
+        routes::event::update_event,
+        routes::event::delete_event,
+        routes::event::finalize_event,
+        routes::event::merge_events,
+        routes::event::import_event,
+        routes::event::duplicate_event,
+        routes::group::get_group,
+        routes::group::copy_group_availability,
+        routes::template::create_template,
+        routes::template::create_template_event,
+        routes::event::put_webhook,
+        routes::event::delete_webhook,
+        routes::event::put_chat_webhook,
+        routes::event::delete_chat_webhook,
+        routes::event::lookup_events,
+        routes::event::get_events,
+        routes::event::extend_event,
+        routes::directory::get_directory,
+        routes::me::get_me,
+        routes::me::get_my_events,
+        routes::availability::get_availability,
+        routes::live::get_live_events,
+        routes::sync::get_sync,
+        routes::sync::post_sync,
+        routes::badge::get_badge,
+        routes::heatmap::get_heatmap_svg,
+        routes::heatmap::get_heatmap_png,
+        routes::short_link::get_short_link,
+        routes::calendar::get_calendar,
+        routes::calendar::get_person_calendar,
+        routes::interview::assign_interviews,
+        routes::activity::get_activity,
+        routes::analytics::get_analytics,
+        routes::export::get_export,
+        routes::comment::get_comments,
+        routes::comment::create_comment,
         routes::person::get_people,
         routes::person::get_person,
         routes::person::update_person,
+        routes::person::delete_person,
+        routes::person::rotate_edit_token,
+        routes::person::revoke_edit_token,
+        routes::person::copy_person_availability,
+        routes::google::connect_google,
+        routes::google::disconnect_google,
+        routes::google::get_suggested_availability,
+        routes::google::google_callback,
         routes::tasks::cleanup,
+        routes::tasks::seed,
+        routes::admin::get_route_matrix,
+        routes::admin::get_cache_stats,
+        routes::admin::get_adaptor_metrics,
+        routes::admin::delist_event,
+        routes::admin::restore_event,
+        routes::admin::list_events,
+        routes::admin::get_event_details,
+        routes::admin::purge_event,
     ),
     components(schemas(
         payloads::StatsResponse,
+        payloads::GraphqlInput,
+        payloads::ValidationErrorResponse,
+        payloads::FieldErrorResponse,
+        payloads::StatsGranularity,
+        payloads::StatsBucketResponse,
+        payloads::StatsTimeseriesResponse,
+        payloads::HealthResponse,
+        payloads::HealthStatus,
+        payloads::MetaResponse,
+        payloads::BrandingResponse,
+        payloads::ChangelogVersionResponse,
+        payloads::ChangeResponse,
+        payloads::ChangeKind,
         payloads::EventResponse,
         payloads::PersonResponse,
         payloads::EventInput,
+        payloads::EventLookupInput,
+        payloads::EventUpdateInput,
+        payloads::FinalizeInput,
+        payloads::MergeInput,
+        payloads::WebhookInput,
+        payloads::WebhookResponse,
+        payloads::ChatWebhookInput,
+        payloads::ChatWebhookResponse,
+        chat::ChatPlatform,
+        payloads::EventOwnerInput,
+        payloads::MergeResponse,
+        payloads::DuplicateInput,
+        payloads::GroupResponse,
+        payloads::GroupAvailabilityInput,
+        payloads::CopyAvailabilityInput,
+        payloads::GroupAvailabilityResponse,
+        payloads::ImportSource,
+        payloads::SlotKind,
+        payloads::TemplateInput,
+        payloads::TemplateResponse,
+        payloads::TemplateEventInput,
+        payloads::ImportInput,
+        payloads::ImportResponse,
+        payloads::SyncResponse,
+        payloads::SyncInput,
+        payloads::SyncMutation,
+        payloads::SyncResultResponse,
+        payloads::EventLookupResponse,
+        payloads::DirectoryEntryResponse,
+        payloads::ExtendResponse,
         payloads::PersonInput,
+        payloads::EditTokenResponse,
+        payloads::GoogleConnectResponse,
+        payloads::SuggestedAvailabilityResponse,
+        payloads::MeResponse,
+        payloads::LiveUpdate,
+        payloads::LiveUpdateKind,
+        payloads::PeopleSortParam,
+        payloads::SortOrder,
+        payloads::RespondedFilter,
+        payloads::SlotAvailabilityResponse,
+        payloads::LocalTimeResponse,
+        scoring::Scoring,
+        payloads::ActivityResponse,
+        payloads::EventAnalyticsResponse,
+        payloads::ExportResponse,
+        payloads::ActivityKind,
+        payloads::CommentInput,
+        payloads::CommentResponse,
+        payloads::InterviewInput,
+        payloads::InterviewPoolInput,
+        payloads::InterviewResponse,
+        payloads::InterviewAssignmentResponse,
+        payloads::InterviewerResponse,
+        routes::badge::BadgeKind,
+        routes::calendar::CalendarKind,
+        routes::Auth,
+        routes::RateLimit,
+        payloads::RouteMatrixResponse,
+        payloads::CacheStatsResponse,
+        payloads::AdaptorMetricsResponse,
+        payloads::AdminEventResponse,
+        payloads::CleanupResponse,
+        payloads::SeedResponse,
     )),
     tags(
         (name = "info"),
         (name = "event"),
         (name = "person"),
+        (name = "template"),
         (name = "tasks"),
+        (name = "admin"),
     ),
     modifiers(&SecurityAddon),
 )]
@@ -53,8 +194,24 @@ impl Modify for SecurityAddon {
             ),
         );
         openapi.components.as_mut().unwrap().add_security_scheme(
-            "cron-key",
-            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Cron-Key"))),
+            "edit-token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        openapi.components.as_mut().unwrap().add_security_scheme(
+            "creator-token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        openapi.components.as_mut().unwrap().add_security_scheme(
+            "id-token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Id-Token"))),
+        );
+        openapi.components.as_mut().unwrap().add_security_scheme(
+            "event-password",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Event-Password"))),
+        );
+        openapi.components.as_mut().unwrap().add_security_scheme(
+            "admin-key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Admin-Key"))),
         );
     }
 }
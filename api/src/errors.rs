@@ -1,10 +1,20 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{http::StatusCode, response::IntoResponse, Json};
 use common::Adaptor;
 
+use crate::payloads::{FieldErrorResponse, ValidationErrorResponse};
+
 pub enum ApiError<A: Adaptor> {
     AdaptorError(A::Error),
     NotFound,
     NotAuthorized,
+    InvalidInput(String),
+    InvalidFields(Vec<FieldErrorResponse>),
+    Conflict(String),
+    PreconditionFailed(String),
+    Spam,
+    CaptchaFailed,
+    /// A service the request relies on, like Google Calendar, failed
+    Upstream(String),
 }
 
 // Define what the error types above should return
@@ -17,6 +27,26 @@ impl<A: Adaptor> IntoResponse for ApiError<A> {
             }
             ApiError::NotFound => StatusCode::NOT_FOUND.into_response(),
             ApiError::NotAuthorized => StatusCode::UNAUTHORIZED.into_response(),
+            ApiError::InvalidInput(message) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+            }
+            ApiError::InvalidFields(errors) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ValidationErrorResponse {
+                    message: "Invalid input provided".to_owned(),
+                    errors,
+                }),
+            )
+                .into_response(),
+            ApiError::Conflict(message) => (StatusCode::CONFLICT, message).into_response(),
+            ApiError::PreconditionFailed(message) => {
+                (StatusCode::PRECONDITION_FAILED, message).into_response()
+            }
+            ApiError::Spam => (StatusCode::FORBIDDEN, "Rejected as spam").into_response(),
+            ApiError::CaptchaFailed => {
+                (StatusCode::FORBIDDEN, "Missing or invalid CAPTCHA token").into_response()
+            }
+            ApiError::Upstream(message) => (StatusCode::BAD_GATEWAY, message).into_response(),
         }
     }
 }
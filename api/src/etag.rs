@@ -0,0 +1,91 @@
+use std::hash::{Hash, Hasher};
+
+use axum::{
+    http::{
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use common::Event;
+use sha2::{Digest, Sha256};
+
+// Shared caches can keep responses about public events for a few minutes, as link previews
+// and embeds are fetched once and kept
+const PUBLIC_CACHE_CONTROL: &str = "public, max-age=300, stale-while-revalidate=86400";
+const PRIVATE_CACHE_CONTROL: &str = "private, no-store";
+
+/// The `Cache-Control` header for a response about an event, which can't be stored at all if
+/// the event has a password, so caches don't show it to people without one
+pub fn cache_control(event: &Event) -> &'static str {
+    match event.password_hash {
+        Some(_) => PRIVATE_CACHE_CONTROL,
+        None => PUBLIC_CACHE_CONTROL,
+    }
+}
+
+// Unlike `DefaultHasher`, gives the same hash whichever version of Rust the API was built
+// with, so tags stay valid across deploys
+struct StableHasher(Sha256);
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_be_bytes(
+            digest[..8]
+                .try_into()
+                .expect("SHA-256 is longer than 8 bytes"),
+        )
+    }
+}
+
+/// A weak ETag for a response, made from whatever the response depends on, so
+/// clients polling for changes can be told nothing has changed without building
+/// the response again
+pub struct ETag(String);
+
+impl ETag {
+    pub fn new(parts: impl Hash) -> Self {
+        let mut hasher = StableHasher(Sha256::new());
+        parts.hash(&mut hasher);
+        Self(format!("W/\"{:x}\"", hasher.finish()))
+    }
+
+    /// Whether the request's `If-None-Match` header includes this tag
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get_all(IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag == self.0)
+    }
+
+    /// Respond with 304 Not Modified, for when the client's copy is up to date
+    pub fn not_modified(self) -> Response {
+        (StatusCode::NOT_MODIFIED, [(ETAG, self.0)]).into_response()
+    }
+
+    /// Add the tag to a response
+    pub fn attach(self, response: impl IntoResponse) -> Response {
+        ([(ETAG, self.0)], response).into_response()
+    }
+}
+
+/// Whether the request's `If-Match` header allows changing something at this version, which
+/// it does if the header is missing. Tags can be sent with or without quotes.
+pub fn if_match(headers: &HeaderMap, version: &str) -> bool {
+    let mut tags = headers
+        .get_all(IF_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/").trim_matches('"'))
+        .peekable();
+    tags.peek().is_none() || tags.any(|tag| tag == "*" || tag == version)
+}
@@ -0,0 +1,276 @@
+use std::io::Write;
+
+use chrono::Utc;
+use common::{Activity, Adaptor, Comment, Event, EventQuery, PageRange, Person, Stats};
+use serde::Serialize;
+
+// Events are read a page at a time so the whole store never has to fit in memory
+const PAGE_SIZE: usize = 100;
+
+#[derive(Serialize)]
+struct ExportedStats {
+    event_count: i64,
+    person_count: i64,
+}
+
+impl From<Stats> for ExportedStats {
+    fn from(value: Stats) -> Self {
+        Self {
+            event_count: value.event_count,
+            person_count: value.person_count,
+        }
+    }
+}
+
+/// Everything stored about an event, exactly as the adaptor has it
+#[derive(Serialize)]
+struct ExportedEvent {
+    id: String,
+    name: String,
+    created_at: i64,
+    visited_at: i64,
+    updated_at: i64,
+    expired_at: Option<i64>,
+    times: Vec<String>,
+    timezone: String,
+    edit_token_hash: Option<String>,
+    scoring: Option<String>,
+    listed: bool,
+    tags: Vec<String>,
+    finalized_time: Option<String>,
+    password_hash: Option<String>,
+    invitees: Vec<String>,
+    responses_closed: bool,
+    webhook_url: Option<String>,
+    webhook_secret: Option<String>,
+    removed_people: Vec<ExportedRemovedPerson>,
+    retention_days: Option<i64>,
+    group_id: Option<String>,
+    creator_id: Option<String>,
+    max_people: Option<i64>,
+    chat_webhook_url: Option<String>,
+    people: Vec<ExportedPerson>,
+    comments: Vec<ExportedComment>,
+    activity: Vec<ExportedActivity>,
+}
+
+#[derive(Serialize)]
+struct ExportedRemovedPerson {
+    name: String,
+    removed_at: i64,
+}
+
+#[derive(Serialize)]
+struct ExportedPerson {
+    name: String,
+    password_hash: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+    availability: Vec<String>,
+    if_needed: Vec<String>,
+    undecided: Vec<String>,
+    edit_token_hash: Option<String>,
+    edit_token_expires_at: Option<i64>,
+    subject_id: Option<String>,
+    avatar_color: Option<String>,
+    avatar_emoji: Option<String>,
+    timezone: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ExportedComment {
+    author: String,
+    body: String,
+    created_at: i64,
+}
+
+#[derive(Serialize)]
+struct ExportedActivity {
+    kind: String,
+    person: Option<String>,
+    created_at: i64,
+}
+
+impl ExportedEvent {
+    fn new(
+        event: Event,
+        people: Vec<Person>,
+        comments: Vec<Comment>,
+        activity: Vec<Activity>,
+    ) -> Self {
+        Self {
+            id: event.id,
+            name: event.name,
+            created_at: event.created_at.timestamp(),
+            visited_at: event.visited_at.timestamp(),
+            updated_at: event.updated_at.timestamp(),
+            expired_at: event.expired_at.map(|t| t.timestamp()),
+            times: event.times,
+            timezone: event.timezone,
+            edit_token_hash: event.edit_token_hash,
+            scoring: event.scoring,
+            listed: event.listed,
+            tags: event.tags,
+            finalized_time: event.finalized_time,
+            password_hash: event.password_hash,
+            invitees: event.invitees,
+            responses_closed: event.responses_closed,
+            webhook_url: event.webhook_url,
+            webhook_secret: event.webhook_secret,
+            removed_people: event
+                .removed_people
+                .into_iter()
+                .map(|p| ExportedRemovedPerson {
+                    name: p.name,
+                    removed_at: p.removed_at.timestamp(),
+                })
+                .collect(),
+            retention_days: event.retention_days,
+            group_id: event.group_id,
+            creator_id: event.creator_id,
+            max_people: event.max_people,
+            chat_webhook_url: event.chat_webhook_url,
+            people: people.into_iter().map(ExportedPerson::from).collect(),
+            comments: comments
+                .into_iter()
+                .map(|c| ExportedComment {
+                    author: c.author,
+                    body: c.body,
+                    created_at: c.created_at.timestamp(),
+                })
+                .collect(),
+            activity: activity
+                .into_iter()
+                .map(|a| ExportedActivity {
+                    kind: a.kind,
+                    person: a.person,
+                    created_at: a.created_at.timestamp(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Person> for ExportedPerson {
+    fn from(value: Person) -> Self {
+        Self {
+            name: value.name,
+            password_hash: value.password_hash,
+            created_at: value.created_at.timestamp(),
+            updated_at: value.updated_at.timestamp(),
+            availability: value.availability,
+            if_needed: value.if_needed,
+            undecided: value.undecided,
+            edit_token_hash: value.edit_token_hash,
+            edit_token_expires_at: value.edit_token_expires_at.map(|t| t.timestamp()),
+            subject_id: value.subject_id,
+            avatar_color: value.avatar_color,
+            avatar_emoji: value.avatar_emoji,
+            timezone: value.timezone,
+        }
+    }
+}
+
+/// Write every event, including expired ones, with their people, comments and activity as
+/// one JSON document, along with the stats. Reading events doesn't count as visiting them.
+///
+/// Hashes of passwords and tokens are included, so a backup can be restored with everyone
+/// still able to edit, which means the export should be kept as private as the database.
+/// Templates and idempotency keys are left out, as they can't be listed.
+///
+/// Returns how many events were written.
+pub async fn export<A: Adaptor, W: Write>(adaptor: &A, mut writer: W) -> Result<usize, String> {
+    let stats = adaptor.get_stats().await.map_err(|e| e.to_string())?;
+    write!(
+        writer,
+        "{{\"exported_at\":{},\"stats\":{},\"events\":[",
+        Utc::now().timestamp(),
+        serde_json::to_string(&ExportedStats::from(stats)).map_err(|e| e.to_string())?
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut offset = 0;
+    let mut count = 0;
+    loop {
+        let page = adaptor
+            .query_events(
+                EventQuery::default(),
+                PageRange {
+                    offset,
+                    limit: Some(PAGE_SIZE),
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        if page.items.is_empty() {
+            break;
+        }
+
+        for event in page.items {
+            // Events deleted since the page was read are skipped
+            let Some(exported) = export_event(adaptor, event).await? else {
+                continue;
+            };
+
+            if count > 0 {
+                writer.write_all(b",").map_err(|e| e.to_string())?;
+            }
+            serde_json::to_writer(&mut writer, &exported).map_err(|e| e.to_string())?;
+            count += 1;
+        }
+        offset += PAGE_SIZE;
+    }
+
+    writer.write_all(b"]}").map_err(|e| e.to_string())?;
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// Write events with their people, comments and activity as newline-delimited JSON, one
+/// event per line in the same format as the export
+///
+/// Returns the IDs of the events that were written, events that have already been deleted
+/// are left out.
+pub async fn export_lines<A: Adaptor, W: Write>(
+    adaptor: &A,
+    events: Vec<Event>,
+    mut writer: W,
+) -> Result<Vec<String>, String> {
+    let mut written = Vec::new();
+    for event in events {
+        let id = event.id.clone();
+        let Some(exported) = export_event(adaptor, event).await? else {
+            continue;
+        };
+        serde_json::to_writer(&mut writer, &exported).map_err(|e| e.to_string())?;
+        writer.write_all(b"\n").map_err(|e| e.to_string())?;
+        written.push(id);
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(written)
+}
+
+// Everything stored about an event, or None if it was deleted before it could all be read
+async fn export_event<A: Adaptor>(
+    adaptor: &A,
+    event: Event,
+) -> Result<Option<ExportedEvent>, String> {
+    let (Some(people), Some(comments), Some(activity)) = (
+        adaptor
+            .get_people(event.id.clone())
+            .await
+            .map_err(|e| e.to_string())?,
+        adaptor
+            .get_comments(event.id.clone())
+            .await
+            .map_err(|e| e.to_string())?,
+        adaptor
+            .get_activity(event.id.clone())
+            .await
+            .map_err(|e| e.to_string())?,
+    ) else {
+        return Ok(None);
+    };
+
+    Ok(Some(ExportedEvent::new(event, people, comments, activity)))
+}
@@ -0,0 +1,314 @@
+use std::{collections::HashMap, time::Duration as StdDuration};
+
+use axum::http::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    Request, StatusCode,
+};
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use hyper::{body, client::HttpConnector, Body, Client};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::{
+    config::GoogleConfig,
+    slots::{Slot, SLOT_MINUTES},
+};
+
+const AUTHORIZE_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+const CALENDAR_URL: &str = "https://www.googleapis.com/calendar/v3";
+// Only lets the API see when someone is busy, not what their events are
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.freebusy";
+// How long someone has to finish connecting their calendar after starting
+const STATE_LIFETIME_MINUTES: i64 = 10;
+const REQUEST_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+/// Why someone's busy times couldn't be read
+pub enum CalendarError {
+    /// The person took back access, so the refresh token is no use any more
+    Revoked,
+    Failed(String),
+}
+
+// Which person on which event a calendar is being connected for, signed so the callback can
+// trust it without anything being stored in between
+#[derive(Serialize, Deserialize)]
+struct ConnectState {
+    event_id: String,
+    person_name: String,
+    expires_at: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct FreeBusyResponse {
+    calendars: HashMap<String, FreeBusyCalendar>,
+}
+
+#[derive(Deserialize)]
+struct FreeBusyCalendar {
+    #[serde(default)]
+    busy: Vec<BusyPeriod>,
+}
+
+#[derive(Deserialize)]
+struct BusyPeriod {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+/// Reads when people are busy from their Google Calendar, once they've let this API's OAuth
+/// client in `google.client_id` do so. Their tokens stay on the server.
+pub struct GoogleCalendar {
+    client_id: String,
+    client_secret: String,
+    redirect_url: String,
+    token_url: String,
+    calendar_url: String,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl GoogleCalendar {
+    /// Calendars can't be connected unless there's a client ID, and the config has already
+    /// checked there's a secret and redirect URL with it
+    pub fn from_config(config: &GoogleConfig) -> Option<Self> {
+        let client_id = config.client_id.clone()?;
+        let client_secret = config.client_secret.clone()?;
+        let redirect_url = config.redirect_url.clone()?;
+
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Some(Self {
+            client_id,
+            client_secret,
+            redirect_url,
+            token_url: config
+                .token_url
+                .clone()
+                .unwrap_or_else(|| TOKEN_URL.to_owned()),
+            calendar_url: config
+                .calendar_url
+                .clone()
+                .unwrap_or_else(|| CALENDAR_URL.to_owned())
+                .trim_end_matches('/')
+                .to_owned(),
+            client: Client::builder().build(connector),
+        })
+    }
+
+    /// Where to send someone to let the API read their free/busy times, which comes back to
+    /// `/google/callback` afterwards
+    pub fn authorize_url(&self, event_id: &str, person_name: &str) -> String {
+        let state = self.sign(&ConnectState {
+            event_id: event_id.to_owned(),
+            person_name: person_name.to_owned(),
+            expires_at: (Utc::now() + Duration::minutes(STATE_LIFETIME_MINUTES)).timestamp(),
+        });
+        let query = serde_urlencoded::to_string([
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", &self.redirect_url),
+            ("response_type", "code"),
+            ("scope", SCOPE),
+            // A refresh token is only given with offline access, and only on consent
+            ("access_type", "offline"),
+            ("prompt", "consent"),
+            ("state", &state),
+        ])
+        .expect("Strings can always be URL encoded");
+        format!("{}?{}", AUTHORIZE_URL, query)
+    }
+
+    /// The event ID and person name from a callback's `state`, if it was made by this API and
+    /// hasn't expired
+    pub fn verify_state(&self, state: &str) -> Option<(String, String)> {
+        let (payload, signature) = state.split_once('.')?;
+        let signature = general_purpose::URL_SAFE_NO_PAD.decode(signature).ok()?;
+        self.mac(payload).verify_slice(&signature).ok()?;
+
+        let payload = general_purpose::URL_SAFE_NO_PAD.decode(payload).ok()?;
+        let state: ConnectState = serde_json::from_slice(&payload).ok()?;
+        (state.expires_at > Utc::now().timestamp()).then_some((state.event_id, state.person_name))
+    }
+
+    /// Swap the code from a callback for a refresh token
+    pub async fn exchange_code(&self, code: &str) -> Result<String, String> {
+        let response: TokenResponse = self
+            .token_request(&[
+                ("code", code),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("redirect_uri", &self.redirect_url),
+                ("grant_type", "authorization_code"),
+            ])
+            .await
+            .map_err(|e| match e {
+                CalendarError::Revoked => "The code was invalid or already used".to_owned(),
+                CalendarError::Failed(e) => e,
+            })?;
+        response
+            .refresh_token
+            .ok_or_else(|| "No refresh token was given".to_owned())
+    }
+
+    /// When someone is busy between two times in their primary calendar
+    pub async fn busy(
+        &self,
+        refresh_token: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>, CalendarError> {
+        let access_token = self
+            .token_request::<TokenResponse>(&[
+                ("refresh_token", refresh_token),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("grant_type", "refresh_token"),
+            ])
+            .await?
+            .access_token
+            .ok_or_else(|| CalendarError::Failed("No access token was given".to_owned()))?;
+
+        let body = json!({
+            "timeMin": start.to_rfc3339(),
+            "timeMax": end.to_rfc3339(),
+            "items": [{ "id": "primary" }],
+        });
+        let request = Request::post(format!("{}/freeBusy", self.calendar_url))
+            .header(AUTHORIZATION, format!("Bearer {}", access_token))
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .map_err(|e| CalendarError::Failed(e.to_string()))?;
+        let response: FreeBusyResponse = self.send(request).await?;
+
+        Ok(response
+            .calendars
+            .into_values()
+            .flat_map(|calendar| calendar.busy)
+            .map(|period| (period.start, period.end))
+            .collect())
+    }
+
+    /// Let Google know a refresh token won't be used again
+    pub async fn revoke(&self, refresh_token: &str) -> Result<(), String> {
+        let form =
+            serde_urlencoded::to_string([("token", refresh_token)]).map_err(|e| e.to_string())?;
+        let request = Request::post(REVOKE_URL)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(form))
+            .map_err(|e| e.to_string())?;
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| "Timed out".to_owned())?
+            .map_err(|e| e.to_string())?;
+        // Tokens that were already revoked are rejected, which is just as good
+        if !response.status().is_success() && response.status() != StatusCode::BAD_REQUEST {
+            return Err(format!("Responded with {}", response.status()));
+        }
+        Ok(())
+    }
+
+    async fn token_request<T: DeserializeOwned>(
+        &self,
+        form: &[(&str, &str)],
+    ) -> Result<T, CalendarError> {
+        let form =
+            serde_urlencoded::to_string(form).map_err(|e| CalendarError::Failed(e.to_string()))?;
+        let request = Request::post(&self.token_url)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(form))
+            .map_err(|e| CalendarError::Failed(e.to_string()))?;
+        self.send(request).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: Request<Body>) -> Result<T, CalendarError> {
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| CalendarError::Failed("Timed out".to_owned()))?
+            .map_err(|e| CalendarError::Failed(e.to_string()))?;
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| CalendarError::Failed(e.to_string()))?;
+
+        if !status.is_success() {
+            let revoked = serde_json::from_slice::<TokenError>(&bytes)
+                .is_ok_and(|e| e.error == "invalid_grant");
+            return Err(match revoked {
+                true => CalendarError::Revoked,
+                false => CalendarError::Failed(format!("Responded with {}", status)),
+            });
+        }
+        serde_json::from_slice(&bytes)
+            .map_err(|e| CalendarError::Failed(format!("Invalid response: {}", e)))
+    }
+
+    fn sign(&self, state: &ConnectState) -> String {
+        let payload = general_purpose::URL_SAFE_NO_PAD
+            .encode(serde_json::to_vec(state).expect("The state can always be serialized"));
+        let signature =
+            general_purpose::URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.client_secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+/// When a slot starts, with days of the week resolved to the next time they come around
+pub fn upcoming_start(slot: &Slot) -> DateTime<Utc> {
+    let start = slot.datetime();
+    match slot {
+        Slot::Weekday(..) if start + Duration::minutes(SLOT_MINUTES) <= Utc::now() => {
+            start + Duration::days(7)
+        }
+        _ => start,
+    }
+}
+
+/// Split an event's times into the ones that don't overlap any busy period, and the ones that
+/// do
+pub fn split_busy(
+    times: &[String],
+    busy: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> (Vec<String>, Vec<String>) {
+    let mut free_times = Vec::new();
+    let mut busy_times = Vec::new();
+    for time in times {
+        let Ok(slot) = time.parse::<Slot>() else {
+            continue;
+        };
+        let start = upcoming_start(&slot);
+        let end = start + Duration::minutes(SLOT_MINUTES);
+        match busy
+            .iter()
+            .any(|(busy_start, busy_end)| start < *busy_end && *busy_start < end)
+        {
+            true => busy_times.push(time.clone()),
+            false => free_times.push(time.clone()),
+        }
+    }
+    (free_times, busy_times)
+}
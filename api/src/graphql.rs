@@ -0,0 +1,445 @@
+use std::marker::PhantomData;
+
+use async_graphql::{
+    Context, EmptySubscription, Error, ErrorExtensions, InputObject, Object, Result, Schema,
+    SimpleObject,
+};
+use axum::{
+    headers::{authorization::Bearer, Authorization},
+    http::HeaderMap,
+    TypedHeader,
+};
+use common::{Adaptor, Stats};
+
+use crate::{
+    auth::Identity,
+    captcha::check_captcha,
+    errors::ApiError,
+    middleware::client_ip::ClientIp,
+    names::locale_from_headers,
+    payloads::{EditTokenParams, EventInput, EventResponse, PersonInput, PersonResponse},
+    routes::{
+        analytics::record_view,
+        event::{get_authorized_event, insert_event},
+        person::{login_or_create_person, save_availability},
+    },
+    validation, AppState,
+};
+
+pub type GraphqlSchema<A> = Schema<QueryRoot<A>, MutationRoot<A>, EmptySubscription>;
+
+// Deep enough for the introspection query, which nests `ofType` to unwrap list and non-null
+// types, with a complexity that stops one query aliasing `event` enough times to act like
+// hundreds of requests
+const MAX_DEPTH: usize = 16;
+const MAX_COMPLEXITY: usize = 500;
+
+/// Build the schema once, the state and request details are attached to each request
+pub fn schema<A: Adaptor + 'static>() -> GraphqlSchema<A> {
+    Schema::build(
+        QueryRoot(PhantomData),
+        MutationRoot(PhantomData),
+        EmptySubscription,
+    )
+    .limit_depth(MAX_DEPTH)
+    .limit_complexity(MAX_COMPLEXITY)
+    .finish()
+}
+
+/// What resolvers need from the HTTP request, so they authenticate the same way as the
+/// REST routes, with the bearer token, `X-Event-Password` header and who's logged in
+pub struct RequestContext<A> {
+    pub state: AppState<A>,
+    pub headers: HeaderMap,
+    pub bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    pub client_ip: Option<ClientIp>,
+    pub identity: Option<Identity>,
+}
+
+fn request<'a, A: Adaptor + 'static>(ctx: &Context<'a>) -> &'a RequestContext<A> {
+    ctx.data_unchecked::<RequestContext<A>>()
+}
+
+// Errors keep the REST route's status as a `code` extension, so clients can tell them apart
+fn graphql_error<A: Adaptor>(error: ApiError<A>) -> Error {
+    let (code, message) = match error {
+        ApiError::AdaptorError(e) => {
+            tracing::error!(?e);
+            ("INTERNAL_SERVER_ERROR", "Internal server error".to_owned())
+        }
+        ApiError::NotFound => ("NOT_FOUND", "Not found".to_owned()),
+        ApiError::NotAuthorized => ("UNAUTHORIZED", "Missing or incorrect password".to_owned()),
+        ApiError::InvalidInput(message) => ("INVALID_INPUT", message),
+        ApiError::InvalidFields(errors) => ("INVALID_INPUT", validation::summary(&errors)),
+        ApiError::Conflict(message) => ("CONFLICT", message),
+        ApiError::PreconditionFailed(message) => ("PRECONDITION_FAILED", message),
+        ApiError::Spam => ("SPAM", "Rejected as spam".to_owned()),
+        ApiError::CaptchaFailed => (
+            "CAPTCHA_FAILED",
+            "Missing or invalid CAPTCHA token".to_owned(),
+        ),
+        ApiError::Upstream(message) => ("BAD_GATEWAY", message),
+    };
+    Error::new(message).extend_with(|_, e| e.set("code", code))
+}
+
+pub struct QueryRoot<A>(PhantomData<A>);
+
+#[Object]
+impl<A: Adaptor + 'static> QueryRoot<A> {
+    /// Get an event, which counts as a view in its analytics like the REST route
+    async fn event(&self, ctx: &Context<'_>, id: String) -> Result<EventObject<A>> {
+        let request = request::<A>(ctx);
+        let adaptor = &request.state.adaptor;
+
+        let event = get_authorized_event(adaptor, id.clone(), &request.headers)
+            .await
+            .map_err(graphql_error)?;
+        record_view(&request.state.jobs, id, request.client_ip);
+
+        Ok(EventObject::new(event.into()))
+    }
+
+    /// How many events and people have been created
+    async fn stats(&self, ctx: &Context<'_>) -> Result<StatsObject> {
+        let state = &request::<A>(ctx).state;
+
+        let stats = state
+            .adaptor
+            .get_stats()
+            .await
+            .map_err(|e| graphql_error(ApiError::<A>::AdaptorError(e)))?;
+        let pending = state.stat_counters.pending();
+
+        Ok(Stats {
+            event_count: stats.event_count + pending.event_count,
+            person_count: stats.person_count + pending.person_count,
+        }
+        .into())
+    }
+}
+
+pub struct MutationRoot<A>(PhantomData<A>);
+
+#[Object]
+impl<A: Adaptor + 'static> MutationRoot<A> {
+    /// Create a new event, the response includes the `editToken` needed to manage it, and a
+    /// `creatorToken` to list it along with other events created with the same token.
+    /// Needs an `X-Captcha-Token` header if the instance has CAPTCHAs turned on.
+    async fn create_event(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateEventInput,
+    ) -> Result<EventObject<A>> {
+        let request = request::<A>(ctx);
+
+        check_captcha::<A>(
+            request.state.captcha.as_ref(),
+            &request.headers,
+            request.client_ip.map(|ClientIp(ip)| ip),
+        )
+        .await
+        .map_err(graphql_error)?;
+
+        let event = insert_event(
+            &request.state,
+            request.identity.as_ref(),
+            EventInput {
+                name: input.name,
+                times: input.times,
+                timezone: input.timezone,
+                scoring: None,
+                listed: input.listed,
+                tags: input.tags,
+                password: input.password,
+                invitees: input.invitees,
+                slug: input.slug,
+                retention_days: input.retention_days,
+                max_people: input.max_people,
+                group_id: input.group_id,
+                locale: input
+                    .locale
+                    .or_else(|| locale_from_headers(&request.headers)),
+                creator_token: input.creator_token,
+                chat_webhook_url: None,
+            },
+        )
+        .await
+        .map_err(graphql_error)?;
+
+        Ok(EventObject::new(event))
+    }
+
+    /// Log in as a person on an event, or add them if nobody has their name yet, with the
+    /// bearer token as their password
+    async fn join_event(
+        &self,
+        ctx: &Context<'_>,
+        event_id: String,
+        name: String,
+        edit_token: Option<String>,
+    ) -> Result<PersonObject> {
+        let request = request::<A>(ctx);
+
+        let person = login_or_create_person(
+            &request.state,
+            event_id,
+            name,
+            EditTokenParams { edit_token },
+            request.bearer.clone(),
+            request.identity.as_ref(),
+            &request.headers,
+        )
+        .await
+        .map_err(graphql_error)?;
+
+        Ok(PersonObject(person.into()))
+    }
+
+    /// Update a person's availability, with the bearer token as their password
+    async fn update_person(
+        &self,
+        ctx: &Context<'_>,
+        event_id: String,
+        name: String,
+        edit_token: Option<String>,
+        input: UpdatePersonInput,
+    ) -> Result<PersonObject> {
+        let request = request::<A>(ctx);
+
+        let person = save_availability(
+            &request.state,
+            event_id,
+            name,
+            EditTokenParams { edit_token },
+            request.bearer.clone(),
+            request.identity.as_ref(),
+            &request.headers,
+            PersonInput {
+                availability: input.availability,
+                if_needed: input.if_needed,
+                undecided: input.undecided,
+                avatar_color: input.avatar_color,
+                avatar_emoji: input.avatar_emoji,
+                timezone: input.timezone,
+            },
+        )
+        .await
+        .map_err(graphql_error)?;
+
+        Ok(PersonObject(person))
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateEventInput {
+    name: Option<String>,
+    times: Vec<String>,
+    timezone: String,
+    listed: Option<bool>,
+    tags: Option<Vec<String>>,
+    password: Option<String>,
+    invitees: Option<Vec<String>>,
+    slug: Option<String>,
+    retention_days: Option<i64>,
+    max_people: Option<i64>,
+    group_id: Option<String>,
+    locale: Option<String>,
+    creator_token: Option<String>,
+}
+
+#[derive(InputObject)]
+pub struct UpdatePersonInput {
+    availability: Vec<String>,
+    if_needed: Option<Vec<String>>,
+    undecided: Option<Vec<String>>,
+    avatar_color: Option<String>,
+    avatar_emoji: Option<String>,
+    timezone: Option<String>,
+}
+
+pub struct EventObject<A> {
+    event: EventResponse,
+    adaptor: PhantomData<A>,
+}
+
+impl<A> EventObject<A> {
+    fn new(event: EventResponse) -> Self {
+        Self {
+            event,
+            adaptor: PhantomData,
+        }
+    }
+}
+
+#[Object(name = "Event")]
+impl<A: Adaptor + 'static> EventObject<A> {
+    async fn id(&self) -> &str {
+        &self.event.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.event.name
+    }
+
+    async fn times(&self) -> &[String] {
+        &self.event.times
+    }
+
+    async fn timezone(&self) -> &str {
+        &self.event.timezone
+    }
+
+    async fn created_at(&self) -> i64 {
+        self.event.created_at
+    }
+
+    /// Token needed to manage the event, only included when the event is created
+    async fn edit_token(&self) -> Option<&str> {
+        self.event.edit_token.as_deref()
+    }
+
+    /// Token to list the events created with it, only included when the event is created
+    async fn creator_token(&self) -> Option<&str> {
+        self.event.creator_token.as_deref()
+    }
+
+    async fn listed(&self) -> bool {
+        self.event.listed
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.event.tags
+    }
+
+    /// The time chosen by the organizer, null until the event is finalized
+    async fn finalized_time(&self) -> Option<&str> {
+        self.event.finalized_time.as_deref()
+    }
+
+    /// Whether a password is needed to view the event
+    async fn private(&self) -> bool {
+        self.event.private
+    }
+
+    async fn invitees(&self) -> &[String] {
+        &self.event.invitees
+    }
+
+    async fn responses_closed(&self) -> bool {
+        self.event.responses_closed
+    }
+
+    async fn retention_days(&self) -> Option<i64> {
+        self.event.retention_days
+    }
+
+    /// The most people that can join the event, null if there's no limit
+    async fn max_people(&self) -> Option<i64> {
+        self.event.max_people
+    }
+
+    async fn group_id(&self) -> Option<&str> {
+        self.event.group_id.as_deref()
+    }
+
+    /// People on the event, only those who have filled in their availability unless `all`
+    /// is set, like the REST route
+    async fn people(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] all: bool,
+    ) -> Result<Vec<PersonObject>> {
+        let people = request::<A>(ctx)
+            .state
+            .adaptor
+            .get_people(self.event.id.clone())
+            .await
+            .map_err(|e| graphql_error(ApiError::<A>::AdaptorError(e)))?
+            .unwrap_or_default();
+
+        Ok(people
+            .into_iter()
+            .filter(|person| all || !person.availability.is_empty())
+            .map(|person| PersonObject(person.into()))
+            .collect())
+    }
+}
+
+pub struct PersonObject(PersonResponse);
+
+#[Object(name = "Person")]
+impl PersonObject {
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn availability(&self) -> &[String] {
+        &self.0.availability
+    }
+
+    /// Times in `availability` the person can only make if needed
+    async fn if_needed(&self) -> &[String] {
+        &self.0.if_needed
+    }
+
+    /// Times the person hasn't decided on yet
+    async fn undecided(&self) -> &[String] {
+        &self.0.undecided
+    }
+
+    /// Hex color like `#ff8800` the person has chosen to be shown in
+    async fn avatar_color(&self) -> Option<&str> {
+        self.0.avatar_color.as_deref()
+    }
+
+    /// Emoji the person has chosen to show next to their name
+    async fn avatar_emoji(&self) -> Option<&str> {
+        self.0.avatar_emoji.as_deref()
+    }
+
+    /// The person's own timezone, if they've set one
+    async fn timezone(&self) -> Option<&str> {
+        self.0.timezone.as_deref()
+    }
+
+    async fn created_at(&self) -> i64 {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> i64 {
+        self.0.updated_at
+    }
+
+    /// Changes whenever the person does
+    async fn version(&self) -> &str {
+        &self.0.version
+    }
+
+    /// Token for editing without a password, only included when it's first issued
+    async fn edit_token(&self) -> Option<&str> {
+        self.0.edit_token.as_deref()
+    }
+
+    async fn edit_token_expires_at(&self) -> Option<i64> {
+        self.0.edit_token_expires_at
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Stats")]
+pub struct StatsObject {
+    event_count: i64,
+    person_count: i64,
+    version: String,
+}
+
+impl From<Stats> for StatsObject {
+    fn from(value: Stats) -> Self {
+        Self {
+            event_count: value.event_count,
+            person_count: value.person_count,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{self, Path, Query},
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
+    http::HeaderMap,
+    TypedHeader,
+};
+use common::Adaptor;
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::{
+    captcha::check_captcha,
+    config::config,
+    errors::ApiError,
+    middleware::edit_token::{get_owned_event, OwnedEvent},
+    payloads::{EditTokenParams, EventInput, EventResponse, PersonInput, PersonResponse},
+    routes::{
+        analytics::record_view,
+        event::{delete_event, get_authorized_event, insert_event},
+        person::{delete_person, login_or_create_person, save_availability},
+        stats::get_stats,
+    },
+    shutdown::Shutdown,
+    validation, AppState,
+};
+
+mod proto {
+    tonic::include_proto!("jellifit.v1");
+}
+
+use proto::{
+    jelli_fit_server::{JelliFit, JelliFitServer},
+    CreateEventRequest, DeleteEventRequest, DeletePersonRequest, Empty, Event, GetEventRequest,
+    GetPersonRequest, ListPeopleRequest, ListPeopleResponse, Person, Stats, UpdatePersonRequest,
+};
+
+/// Serve the gRPC API on `server.grpc_port`, alongside the HTTP server and on the same host
+pub async fn serve<A: Adaptor + 'static>(state: AppState<A>, shutdown: Shutdown) {
+    let server = &config().server;
+    let addr = SocketAddr::new(server.host, server.grpc_port);
+
+    println!("🪼 Jelli Fit gRPC API listening at http://{}", addr);
+    if let Err(e) = Server::builder()
+        .add_service(JelliFitServer::new(GrpcService { state }))
+        .serve_with_shutdown(addr, shutdown.signalled())
+        .await
+    {
+        tracing::error!("gRPC server stopped: {}", e);
+    }
+}
+
+struct GrpcService<A> {
+    state: AppState<A>,
+}
+
+// Requests carry the same credentials as the HTTP API, as metadata instead of headers
+fn credentials<T>(request: &Request<T>) -> (HeaderMap, Option<TypedHeader<Authorization<Bearer>>>) {
+    let headers = request.metadata().clone().into_headers();
+    let bearer = headers
+        .typed_get::<Authorization<Bearer>>()
+        .map(TypedHeader);
+    (headers, bearer)
+}
+
+fn status<A: Adaptor>(error: ApiError<A>) -> Status {
+    match error {
+        ApiError::AdaptorError(e) => {
+            tracing::error!(?e);
+            Status::internal("Internal server error")
+        }
+        ApiError::NotFound => Status::not_found("Not found"),
+        ApiError::NotAuthorized => Status::unauthenticated("Missing or incorrect password"),
+        ApiError::InvalidInput(message) => Status::invalid_argument(message),
+        ApiError::InvalidFields(errors) => Status::invalid_argument(validation::summary(&errors)),
+        ApiError::Conflict(message) | ApiError::PreconditionFailed(message) => {
+            Status::failed_precondition(message)
+        }
+        ApiError::Spam => Status::permission_denied("Rejected as spam"),
+        ApiError::CaptchaFailed => Status::permission_denied("Missing or invalid CAPTCHA token"),
+        ApiError::Upstream(message) => Status::unavailable(message),
+    }
+}
+
+#[tonic::async_trait]
+impl<A: Adaptor + 'static> JelliFit for GrpcService<A> {
+    async fn get_event(
+        &self,
+        request: Request<GetEventRequest>,
+    ) -> Result<Response<Event>, Status> {
+        let (headers, _) = credentials(&request);
+        let id = request.into_inner().id;
+        let adaptor = &self.state.adaptor;
+
+        let event = get_authorized_event(adaptor, id.clone(), &headers)
+            .await
+            .map_err(status)?;
+        record_view(&self.state.jobs, id, None);
+
+        Ok(Response::new(EventResponse::from(event).into()))
+    }
+
+    async fn create_event(
+        &self,
+        request: Request<CreateEventRequest>,
+    ) -> Result<Response<Event>, Status> {
+        let (headers, _) = credentials(&request);
+        check_captcha::<A>(
+            self.state.captcha.as_ref(),
+            &headers,
+            request.remote_addr().map(|addr| addr.ip()),
+        )
+        .await
+        .map_err(status)?;
+        let input = request.into_inner();
+
+        let event = insert_event(
+            &self.state,
+            None,
+            EventInput {
+                name: input.name,
+                times: input.times,
+                timezone: input.timezone,
+                scoring: None,
+                listed: input.listed,
+                tags: Some(input.tags),
+                password: input.password,
+                invitees: Some(input.invitees),
+                slug: input.slug,
+                retention_days: input.retention_days,
+                max_people: input.max_people,
+                group_id: input.group_id,
+                locale: input.locale,
+                creator_token: input.creator_token,
+                chat_webhook_url: None,
+            },
+        )
+        .await
+        .map_err(status)?;
+
+        Ok(Response::new(event.into()))
+    }
+
+    async fn delete_event(
+        &self,
+        request: Request<DeleteEventRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (_, bearer) = credentials(&request);
+        let event = get_owned_event(
+            &self.state.adaptor,
+            request.into_inner().id,
+            bearer
+                .as_ref()
+                .map(|TypedHeader(Authorization(bearer))| bearer.token()),
+        )
+        .await
+        .map_err(status)?;
+
+        delete_event(extract::State(self.state.clone()), OwnedEvent(event))
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn list_people(
+        &self,
+        request: Request<ListPeopleRequest>,
+    ) -> Result<Response<ListPeopleResponse>, Status> {
+        let (headers, _) = credentials(&request);
+        let input = request.into_inner();
+        let adaptor = &self.state.adaptor;
+
+        get_authorized_event(adaptor, input.event_id.clone(), &headers)
+            .await
+            .map_err(status)?;
+        let people = adaptor
+            .get_people(input.event_id)
+            .await
+            .map_err(|e| status(ApiError::<A>::AdaptorError(e)))?
+            .unwrap_or_default();
+
+        Ok(Response::new(ListPeopleResponse {
+            people: people
+                .into_iter()
+                .filter(|person| input.all || !person.availability.is_empty())
+                .map(|person| PersonResponse::from(person).into())
+                .collect(),
+        }))
+    }
+
+    async fn get_person(
+        &self,
+        request: Request<GetPersonRequest>,
+    ) -> Result<Response<Person>, Status> {
+        let (headers, bearer) = credentials(&request);
+        let input = request.into_inner();
+
+        let person = login_or_create_person(
+            &self.state,
+            input.event_id,
+            input.name,
+            EditTokenParams {
+                edit_token: input.edit_token,
+            },
+            bearer,
+            None,
+            &headers,
+        )
+        .await
+        .map_err(status)?;
+
+        Ok(Response::new(PersonResponse::from(person).into()))
+    }
+
+    async fn update_person(
+        &self,
+        request: Request<UpdatePersonRequest>,
+    ) -> Result<Response<Person>, Status> {
+        let (headers, bearer) = credentials(&request);
+        let input = request.into_inner();
+
+        let person = save_availability(
+            &self.state,
+            input.event_id,
+            input.name,
+            EditTokenParams {
+                edit_token: input.edit_token,
+            },
+            bearer,
+            None,
+            &headers,
+            PersonInput {
+                availability: input.availability,
+                if_needed: Some(input.if_needed),
+                undecided: Some(input.undecided),
+                avatar_color: input.avatar_color,
+                avatar_emoji: input.avatar_emoji,
+                timezone: input.timezone,
+            },
+        )
+        .await
+        .map_err(status)?;
+
+        Ok(Response::new(person.into()))
+    }
+
+    async fn delete_person(
+        &self,
+        request: Request<DeletePersonRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (headers, bearer) = credentials(&request);
+        let input = request.into_inner();
+
+        delete_person(
+            extract::State(self.state.clone()),
+            Path((input.event_id, input.name)),
+            Query(EditTokenParams {
+                edit_token: input.edit_token,
+            }),
+            bearer,
+            None,
+            headers,
+        )
+        .await
+        .map_err(status)?;
+
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn get_stats(&self, _: Request<Empty>) -> Result<Response<Stats>, Status> {
+        let stats = get_stats(extract::State(self.state.clone()))
+            .await
+            .map_err(status)?
+            .0;
+
+        Ok(Response::new(Stats {
+            event_count: stats.event_count,
+            person_count: stats.person_count,
+            version: stats.version,
+        }))
+    }
+}
+
+impl From<EventResponse> for Event {
+    fn from(value: EventResponse) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            times: value.times,
+            timezone: value.timezone,
+            created_at: value.created_at,
+            edit_token: value.edit_token,
+            creator_token: value.creator_token,
+            listed: value.listed,
+            tags: value.tags,
+            finalized_time: value.finalized_time,
+            private: value.private,
+            invitees: value.invitees,
+            responses_closed: value.responses_closed,
+            retention_days: value.retention_days,
+            max_people: value.max_people,
+            group_id: value.group_id,
+        }
+    }
+}
+
+impl From<PersonResponse> for Person {
+    fn from(value: PersonResponse) -> Self {
+        Self {
+            name: value.name,
+            availability: value.availability,
+            if_needed: value.if_needed,
+            undecided: value.undecided,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+            edit_token: value.edit_token,
+            edit_token_expires_at: value.edit_token_expires_at,
+            avatar_color: value.avatar_color,
+            avatar_emoji: value.avatar_emoji,
+            timezone: value.timezone,
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
+use chrono_tz::Tz;
+use common::Person;
+
+use crate::slots::{self, Slot};
+
+// Cell sizes in the SVG, each row is one slot
+const CELL_WIDTH: usize = 44;
+const CELL_HEIGHT: usize = 12;
+const LABEL_WIDTH: usize = 52;
+const TITLE_HEIGHT: usize = 44;
+const HEADER_HEIGHT: usize = 22;
+const PADDING: usize = 12;
+// The size Open Graph images are shown at in most link previews
+const PNG_WIDTH: u32 = 1200;
+const PNG_HEIGHT: u32 = 630;
+const PNG_PADDING: f64 = 40.0;
+// Times nobody is available at, and the page behind the grid
+const EMPTY_COLOR: [u8; 3] = [0xee, 0xee, 0xee];
+const BACKGROUND_COLOR: [u8; 3] = [0xff, 0xff, 0xff];
+// Times only a few people are available at are still shaded enough to see
+const MIN_OPACITY: f64 = 0.15;
+
+// A day of the event in the timezone it's shown in, dates come before days of the week
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Column {
+    Date(NaiveDate),
+    Weekday(u32),
+}
+
+impl Column {
+    fn label(&self) -> String {
+        match self {
+            Column::Date(date) => date.format("%a %-d %b").to_string(),
+            Column::Weekday(day) => {
+                ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"][*day as usize].to_owned()
+            }
+        }
+    }
+}
+
+/// How many people are available at each of an event's times, laid out as a grid of days by
+/// times of day in a timezone, ready to be drawn
+pub struct Heatmap {
+    title: String,
+    timezone: Tz,
+    responses: usize,
+    columns: Vec<Column>,
+    rows: Vec<NaiveTime>,
+    // Attendance as a share of everyone who responded, or None if it isn't one of the
+    // event's times
+    cells: Vec<Vec<Option<f64>>>,
+}
+
+impl Heatmap {
+    pub fn new(title: &str, times: &[String], people: &[Person], timezone: Tz) -> Self {
+        let responses = people.iter().filter(|p| !p.availability.is_empty()).count();
+        let attendance: HashMap<String, f64> = slots::rank(times, people)
+            .into_iter()
+            .map(|slot| {
+                let attendance = slot.attendance();
+                (slot.time, attendance)
+            })
+            .collect();
+
+        let mut grid: BTreeMap<(Column, NaiveTime), f64> = BTreeMap::new();
+        for time in times {
+            let Ok(slot) = time.parse::<Slot>() else {
+                continue;
+            };
+            let local = slot.datetime_in(timezone);
+            let column = match slot {
+                Slot::Date(_) => Column::Date(local.date_naive()),
+                Slot::Weekday(..) => Column::Weekday(local.weekday().num_days_from_sunday()),
+            };
+            let share = match responses {
+                0 => 0.0,
+                _ => attendance.get(time).copied().unwrap_or(0.0) / responses as f64,
+            };
+            grid.insert((column, local.time()), share);
+        }
+
+        let columns: Vec<Column> = grid
+            .keys()
+            .map(|(column, _)| *column)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let rows: Vec<NaiveTime> = grid
+            .keys()
+            .map(|(_, row)| *row)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let cells = rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| grid.get(&(*column, *row)).copied())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            title: title.to_owned(),
+            timezone,
+            responses,
+            columns,
+            rows,
+            cells,
+        }
+    }
+
+    /// Draw the grid with each day and hour labelled, shading times by how many people are
+    /// available in `color`, a hex color like `#0eaac5`
+    pub fn to_svg(&self, color: &str) -> String {
+        let color = parse_color(color);
+        let width = LABEL_WIDTH + self.columns.len() * CELL_WIDTH + PADDING;
+        let height = TITLE_HEIGHT + HEADER_HEIGHT + self.rows.len() * CELL_HEIGHT + PADDING;
+        let title = escape_xml(&self.title);
+        let subtitle = escape_xml(&format!(
+            "{} {} · {}",
+            self.responses,
+            if self.responses == 1 {
+                "response"
+            } else {
+                "responses"
+            },
+            self.timezone.name()
+        ));
+
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" role="img" aria-label="{title}"><title>{title}</title><rect width="{width}" height="{height}" fill="{background}"/><g font-family="Verdana,Geneva,DejaVu Sans,sans-serif" fill="#333"><text x="{PADDING}" y="22" font-size="15" font-weight="bold">{title}</text><text x="{PADDING}" y="38" font-size="11" fill="#777">{subtitle}</text></g><g font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="10" fill="#555" text-anchor="middle">"##,
+            background = hex(BACKGROUND_COLOR),
+        );
+        for (i, column) in self.columns.iter().enumerate() {
+            svg.push_str(&format!(
+                r#"<text x="{}" y="{}">{}</text>"#,
+                LABEL_WIDTH + i * CELL_WIDTH + CELL_WIDTH / 2,
+                TITLE_HEIGHT + HEADER_HEIGHT - 8,
+                column.label()
+            ));
+        }
+        svg.push_str(r##"</g><g font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="10" fill="#555" text-anchor="end">"##);
+        for (i, row) in self.rows.iter().enumerate() {
+            // Only the hours are labelled, or there'd be no room between them
+            if row.minute() == 0 {
+                svg.push_str(&format!(
+                    r#"<text x="{}" y="{}">{}</text>"#,
+                    LABEL_WIDTH - 6,
+                    TITLE_HEIGHT + HEADER_HEIGHT + i * CELL_HEIGHT + 9,
+                    row.format("%H:%M")
+                ));
+            }
+        }
+        svg.push_str("</g><g>");
+        for (i, row) in self.cells.iter().enumerate() {
+            for (j, share) in row.iter().enumerate() {
+                let Some(share) = share else {
+                    continue;
+                };
+                svg.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    LABEL_WIDTH + j * CELL_WIDTH + 1,
+                    TITLE_HEIGHT + HEADER_HEIGHT + i * CELL_HEIGHT + 1,
+                    CELL_WIDTH - 2,
+                    CELL_HEIGHT - 2,
+                    hex(shade(color, *share))
+                ));
+            }
+        }
+        svg.push_str("</g></svg>");
+        svg
+    }
+
+    /// Draw just the grid, scaled to fill an Open Graph image, as there's no text renderer
+    /// for the labels
+    pub fn to_png(&self, color: &str) -> Vec<u8> {
+        let color = parse_color(color);
+        let mut pixels = [BACKGROUND_COLOR]
+            .repeat((PNG_WIDTH * PNG_HEIGHT) as usize)
+            .concat();
+
+        let cell_width = (PNG_WIDTH as f64 - PNG_PADDING * 2.0) / self.columns.len().max(1) as f64;
+        let cell_height = (PNG_HEIGHT as f64 - PNG_PADDING * 2.0) / self.rows.len().max(1) as f64;
+        // Gaps between cells are left out once they'd take up most of the cell
+        let gap = if cell_width.min(cell_height) >= 4.0 {
+            1.0
+        } else {
+            0.0
+        };
+        for (i, row) in self.cells.iter().enumerate() {
+            for (j, share) in row.iter().enumerate() {
+                let Some(share) = share else {
+                    continue;
+                };
+                let left = (PNG_PADDING + j as f64 * cell_width + gap).round() as u32;
+                let right = (PNG_PADDING + (j + 1) as f64 * cell_width - gap).round() as u32;
+                let top = (PNG_PADDING + i as f64 * cell_height + gap).round() as u32;
+                let bottom = (PNG_PADDING + (i + 1) as f64 * cell_height - gap).round() as u32;
+                let fill = shade(color, *share);
+                for y in top..bottom.max(top + 1) {
+                    for x in left..right.max(left + 1) {
+                        let offset = ((y * PNG_WIDTH + x) * 3) as usize;
+                        pixels[offset..offset + 3].copy_from_slice(&fill);
+                    }
+                }
+            }
+        }
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, PNG_WIDTH, PNG_HEIGHT);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .expect("The image is the size in its header, and is written to memory");
+        png
+    }
+}
+
+// The color blended over the background by how many people are available
+fn shade(color: [u8; 3], share: f64) -> [u8; 3] {
+    if share <= 0.0 {
+        return EMPTY_COLOR;
+    }
+    let opacity = MIN_OPACITY + (1.0 - MIN_OPACITY) * share.min(1.0);
+    std::array::from_fn(|i| {
+        (BACKGROUND_COLOR[i] as f64 * (1.0 - opacity) + color[i] as f64 * opacity).round() as u8
+    })
+}
+
+// A `#rrggbb` color, falling back to Jelli Fit's own if the branding isn't one
+fn parse_color(color: &str) -> [u8; 3] {
+    let channel = |i: usize| {
+        color
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok())
+    };
+    match (channel(0), channel(1), channel(2)) {
+        (Some(r), Some(g), Some(b)) => [r, g, b],
+        _ => [0x0e, 0xaa, 0xc5],
+    }
+}
+
+fn hex(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+
+// Lines longer than this many octets have to be folded
+const MAX_LINE_LENGTH: usize = 75;
+
+/// An iCalendar (RFC 5545) document, built up one content line at a time
+pub struct Calendar {
+    lines: Vec<String>,
+}
+
+impl Calendar {
+    pub fn new() -> Self {
+        let mut calendar = Self { lines: Vec::new() };
+        calendar.property("BEGIN", "VCALENDAR");
+        calendar.property("VERSION", "2.0");
+        calendar.property("PRODID", "-//Jelli Fit//API//EN");
+        calendar.property("CALSCALE", "GREGORIAN");
+        calendar
+    }
+
+    /// Add a content line, the value should already be escaped if it's text
+    pub fn property(&mut self, name: &str, value: &str) {
+        self.lines.push(fold(&format!("{}:{}", name, value)));
+    }
+
+    pub fn finish(mut self) -> String {
+        self.property("END", "VCALENDAR");
+        self.lines.join("\r\n") + "\r\n"
+    }
+}
+
+/// Escape a text value
+pub fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Format a date-time in UTC, e.g. `20230521T093000Z`
+pub fn format_utc(datetime: DateTime<Utc>) -> String {
+    datetime.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Split a line into chunks of at most 75 octets, continuing each with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::slots::{Slot, SLOT_MINUTES};
+
+/// An event read from another scheduling site, with its times in `Event::times` format
+pub struct ImportedEvent {
+    pub name: Option<String>,
+    pub timezone: Option<String>,
+    pub times: Vec<String>,
+    pub people: Vec<ImportedPerson>,
+}
+
+pub struct ImportedPerson {
+    pub name: String,
+    pub availability: Vec<String>,
+}
+
+/// Read an event from the source of a When2Meet event page
+///
+/// When2Meet has no export, but its pages list every slot as a Unix timestamp along with who
+/// is available at it, in the script that draws the grid. Its slots are 15 minutes long like
+/// ours, so they map across directly.
+pub fn from_when2meet(page: &str) -> Result<ImportedEvent, String> {
+    let title = Regex::new(r"<title>\s*(.*?)\s*-\s*When2meet\s*</title>").unwrap();
+    let slot = Regex::new(r"TimeOfSlot\[(\d+)\]\s*=\s*(\d+)").unwrap();
+    let name = Regex::new(r"PeopleNames\[(\d+)\]\s*=\s*'((?:[^'\\]|\\.)*)'").unwrap();
+    let id = Regex::new(r"PeopleIDs\[(\d+)\]\s*=\s*(\d+)").unwrap();
+    let available = Regex::new(r"AvailableAtSlot\[(\d+)\]\.push\((\d+)\)").unwrap();
+
+    let mut slots: Vec<(usize, String)> = Vec::new();
+    for captures in slot.captures_iter(page) {
+        let timestamp = captures[2].parse::<i64>().map_err(|e| e.to_string())?;
+        let datetime = NaiveDateTime::from_timestamp_opt(timestamp, 0)
+            .ok_or(format!("Invalid slot time {}", timestamp))?;
+        slots.push((parse_index(&captures[1])?, Slot::Date(datetime).to_string()));
+    }
+    if slots.is_empty() {
+        return Err(
+            "No times found, make sure this is the source of a When2Meet event page".to_owned(),
+        );
+    }
+    let times: HashMap<usize, String> = slots.iter().cloned().collect();
+
+    // Names and IDs are listed separately, matched up by their index
+    let names: HashMap<usize, String> = name
+        .captures_iter(page)
+        .map(|captures| Ok((parse_index(&captures[1])?, unescape_js(&captures[2]))))
+        .collect::<Result<_, String>>()?;
+    let mut people: Vec<(String, ImportedPerson)> = Vec::new();
+    for captures in id.captures_iter(page) {
+        if let Some(name) = names.get(&parse_index(&captures[1])?) {
+            people.push((
+                captures[2].to_owned(),
+                ImportedPerson {
+                    name: name.clone(),
+                    availability: Vec::new(),
+                },
+            ));
+        }
+    }
+
+    for captures in available.captures_iter(page) {
+        let (Some(time), Some((_, person))) = (
+            times.get(&parse_index(&captures[1])?),
+            people.iter_mut().find(|(id, _)| *id == captures[2]),
+        ) else {
+            continue;
+        };
+        if !person.availability.contains(time) {
+            person.availability.push(time.clone());
+        }
+    }
+
+    Ok(ImportedEvent {
+        name: title
+            .captures(page)
+            .map(|captures| unescape_html(&captures[1])),
+        timezone: None,
+        times: slots.into_iter().map(|(_, time)| time).collect(),
+        people: people.into_iter().map(|(_, person)| person).collect(),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LettuceMeetExport {
+    // The response from LettuceMeet's API, or just the event inside it
+    Response { data: LettuceMeetData },
+    Event(LettuceMeetEvent),
+}
+
+#[derive(Deserialize)]
+struct LettuceMeetData {
+    event: LettuceMeetEvent,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LettuceMeetEvent {
+    title: Option<String>,
+    time_zone: Option<String>,
+    // The first day's window, which every other day repeats
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    poll_dates: Vec<NaiveDate>,
+    #[serde(default)]
+    poll_responses: Vec<LettuceMeetResponse>,
+}
+
+#[derive(Deserialize)]
+struct LettuceMeetResponse {
+    user: LettuceMeetUser,
+    #[serde(default)]
+    availabilities: Vec<LettuceMeetRange>,
+}
+
+#[derive(Deserialize)]
+struct LettuceMeetUser {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct LettuceMeetRange {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+/// Read an event from the JSON LettuceMeet's API returns for it
+///
+/// LettuceMeet events are a window of time repeated on each of the event's dates, and people
+/// are available for ranges of time within them, which are split up into our 15 minute slots.
+pub fn from_lettucemeet(json: &str) -> Result<ImportedEvent, String> {
+    let event = match serde_json::from_str(json).map_err(|e| e.to_string())? {
+        LettuceMeetExport::Response { data } => data.event,
+        LettuceMeetExport::Event(event) => event,
+    };
+
+    let tz: Tz = match &event.time_zone {
+        Some(timezone) => timezone
+            .parse()
+            .map_err(|_| format!("Unknown timezone \"{}\"", timezone))?,
+        None => Tz::UTC,
+    };
+    let window = event.end - event.start;
+    if window <= Duration::zero() || window > Duration::days(1) {
+        return Err("The event's end has to be within a day after its start".to_owned());
+    }
+
+    // Each day's window starts at the same local time, even across daylight saving changes
+    let start_time = event.start.with_timezone(&tz).time();
+    let mut slots: Vec<DateTime<Utc>> = Vec::new();
+    for date in &event.poll_dates {
+        let start = tz
+            .from_local_datetime(&date.and_time(start_time))
+            .earliest()
+            .ok_or(format!("{} doesn't exist in {}", date, tz))?
+            .with_timezone(&Utc);
+        let mut slot = start;
+        while slot < start + window {
+            slots.push(slot);
+            slot += Duration::minutes(SLOT_MINUTES);
+        }
+    }
+    let slot_string = |slot: &DateTime<Utc>| Slot::Date(slot.naive_utc()).to_string();
+
+    let people = event
+        .poll_responses
+        .into_iter()
+        .map(|response| ImportedPerson {
+            name: response.user.name,
+            availability: slots
+                .iter()
+                .filter(|slot| {
+                    response
+                        .availabilities
+                        .iter()
+                        .any(|range| range.start <= **slot && **slot < range.end)
+                })
+                .map(slot_string)
+                .collect(),
+        })
+        .collect();
+
+    Ok(ImportedEvent {
+        name: event.title,
+        timezone: event.time_zone,
+        times: slots.iter().map(slot_string).collect(),
+        people,
+    })
+}
+
+fn parse_index(index: &str) -> Result<usize, String> {
+    index
+        .parse()
+        .map_err(|_| format!("Invalid index {}", index))
+}
+
+// Names are single quoted JavaScript strings in When2Meet's script
+fn unescape_js(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
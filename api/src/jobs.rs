@@ -0,0 +1,175 @@
+use std::{fmt, sync::Weak, time::Duration};
+
+use chrono::NaiveDate;
+use common::{Adaptor, Stats};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinSet,
+};
+
+use crate::{chat::ChatMessage, webhooks::Delivery, ApiState};
+
+// Jobs waiting to start, new ones are dropped while the queue is full
+const QUEUE_CAPACITY: usize = 4096;
+// How many times to try each job, waiting twice as long after each failure
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Bookkeeping that doesn't need to finish before a response is sent
+pub enum Job {
+    RecordView {
+        event_id: String,
+        register: usize,
+        rank: u8,
+    },
+    AddStats(Stats),
+    AddDailyStats(NaiveDate, Stats),
+    Webhook(Delivery),
+    Chat(ChatMessage),
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Job::RecordView { event_id, .. } => write!(f, "recording a view of {}", event_id),
+            Job::AddStats(_) => write!(f, "adding stats"),
+            Job::AddDailyStats(date, _) => write!(f, "adding daily stats for {}", date),
+            Job::Webhook(delivery) => write!(f, "webhook delivery to {}", delivery.host()),
+            Job::Chat(message) => write!(f, "{} message", message.platform.name()),
+        }
+    }
+}
+
+impl Job {
+    async fn run<A: Adaptor>(&self, state: &ApiState<A>) -> Result<(), String> {
+        let adaptor = &state.adaptor;
+        match self {
+            Job::RecordView {
+                event_id,
+                register,
+                rank,
+            } => adaptor
+                .record_event_view(event_id.clone(), *register, *rank)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Job::AddStats(stats) => adaptor
+                .add_stats(stats.clone())
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Job::AddDailyStats(date, stats) => adaptor
+                .add_daily_stats(*date, stats.clone())
+                .await
+                .map_err(|e| e.to_string()),
+            Job::Webhook(delivery) => state.webhooks.deliver(delivery).await,
+            Job::Chat(message) => state.chat.deliver(message).await,
+        }
+    }
+}
+
+enum Message {
+    Run(Job),
+    // Sent once everything queued before it has finished
+    Drain(oneshot::Sender<()>),
+}
+
+/// Runs jobs in the background, retrying the ones that fail, so requests don't wait on them
+#[derive(Clone)]
+pub struct Jobs {
+    sender: mpsc::Sender<Message>,
+}
+
+impl Jobs {
+    /// Start the worker, which stops once the state is dropped or the queue is drained. Must
+    /// be called from within the runtime.
+    pub fn new<A: Adaptor + 'static>(state: Weak<ApiState<A>>) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(work(state, receiver));
+        Self { sender }
+    }
+
+    /// Queue a job, returning false if it was dropped because the queue is full or drained
+    pub fn push(&self, job: Job) -> bool {
+        match self.sender.try_send(Message::Run(job)) {
+            Ok(()) => true,
+            Err(
+                mpsc::error::TrySendError::Full(message)
+                | mpsc::error::TrySendError::Closed(message),
+            ) => {
+                if let Message::Run(job) = message {
+                    tracing::warn!("Job queue is full or drained, dropping {}", job);
+                }
+                false
+            }
+        }
+    }
+
+    /// Wait for every queued job to finish, giving up after `timeout`. Jobs queued afterwards
+    /// are dropped.
+    pub async fn drain(&self, timeout: Duration) {
+        let (done, finished) = oneshot::channel();
+        let drained = tokio::time::timeout(timeout, async {
+            if self.sender.send(Message::Drain(done)).await.is_ok() {
+                let _ = finished.await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!("Gave up waiting for background jobs to finish");
+        }
+    }
+}
+
+async fn work<A: Adaptor + 'static>(
+    state: Weak<ApiState<A>>,
+    mut receiver: mpsc::Receiver<Message>,
+) {
+    // Each job runs separately, so one that keeps failing doesn't hold up the rest
+    let mut running = JoinSet::new();
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(Message::Run(job)) => {
+                    running.spawn(run(state.clone(), job));
+                }
+                Some(Message::Drain(done)) => {
+                    while running.join_next().await.is_some() {}
+                    let _ = done.send(());
+                    return;
+                }
+                None => return,
+            },
+            Some(_) = running.join_next(), if !running.is_empty() => {}
+        }
+    }
+}
+
+async fn run<A: Adaptor>(state: Weak<ApiState<A>>, job: Job) {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        // Nothing is left to write to once the state is gone
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let result = job.run(&state).await;
+        drop(state);
+
+        match result {
+            Ok(()) => return,
+            Err(e) => tracing::warn!(
+                "Background job {} failed (attempt {} of {}): {}",
+                job,
+                attempt,
+                MAX_ATTEMPTS,
+                e
+            ),
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+    tracing::error!("Gave up on {}", job);
+}
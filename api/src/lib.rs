@@ -0,0 +1,254 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::Body,
+    extract,
+    http::{
+        header::{
+            ACCEPT, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, AUTHORIZATION,
+            CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, ORIGIN,
+        },
+        HeaderName, Method, Request,
+    },
+    middleware::{from_fn, from_fn_with_state},
+    Router,
+};
+use common::Adaptor;
+use tower_http::{
+    cors::CorsLayer,
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::Level;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::archive::Archive;
+use crate::auth::{Oidc, ID_TOKEN_HEADER};
+use crate::cache::{CachedAdaptor, EventCache};
+use crate::captcha::{Captcha, CAPTCHA_TOKEN_HEADER};
+use crate::chat::Chat;
+use crate::config::config;
+use crate::docs::ApiDoc;
+use crate::google::GoogleCalendar;
+use crate::graphql::GraphqlSchema;
+use crate::jobs::Jobs;
+use crate::live::LiveUpdates;
+use crate::metrics::{AdaptorMetrics, MeteredAdaptor};
+use crate::middleware::client_ip::{client_ip, ClientIp, TrustedProxies};
+use crate::middleware::identity::identify;
+use crate::middleware::request_id::{request_id, REQUEST_ID_HEADER};
+use crate::middleware::signature::{verify_signature, SignatureKeys};
+use crate::middleware::terms::{require_terms, TERMS_VERSION_HEADER};
+use crate::rate_limit::RateLimits;
+use crate::routes::event::{
+    EVENT_PASSWORD_HEADER, IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER,
+};
+use crate::routes::person::TOTAL_COUNT_HEADER;
+use crate::spam::SpamFilter;
+use crate::stat_counters::StatCounters;
+use crate::webhooks::Webhooks;
+
+pub mod adaptors;
+pub mod archive;
+pub mod auth;
+pub mod cache;
+mod captcha;
+mod chat;
+pub mod cleanup;
+pub mod config;
+mod cors;
+mod docs;
+mod errors;
+mod etag;
+pub mod export;
+mod google;
+mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod heatmap;
+mod ics;
+mod import;
+mod jobs;
+pub mod listen;
+mod live;
+pub mod logging;
+pub mod metrics;
+pub mod middleware;
+mod msgpack;
+mod names;
+mod payloads;
+pub mod rate_limit;
+pub mod replicas;
+mod routes;
+mod scheduling;
+pub mod schema;
+mod scoring;
+pub mod seed;
+pub mod shutdown;
+mod slots;
+mod spam;
+pub mod stat_counters;
+pub mod tls;
+mod tokens;
+mod validation;
+mod visitors;
+mod webhooks;
+
+// Short enough that it still fits in the grace period after the requests' shutdown timeout
+const JOB_DRAIN_TIMEOUT: Duration = Duration::from_secs(4);
+
+// Adaptors only need `&self` and handle their own connection pooling,
+// so the state can be shared between requests without locking
+pub struct ApiState<A> {
+    adaptor: A,
+    archive: Option<Archive>,
+    spam_filter: Option<SpamFilter>,
+    captcha: Option<Captcha>,
+    google: Option<GoogleCalendar>,
+    live: LiveUpdates,
+    jobs: Jobs,
+    webhooks: Webhooks,
+    chat: Chat,
+    stat_counters: StatCounters,
+    graphql: GraphqlSchema<A>,
+    cache: EventCache,
+    metrics: AdaptorMetrics,
+}
+
+pub type AppState<A> = Arc<ApiState<A>>;
+pub type State<A> = extract::State<AppState<A>>;
+
+impl<A: Adaptor> ApiState<A> {
+    /// Finish up once the server has stopped
+    pub async fn close(&self) {
+        // Don't lose increments that were still buffered when the server stopped, or
+        // anything else that was still waiting to be written
+        self.stat_counters.flush(&self.jobs);
+        self.jobs.drain(JOB_DRAIN_TIMEOUT).await;
+        if let Err(e) = self.adaptor.close().await {
+            tracing::error!("Failed to close the adaptor: {}", e);
+        }
+    }
+}
+
+/// The state shared by every request, with the adaptor wrapped in the event cache, and
+/// metered underneath it so only calls that reach storage are counted
+pub fn state<A: Adaptor + 'static>(adaptor: A) -> AppState<CachedAdaptor<MeteredAdaptor<A>>>
+where
+    A::Error: Send,
+{
+    let config = config();
+    let cache = EventCache::from_config(&config.cache);
+    let metrics = AdaptorMetrics::default();
+    Arc::new_cyclic(|state| {
+        let jobs = Jobs::new(state.clone());
+        ApiState {
+            adaptor: CachedAdaptor::new(
+                MeteredAdaptor::new(adaptor, metrics.clone()),
+                cache.clone(),
+            ),
+            archive: Archive::from_config(&config.archive),
+            spam_filter: SpamFilter::from_config(&config.spam),
+            captcha: Captcha::from_config(&config.captcha),
+            google: GoogleCalendar::from_config(&config.google),
+            live: LiveUpdates::new(),
+            webhooks: Webhooks::new(jobs.clone()),
+            chat: Chat::new(jobs.clone()),
+            jobs,
+            stat_counters: StatCounters::default(),
+            graphql: graphql::schema(),
+            cache,
+            metrics,
+        }
+    })
+}
+
+/// Every route along with the docs and middleware, ready to serve. Requests need a
+/// `ConnectInfo<SocketAddr>` or `UnixPeer` extension so the client's IP can be found.
+pub fn app<A: Adaptor + 'static>(
+    state: AppState<A>,
+    rate_limits: &RateLimits,
+    oidc: Option<Oidc>,
+) -> Router {
+    // CORS configuration
+    let cors = CorsLayer::new()
+        .allow_credentials(true)
+        .allow_headers([
+            AUTHORIZATION,
+            ACCEPT,
+            CONTENT_TYPE,
+            IF_MATCH,
+            IF_NONE_MATCH,
+            HeaderName::from_static(TERMS_VERSION_HEADER),
+            HeaderName::from_static(EVENT_PASSWORD_HEADER),
+            HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(ID_TOKEN_HEADER),
+            HeaderName::from_static(CAPTCHA_TOKEN_HEADER),
+        ])
+        .expose_headers([
+            ETAG,
+            HeaderName::from_static(TOTAL_COUNT_HEADER),
+            HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_origin(cors::allowed_origins(&config().cors))
+        // Replaces any `Vary` header set by handlers, so responses that can be MessagePack
+        // rely on `Accept` being listed here
+        .vary([
+            ORIGIN,
+            ACCESS_CONTROL_REQUEST_METHOD,
+            ACCESS_CONTROL_REQUEST_HEADERS,
+            ACCEPT,
+        ]);
+
+    routes::router(state, rate_limits)
+        .merge(SwaggerUi::new("/docs").url("/docs/openapi.json", ApiDoc::openapi()))
+        .layer(from_fn(require_terms))
+        .layer(from_fn_with_state(
+            Arc::new(SignatureKeys::from_config(&config().signatures)),
+            verify_signature,
+        ))
+        .layer(from_fn_with_state(Arc::new(oidc), identify))
+        .layer(cors)
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(|request: &Request<Body>| {
+                    let request_id = request
+                        .headers()
+                        .get(REQUEST_ID_HEADER)
+                        .and_then(|id| id.to_str().ok())
+                        .unwrap_or_default();
+                    let client_ip = request
+                        .extensions()
+                        .get::<ClientIp>()
+                        .map(|ClientIp(ip)| ip.to_string())
+                        .unwrap_or_default();
+                    tracing::info_span!(
+                        "request",
+                        method = %request.method(),
+                        uri = %request.uri(),
+                        request_id,
+                        client_ip,
+                    )
+                })
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        // Outside the trace layer, so these are set before the request's span is created
+        .layer(from_fn(request_id))
+        .layer(from_fn_with_state(
+            Arc::new(TrustedProxies::from_config(&config().server)),
+            client_ip,
+        ))
+}
+
+async fn get_root() -> String {
+    format!("Jelli Fit API v{}", env!("CARGO_PKG_VERSION"))
+}
@@ -0,0 +1,128 @@
+use std::{
+    env, io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener},
+    os::{
+        fd::{FromRawFd, IntoRawFd},
+        unix::net::UnixListener as StdUnixListener,
+    },
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::extract::ConnectInfo;
+use hyper::server::accept::Accept;
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::config::ServerConfig;
+
+// The first file descriptor passed by systemd socket activation
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Where the server accepts connections
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener, Option<PathBuf>),
+}
+
+impl Listener {
+    /// A socket passed in by systemd socket activation if there is one, otherwise a Unix
+    /// socket at `server.unix_socket_path` if it's set, otherwise `server.host` and `server.port`
+    pub fn from_config(config: &ServerConfig) -> Self {
+        if let Some(listener) = Self::from_systemd() {
+            return listener;
+        }
+
+        if let Some(path) = &config.unix_socket_path {
+            // A socket left behind by a previous run would stop us binding to it
+            let _ = std::fs::remove_file(path);
+            let listener = UnixListener::bind(path).expect("Failed to bind to the Unix socket");
+            return Self::Unix(listener, Some(path.clone()));
+        }
+
+        let listener = TcpListener::bind(SocketAddr::new(config.host, config.port))
+            .expect("Failed to bind to the host and port");
+        listener
+            .set_nonblocking(true)
+            .expect("Failed to make the listener non-blocking");
+        Self::Tcp(listener)
+    }
+
+    // https://www.freedesktop.org/software/systemd/man/sd_listen_fds.html
+    fn from_systemd() -> Option<Self> {
+        let fds: i32 = env::var("LISTEN_FDS").ok()?.parse().ok()?;
+        let for_us = env::var("LISTEN_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .is_none_or(|pid| pid == std::process::id());
+        if fds < 1 || !for_us {
+            return None;
+        }
+        if fds > 1 {
+            tracing::warn!("Only the first of {} sockets from systemd is used", fds);
+        }
+        // Child processes shouldn't think the sockets are for them
+        env::remove_var("LISTEN_FDS");
+        env::remove_var("LISTEN_PID");
+        env::remove_var("LISTEN_FDNAMES");
+
+        // SAFETY: systemd passes the sockets starting at this descriptor, open and owned by
+        // this process, and nothing else takes ownership of them
+        let listener = unsafe { TcpListener::from_raw_fd(SD_LISTEN_FDS_START) };
+        // Getting the address only works if it's actually a TCP socket
+        let listener = match listener.local_addr() {
+            Ok(_) => {
+                listener
+                    .set_nonblocking(true)
+                    .expect("Failed to make the listener non-blocking");
+                Self::Tcp(listener)
+            }
+            Err(_) => {
+                // SAFETY: as above, ownership moves from the TCP listener
+                let listener = unsafe { StdUnixListener::from_raw_fd(listener.into_raw_fd()) };
+                listener
+                    .set_nonblocking(true)
+                    .expect("Failed to make the listener non-blocking");
+                Self::Unix(
+                    UnixListener::from_std(listener).expect("Invalid socket from systemd"),
+                    None,
+                )
+            }
+        };
+        Some(listener)
+    }
+
+    /// Where the server can be reached, for logging
+    pub fn describe(&self, tls: bool) -> String {
+        match self {
+            Self::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => format!("{}://{}", if tls { "https" } else { "http" }, addr),
+                Err(_) => "an unknown address".to_owned(),
+            },
+            Self::Unix(_, Some(path)) => format!("unix:{}", path.display()),
+            Self::Unix(_, None) => "a Unix socket from systemd".to_owned(),
+        }
+    }
+}
+
+/// Connections over a Unix socket don't have a peer address, so they count as coming from
+/// localhost, which can be added to the trusted proxies to use the forwarding proxy's headers
+pub const UNIX_PEER: ConnectInfo<SocketAddr> =
+    ConnectInfo(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0));
+
+/// Accepts connections on a Unix socket for hyper
+pub struct UnixAccept(pub UnixListener);
+
+impl Accept for UnixAccept {
+    type Conn = UnixStream;
+    type Error = io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        self.0
+            .poll_accept(cx)
+            .map(|result| Some(result.map(|(stream, _)| stream)))
+    }
+}
@@ -0,0 +1,49 @@
+use axum::response::sse::Event as SseEvent;
+use futures_util::{stream, Stream};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::payloads::LiveUpdate;
+
+// Updates buffered per subscriber, slow subscribers miss older updates past this
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Broadcasts changes to events, so clients can show them as they happen
+pub struct LiveUpdates {
+    sender: broadcast::Sender<LiveUpdate>,
+}
+
+impl LiveUpdates {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, update: LiveUpdate) {
+        // Only errors if nobody is subscribed
+        self.sender.send(update).ok();
+    }
+
+    /// Stream the updates for a single event as server-sent events
+    pub fn subscribe(
+        &self,
+        event_id: String,
+    ) -> impl Stream<Item = Result<SseEvent, serde_json::Error>> {
+        stream::unfold(
+            (self.sender.subscribe(), event_id),
+            |(mut receiver, event_id)| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(update) if update.event_id == event_id => {
+                            let event = SseEvent::default()
+                                .event(update.kind.as_str())
+                                .json_data(&update);
+                            return Some((event, (receiver, event_id)));
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        )
+    }
+}
@@ -1,126 +1,245 @@
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{fs::File, io::BufWriter, net::SocketAddr, path::PathBuf};
 
-use axum::{
-    error_handling::HandleErrorLayer,
-    extract,
-    http::{
-        header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
-        HeaderValue, Method,
-    },
-    routing::{get, patch, post},
-    BoxError, Router, Server,
-};
-use routes::*;
-use tokio::sync::Mutex;
-use tower::ServiceBuilder;
-use tower_governor::{errors::display_error, governor::GovernorConfigBuilder, GovernorLayer};
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::Level;
-use utoipa::OpenApi;
-use utoipa_swagger_ui::SwaggerUi;
-
-use crate::adaptors::create_adaptor;
-use crate::docs::ApiDoc;
+use axum::{Extension, Server};
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use common::Adaptor;
+use jellifit_api::adaptors::create_adaptor;
+use jellifit_api::archive::Archive;
+use jellifit_api::auth::Oidc;
+use jellifit_api::listen::{Listener, UnixAccept, UNIX_PEER};
+use jellifit_api::middleware::admin_key::admin_key;
+use jellifit_api::rate_limit::RateLimits;
+use jellifit_api::shutdown::Shutdown;
+use jellifit_api::stat_counters::flush_periodically;
+use jellifit_api::tls::TlsPaths;
+use jellifit_api::{cleanup, config, export, logging, schema, seed, tls};
 
-mod adaptors;
-mod docs;
-mod errors;
-mod payloads;
-mod routes;
+/// API for Jelli Fit, which serves requests when run without a command
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// A TOML or YAML config file, defaults to JELLI_CONFIG or jelli.toml, jelli.yaml or
+    /// jelli.yml if one exists. Environment variables override what's in it.
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
 
-pub struct ApiState<A> {
-    adaptor: A,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-pub type State<A> = extract::State<Arc<Mutex<ApiState<A>>>>;
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the API, which is the default
+    Serve,
+    /// Expire stale events and delete old ones once, then exit
+    Cleanup {
+        /// List what would be expired and deleted without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Migrate the stored data to the schema version this build needs, then exit. Use with
+    /// auto_migrate turned off to migrate as a separate step when deploying.
+    Migrate,
+    /// Write every event with its people, comments and activity to a JSON file, then exit
+    Export {
+        /// Where to write the export, defaults to jellifit-export-<date>.json
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Fill the adaptor with fake events for development, then exit. Only allowed in debug
+    /// builds, or with allow_seeding turned on.
+    Seed {
+        /// How many events to create
+        #[arg(default_value_t = 10)]
+        count: usize,
+    },
+}
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt().with_max_level(Level::INFO).init();
-
     // Load env
     dotenvy::dotenv().ok();
 
-    let shared_state = Arc::new(Mutex::new(ApiState {
-        adaptor: create_adaptor().await,
-    }));
+    // Parsed before anything starts, so `--help` and mistakes don't need a database
+    let cli = Cli::parse();
+    let config = config::init(cli.config.as_deref());
+    if let Some(Command::Seed { count }) = cli.command {
+        if !seed::enabled() {
+            panic!("Seeding is only allowed in debug builds, or with allow_seeding turned on");
+        }
+        if count > seed::MAX_SEED_EVENTS {
+            panic!(
+                "At most {} events can be seeded at once",
+                seed::MAX_SEED_EVENTS
+            );
+        }
+    }
+
+    logging::init(config.logging.format);
 
-    // CORS configuration
-    let cors = CorsLayer::new()
-        .allow_credentials(true)
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE])
-        .allow_methods([Method::GET, Method::POST, Method::PATCH])
-        .allow_origin(
-            if cfg!(debug_assertions) {
-                "http://localhost:1234".to_owned()
-            } else {
-                env::var("FRONTEND_URL").expect("Missing FRONTEND_URL environment variable")
+    let adaptor = create_adaptor(&config.adaptor).await;
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            schema::prepare(&adaptor).await;
+            serve(adaptor).await;
+        }
+        Command::Cleanup { dry_run: true } => {
+            schema::prepare(&adaptor).await;
+            let preview = cleanup::preview(&adaptor)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to preview the cleanup: {}", e));
+            println!(
+                "Would expire {} events and delete {} events with {} people",
+                preview.stale_events.len(),
+                preview.expired_events.len(),
+                preview.person_count
+            );
+            for event in preview.stale_events {
+                println!("Expire {}", event.id);
             }
-            .parse::<HeaderValue>()
-            .unwrap(),
-        );
+            for event in preview.expired_events {
+                println!("Delete {}", event.id);
+            }
+        }
+        Command::Cleanup { dry_run: false } => {
+            schema::prepare(&adaptor).await;
+            let archive = Archive::from_config(&config.archive);
+            let report = cleanup::run(&adaptor, archive.as_ref())
+                .await
+                .unwrap_or_else(|e| panic!("Failed to clean up: {}", e))
+                .expect("Nothing else is running in this process");
+            println!(
+                "✅ Expired {} events, and deleted {} events, {} people, {} idempotency keys and {} templates",
+                report.expired_count,
+                report.deleted.event_count,
+                report.deleted.person_count,
+                report.keys_deleted,
+                report.templates_deleted
+            );
+            if let Some(key) = report.archive_key {
+                println!("Archived the deleted events to {}", key);
+            }
+        }
+        Command::Migrate => {
+            let version = schema::migrate(&adaptor).await;
+            println!("✅ Stored data is at schema version {}", version.current);
+        }
+        Command::Export { output } => {
+            schema::prepare(&adaptor).await;
+            let path = output.unwrap_or_else(|| {
+                format!("jellifit-export-{}.json", Utc::now().format("%Y-%m-%d")).into()
+            });
+            let file = File::create(&path)
+                .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e));
+            let count = export::export(&adaptor, BufWriter::new(file))
+                .await
+                .unwrap_or_else(|e| panic!("Failed to export: {}", e));
+            println!("✅ Exported {} events to {}", count, path.display());
+        }
+        Command::Seed { count } => {
+            schema::prepare(&adaptor).await;
+            let report = seed::seed(&adaptor, count)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to seed events: {}", e));
+            println!(
+                "✅ Seeded {} events with {} people\nEdit token: {}\nCreator token: {}",
+                report.event_ids.len(),
+                report.person_count,
+                report.edit_token,
+                report.creator_token
+            );
+        }
+    }
+}
 
-    // Rate limiting configuration (using tower_governor)
-    // From the docs: Allows bursts with up to 20 requests and replenishes
-    // one element after 500ms, based on peer IP.
-    let governor_config = Box::new(
-        GovernorConfigBuilder::default()
-            .burst_size(20)
-            .finish()
-            .unwrap(),
-    );
-    let rate_limit = ServiceBuilder::new()
-        // Handle errors from governor and convert into HTTP responses
-        .layer(HandleErrorLayer::new(|e: BoxError| async move {
-            display_error(e)
-        }))
-        .layer(GovernorLayer {
-            config: Box::leak(governor_config),
-        });
+async fn serve<A: Adaptor + 'static>(adaptor: A)
+where
+    A::Error: Send,
+{
+    let config = config::config();
+    if admin_key().is_none() {
+        tracing::warn!("No admin key is set, so admin routes will always respond with 401");
+    }
 
-    let app = Router::new()
-        .merge(SwaggerUi::new("/docs").url("/docs/openapi.json", ApiDoc::openapi()))
-        .route("/", get(get_root))
-        .route("/stats", get(stats::get_stats))
-        .route("/event", post(event::create_event))
-        .route("/event/:event_id", get(event::get_event))
-        .route("/event/:event_id/people", get(person::get_people))
-        .route(
-            "/event/:event_id/people/:person_name",
-            get(person::get_person),
-        )
-        .route(
-            "/event/:event_id/people/:person_name",
-            patch(person::update_person),
-        )
-        .route("/tasks/cleanup", get(tasks::cleanup))
-        .with_state(shared_state)
-        .layer(cors)
-        .layer(rate_limit)
-        .layer(TraceLayer::new_for_http());
+    let shared_state = jellifit_api::state(adaptor);
+    let oidc = Oidc::from_config(&config.oidc);
+    if oidc.is_none() {
+        tracing::info!("No OIDC issuer is set, so logging in is turned off");
+    }
+    let shutdown = Shutdown::listen();
+    tokio::spawn(flush_periodically(shared_state.clone()));
+    if let Some(period) = cleanup::interval(&config.retention) {
+        tokio::spawn(cleanup::run_periodically(shared_state.clone(), period));
+    }
+    #[cfg(feature = "grpc")]
+    tokio::spawn(jellifit_api::grpc::serve(
+        shared_state.clone(),
+        shutdown.clone(),
+    ));
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    let app = jellifit_api::app(
+        shared_state.clone(),
+        &RateLimits::from_config(&config.rate_limits),
+        oidc,
+    );
+
+    let listener = Listener::from_config(&config.server);
+    let tls_paths = TlsPaths::from_config(&config.server);
+    // A socket from systemd could still be a Unix socket, which the config can't know about
+    if matches!(listener, Listener::Unix(..)) && tls_paths.is_some() {
+        panic!(
+            "TLS can't be used with a Unix socket, the proxy in front of it should handle HTTPS"
+        );
+    }
 
     println!(
-        "🪼 Jelli Fit API listening at http://{} in {} mode",
-        addr,
+        "🪼 Jelli Fit API listening at {} in {} mode",
+        listener.describe(tls_paths.is_some()),
         if cfg!(debug_assertions) {
             "debug"
         } else {
             "release"
         }
     );
-    Server::bind(&addr)
-        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
-        .with_graceful_shutdown(async {
-            tokio::signal::ctrl_c()
+    match (listener, tls_paths) {
+        (Listener::Tcp(listener), Some(paths)) => {
+            let config = paths.load().await;
+            tokio::spawn(tls::reload_on_change(config.clone(), paths));
+
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                let timeout = shutdown.timeout;
+                shutdown.signalled().await;
+                shutdown_handle.graceful_shutdown(Some(timeout));
+            });
+            axum_server::from_tcp_rustls(listener, config)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
                 .await
-                .expect("Failed to install Ctrl+C handler")
-        })
-        .await
-        .unwrap();
-}
+                .unwrap();
+        }
+        (Listener::Tcp(listener), None) => {
+            let server = Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(shutdown.clone().signalled());
+            tokio::select! {
+                result = server => result.unwrap(),
+                _ = shutdown.deadline() => {}
+            }
+        }
+        (Listener::Unix(listener, _), _) => {
+            let server = Server::builder(UnixAccept(listener))
+                .serve(app.layer(Extension(UNIX_PEER)).into_make_service())
+                .with_graceful_shutdown(shutdown.clone().signalled());
+            tokio::select! {
+                result = server => result.unwrap(),
+                _ = shutdown.deadline() => {}
+            }
+        }
+    }
 
-async fn get_root() -> String {
-    format!("Jelli Fit API v{}", env!("CARGO_PKG_VERSION"))
+    shared_state.close().await;
 }
@@ -0,0 +1,494 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    Activity, Adaptor, CleanupPreview, Comment, DailyStats, Event, EventQuery, EventViews,
+    IdempotencyKey, Page, PageRange, PeopleQuery, PeopleVersion, Person, PersonInsert,
+    PersonUpdate, SchemaVersion, Stats, Template,
+};
+
+/// How often each adaptor method has been called, how long the calls took and how many
+/// failed, so operators can see whether slowness is in the API or in storage.
+///
+/// Clones share the same counts, so the state can keep one to report them.
+#[derive(Clone, Default)]
+pub struct AdaptorMetrics {
+    methods: Arc<RwLock<HashMap<&'static str, Arc<MethodCounters>>>>,
+}
+
+#[derive(Default)]
+struct MethodCounters {
+    calls: AtomicU64,
+    errors: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+}
+
+pub struct MethodMetrics {
+    pub method: &'static str,
+    pub calls: u64,
+    pub errors: u64,
+    pub total_micros: u64,
+    pub max_micros: u64,
+}
+
+impl AdaptorMetrics {
+    /// Every method that's been called so far, in alphabetical order
+    pub fn snapshot(&self) -> Vec<MethodMetrics> {
+        let methods = self.methods.read().expect("Metrics lock was poisoned");
+        let mut snapshot: Vec<_> = methods
+            .iter()
+            .map(|(method, counters)| MethodMetrics {
+                method,
+                calls: counters.calls.load(Ordering::Relaxed),
+                errors: counters.errors.load(Ordering::Relaxed),
+                total_micros: counters.total_micros.load(Ordering::Relaxed),
+                max_micros: counters.max_micros.load(Ordering::Relaxed),
+            })
+            .collect();
+        snapshot.sort_by_key(|metrics| metrics.method);
+        snapshot
+    }
+
+    async fn record<T, E>(
+        &self,
+        method: &'static str,
+        call: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let result = call.await;
+        let micros = start.elapsed().as_micros() as u64;
+
+        let counters = self.counters(method);
+        counters.calls.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            counters.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        counters.total_micros.fetch_add(micros, Ordering::Relaxed);
+        counters.max_micros.fetch_max(micros, Ordering::Relaxed);
+        result
+    }
+
+    // Methods are only added the first time they're called, so the write lock is rarely needed
+    fn counters(&self, method: &'static str) -> Arc<MethodCounters> {
+        if let Some(counters) = self
+            .methods
+            .read()
+            .expect("Metrics lock was poisoned")
+            .get(method)
+        {
+            return counters.clone();
+        }
+        self.methods
+            .write()
+            .expect("Metrics lock was poisoned")
+            .entry(method)
+            .or_default()
+            .clone()
+    }
+}
+
+/// Wraps an adaptor to record [`AdaptorMetrics`] for every call, whatever the backend is.
+pub struct MeteredAdaptor<A> {
+    inner: A,
+    metrics: AdaptorMetrics,
+}
+
+impl<A> MeteredAdaptor<A> {
+    pub fn new(inner: A, metrics: AdaptorMetrics) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+#[async_trait]
+impl<A: Adaptor> Adaptor for MeteredAdaptor<A>
+where
+    A::Error: Send,
+{
+    type Error = A::Error;
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.metrics.record("ping", self.inner.ping()).await
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        self.metrics.record("close", self.inner.close()).await
+    }
+
+    async fn get_schema_version(&self) -> Result<SchemaVersion, Self::Error> {
+        self.metrics
+            .record("get_schema_version", self.inner.get_schema_version())
+            .await
+    }
+
+    async fn migrate(&self) -> Result<SchemaVersion, Self::Error> {
+        self.metrics.record("migrate", self.inner.migrate()).await
+    }
+
+    async fn get_stats(&self) -> Result<Stats, Self::Error> {
+        self.metrics
+            .record("get_stats", self.inner.get_stats())
+            .await
+    }
+
+    async fn increment_stat_event_count(&self) -> Result<i64, Self::Error> {
+        self.metrics
+            .record(
+                "increment_stat_event_count",
+                self.inner.increment_stat_event_count(),
+            )
+            .await
+    }
+
+    async fn increment_stat_person_count(&self) -> Result<i64, Self::Error> {
+        self.metrics
+            .record(
+                "increment_stat_person_count",
+                self.inner.increment_stat_person_count(),
+            )
+            .await
+    }
+
+    async fn add_stats(&self, stats: Stats) -> Result<Stats, Self::Error> {
+        self.metrics
+            .record("add_stats", self.inner.add_stats(stats))
+            .await
+    }
+
+    async fn add_daily_stats(&self, date: NaiveDate, stats: Stats) -> Result<(), Self::Error> {
+        self.metrics
+            .record("add_daily_stats", self.inner.add_daily_stats(date, stats))
+            .await
+    }
+
+    async fn get_daily_stats(&self, since: NaiveDate) -> Result<Vec<DailyStats>, Self::Error> {
+        self.metrics
+            .record("get_daily_stats", self.inner.get_daily_stats(since))
+            .await
+    }
+
+    async fn get_people(&self, event_id: String) -> Result<Option<Vec<Person>>, Self::Error> {
+        self.metrics
+            .record("get_people", self.inner.get_people(event_id))
+            .await
+    }
+
+    async fn query_people(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+    ) -> Result<Option<Vec<Person>>, Self::Error> {
+        self.metrics
+            .record("query_people", self.inner.query_people(event_id, query))
+            .await
+    }
+
+    async fn query_people_page(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+        range: PageRange,
+    ) -> Result<Option<Page<Person>>, Self::Error> {
+        self.metrics
+            .record(
+                "query_people_page",
+                self.inner.query_people_page(event_id, query, range),
+            )
+            .await
+    }
+
+    async fn get_people_version(
+        &self,
+        event_id: String,
+    ) -> Result<Option<PeopleVersion>, Self::Error> {
+        self.metrics
+            .record(
+                "get_people_version",
+                self.inner.get_people_version(event_id),
+            )
+            .await
+    }
+
+    async fn upsert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<Person>, Self::Error> {
+        self.metrics
+            .record("upsert_person", self.inner.upsert_person(event_id, person))
+            .await
+    }
+
+    async fn insert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<PersonInsert>, Self::Error> {
+        self.metrics
+            .record("insert_person", self.inner.insert_person(event_id, person))
+            .await
+    }
+
+    async fn update_person(
+        &self,
+        event_id: String,
+        person: Person,
+        expected_updated_at: DateTime<Utc>,
+    ) -> Result<Option<PersonUpdate>, Self::Error> {
+        self.metrics
+            .record(
+                "update_person",
+                self.inner
+                    .update_person(event_id, person, expected_updated_at),
+            )
+            .await
+    }
+
+    async fn delete_person(
+        &self,
+        event_id: String,
+        person_name: String,
+    ) -> Result<Option<Person>, Self::Error> {
+        self.metrics
+            .record(
+                "delete_person",
+                self.inner.delete_person(event_id, person_name),
+            )
+            .await
+    }
+
+    async fn get_comments(&self, event_id: String) -> Result<Option<Vec<Comment>>, Self::Error> {
+        self.metrics
+            .record("get_comments", self.inner.get_comments(event_id))
+            .await
+    }
+
+    async fn create_comment(
+        &self,
+        event_id: String,
+        comment: Comment,
+    ) -> Result<Option<Comment>, Self::Error> {
+        self.metrics
+            .record(
+                "create_comment",
+                self.inner.create_comment(event_id, comment),
+            )
+            .await
+    }
+
+    async fn get_activity(&self, event_id: String) -> Result<Option<Vec<Activity>>, Self::Error> {
+        self.metrics
+            .record("get_activity", self.inner.get_activity(event_id))
+            .await
+    }
+
+    async fn create_activity(
+        &self,
+        event_id: String,
+        activity: Activity,
+    ) -> Result<Option<Activity>, Self::Error> {
+        self.metrics
+            .record(
+                "create_activity",
+                self.inner.create_activity(event_id, activity),
+            )
+            .await
+    }
+
+    async fn get_event_views(&self, event_id: String) -> Result<Option<EventViews>, Self::Error> {
+        self.metrics
+            .record("get_event_views", self.inner.get_event_views(event_id))
+            .await
+    }
+
+    async fn record_event_view(
+        &self,
+        event_id: String,
+        register: usize,
+        rank: u8,
+    ) -> Result<Option<EventViews>, Self::Error> {
+        self.metrics
+            .record(
+                "record_event_view",
+                self.inner.record_event_view(event_id, register, rank),
+            )
+            .await
+    }
+
+    async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        self.metrics
+            .record("get_event", self.inner.get_event(id))
+            .await
+    }
+
+    async fn get_events(&self, ids: Vec<String>) -> Result<Vec<Event>, Self::Error> {
+        self.metrics
+            .record("get_events", self.inner.get_events(ids))
+            .await
+    }
+
+    async fn create_event(&self, event: Event) -> Result<Event, Self::Error> {
+        self.metrics
+            .record("create_event", self.inner.create_event(event))
+            .await
+    }
+
+    async fn update_event(&self, event: Event) -> Result<Option<Event>, Self::Error> {
+        self.metrics
+            .record("update_event", self.inner.update_event(event))
+            .await
+    }
+
+    async fn get_listed_events(&self, tag: Option<String>) -> Result<Vec<Event>, Self::Error> {
+        self.metrics
+            .record("get_listed_events", self.inner.get_listed_events(tag))
+            .await
+    }
+
+    async fn get_group_events(&self, group_id: String) -> Result<Vec<Event>, Self::Error> {
+        self.metrics
+            .record("get_group_events", self.inner.get_group_events(group_id))
+            .await
+    }
+
+    async fn get_creator_events(&self, creator_id: String) -> Result<Vec<Event>, Self::Error> {
+        self.metrics
+            .record(
+                "get_creator_events",
+                self.inner.get_creator_events(creator_id),
+            )
+            .await
+    }
+
+    async fn query_events(
+        &self,
+        query: EventQuery,
+        range: PageRange,
+    ) -> Result<Page<Event>, Self::Error> {
+        self.metrics
+            .record("query_events", self.inner.query_events(query, range))
+            .await
+    }
+
+    async fn peek_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        self.metrics
+            .record("peek_event", self.inner.peek_event(id))
+            .await
+    }
+
+    async fn get_idempotency_key(
+        &self,
+        key: String,
+    ) -> Result<Option<IdempotencyKey>, Self::Error> {
+        self.metrics
+            .record("get_idempotency_key", self.inner.get_idempotency_key(key))
+            .await
+    }
+
+    async fn create_idempotency_key(&self, key: IdempotencyKey) -> Result<bool, Self::Error> {
+        self.metrics
+            .record(
+                "create_idempotency_key",
+                self.inner.create_idempotency_key(key),
+            )
+            .await
+    }
+
+    async fn set_idempotency_key_event(
+        &self,
+        key: String,
+        event_id: String,
+    ) -> Result<(), Self::Error> {
+        self.metrics
+            .record(
+                "set_idempotency_key_event",
+                self.inner.set_idempotency_key_event(key, event_id),
+            )
+            .await
+    }
+
+    async fn delete_idempotency_key(&self, key: String) -> Result<(), Self::Error> {
+        self.metrics
+            .record(
+                "delete_idempotency_key",
+                self.inner.delete_idempotency_key(key),
+            )
+            .await
+    }
+
+    async fn delete_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        self.metrics
+            .record(
+                "delete_idempotency_keys",
+                self.inner.delete_idempotency_keys(cutoff),
+            )
+            .await
+    }
+
+    async fn get_template(&self, id: String) -> Result<Option<Template>, Self::Error> {
+        self.metrics
+            .record("get_template", self.inner.get_template(id))
+            .await
+    }
+
+    async fn create_template(&self, template: Template) -> Result<Template, Self::Error> {
+        self.metrics
+            .record("create_template", self.inner.create_template(template))
+            .await
+    }
+
+    async fn delete_templates(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        self.metrics
+            .record("delete_templates", self.inner.delete_templates(cutoff))
+            .await
+    }
+
+    async fn expire_events(&self, default_retention_days: i64) -> Result<i64, Self::Error> {
+        self.metrics
+            .record(
+                "expire_events",
+                self.inner.expire_events(default_retention_days),
+            )
+            .await
+    }
+
+    async fn restore_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        self.metrics
+            .record("restore_event", self.inner.restore_event(id))
+            .await
+    }
+
+    async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error> {
+        self.metrics
+            .record("delete_events", self.inner.delete_events(cutoff))
+            .await
+    }
+
+    async fn preview_cleanup(
+        &self,
+        default_retention_days: i64,
+        cutoff: DateTime<Utc>,
+    ) -> Result<CleanupPreview, Self::Error> {
+        self.metrics
+            .record(
+                "preview_cleanup",
+                self.inner.preview_cleanup(default_retention_days, cutoff),
+            )
+            .await
+    }
+
+    async fn delete_event(&self, id: String) -> Result<Option<Stats>, Self::Error> {
+        self.metrics
+            .record("delete_event", self.inner.delete_event(id))
+            .await
+    }
+}
@@ -0,0 +1,47 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+};
+
+use crate::config::config;
+
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+// Accepted as well, from before the cron key was generalized to all admin routes
+const LEGACY_CRON_KEY_HEADER: &str = "x-cron-key";
+
+/// The key needed to call admin routes, from `admin.key`, which is set with `ADMIN_KEY` or
+/// the older `CRON_KEY`. Admin routes can't be called at all if it isn't set.
+pub fn admin_key() -> Option<String> {
+    config().admin.key.clone()
+}
+
+/// Proof that a request sent the admin key, rejecting it with 401 otherwise
+///
+/// Routes with [`Auth::Admin`](crate::routes::Auth::Admin) in the registry require it
+/// automatically, but it can also be taken as an argument by handlers.
+pub struct AdminKey;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AdminKey {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let expected = admin_key().ok_or(StatusCode::UNAUTHORIZED)?;
+        let provided = parts
+            .headers
+            .get(ADMIN_KEY_HEADER)
+            .or_else(|| parts.headers.get(LEGACY_CRON_KEY_HEADER))
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+
+        match constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+            true => Ok(Self),
+            false => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+
+// Compare every byte, so how long the comparison takes doesn't reveal how much of the key matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
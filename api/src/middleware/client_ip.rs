@@ -0,0 +1,175 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header::FORWARDED, HeaderMap, Request},
+    middleware::Next,
+    response::Response,
+};
+use tower_governor::{key_extractor::KeyExtractor, GovernorError};
+
+use crate::config::{ProxyHeader, ServerConfig};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// The IP address of whoever made a request, looking past any trusted proxies
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIp(pub IpAddr);
+
+/// Proxies that are trusted to say who they're forwarding a request for, set with a list of
+/// addresses or CIDR ranges in `server.trusted_proxies`. They're trusted to set
+/// `X-Forwarded-For`, or `Forwarded` if `server.trusted_proxy_header` is `forwarded`, and
+/// the other header is ignored so clients can't fake it.
+#[derive(Default)]
+pub struct TrustedProxies {
+    ranges: Vec<(IpAddr, u8)>,
+    use_forwarded: bool,
+}
+
+impl TrustedProxies {
+    /// The proxies have already been checked when the config was loaded
+    pub fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            use_forwarded: config.trusted_proxy_header == ProxyHeader::Forwarded,
+            ranges: config
+                .trusted_proxies
+                .iter()
+                .filter_map(|proxy| parse_range(proxy))
+                .collect(),
+        }
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        self.ranges
+            .iter()
+            .any(|(range, prefix)| in_range(ip, *range, *prefix))
+    }
+
+    /// Find the client's IP by following the chain of proxies back from the peer, stopping
+    /// at the first address that isn't a trusted proxy, as anything before it could be faked
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let mut client = peer;
+        if !self.contains(peer) {
+            return client;
+        }
+
+        let chain = match self.use_forwarded {
+            true => forwarded_for(headers),
+            false => x_forwarded_for(headers),
+        };
+        for hop in chain.into_iter().rev() {
+            let Some(ip) = hop else {
+                break;
+            };
+            client = ip;
+            if !self.contains(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Work out who made each request, so it can be rate limited and logged by the client's IP
+/// rather than their proxy's
+pub async fn client_ip<B>(
+    State(proxies): State<Arc<TrustedProxies>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if let Some(ConnectInfo(addr)) = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .copied()
+    {
+        let ip = proxies.client_ip(addr.ip(), request.headers());
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+
+    next.run(request).await
+}
+
+/// Rate limit by the client's IP, as worked out by the `client_ip` middleware
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIpKeyExtractor;
+
+impl KeyExtractor for ClientIpKeyExtractor {
+    type Key = IpAddr;
+    type KeyExtractionError = GovernorError;
+
+    fn extract<T>(&self, req: &Request<T>) -> Result<Self::Key, GovernorError> {
+        req.extensions()
+            .get::<ClientIp>()
+            .map(|ClientIp(ip)| *ip)
+            .ok_or(GovernorError::UnableToExtractKey)
+    }
+}
+
+// Addresses from `X-Forwarded-For`, closest proxy last, with None for any that can't be parsed
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+        .map(|hop| hop.trim().parse().ok())
+        .collect()
+}
+
+// The `for` addresses from `Forwarded`, like `for=192.0.2.60;proto=http, for="[2001:db8::1]:4711"`,
+// closest proxy last, with None for obfuscated or unknown ones
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    headers
+        .get_all(FORWARDED)
+        .iter()
+        .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("for")
+                    .then(|| parse_node(value.trim().trim_matches('"')))
+            })
+        })
+        .collect()
+}
+
+// A node is an IPv4 address or bracketed IPv6 address, optionally followed by a port
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.split(':').next()?.parse().ok()
+}
+
+// An address, or a CIDR range like `10.0.0.0/8`
+pub(crate) fn parse_range(range: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix) = match range.split_once('/') {
+        Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, prefix.parse::<u8>().ok()?),
+        None => {
+            let addr = range.parse::<IpAddr>().ok()?;
+            (addr, if addr.is_ipv4() { 32 } else { 128 })
+        }
+    };
+    let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+    (prefix <= max_prefix).then_some((addr, prefix))
+}
+
+fn in_range(ip: IpAddr, range: IpAddr, prefix: u8) -> bool {
+    match (ip, range) {
+        (IpAddr::V4(ip), IpAddr::V4(range)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(range) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(range)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(range) & mask
+        }
+        // Proxies connecting over IPv6 with an IPv4 address
+        (IpAddr::V6(ip), IpAddr::V4(_)) => ip
+            .to_ipv4_mapped()
+            .is_some_and(|ip| in_range(IpAddr::V4(ip), range, prefix)),
+        (IpAddr::V4(_), IpAddr::V6(_)) => false,
+    }
+}
@@ -0,0 +1,71 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path},
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
+    http::request::Parts,
+};
+use common::{Adaptor, Event};
+use serde::Deserialize;
+
+use crate::{errors::ApiError, tokens::verify_token, AppState};
+
+#[derive(Deserialize)]
+struct EventPath {
+    event_id: String,
+}
+
+/// The event from the `event_id` in the path, once the request has proven it's the organizer
+/// by sending the event's edit token as a bearer token. Rejects with 404 if there's no such
+/// event, and 401 if the token is missing or wrong.
+///
+/// Routes with [`Auth::OwnerToken`](crate::routes::Auth::OwnerToken) on an event take this
+/// as an argument instead of checking the token themselves.
+pub struct OwnedEvent(pub Event);
+
+#[async_trait]
+impl<A: Adaptor> FromRequestParts<AppState<A>> for OwnedEvent {
+    type Rejection = ApiError<A>;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<A>,
+    ) -> Result<Self, Self::Rejection> {
+        let Path(EventPath { event_id }) = Path::<EventPath>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| ApiError::NotFound)?;
+        let token = parts
+            .headers
+            .typed_get::<Authorization<Bearer>>()
+            .map(|Authorization(bearer)| bearer.token().to_owned());
+
+        get_owned_event(&state.adaptor, event_id, token.as_deref())
+            .await
+            .map(Self)
+    }
+}
+
+/// Like [`OwnedEvent`], for callers that aren't axum handlers
+pub async fn get_owned_event<A: Adaptor>(
+    adaptor: &A,
+    event_id: String,
+    edit_token: Option<&str>,
+) -> Result<Event, ApiError<A>> {
+    let event = adaptor
+        .get_event(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    match edit_token {
+        Some(token) if owns_event(&event, token) => Ok(event),
+        _ => Err(ApiError::NotAuthorized),
+    }
+}
+
+pub fn owns_event(event: &Event, edit_token: &str) -> bool {
+    match &event.edit_token_hash {
+        Some(hash) => verify_token(edit_token, hash),
+        // Events created before edit tokens existed can't be edited
+        None => false,
+    }
+}
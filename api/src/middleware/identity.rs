@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::auth::{Oidc, ID_TOKEN_HEADER};
+
+/// Verify the ID token in the `X-Id-Token` header if there is one, adding the
+/// [`Identity`](crate::auth::Identity) it's for to the request's extensions. Requests with
+/// a token that isn't valid get a 401, so clients know to log in again instead of quietly
+/// being treated as anonymous. Without the header, or if logging in isn't set up, requests
+/// carry on anonymously.
+pub async fn identify<B>(
+    State(oidc): State<Arc<Option<Oidc>>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let (Some(oidc), Some(token)) = (
+        oidc.as_ref(),
+        request
+            .headers()
+            .get(ID_TOKEN_HEADER)
+            .and_then(|token| token.to_str().ok()),
+    ) else {
+        return next.run(request).await;
+    };
+
+    match oidc.verify(token.trim()).await {
+        Ok(identity) => {
+            request.extensions_mut().insert(identity);
+            next.run(request).await
+        }
+        Err(e) => {
+            tracing::debug!("Rejected ID token: {}", e);
+            (StatusCode::UNAUTHORIZED, "Invalid ID token").into_response()
+        }
+    }
+}
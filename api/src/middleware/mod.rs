@@ -0,0 +1,7 @@
+pub mod admin_key;
+pub mod client_ip;
+pub mod edit_token;
+pub mod identity;
+pub mod request_id;
+pub mod signature;
+pub mod terms;
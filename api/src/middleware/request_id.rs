@@ -0,0 +1,36 @@
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Longest request ID accepted from a client or proxy, longer ones are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Give each request an ID to include in its logs, reusing the one sent by a proxy in
+/// front of the API if there is one, and send it back in the response
+pub async fn request_id<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH)
+        .cloned()
+        .unwrap_or_else(generate_request_id);
+    request.headers_mut().insert(REQUEST_ID_HEADER, id.clone());
+
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, id);
+    response
+}
+
+fn generate_request_id() -> HeaderValue {
+    let id: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    HeaderValue::from_str(&id).expect("Alphanumeric characters are valid in a header")
+}
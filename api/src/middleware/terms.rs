@@ -0,0 +1,47 @@
+use axum::{
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::config::config;
+
+pub const TERMS_VERSION_HEADER: &str = "x-terms-version";
+
+/// The version of the terms people have to agree to, if the instance requires it
+pub fn terms_version() -> Option<String> {
+    config().events.terms_version.clone()
+}
+
+/// Require requests that write data to acknowledge the current terms version,
+/// responding with 428 if the header is missing or 451 if it's out of date
+pub async fn require_terms<B>(request: Request<B>, next: Next<B>) -> Response {
+    let Some(terms_version) = terms_version() else {
+        return next.run(request).await;
+    };
+
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return next.run(request).await;
+    }
+
+    match request
+        .headers()
+        .get(TERMS_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        None => (
+            StatusCode::PRECONDITION_REQUIRED,
+            format!("The {} header is required", TERMS_VERSION_HEADER),
+        )
+            .into_response(),
+        Some(version) if version != terms_version => (
+            StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+            format!("The current terms version is {}", terms_version),
+        )
+            .into_response(),
+        Some(_) => next.run(request).await,
+    }
+}
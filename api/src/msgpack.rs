@@ -0,0 +1,130 @@
+use axum::{
+    async_trait,
+    body::{Bytes, HttpBody},
+    extract::{FromRequest, FromRequestParts},
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        request::Parts,
+        HeaderMap, Request, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    BoxError, Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+// Other names clients use for the same format
+const MSGPACK_ALIASES: [&str; 3] = [
+    MSGPACK_CONTENT_TYPE,
+    "application/x-msgpack",
+    "application/vnd.msgpack",
+];
+
+/// The format to respond in, from the request's `Accept` header
+///
+/// MessagePack is only used if it's preferred over JSON, so clients that don't ask for it
+/// keep getting JSON.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    MsgPack,
+}
+
+impl Format {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut best = (Self::Json, 0.0);
+        for accept in headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+        {
+            let mut parts = accept.split(';');
+            let media_type = parts.next().unwrap_or_default().trim().to_lowercase();
+            let Some(quality) = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())
+            else {
+                continue;
+            };
+
+            let format = match media_type.as_str() {
+                t if MSGPACK_ALIASES.contains(&t) => Self::MsgPack,
+                "application/json" | "application/*" | "*/*" => Self::Json,
+                _ => continue,
+            };
+            // Earlier types win ties
+            if quality > best.1 {
+                best = (format, quality);
+            }
+        }
+        best.0
+    }
+
+    /// Serialize a value in this format
+    ///
+    /// Caches keep the formats apart with `Vary: Accept`, which the CORS layer adds to every
+    /// response.
+    pub fn respond<T: Serialize>(self, value: T) -> Response {
+        match self {
+            Self::Json => Json(value).into_response(),
+            Self::MsgPack => match rmp_serde::to_vec_named(&value) {
+                Ok(body) => ([(CONTENT_TYPE, MSGPACK_CONTENT_TYPE)], body).into_response(),
+                Err(e) => {
+                    tracing::error!(?e);
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Format {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+/// A request body in JSON, or in MessagePack if the `Content-Type` says so
+pub struct JsonOrMsgPack<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for JsonOrMsgPack<T>
+where
+    T: DeserializeOwned,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let is_msgpack = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|t| MSGPACK_ALIASES.contains(&t.trim().to_lowercase().as_str()));
+        if !is_msgpack {
+            let Json(value) = Json::<T>::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Self(value));
+        }
+
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        rmp_serde::from_slice(&body).map(Self).map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Failed to deserialize the MessagePack body: {}", e),
+            )
+                .into_response()
+        })
+    }
+}
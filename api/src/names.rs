@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+
+use axum::http::{header::ACCEPT_LANGUAGE, HeaderMap};
+use rand::{seq::SliceRandom, thread_rng};
+use serde::Deserialize;
+
+// Word lists for generated event names, English first as the fallback
+const LOCALES: [(&str, &[u8]); 4] = [
+    ("en", include_bytes!("res/names/en.json")),
+    ("de", include_bytes!("res/names/de.json")),
+    ("es", include_bytes!("res/names/es.json")),
+    ("fr", include_bytes!("res/names/fr.json")),
+];
+
+#[derive(Deserialize)]
+struct NameWords {
+    /// How the words are put together, with `{adjective}` and `{jelly}` placeholders
+    format: String,
+    adjectives: Vec<String>,
+    jellies: Vec<String>,
+}
+
+/// Generate a random name for an event, like "Brave Bell jelly Jelly", in a language if
+/// there are words for it, or English otherwise
+pub fn generate_name(locale: Option<&str>) -> String {
+    let data = locale
+        .and_then(|locale| LOCALES.iter().find(|(code, _)| *code == language(locale)))
+        .unwrap_or(&LOCALES[0])
+        .1;
+    let words: NameWords = serde_json::from_slice(data).unwrap();
+
+    // Some words are harmless on their own but not next to each other
+    loop {
+        let name = words
+            .format
+            .replace(
+                "{adjective}",
+                words.adjectives.choose(&mut thread_rng()).unwrap(),
+            )
+            .replace("{jelly}", words.jellies.choose(&mut thread_rng()).unwrap());
+        if !is_offensive(&name) {
+            return name;
+        }
+    }
+}
+
+/// Whether a name or slug contains a word from the block list, either on its own or split
+/// across two words, like `ass-hat`
+///
+/// Only whole words are matched, so names like "Scunthorpe" aren't caught.
+pub fn is_offensive(text: &str) -> bool {
+    let blocked: HashSet<String> =
+        serde_json::from_slice(include_bytes!("res/blocked_words.json")).unwrap();
+
+    let text = text.to_lowercase();
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    words.iter().any(|word| blocked.contains(*word))
+        || words
+            .windows(2)
+            .any(|pair| blocked.contains(&pair.concat()))
+}
+
+/// The most preferred language in an `Accept-Language` header that names can be generated in
+pub fn locale_from_headers(headers: &HeaderMap) -> Option<String> {
+    let header = headers.get(ACCEPT_LANGUAGE)?.to_str().ok()?;
+
+    let mut languages: Vec<(&str, f32)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut parts = part.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            Some((tag, quality))
+        })
+        .filter(|(tag, quality)| *quality > 0.0 && *tag != "*")
+        .collect();
+    // Stable, so languages with the same quality keep the order they were sent in
+    languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    languages
+        .into_iter()
+        .map(|(tag, _)| language(tag))
+        .find(|language| LOCALES.iter().any(|(code, _)| code == language))
+}
+
+// Only the language matters for names, not the region, so `es-MX` uses `es`
+fn language(locale: &str) -> String {
+    locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
@@ -1,17 +1,130 @@
+use std::collections::HashSet;
+
 use axum::Json;
-use common::{Event, Person, Stats};
-use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use chrono::{TimeZone, Utc};
+use common::{
+    Activity, CleanupPreview, Comment, Event, EventQuery, PageRange, PeopleQuery, PeopleSort,
+    Person, Stats, Template,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::errors::ApiError;
+use crate::{
+    cache::CacheStats,
+    chat::ChatPlatform,
+    cleanup::CleanupReport,
+    errors::ApiError,
+    metrics::MethodMetrics,
+    routes::{Auth, RateLimit},
+    scheduling::Assignment,
+    scoring::{ScoredSlot, Scoring},
+    seed::SeedReport,
+    slots::Slot,
+};
 
 pub type ApiResult<T, A> = Result<Json<T>, ApiError<A>>;
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsQuery {
+    /// Comma separated list of fields to include in the response, defaults to all fields
+    pub fields: Option<String>,
+}
+
+impl FieldsQuery {
+    pub fn includes(&self, field: &str) -> bool {
+        match &self.fields {
+            Some(fields) => fields.split(',').any(|f| f.trim() == field),
+            None => true,
+        }
+    }
+
+    /// Wrap a response so only the requested fields are serialized
+    pub fn sparse<T>(&self, value: T) -> Sparse<T> {
+        Sparse {
+            value,
+            fields: self.fields.as_ref().map(|fields| {
+                fields
+                    .split(',')
+                    .map(|f| f.trim().to_owned())
+                    .filter(|f| !f.is_empty())
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// A response that only serializes a subset of its fields. If the response
+/// is a list, the fields of each item are filtered instead.
+pub struct Sparse<T> {
+    value: T,
+    fields: Option<HashSet<String>>,
+}
+
+impl<T: Serialize> Serialize for Sparse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.fields {
+            Some(fields) => {
+                let value = serde_json::to_value(&self.value).map_err(serde::ser::Error::custom)?;
+                retain_fields(value, fields).serialize(serializer)
+            }
+            None => self.value.serialize(serializer),
+        }
+    }
+}
+
+fn retain_fields(value: Value, fields: &HashSet<String>) -> Value {
+    match value {
+        Value::Object(mut map) => {
+            map.retain(|key, _| fields.contains(key));
+            Value::Object(map)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| retain_fields(item, fields))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct EventInput {
     pub name: Option<String>,
     pub times: Vec<String>,
     pub timezone: String,
+    /// How to rank the event's times, defaults to maximizing attendance
+    pub scoring: Option<Scoring>,
+    /// Show the event in the public directory, defaults to false
+    pub listed: Option<bool>,
+    /// Tags to find the event by in the public directory
+    pub tags: Option<Vec<String>>,
+    /// Make the event private, so viewing it requires this password
+    pub password: Option<String>,
+    /// Names of the people expected to respond, once they all have
+    /// filled in their availability, responses are closed
+    pub invitees: Option<Vec<String>>,
+    /// ID to use for the event instead of generating one from its name, like `team-standup`
+    pub slug: Option<String>,
+    /// Days to keep the event for after it was last visited, up to 365, defaults to the
+    /// instance's retention
+    pub retention_days: Option<i64>,
+    /// The most people that can join the event, defaults to no limit
+    pub max_people: Option<i64>,
+    /// Group to add the event to, so it can be fetched along with related events, like
+    /// `team-standup-march`
+    pub group_id: Option<String>,
+    /// Language to generate the event's name in if it doesn't have one, like `es`, defaults
+    /// to the `Accept-Language` header
+    pub locale: Option<String>,
+    /// The `creator_token` returned with an earlier event, so both are listed together at
+    /// `/me/events`. A new token is returned if this is left out.
+    pub creator_token: Option<String>,
+    /// Slack or Discord incoming webhook URL to post to when the event is created, someone
+    /// responds, or a time is chosen
+    pub chat_webhook_url: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -21,20 +134,306 @@ pub struct EventResponse {
     pub times: Vec<String>,
     pub timezone: String,
     pub created_at: i64,
+    /// Number of people who have responded, only included when requested with `fields` or
+    /// listing your own events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub people_count: Option<usize>,
+    /// Token needed to delete the event, only included when the event is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_token: Option<String>,
+    /// Token to list the events created with it at `/me/events`, only included when the
+    /// event is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creator_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scoring: Option<Scoring>,
+    pub listed: bool,
+    pub tags: Vec<String>,
+    /// The time chosen by the organizer, null until the event is finalized
+    pub finalized_time: Option<String>,
+    /// Whether a password is needed to view the event
+    pub private: bool,
+    pub invitees: Vec<String>,
+    /// Whether people can no longer change their availability, as everyone invited has responded
+    pub responses_closed: bool,
+    /// Days the event is kept for after it was last visited, null if it uses the instance's retention
+    pub retention_days: Option<i64>,
+    /// The most people that can join the event, null if there's no limit
+    pub max_people: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
 }
 
 impl From<Event> for EventResponse {
     fn from(value: Event) -> Self {
+        let scoring = value.scoring.is_some().then(|| Scoring::of(&value));
         Self {
             id: value.id,
             name: value.name,
             times: value.times,
             timezone: value.timezone,
             created_at: value.created_at.timestamp(),
+            people_count: None,
+            edit_token: None,
+            creator_token: None,
+            scoring,
+            listed: value.listed,
+            tags: value.tags,
+            finalized_time: value.finalized_time,
+            private: value.password_hash.is_some(),
+            invitees: value.invitees,
+            responses_closed: value.responses_closed,
+            retention_days: value.retention_days,
+            max_people: value.max_people,
+            group_id: value.group_id,
         }
     }
 }
 
+/// Changes to an event's details, anything left out stays as it is
+#[derive(Deserialize, ToSchema)]
+pub struct EventUpdateInput {
+    pub name: Option<String>,
+    /// Show the event in the public directory
+    pub listed: Option<bool>,
+    /// Tags to find the event by in the public directory, replacing the existing ones
+    pub tags: Option<Vec<String>>,
+    /// The most people that can join the event, 0 removes the limit. People who have
+    /// already joined are kept if it's lowered below how many there are.
+    pub max_people: Option<i64>,
+    /// Days to keep the event for after it was last visited, up to 365, or 0 to go back
+    /// to the instance's retention. Counts from the last visit, so it can be shortened
+    /// to have an event deleted sooner.
+    pub retention_days: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct FinalizeInput {
+    /// One of the event's times, or null to reopen the event for responses
+    pub time: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DirectoryParams {
+    /// Only include events with this tag
+    pub tag: Option<String>,
+}
+
+/// The public details of an event listed in the directory
+#[derive(Serialize, ToSchema)]
+pub struct DirectoryEntryResponse {
+    pub id: String,
+    pub name: String,
+    /// Earliest date of the event's times as `YYYY-MM-DD`, null if it uses days of the week
+    pub start_date: Option<String>,
+    /// Latest date of the event's times as `YYYY-MM-DD`, null if it uses days of the week
+    pub end_date: Option<String>,
+    /// Number of people who have responded
+    pub people_count: usize,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventIdsParams {
+    /// Comma separated list of event IDs, like `a,b,c`
+    pub ids: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EventLookupInput {
+    pub ids: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EventLookupResponse {
+    pub id: String,
+    /// Null if the event doesn't exist
+    pub event: Option<EventResponse>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WebhookInput {
+    /// Where to `POST` changes to the event, must be plain HTTP
+    pub url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub url: String,
+    /// Key used to sign each payload with HMAC-SHA256, a new one is issued each time
+    /// the webhook is registered
+    pub secret: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChatWebhookInput {
+    /// A Slack or Discord incoming webhook URL
+    pub url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ChatWebhookResponse {
+    pub url: String,
+    /// Which platform the URL belongs to, messages are formatted for it
+    pub platform: ChatPlatform,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MergeInput {
+    /// The event to merge into
+    pub target: EventOwnerInput,
+    /// The event to merge times and people from, which is left as is
+    pub source: EventOwnerInput,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GroupResponse {
+    pub id: String,
+    pub events: Vec<EventResponse>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GroupAvailabilityInput {
+    /// ID of the event in the group to copy the person's availability from
+    pub from: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CopyAvailabilityInput {
+    /// ID of the event to copy the person's availability from
+    pub from: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GroupAvailabilityResponse {
+    /// IDs of the events the availability was copied to
+    pub updated: Vec<String>,
+    /// IDs of the events the person couldn't respond to
+    pub skipped: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DuplicateInput {
+    /// Name for the copy, defaults to the original event's name
+    pub name: Option<String>,
+    /// ID to use for the copy instead of generating one from its name
+    pub slug: Option<String>,
+    /// Weeks to move the copy's dates forward by, up to 52. Events using days of the week
+    /// aren't affected.
+    pub shift_weeks: Option<i64>,
+}
+
+/// Scheduling sites events can be imported from
+#[derive(Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    When2meet,
+    Lettucemeet,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ImportInput {
+    pub source: ImportSource,
+    /// The source of the When2Meet event page, or the JSON LettuceMeet's API returns for the
+    /// event. The API can't fetch these itself, so they have to be downloaded first.
+    pub payload: String,
+    /// Name to use instead of the imported event's
+    pub name: Option<String>,
+    /// Timezone to use instead of the imported event's, needed for When2Meet events
+    pub timezone: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportResponse {
+    /// The new event, including its edit token
+    pub event: EventResponse,
+    /// Names of the people copied from the imported event
+    pub people: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EventOwnerInput {
+    pub id: String,
+    /// The edit token returned when the event was created
+    pub edit_token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MergeResponse {
+    /// The target event, with the times from the source event added
+    pub event: EventResponse,
+    /// Names of the people copied from the source event
+    pub merged: Vec<String>,
+    /// Names of the people on the source event who weren't copied, as someone
+    /// with the same name is already on the target event
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SyncParams {
+    /// The `version` from the last sync, leave out to get everything
+    pub since_version: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncResponse {
+    /// Pass as `since_version` to get changes after this sync. Changes made at exactly
+    /// this version may be included again, so applying them has to be idempotent.
+    pub version: i64,
+    /// The event, if its details have changed
+    pub event: Option<EventResponse>,
+    /// People who have joined or changed their availability
+    pub people: Vec<PersonResponse>,
+    /// Names of people who have been removed from the event
+    pub removed: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SyncInput {
+    /// Changes made while offline, applied in the order they were made
+    pub mutations: Vec<SyncMutation>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SyncMutation {
+    /// The name of a person already on the event
+    pub name: String,
+    pub availability: Vec<String>,
+    pub if_needed: Option<Vec<String>>,
+    pub undecided: Option<Vec<String>>,
+    /// Unix timestamp in milliseconds of when the change was made on the client
+    pub edited_at: i64,
+    /// The person's edit token, required if they have a password
+    pub edit_token: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncResultResponse {
+    /// People whose changes were saved
+    pub applied: Vec<PersonResponse>,
+    /// People who were changed on the server after the client's edit was made, so their
+    /// changes weren't saved. Contains the server's copy, which replaces the client's.
+    pub conflicts: Vec<PersonResponse>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExtendParams {
+    /// Name of the person extending the event, if it isn't extended with the event's edit
+    /// token, which the instance might not allow
+    pub person: Option<String>,
+    /// Token from the person's private edit link, can be used instead of their password
+    pub edit_token: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExtendResponse {
+    /// Unix timestamp of when the event will be deleted if it isn't visited again
+    pub expires_at: i64,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct StatsResponse {
     pub event_count: i64,
@@ -52,11 +451,121 @@ impl From<Stats> for StatsResponse {
     }
 }
 
+#[derive(Deserialize, Serialize, ToSchema, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsGranularity {
+    #[default]
+    Day,
+    /// Weeks starting on Monday
+    Week,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsTimeseriesParams {
+    /// Defaults to `day`
+    #[serde(default)]
+    pub granularity: StatsGranularity,
+    /// How many days back to go, including today, defaults to 30
+    pub days: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StatsBucketResponse {
+    /// First day of the period as `YYYY-MM-DD`
+    pub start: String,
+    /// Events created during the period
+    pub event_count: i64,
+    /// People who joined events during the period
+    pub person_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StatsTimeseriesResponse {
+    pub granularity: StatsGranularity,
+    /// Every period in the range, oldest first, including those nothing was created in
+    pub buckets: Vec<StatsBucketResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Unavailable,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: HealthStatus,
+    /// Why the API isn't ready, if it isn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
 #[derive(Serialize, ToSchema)]
+pub struct MetaResponse {
+    pub version: String,
+    /// If set, requests that write data need an `X-Terms-Version` header with this value
+    pub terms_version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ChangelogVersionResponse {
+    pub version: String,
+    pub changes: Vec<ChangeResponse>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ChangeResponse {
+    pub kind: ChangeKind,
+    /// The routes affected, empty if the change applies to the whole API
+    pub paths: Vec<String>,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Deprecated,
+    Removed,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BrandingResponse {
+    pub name: String,
+    pub logo_url: Option<String>,
+    /// Hex colour code, e.g. `#0eaac5`
+    pub color: String,
+    /// An email address or url people can use to get help
+    pub support_contact: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Clone)]
 pub struct PersonResponse {
     pub name: String,
     pub availability: Vec<String>,
+    /// Times in `availability` the person can only make if needed
+    pub if_needed: Vec<String>,
+    /// Times the person hasn't decided on yet
+    pub undecided: Vec<String>,
+    /// Hex color like `#ff8800` the person has chosen to be shown in
+    pub avatar_color: Option<String>,
+    /// Emoji the person has chosen to show next to their name
+    pub avatar_emoji: Option<String>,
+    /// The person's own timezone, if they've set one
+    pub timezone: Option<String>,
     pub created_at: i64,
+    pub updated_at: i64,
+    /// Changes whenever the person does, send as `If-Match` when updating them to avoid
+    /// overwriting someone else's changes
+    pub version: String,
+    /// Token for editing without a password, only included when it's first issued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_token_expires_at: Option<i64>,
 }
 
 impl From<Person> for PersonResponse {
@@ -64,7 +573,393 @@ impl From<Person> for PersonResponse {
         Self {
             name: value.name,
             availability: value.availability,
+            if_needed: value.if_needed,
+            undecided: value.undecided,
+            avatar_color: value.avatar_color,
+            avatar_emoji: value.avatar_emoji,
+            timezone: value.timezone,
             created_at: value.created_at.timestamp(),
+            updated_at: value.updated_at.timestamp(),
+            version: value.updated_at.timestamp_millis().to_string(),
+            edit_token: None,
+            edit_token_expires_at: None,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TemplateInput {
+    /// Name for events created from the template, where `{date}` is replaced with the
+    /// first day of the event's week, like `Standup, week of {date}`
+    pub name: String,
+    /// Times in the `HHmm-d` days of the week format
+    pub times: Vec<String>,
+    pub timezone: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TemplateResponse {
+    pub id: String,
+    pub name: String,
+    pub times: Vec<String>,
+    pub timezone: String,
+    pub created_at: i64,
+    /// Token needed to create events from the template, only included when it's created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_token: Option<String>,
+}
+
+impl From<Template> for TemplateResponse {
+    fn from(value: Template) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            times: value.times,
+            timezone: value.timezone,
+            created_at: value.created_at.timestamp(),
+            edit_token: None,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TemplateEventInput {
+    /// Unix timestamp of any time in the week to create the event for, weeks start on
+    /// Sunday. Defaults to the current week.
+    pub week_of: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlInput {
+    pub query: String,
+    pub operation_name: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub variables: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlParams {
+    pub query: String,
+    #[param(rename = "operationName")]
+    pub operation_name: Option<String>,
+    /// Variables for the query, as JSON
+    pub variables: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CommentInput {
+    pub author: String,
+    pub body: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CommentResponse {
+    pub author: String,
+    pub body: String,
+    pub created_at: i64,
+}
+
+impl From<Comment> for CommentResponse {
+    fn from(value: Comment) -> Self {
+        Self {
+            author: value.author,
+            body: value.body,
+            created_at: value.created_at.timestamp(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    /// Someone joined without filling in their availability yet
+    Joined,
+    /// Someone filled in their availability for the first time
+    Responded,
+    UpdatedAvailability,
+    Removed,
+    /// Everyone invited has responded
+    ResponsesClosed,
+    Finalized,
+    Reopened,
+}
+
+impl ActivityKind {
+    const ALL: [ActivityKind; 7] = [
+        ActivityKind::Joined,
+        ActivityKind::Responded,
+        ActivityKind::UpdatedAvailability,
+        ActivityKind::Removed,
+        ActivityKind::ResponsesClosed,
+        ActivityKind::Finalized,
+        ActivityKind::Reopened,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::Joined => "joined",
+            ActivityKind::Responded => "responded",
+            ActivityKind::UpdatedAvailability => "updated_availability",
+            ActivityKind::Removed => "removed",
+            ActivityKind::ResponsesClosed => "responses_closed",
+            ActivityKind::Finalized => "finalized",
+            ActivityKind::Reopened => "reopened",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == kind)
+    }
+
+    /// Describe the change in a sentence that can be shown as is
+    pub fn describe(&self, person: Option<&str>) -> String {
+        let person = person.unwrap_or("Someone");
+        match self {
+            ActivityKind::Joined => format!("{} joined", person),
+            ActivityKind::Responded => format!("{} responded", person),
+            ActivityKind::UpdatedAvailability => format!("{} updated their availability", person),
+            ActivityKind::Removed => format!("{} was removed", person),
+            ActivityKind::ResponsesClosed => "Everyone invited has responded".to_owned(),
+            ActivityKind::Finalized => "The event was finalized".to_owned(),
+            ActivityKind::Reopened => "The event was reopened".to_owned(),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ActivityParams {
+    /// Only include changes made at or after this unix timestamp, such as the last visit
+    pub since: Option<i64>,
+    /// How many changes to return, defaults to all of them
+    pub limit: Option<usize>,
+    /// How many changes to skip, defaults to 0
+    pub offset: Option<usize>,
+}
+
+impl ActivityParams {
+    pub fn range(&self) -> PageRange {
+        PageRange {
+            offset: self.offset.unwrap_or(0),
+            limit: self.limit,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// Include the event's comments, defaults to false
+    #[serde(default)]
+    pub comments: bool,
+}
+
+/// A copy of everything stored about an event
+#[derive(Serialize, ToSchema)]
+pub struct ExportResponse {
+    pub exported_at: i64,
+    /// When the event will be deleted if nobody visits it again
+    pub expires_at: i64,
+    pub event: EventResponse,
+    /// Everyone who has joined the event, including those who haven't responded yet
+    pub people: Vec<PersonResponse>,
+    /// Only included when requested with `comments`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<CommentResponse>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EventAnalyticsResponse {
+    /// How many times the event has been opened
+    pub view_count: i64,
+    /// Estimate of how many different people opened it, based on their IP addresses
+    pub unique_visitors: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ActivityResponse {
+    pub kind: ActivityKind,
+    /// Who made the change, missing for changes to the event itself
+    pub person: Option<String>,
+    /// A description of the change, like "Amy updated their availability"
+    pub message: String,
+    pub created_at: i64,
+}
+
+impl ActivityResponse {
+    /// Returns None for kinds of changes this version of the API doesn't know about
+    pub fn from_activity(activity: Activity) -> Option<Self> {
+        let kind = ActivityKind::parse(&activity.kind)?;
+        Some(Self {
+            kind,
+            message: kind.describe(activity.person.as_deref()),
+            person: activity.person,
+            created_at: activity.created_at.timestamp(),
+        })
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EditTokenParams {
+    /// Token from the person's private edit link, can be used instead of their password
+    pub edit_token: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EditTokenResponse {
+    pub edit_token: String,
+    pub expires_at: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GoogleConnectResponse {
+    /// Google's consent screen, send the person here to connect their calendar. It's only
+    /// valid for 10 minutes.
+    pub url: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GoogleCallbackParams {
+    /// Given by Google once the person has agreed
+    pub code: Option<String>,
+    /// Passed through from the consent screen's URL
+    pub state: String,
+    /// Given by Google instead of a code if the person said no
+    pub error: Option<String>,
+}
+
+/// An event's times split by whether the person's Google Calendar has them busy
+#[derive(Serialize, ToSchema)]
+pub struct SuggestedAvailabilityResponse {
+    /// Times the person has nothing on, in the same format as `availability`
+    pub availability: Vec<String>,
+    /// Times that overlap something in the person's calendar
+    pub busy: Vec<String>,
+}
+
+/// Who is logged in, to fill in their name when they join an event
+#[derive(Serialize, ToSchema)]
+pub struct MeResponse {
+    /// Stays the same across devices, and is what events and people are linked to
+    pub subject_id: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveUpdateKind {
+    PersonAdded,
+    PersonUpdated,
+    PersonRemoved,
+    /// Everyone invited has responded, so it's a good time to finalize the event
+    ResponsesClosed,
+}
+
+impl LiveUpdateKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiveUpdateKind::PersonAdded => "person_added",
+            LiveUpdateKind::PersonUpdated => "person_updated",
+            LiveUpdateKind::PersonRemoved => "person_removed",
+            LiveUpdateKind::ResponsesClosed => "responses_closed",
+        }
+    }
+}
+
+/// A change to an event or the people on it
+#[derive(Serialize, ToSchema, Clone)]
+pub struct LiveUpdate {
+    #[serde(skip)]
+    pub event_id: String,
+    pub kind: LiveUpdateKind,
+    /// The person whose change caused the update
+    pub person: PersonResponse,
+}
+
+impl LiveUpdate {
+    pub fn new(event_id: String, kind: LiveUpdateKind, person: Person) -> Self {
+        Self {
+            event_id,
+            kind,
+            person: person.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PeopleSortParam {
+    Name,
+    CreatedAt,
+    SlotsCount,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Deserialize, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RespondedFilter {
+    /// Only people who have filled in their availability
+    #[default]
+    Responded,
+    /// Only people who have joined but not filled in their availability
+    NotResponded,
+    All,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PeopleParams {
+    pub sort: Option<PeopleSortParam>,
+    pub order: Option<SortOrder>,
+    /// Defaults to `responded`
+    #[serde(default)]
+    pub filter: RespondedFilter,
+    /// Only include people updated at or after this unix timestamp
+    pub updated_since: Option<i64>,
+    /// How many people to return, defaults to everyone
+    pub limit: Option<usize>,
+    /// How many people to skip, defaults to 0
+    pub offset: Option<usize>,
+}
+
+impl PeopleParams {
+    pub fn range(&self) -> PageRange {
+        PageRange {
+            offset: self.offset.unwrap_or(0),
+            limit: self.limit,
+        }
+    }
+}
+
+impl From<PeopleParams> for PeopleQuery {
+    fn from(value: PeopleParams) -> Self {
+        Self {
+            sort: value.sort.map(|sort| match sort {
+                PeopleSortParam::Name => PeopleSort::Name,
+                PeopleSortParam::CreatedAt => PeopleSort::CreatedAt,
+                PeopleSortParam::SlotsCount => PeopleSort::SlotsCount,
+            }),
+            descending: matches!(value.order, Some(SortOrder::Desc)),
+            responded: match value.filter {
+                RespondedFilter::Responded => Some(true),
+                RespondedFilter::NotResponded => Some(false),
+                RespondedFilter::All => None,
+            },
+            updated_since: value
+                .updated_since
+                .and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
         }
     }
 }
@@ -72,4 +967,407 @@ impl From<Person> for PersonResponse {
 #[derive(Deserialize, ToSchema)]
 pub struct PersonInput {
     pub availability: Vec<String>,
+    /// Times in `availability` the person can only make if needed, defaults to none
+    pub if_needed: Option<Vec<String>>,
+    /// Times not in `availability` the person hasn't decided on yet, defaults to none,
+    /// so every other time is one they can't make
+    pub undecided: Option<Vec<String>>,
+    /// Hex color like `#ff8800` to show the person in, left as it was if missing, or
+    /// cleared with an empty string
+    pub avatar_color: Option<String>,
+    /// A single emoji to show next to the person's name, left as it was if missing, or
+    /// cleared with an empty string
+    pub avatar_emoji: Option<String>,
+    /// The person's own IANA timezone, like `America/New_York`, left as it was if
+    /// missing, or cleared with an empty string
+    pub timezone: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AvailabilityParams {
+    /// Only include times at least this fraction of people have decided on, from 0 to 1
+    pub min_answered: Option<f64>,
+    /// Only include times of this kind, for events that have both
+    pub kind: Option<SlotKind>,
+}
+
+/// Whether a time is on a specific date or repeats every week
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SlotKind {
+    Date,
+    Weekday,
+}
+
+impl From<Slot> for SlotKind {
+    fn from(value: Slot) -> Self {
+        match value {
+            Slot::Date(_) => Self::Date,
+            Slot::Weekday(..) => Self::Weekday,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SlotAvailabilityResponse {
+    pub time: String,
+    /// Null if the time isn't valid, which only older events can have
+    pub kind: Option<SlotKind>,
+    pub count: usize,
+    /// Names of the people available at this time
+    pub people: Vec<String>,
+    /// Names of the people in `people` who can only make it if needed
+    pub if_needed: Vec<String>,
+    /// Names of the people who haven't decided on this time yet
+    pub undecided: Vec<String>,
+    /// Score given by the event's scoring strategy, higher is better
+    pub score: f64,
+    /// What the time is for each available person who has set their own timezone
+    pub local_times: Vec<LocalTimeResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LocalTimeResponse {
+    pub person: String,
+    pub timezone: String,
+    /// Like `Tue 14:00`, with the date for events on specific dates
+    pub time: String,
+    /// Whether it's early in the morning or late at night for them
+    pub unsociable: bool,
+}
+
+impl From<ScoredSlot> for SlotAvailabilityResponse {
+    fn from(value: ScoredSlot) -> Self {
+        Self {
+            kind: value
+                .availability
+                .time
+                .parse::<Slot>()
+                .ok()
+                .map(SlotKind::from),
+            time: value.availability.time,
+            count: value.availability.people.len(),
+            people: value.availability.people,
+            if_needed: value.availability.if_needed,
+            undecided: value.availability.undecided,
+            score: value.score,
+            local_times: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct InterviewInput {
+    /// Names of people on the event who are being interviewed
+    pub candidates: Vec<String>,
+    pub pools: Vec<InterviewPoolInput>,
+    /// The most interviews any one interviewer can do per day
+    pub max_per_day: Option<usize>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct InterviewPoolInput {
+    pub name: String,
+    /// Names of people on the event who can interview for this pool
+    pub interviewers: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InterviewResponse {
+    pub assignments: Vec<InterviewAssignmentResponse>,
+    /// Candidates that couldn't be given a time
+    pub unassigned: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InterviewAssignmentResponse {
+    pub candidate: String,
+    pub time: String,
+    pub interviewers: Vec<InterviewerResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InterviewerResponse {
+    pub pool: String,
+    pub name: String,
+}
+
+impl From<Assignment> for InterviewAssignmentResponse {
+    fn from(value: Assignment) -> Self {
+        Self {
+            candidate: value.candidate,
+            time: value.time,
+            interviewers: value
+                .interviewers
+                .into_iter()
+                .map(|(pool, name)| InterviewerResponse { pool, name })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RouteMatrixResponse {
+    pub method: String,
+    pub path: String,
+    pub auth: Auth,
+    pub rate_limit: RateLimit,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CacheStatsResponse {
+    /// False if `EVENT_CACHE_TTL_SECS` or `EVENT_CACHE_CAPACITY` is 0
+    pub enabled: bool,
+    /// Number of events in the cache
+    pub events: u64,
+    /// Number of events with their people in the cache
+    pub people: u64,
+    /// Reads of events and people served from the cache since the API started
+    pub hits: u64,
+    /// Reads of events and people that went to storage since the API started
+    pub misses: u64,
+    /// Fraction of reads served from the cache, from 0 to 1
+    pub hit_rate: f64,
+}
+
+impl From<CacheStats> for CacheStatsResponse {
+    fn from(value: CacheStats) -> Self {
+        let reads = value.hits + value.misses;
+        Self {
+            enabled: value.enabled,
+            events: value.events,
+            people: value.people,
+            hits: value.hits,
+            misses: value.misses,
+            hit_rate: match reads {
+                0 => 0.0,
+                _ => value.hits as f64 / reads as f64,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdaptorMetricsResponse {
+    /// The adaptor method, like `get_event`
+    pub method: String,
+    /// Calls that reached storage since the API started
+    pub calls: u64,
+    /// Calls that failed
+    pub errors: u64,
+    /// Fraction of calls that failed, from 0 to 1
+    pub error_rate: f64,
+    /// Average time a call took, in milliseconds
+    pub mean_ms: f64,
+    /// Longest time a call took, in milliseconds
+    pub max_ms: f64,
+}
+
+impl From<MethodMetrics> for AdaptorMetricsResponse {
+    fn from(value: MethodMetrics) -> Self {
+        let (error_rate, mean_ms) = match value.calls {
+            0 => (0.0, 0.0),
+            calls => (
+                value.errors as f64 / calls as f64,
+                value.total_micros as f64 / calls as f64 / 1000.0,
+            ),
+        };
+        Self {
+            method: value.method.to_owned(),
+            calls: value.calls,
+            errors: value.errors,
+            error_rate,
+            mean_ms,
+            max_ms: value.max_micros as f64 / 1000.0,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminEventParams {
+    /// Only include events created after this unix timestamp
+    pub created_after: Option<i64>,
+    /// Only include events created before this unix timestamp
+    pub created_before: Option<i64>,
+    /// Only include events with this in their name, ignoring case
+    pub name: Option<String>,
+    /// How many events to return, defaults to 50
+    pub limit: Option<usize>,
+    /// How many events to skip, defaults to 0
+    pub offset: Option<usize>,
+}
+
+// Most events that can be listed at once
+const MAX_ADMIN_EVENTS_LIMIT: usize = 200;
+
+impl AdminEventParams {
+    pub fn range(&self) -> PageRange {
+        PageRange {
+            offset: self.offset.unwrap_or(0),
+            limit: Some(self.limit.unwrap_or(50).min(MAX_ADMIN_EVENTS_LIMIT)),
+        }
+    }
+}
+
+impl From<AdminEventParams> for EventQuery {
+    fn from(value: AdminEventParams) -> Self {
+        Self {
+            created_after: value
+                .created_after
+                .and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+            created_before: value
+                .created_before
+                .and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+            name_contains: value.name.filter(|name| !name.is_empty()),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CleanupParams {
+    /// Report what would be expired and deleted without changing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SeedParams {
+    /// How many events to create, up to 1000, defaults to 10
+    pub count: Option<usize>,
+}
+
+/// The events created with fake data
+#[derive(Serialize, ToSchema)]
+pub struct SeedResponse {
+    pub event_ids: Vec<String>,
+    /// How many people responded across all the events
+    pub person_count: usize,
+    /// Edit token for every one of the events
+    pub edit_token: String,
+    /// Creator token that lists all the events at `/me/events`
+    pub creator_token: String,
+}
+
+impl From<SeedReport> for SeedResponse {
+    fn from(value: SeedReport) -> Self {
+        Self {
+            event_ids: value.event_ids,
+            person_count: value.person_count,
+            edit_token: value.edit_token,
+            creator_token: value.creator_token,
+        }
+    }
+}
+
+/// What a cleanup did, or would do on a dry run
+#[derive(Serialize, ToSchema)]
+pub struct CleanupResponse {
+    pub dry_run: bool,
+    pub expired_event_count: i64,
+    pub deleted_event_count: i64,
+    pub deleted_person_count: i64,
+    /// Not counted on dry runs
+    pub deleted_idempotency_key_count: Option<i64>,
+    /// Not counted on dry runs
+    pub deleted_template_count: Option<i64>,
+    /// Where the deleted events were archived to in `ARCHIVE_BUCKET`, if they were. Never set
+    /// on dry runs.
+    pub archive_key: Option<String>,
+    /// The events that would be expired, only included on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expired_events: Option<Vec<AdminEventResponse>>,
+    /// The events that would be deleted, only included on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_events: Option<Vec<AdminEventResponse>>,
+}
+
+impl From<CleanupReport> for CleanupResponse {
+    fn from(value: CleanupReport) -> Self {
+        Self {
+            dry_run: false,
+            expired_event_count: value.expired_count,
+            deleted_event_count: value.deleted.event_count,
+            deleted_person_count: value.deleted.person_count,
+            deleted_idempotency_key_count: Some(value.keys_deleted),
+            deleted_template_count: Some(value.templates_deleted),
+            archive_key: value.archive_key,
+            expired_events: None,
+            deleted_events: None,
+        }
+    }
+}
+
+impl From<CleanupPreview> for CleanupResponse {
+    fn from(value: CleanupPreview) -> Self {
+        Self {
+            dry_run: true,
+            expired_event_count: value.stale_events.len() as i64,
+            deleted_event_count: value.expired_events.len() as i64,
+            deleted_person_count: value.person_count,
+            deleted_idempotency_key_count: None,
+            deleted_template_count: None,
+            archive_key: None,
+            expired_events: Some(value.stale_events.into_iter().map(Into::into).collect()),
+            deleted_events: Some(value.expired_events.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+/// An event as seen by an admin, including details that are otherwise hidden
+#[derive(Serialize, ToSchema)]
+pub struct AdminEventResponse {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    pub visited_at: i64,
+    pub updated_at: i64,
+    /// When the event expired, null if it hasn't
+    pub expired_at: Option<i64>,
+    pub retention_days: Option<i64>,
+    pub listed: bool,
+    pub private: bool,
+    pub finalized_time: Option<String>,
+    pub has_webhook: bool,
+    pub has_chat_webhook: bool,
+    /// Number of people who have joined, only included when getting a single event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub people_count: Option<usize>,
+}
+
+impl From<Event> for AdminEventResponse {
+    fn from(value: Event) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            created_at: value.created_at.timestamp(),
+            visited_at: value.visited_at.timestamp(),
+            updated_at: value.updated_at.timestamp(),
+            expired_at: value.expired_at.map(|t| t.timestamp()),
+            retention_days: value.retention_days,
+            listed: value.listed,
+            private: value.password_hash.is_some(),
+            finalized_time: value.finalized_time,
+            has_webhook: value.webhook_url.is_some(),
+            has_chat_webhook: value.chat_webhook_url.is_some(),
+            people_count: None,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ValidationErrorResponse {
+    pub message: String,
+    /// What's wrong with each invalid field, a field can be listed more than once
+    pub errors: Vec<FieldErrorResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FieldErrorResponse {
+    /// The field as it's named in the request body, like `timezone`
+    pub field: String,
+    pub message: String,
 }
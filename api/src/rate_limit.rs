@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use axum::{error_handling::HandleErrorLayer, routing::MethodRouter, BoxError};
+use governor::middleware::NoOpMiddleware;
+use tower::ServiceBuilder;
+use tower_governor::{
+    errors::display_error,
+    governor::{GovernorConfig, GovernorConfigBuilder},
+    GovernorLayer,
+};
+
+use crate::{
+    config::{RateLimitConfig, RateLimitsConfig},
+    middleware::client_ip::ClientIpKeyExtractor,
+    routes::RateLimit,
+};
+
+type Config = GovernorConfig<ClientIpKeyExtractor, NoOpMiddleware>;
+
+/// A separate limit for each group of routes, based on the client's IP. Each limit allows bursts of
+/// up to `burst_size` requests, and replenishes one request every `period`.
+pub struct RateLimits {
+    standard: &'static Config,
+    strict: &'static Config,
+}
+
+impl RateLimits {
+    /// Each group's limit from `rate_limits.<group>`, set with `RATE_LIMIT_<GROUP>_BURST` and
+    /// `RATE_LIMIT_<GROUP>_PERIOD_MS`
+    pub fn from_config(config: &RateLimitsConfig) -> Self {
+        Self {
+            standard: governor_config(&config.standard),
+            strict: governor_config(&config.strict),
+        }
+    }
+
+    /// Wrap a route's handler in the limit for its group
+    pub fn apply<S>(&self, rate_limit: RateLimit, handler: MethodRouter<S>) -> MethodRouter<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let config = match rate_limit {
+            RateLimit::Standard => self.standard,
+            RateLimit::Strict => self.strict,
+        };
+
+        handler.layer(
+            ServiceBuilder::new()
+                // Handle errors from governor and convert into HTTP responses
+                .layer(HandleErrorLayer::new(|e: BoxError| async move {
+                    display_error(e)
+                }))
+                .layer(GovernorLayer { config }),
+        )
+    }
+}
+
+// The config is leaked so every route in the group shares one limiter for the life of the server
+fn governor_config(limit: &RateLimitConfig) -> &'static Config {
+    Box::leak(Box::new(
+        GovernorConfigBuilder::default()
+            .key_extractor(ClientIpKeyExtractor)
+            .burst_size(limit.burst_size)
+            .period(Duration::from_millis(limit.period_ms))
+            .finish()
+            .expect("Burst size and period are both non-zero"),
+    ))
+}
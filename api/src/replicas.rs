@@ -0,0 +1,478 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    Activity, Adaptor, CleanupPreview, Comment, DailyStats, Event, EventQuery, EventViews,
+    IdempotencyKey, Page, PageRange, PeopleQuery, PeopleVersion, Person, PersonInsert,
+    PersonUpdate, SchemaVersion, Stats, Template,
+};
+use moka::future::Cache;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+// Visited dates are only used to decide when events expire, which is counted in days, so
+// a visit only needs to be written to the primary once in a while
+const VISIT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Most events that are remembered as recently written or visited
+const TRACKED_EVENTS: u64 = 100_000;
+
+struct Replica<A> {
+    adaptor: A,
+    // Numbered from 1 in the order they're configured, as URLs can contain passwords
+    number: usize,
+    healthy: AtomicBool,
+}
+
+impl<A> Replica<A> {
+    fn mark_healthy(&self) {
+        if !self.healthy.swap(true, Ordering::Relaxed) {
+            info!("Replica {} is healthy again", self.number);
+        }
+    }
+
+    fn mark_unhealthy(&self, error: &dyn std::error::Error) {
+        if self.healthy.swap(false, Ordering::Relaxed) {
+            warn!(
+                "Replica {} failed, reading from the others until it recovers: {}",
+                self.number, error
+            );
+        }
+    }
+}
+
+/// Wraps a primary adaptor and read replicas of it, sending writes to the primary and
+/// spreading reads across the replicas in turn. A replica that fails is skipped until a
+/// health check can ping it again, and reads fall back to the primary if none are healthy.
+/// Writes aren't failed over, as promoting a replica is up to the database.
+///
+/// Replicas lag behind the primary, so an event is read from the primary for a while after
+/// it's written through this adaptor, and `get_event` goes to the primary every so often to
+/// record the visit.
+pub struct ReplicatedAdaptor<A> {
+    primary: A,
+    replicas: Arc<Vec<Replica<A>>>,
+    next: AtomicUsize,
+    recently_written: Cache<String, ()>,
+    recently_visited: Cache<String, ()>,
+}
+
+impl<A: Adaptor + 'static> ReplicatedAdaptor<A>
+where
+    A::Error: Send,
+{
+    /// Events are read from the primary for `lag` after they're written, and replicas are
+    /// pinged every `health_check_interval`
+    pub fn new(
+        primary: A,
+        replicas: Vec<A>,
+        lag: Duration,
+        health_check_interval: Duration,
+    ) -> Self {
+        let replicas = Arc::new(
+            replicas
+                .into_iter()
+                .enumerate()
+                .map(|(i, adaptor)| Replica {
+                    adaptor,
+                    number: i + 1,
+                    healthy: AtomicBool::new(true),
+                })
+                .collect::<Vec<_>>(),
+        );
+        if !replicas.is_empty() {
+            tokio::spawn(check_health(
+                Arc::downgrade(&replicas),
+                health_check_interval,
+            ));
+        }
+
+        Self {
+            primary,
+            replicas,
+            next: AtomicUsize::new(0),
+            recently_written: Cache::builder()
+                .max_capacity(TRACKED_EVENTS)
+                .time_to_live(lag)
+                .build(),
+            recently_visited: Cache::builder()
+                .max_capacity(TRACKED_EVENTS)
+                .time_to_live(VISIT_INTERVAL)
+                .build(),
+        }
+    }
+}
+
+impl<A: Adaptor> ReplicatedAdaptor<A> {
+    // Try each healthy replica in turn, starting from the next one along, then the primary
+    async fn read<'a, T, F, Fut>(&'a self, read: F) -> Result<T, A::Error>
+    where
+        F: Fn(&'a A) -> Fut,
+        Fut: Future<Output = Result<T, A::Error>>,
+    {
+        let count = self.replicas.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for i in 0..count {
+            let replica = &self.replicas[(start + i) % count];
+            if !replica.healthy.load(Ordering::Relaxed) {
+                continue;
+            }
+            match read(&replica.adaptor).await {
+                Ok(value) => return Ok(value),
+                Err(e) => replica.mark_unhealthy(&e),
+            }
+        }
+        read(&self.primary).await
+    }
+
+    // Reads of an event that was just written go to the primary, which has the change
+    async fn read_event<'a, T, F, Fut>(&'a self, event_id: &str, read: F) -> Result<T, A::Error>
+    where
+        F: Fn(&'a A) -> Fut,
+        Fut: Future<Output = Result<T, A::Error>>,
+    {
+        match self.recently_written.contains_key(event_id) {
+            true => read(&self.primary).await,
+            false => self.read(read).await,
+        }
+    }
+
+    async fn written(&self, event_id: &str) {
+        if !self.replicas.is_empty() {
+            self.recently_written.insert(event_id.to_owned(), ()).await;
+        }
+    }
+}
+
+async fn check_health<A: Adaptor>(replicas: Weak<Vec<Replica<A>>>, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        // Stop once the adaptor has been dropped
+        let Some(replicas) = replicas.upgrade() else {
+            return;
+        };
+        for replica in replicas.iter() {
+            match replica.adaptor.ping().await {
+                Ok(()) => replica.mark_healthy(),
+                Err(e) => replica.mark_unhealthy(&e),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<A: Adaptor> Adaptor for ReplicatedAdaptor<A>
+where
+    A::Error: Send,
+{
+    type Error = A::Error;
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.primary.ping().await
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        for replica in self.replicas.iter() {
+            if let Err(e) = replica.adaptor.close().await {
+                warn!("Failed to close replica {}: {}", replica.number, e);
+            }
+        }
+        self.primary.close().await
+    }
+
+    async fn get_schema_version(&self) -> Result<SchemaVersion, Self::Error> {
+        self.primary.get_schema_version().await
+    }
+
+    async fn migrate(&self) -> Result<SchemaVersion, Self::Error> {
+        self.primary.migrate().await
+    }
+
+    async fn get_stats(&self) -> Result<Stats, Self::Error> {
+        self.read(|adaptor| adaptor.get_stats()).await
+    }
+
+    async fn increment_stat_event_count(&self) -> Result<i64, Self::Error> {
+        self.primary.increment_stat_event_count().await
+    }
+
+    async fn increment_stat_person_count(&self) -> Result<i64, Self::Error> {
+        self.primary.increment_stat_person_count().await
+    }
+
+    async fn add_stats(&self, stats: Stats) -> Result<Stats, Self::Error> {
+        self.primary.add_stats(stats).await
+    }
+
+    async fn add_daily_stats(&self, date: NaiveDate, stats: Stats) -> Result<(), Self::Error> {
+        self.primary.add_daily_stats(date, stats).await
+    }
+
+    async fn get_daily_stats(&self, since: NaiveDate) -> Result<Vec<DailyStats>, Self::Error> {
+        self.read(|adaptor| adaptor.get_daily_stats(since)).await
+    }
+
+    async fn get_people(&self, event_id: String) -> Result<Option<Vec<Person>>, Self::Error> {
+        self.read_event(&event_id, |adaptor| adaptor.get_people(event_id.clone()))
+            .await
+    }
+
+    async fn query_people(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+    ) -> Result<Option<Vec<Person>>, Self::Error> {
+        self.read_event(&event_id, |adaptor| {
+            adaptor.query_people(event_id.clone(), query.clone())
+        })
+        .await
+    }
+
+    async fn query_people_page(
+        &self,
+        event_id: String,
+        query: PeopleQuery,
+        range: PageRange,
+    ) -> Result<Option<Page<Person>>, Self::Error> {
+        self.read_event(&event_id, |adaptor| {
+            adaptor.query_people_page(event_id.clone(), query.clone(), range)
+        })
+        .await
+    }
+
+    async fn get_people_version(
+        &self,
+        event_id: String,
+    ) -> Result<Option<PeopleVersion>, Self::Error> {
+        self.read_event(&event_id, |adaptor| {
+            adaptor.get_people_version(event_id.clone())
+        })
+        .await
+    }
+
+    async fn upsert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<Person>, Self::Error> {
+        self.written(&event_id).await;
+        self.primary.upsert_person(event_id, person).await
+    }
+
+    async fn insert_person(
+        &self,
+        event_id: String,
+        person: Person,
+    ) -> Result<Option<PersonInsert>, Self::Error> {
+        self.written(&event_id).await;
+        self.primary.insert_person(event_id, person).await
+    }
+
+    async fn update_person(
+        &self,
+        event_id: String,
+        person: Person,
+        expected_updated_at: DateTime<Utc>,
+    ) -> Result<Option<PersonUpdate>, Self::Error> {
+        self.written(&event_id).await;
+        self.primary
+            .update_person(event_id, person, expected_updated_at)
+            .await
+    }
+
+    async fn delete_person(
+        &self,
+        event_id: String,
+        person_name: String,
+    ) -> Result<Option<Person>, Self::Error> {
+        self.written(&event_id).await;
+        self.primary.delete_person(event_id, person_name).await
+    }
+
+    async fn get_comments(&self, event_id: String) -> Result<Option<Vec<Comment>>, Self::Error> {
+        self.read_event(&event_id, |adaptor| adaptor.get_comments(event_id.clone()))
+            .await
+    }
+
+    async fn create_comment(
+        &self,
+        event_id: String,
+        comment: Comment,
+    ) -> Result<Option<Comment>, Self::Error> {
+        self.written(&event_id).await;
+        self.primary.create_comment(event_id, comment).await
+    }
+
+    async fn get_activity(&self, event_id: String) -> Result<Option<Vec<Activity>>, Self::Error> {
+        self.read_event(&event_id, |adaptor| adaptor.get_activity(event_id.clone()))
+            .await
+    }
+
+    async fn create_activity(
+        &self,
+        event_id: String,
+        activity: Activity,
+    ) -> Result<Option<Activity>, Self::Error> {
+        self.written(&event_id).await;
+        self.primary.create_activity(event_id, activity).await
+    }
+
+    async fn get_event_views(&self, event_id: String) -> Result<Option<EventViews>, Self::Error> {
+        self.read(|adaptor| adaptor.get_event_views(event_id.clone()))
+            .await
+    }
+
+    // Views are recorded on almost every request for an event, and a view count that's a
+    // few seconds behind doesn't matter, so recording one doesn't send reads to the primary
+    async fn record_event_view(
+        &self,
+        event_id: String,
+        register: usize,
+        rank: u8,
+    ) -> Result<Option<EventViews>, Self::Error> {
+        self.primary
+            .record_event_view(event_id, register, rank)
+            .await
+    }
+
+    async fn get_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        if self.replicas.is_empty()
+            || self.recently_written.contains_key(&id)
+            || !self.recently_visited.contains_key(&id)
+        {
+            let event = self.primary.get_event(id.clone()).await?;
+            if event.is_some() && !self.replicas.is_empty() {
+                self.recently_visited.insert(id, ()).await;
+            }
+            return Ok(event);
+        }
+
+        // Peeking includes expired events, which getting an event doesn't
+        Ok(self
+            .read(|adaptor| adaptor.peek_event(id.clone()))
+            .await?
+            .filter(|event| event.expired_at.is_none()))
+    }
+
+    async fn get_events(&self, ids: Vec<String>) -> Result<Vec<Event>, Self::Error> {
+        self.primary.get_events(ids).await
+    }
+
+    async fn create_event(&self, event: Event) -> Result<Event, Self::Error> {
+        self.written(&event.id).await;
+        self.primary.create_event(event).await
+    }
+
+    async fn update_event(&self, event: Event) -> Result<Option<Event>, Self::Error> {
+        self.written(&event.id).await;
+        self.primary.update_event(event).await
+    }
+
+    async fn get_listed_events(&self, tag: Option<String>) -> Result<Vec<Event>, Self::Error> {
+        self.read(|adaptor| adaptor.get_listed_events(tag.clone()))
+            .await
+    }
+
+    async fn get_group_events(&self, group_id: String) -> Result<Vec<Event>, Self::Error> {
+        self.read(|adaptor| adaptor.get_group_events(group_id.clone()))
+            .await
+    }
+
+    async fn get_creator_events(&self, creator_id: String) -> Result<Vec<Event>, Self::Error> {
+        self.read(|adaptor| adaptor.get_creator_events(creator_id.clone()))
+            .await
+    }
+
+    async fn query_events(
+        &self,
+        query: EventQuery,
+        range: PageRange,
+    ) -> Result<Page<Event>, Self::Error> {
+        self.read(|adaptor| adaptor.query_events(query.clone(), range))
+            .await
+    }
+
+    async fn peek_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        self.read_event(&id, |adaptor| adaptor.peek_event(id.clone()))
+            .await
+    }
+
+    // Idempotency keys and templates are read straight after they're written, and only
+    // when creating events, so they always use the primary
+    async fn get_idempotency_key(
+        &self,
+        key: String,
+    ) -> Result<Option<IdempotencyKey>, Self::Error> {
+        self.primary.get_idempotency_key(key).await
+    }
+
+    async fn create_idempotency_key(&self, key: IdempotencyKey) -> Result<bool, Self::Error> {
+        self.primary.create_idempotency_key(key).await
+    }
+
+    async fn set_idempotency_key_event(
+        &self,
+        key: String,
+        event_id: String,
+    ) -> Result<(), Self::Error> {
+        self.primary.set_idempotency_key_event(key, event_id).await
+    }
+
+    async fn delete_idempotency_key(&self, key: String) -> Result<(), Self::Error> {
+        self.primary.delete_idempotency_key(key).await
+    }
+
+    async fn delete_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        self.primary.delete_idempotency_keys(cutoff).await
+    }
+
+    async fn get_template(&self, id: String) -> Result<Option<Template>, Self::Error> {
+        self.primary.get_template(id).await
+    }
+
+    async fn create_template(&self, template: Template) -> Result<Template, Self::Error> {
+        self.primary.create_template(template).await
+    }
+
+    async fn delete_templates(&self, cutoff: DateTime<Utc>) -> Result<i64, Self::Error> {
+        self.primary.delete_templates(cutoff).await
+    }
+
+    async fn expire_events(&self, default_retention_days: i64) -> Result<i64, Self::Error> {
+        self.primary.expire_events(default_retention_days).await
+    }
+
+    async fn restore_event(&self, id: String) -> Result<Option<Event>, Self::Error> {
+        self.written(&id).await;
+        self.primary.restore_event(id).await
+    }
+
+    async fn delete_events(&self, cutoff: DateTime<Utc>) -> Result<Stats, Self::Error> {
+        self.primary.delete_events(cutoff).await
+    }
+
+    // The cleanup is compared with what it changes, so it has to see the same data
+    async fn preview_cleanup(
+        &self,
+        default_retention_days: i64,
+        cutoff: DateTime<Utc>,
+    ) -> Result<CleanupPreview, Self::Error> {
+        self.primary
+            .preview_cleanup(default_retention_days, cutoff)
+            .await
+    }
+
+    async fn delete_event(&self, id: String) -> Result<Option<Stats>, Self::Error> {
+        self.written(&id).await;
+        self.primary.delete_event(id).await
+    }
+}
@@ -0,0 +1,89 @@
+use axum::{
+    extract::{self, Path, Query},
+    http::{HeaderMap, HeaderName},
+    Json,
+};
+use chrono::{TimeZone, Utc};
+use common::{Activity, Adaptor};
+
+use crate::{
+    errors::ApiError,
+    payloads::{ActivityKind, ActivityParams, ActivityResponse},
+    routes::{event::get_authorized_event, person::TOTAL_COUNT_HEADER},
+    State,
+};
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/activity",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ActivityParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = [ActivityResponse], headers(
+            ("x-total-count" = usize, description = "How many changes match, ignoring `limit` and `offset`"),
+        )),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Event not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get a feed of changes made to an event, newest first
+///
+/// Each change has a message that can be shown as is, like "Amy updated their availability".
+/// Changes made before the feed existed aren't included.
+pub async fn get_activity<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(params): Query<ActivityParams>,
+    headers: HeaderMap,
+) -> Result<([(HeaderName, String); 1], Json<Vec<ActivityResponse>>), ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+
+    let since = params
+        .since
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single());
+    let activity: Vec<ActivityResponse> = adaptor
+        .get_activity(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?
+        .into_iter()
+        .filter(|a| since.is_none_or(|since| a.created_at >= since))
+        .filter_map(ActivityResponse::from_activity)
+        .collect();
+    let page = params.range().apply(activity);
+
+    Ok((
+        [(
+            HeaderName::from_static(TOTAL_COUNT_HEADER),
+            page.total.to_string(),
+        )],
+        Json(page.items),
+    ))
+}
+
+/// Add a change to an event's activity feed
+///
+/// The change has already been made by the time it's recorded, so failing to record it is
+/// only logged.
+pub async fn record_activity<A: Adaptor>(
+    adaptor: &A,
+    event_id: String,
+    kind: ActivityKind,
+    person: Option<String>,
+) {
+    let activity = Activity {
+        kind: kind.as_str().to_owned(),
+        person,
+        created_at: Utc::now(),
+    };
+    if let Err(e) = adaptor.create_activity(event_id, activity).await {
+        tracing::warn!("Failed to record activity: {}", e);
+    }
+}
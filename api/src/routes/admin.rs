@@ -0,0 +1,286 @@
+use axum::{
+    extract::{self, Path, Query},
+    http::{HeaderName, StatusCode},
+    Json,
+};
+use common::{Adaptor, Event, EventQuery};
+
+use crate::{
+    errors::ApiError,
+    payloads::{
+        AdaptorMetricsResponse, AdminEventParams, AdminEventResponse, ApiResult,
+        CacheStatsResponse, EventResponse, RouteMatrixResponse,
+    },
+    routes::{person::TOTAL_COUNT_HEADER, registry},
+    State,
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/route-matrix",
+    responses(
+        (status = 200, description = "Ok", body = [RouteMatrixResponse]),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// List every route along with its authentication and rate limit
+pub async fn get_route_matrix<A: Adaptor + 'static>(
+    _: State<A>,
+) -> ApiResult<Vec<RouteMatrixResponse>, A> {
+    Ok(Json(
+        registry::<A>()
+            .into_iter()
+            .map(|spec| RouteMatrixResponse {
+                method: spec.method.to_string(),
+                path: spec.path.to_owned(),
+                auth: spec.auth,
+                rate_limit: spec.rate_limit,
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/cache",
+    responses(
+        (status = 200, description = "Ok", body = CacheStatsResponse),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// Get how well the in-memory cache of events and people is working
+///
+/// The counts are for this instance only, and start from zero when it restarts.
+pub async fn get_cache_stats<A: Adaptor>(
+    extract::State(state): State<A>,
+) -> Json<CacheStatsResponse> {
+    Json(state.cache.stats().await.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/adaptor",
+    responses(
+        (status = 200, description = "Ok", body = [AdaptorMetricsResponse]),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// Get how often each adaptor method has been called, how long the calls took and how many
+/// failed
+///
+/// Only calls that reach storage are counted, not ones served from the cache. The counts are
+/// for this instance only, and start from zero when it restarts.
+pub async fn get_adaptor_metrics<A: Adaptor>(
+    extract::State(state): State<A>,
+) -> Json<Vec<AdaptorMetricsResponse>> {
+    Json(
+        state
+            .metrics
+            .snapshot()
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+    )
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/directory/{event_id}",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    responses(
+        (status = 204, description = "Removed from the directory"),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// Remove an event from the public directory
+pub async fn delist_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+) -> Result<StatusCode, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let event = adaptor
+        .get_event(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    adaptor
+        .update_event(Event {
+            listed: false,
+            ..event
+        })
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/events/{event_id}/restore",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    responses(
+        (status = 200, description = "Ok", body = EventResponse),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 404, description = "Not found, or already deleted"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// Restore an event that expired, as long as it hasn't been deleted yet
+///
+/// The event counts as visited now, so it's kept for its full retention period again.
+pub async fn restore_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+) -> ApiResult<EventResponse, A> {
+    let event = state
+        .adaptor
+        .restore_event(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(event.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/events",
+    params(AdminEventParams),
+    responses(
+        (status = 200, description = "Ok", body = [AdminEventResponse], headers(
+            ("x-total-count" = usize, description = "How many events match, ignoring `limit` and `offset`"),
+        )),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// List events, newest first, including expired events
+///
+/// Listing events doesn't count as visiting them.
+pub async fn list_events<A: Adaptor>(
+    extract::State(state): State<A>,
+    Query(params): Query<AdminEventParams>,
+) -> Result<([(HeaderName, String); 1], Json<Vec<AdminEventResponse>>), ApiError<A>> {
+    let range = params.range();
+    let page = state
+        .adaptor
+        .query_events(EventQuery::from(params), range)
+        .await
+        .map_err(ApiError::AdaptorError)?;
+
+    Ok((
+        [(
+            HeaderName::from_static(TOTAL_COUNT_HEADER),
+            page.total.to_string(),
+        )],
+        Json(
+            page.items
+                .into_iter()
+                .map(AdminEventResponse::from)
+                .collect(),
+        ),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/events/{event_id}",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    responses(
+        (status = 200, description = "Ok", body = AdminEventResponse),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// Get the details of an event, including how many people have joined it
+///
+/// Works for private and expired events too, and doesn't count as visiting the event.
+pub async fn get_event_details<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+) -> ApiResult<AdminEventResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let event = adaptor
+        .peek_event(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    let people_count = adaptor
+        .get_people_version(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .map(|version| version.count)
+        .unwrap_or(0);
+
+    let mut response = AdminEventResponse::from(event);
+    response.people_count = Some(people_count);
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/events/{event_id}",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "admin",
+)]
+/// Delete an event straight away, along with its people, comments and activity
+///
+/// Unlike expiry, this can't be undone.
+pub async fn purge_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+) -> Result<StatusCode, ApiError<A>> {
+    let deleted = state
+        .adaptor
+        .delete_event(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    tracing::info!(
+        "Admin deleted event {} with {} people",
+        event_id,
+        deleted.person_count
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
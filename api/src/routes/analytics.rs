@@ -0,0 +1,64 @@
+use axum::{extract, Json};
+use common::Adaptor;
+
+use crate::{
+    errors::ApiError,
+    jobs::{Job, Jobs},
+    middleware::{client_ip::ClientIp, edit_token::OwnedEvent},
+    payloads::{ApiResult, EventAnalyticsResponse},
+    visitors, State,
+};
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/analytics",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    responses(
+        (status = 200, description = "Ok", body = EventAnalyticsResponse),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get how many times an event has been viewed, and by roughly how many people
+///
+/// Requires the edit token returned when the event was created. Views from before this was
+/// tracked aren't counted.
+pub async fn get_analytics<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+) -> ApiResult<EventAnalyticsResponse, A> {
+    let views = state
+        .adaptor
+        .get_event_views(event.id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(EventAnalyticsResponse {
+        view_count: views.view_count,
+        // The estimate can be a little high, but there can't be more visitors than views
+        unique_visitors: visitors::estimate(&views.visitor_registers).min(views.view_count),
+    }))
+}
+
+/// Queue a view of an event to be counted, along with who viewed it if their IP is known
+///
+/// The event is returned without waiting for the view to be recorded, so failing to record
+/// it is only logged.
+pub fn record_view(jobs: &Jobs, event_id: String, client_ip: Option<ClientIp>) {
+    let (register, rank) = match client_ip {
+        Some(ClientIp(ip)) => visitors::register_for(&event_id, ip),
+        // Count the view without touching the sketch
+        None => (0, 0),
+    };
+    jobs.push(Job::RecordView {
+        event_id,
+        register,
+        rank,
+    });
+}
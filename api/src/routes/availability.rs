@@ -0,0 +1,135 @@
+use std::{collections::HashMap, ops::Range};
+
+use axum::{
+    extract::{self, Path, Query},
+    http::HeaderMap,
+    Json,
+};
+use chrono::Timelike;
+use chrono_tz::Tz;
+use common::Adaptor;
+
+use crate::{
+    errors::ApiError,
+    payloads::{
+        ApiResult, AvailabilityParams, LocalTimeResponse, SlotAvailabilityResponse, SlotKind,
+    },
+    routes::event::get_authorized_event,
+    scoring::{ScoredSlot, Scoring},
+    slots::Slot,
+    State,
+};
+
+// Local hours it's reasonable to ask someone to meet, anything outside is unsociable
+const SOCIABLE_HOURS: Range<u32> = 8..21;
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/availability",
+    params(
+        ("event_id", description = "The ID of the event"),
+        AvailabilityParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = [SlotAvailabilityResponse]),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Not found"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get an event's times ranked by how many people are available
+///
+/// Times are ranked using the event's scoring strategy, and those with the same score are
+/// ordered by the number of people available, then the earliest slot.
+///
+/// Use `min_answered` to leave out times most people haven't decided on yet. Only people
+/// who have responded count towards it. Events can have both specific dates and days of the
+/// week, use `kind` to only rank one of them.
+///
+/// People who have set their own timezone are listed in `local_times` with what the time is
+/// for them, and whether it's outside 8am to 9pm there.
+pub async fn get_availability<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(params): Query<AvailabilityParams>,
+    headers: HeaderMap,
+) -> ApiResult<Vec<SlotAvailabilityResponse>, A> {
+    let adaptor = &state.adaptor;
+
+    let min_answered = params.min_answered.unwrap_or(0.0);
+    if !(0.0..=1.0).contains(&min_answered) {
+        return Err(ApiError::InvalidInput(
+            "min_answered must be between 0 and 1".to_owned(),
+        ));
+    }
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    let people = adaptor
+        .get_people(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+
+    let responded = people
+        .iter()
+        .filter(|p| !p.availability.is_empty() || !p.undecided.is_empty())
+        .count();
+    let timezones: HashMap<&str, Tz> = people
+        .iter()
+        .filter_map(|p| Some((p.name.as_str(), p.timezone.as_ref()?.parse().ok()?)))
+        .collect();
+
+    Ok(Json(
+        Scoring::of(&event)
+            .rank(
+                &event.times,
+                &people,
+                event.timezone.parse::<Tz>().unwrap_or(Tz::UTC),
+            )
+            .into_iter()
+            .filter(|slot| match params.kind {
+                Some(kind) => slot
+                    .availability
+                    .time
+                    .parse::<Slot>()
+                    .is_ok_and(|slot| SlotKind::from(slot) == kind),
+                None => true,
+            })
+            .filter(|slot| {
+                responded == 0
+                    || (responded - slot.availability.undecided.len()) as f64 / responded as f64
+                        >= min_answered
+            })
+            .map(|slot| with_local_times(slot, &timezones))
+            .collect(),
+    ))
+}
+
+fn with_local_times(slot: ScoredSlot, timezones: &HashMap<&str, Tz>) -> SlotAvailabilityResponse {
+    let parsed = slot.availability.time.parse::<Slot>().ok();
+    let local_times = match parsed {
+        Some(parsed) => slot
+            .availability
+            .people
+            .iter()
+            .filter_map(|person| {
+                let tz = *timezones.get(person.as_str())?;
+                Some(LocalTimeResponse {
+                    person: person.clone(),
+                    timezone: tz.name().to_owned(),
+                    time: parsed.format_in(tz),
+                    unsociable: !SOCIABLE_HOURS.contains(&parsed.datetime_in(tz).hour()),
+                })
+            })
+            .collect(),
+        None => vec![],
+    };
+
+    SlotAvailabilityResponse {
+        local_times,
+        ..slot.into()
+    }
+}
@@ -0,0 +1,139 @@
+use axum::{
+    extract::{self, Path, Query},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+};
+use chrono_tz::Tz;
+use common::Adaptor;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    errors::ApiError,
+    etag::{self, ETag},
+    routes::{event::get_authorized_event, meta::branding},
+    scoring::Scoring,
+    slots, State,
+};
+
+#[derive(Deserialize, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeKind {
+    /// How many of the people who joined have filled in their availability
+    #[default]
+    Responses,
+    /// The time that the most people are available
+    Best,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BadgeQuery {
+    /// Which badge to render, defaults to `responses`
+    #[serde(default)]
+    kind: BadgeKind,
+}
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/badge.svg",
+    params(
+        ("event_id", description = "The ID of the event"),
+        BadgeQuery,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", content_type = "image/svg+xml", body = String),
+        (status = 304, description = "Not modified"),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get an SVG badge summarising an event, for embedding in other pages
+pub async fn get_badge<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(query): Query<BadgeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    let people = adaptor
+        .get_people(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+
+    let joined = people.len();
+    let responded: Vec<_> = people
+        .into_iter()
+        .filter(|p| !p.availability.is_empty())
+        .collect();
+
+    let message = match query.kind {
+        BadgeKind::Responses => format!("{}/{} responded", responded.len(), joined),
+        BadgeKind::Best => {
+            let tz = event.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+            match Scoring::of(&event)
+                .rank(&event.times, &responded, tz)
+                .first()
+            {
+                Some(best) if !best.availability.people.is_empty() => format!(
+                    "best: {}",
+                    best.availability
+                        .time
+                        .parse::<slots::Slot>()
+                        .map(|slot| slot.format_in(tz))
+                        .unwrap_or(best.availability.time.clone())
+                ),
+                _ => "best: no responses".to_owned(),
+            }
+        }
+    };
+    let branding = branding();
+    let svg = render_badge(&branding.name.to_lowercase(), &message, &branding.color);
+
+    // Derive the ETag from the rendered badge, so it only changes when the badge does
+    let etag = ETag::new(&svg);
+    let cache_control = [(CACHE_CONTROL, etag::cache_control(&event))];
+    if etag.matches(&headers) {
+        return Ok((cache_control, etag.not_modified()).into_response());
+    }
+
+    Ok((
+        cache_control,
+        etag.attach(([(CONTENT_TYPE, "image/svg+xml")], svg)),
+    )
+        .into_response())
+}
+
+// Render a flat shields.io style badge
+fn render_badge(label: &str, message: &str, color: &str) -> String {
+    let label = escape_xml(label);
+    let message = escape_xml(message);
+    let color = escape_xml(color);
+
+    // Approximate widths of 11px Verdana text, plus padding
+    let label_width = label.chars().count() * 7 + 10;
+    let message_width = message.chars().count() * 7 + 10;
+    let width = label_width + message_width;
+    let label_x = label_width / 2;
+    let message_x = label_width + message_width / 2;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##
+    )
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{self, Path, Query},
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use common::{Adaptor, Event};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    errors::ApiError,
+    ics::{self, Calendar},
+    routes::event::get_authorized_event,
+    scoring::Scoring,
+    slots::{self, Slot, SLOT_MINUTES},
+    State,
+};
+
+#[derive(Deserialize, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarKind {
+    /// A VEVENT at the best time
+    #[default]
+    Event,
+    /// A VFREEBUSY with all of the event's times
+    Freebusy,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CalendarQuery {
+    /// What to export, defaults to `event`
+    #[serde(default)]
+    kind: CalendarKind,
+}
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/calendar.ics",
+    params(
+        ("event_id", description = "The ID of the event"),
+        CalendarQuery,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", content_type = "text/calendar", body = String),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Event not found, or nobody is available at any time"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Export an event as an iCalendar file, for importing into calendar apps
+///
+/// The `event` kind covers the finalized time, or the best time if the event hasn't been
+/// finalized, extended for as long as everyone available at the start stays available.
+/// Events that use days of the week repeat weekly.
+pub async fn get_calendar<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(query): Query<CalendarQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    let people = adaptor
+        .get_people(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+
+    let mut calendar = Calendar::new();
+    calendar.property("X-WR-CALNAME", &ics::escape_text(&event.name));
+    calendar.property("X-WR-TIMEZONE", &event.timezone);
+
+    match query.kind {
+        CalendarKind::Event => {
+            let tz = event.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+            let chosen = match &event.finalized_time {
+                Some(time) => slots::rank(&event.times, &people)
+                    .into_iter()
+                    .find(|s| &s.time == time),
+                None => Scoring::of(&event)
+                    .rank(&event.times, &people, tz)
+                    .into_iter()
+                    .next()
+                    .map(|best| best.availability)
+                    .filter(|best| !best.people.is_empty()),
+            }
+            .ok_or(ApiError::NotFound)?;
+            let start = chosen
+                .time
+                .parse::<Slot>()
+                .map_err(|_| ApiError::NotFound)?;
+
+            // Keep going while everyone available at the start can still make it. Slots are
+            // compared rather than their times, so a date never continues a day of the week.
+            let by_start: HashMap<Slot, Vec<String>> = slots::rank(&event.times, &people)
+                .into_iter()
+                .filter_map(|s| Some((s.time.parse::<Slot>().ok()?, s.people)))
+                .collect();
+            let mut length = SLOT_MINUTES;
+            while !chosen.people.is_empty()
+                && by_start
+                    .get(&start.add_minutes(length))
+                    .is_some_and(|names| chosen.people.iter().all(|name| names.contains(name)))
+            {
+                length += SLOT_MINUTES;
+            }
+            let end = start.datetime() + Duration::minutes(length);
+
+            calendar.property("BEGIN", "VEVENT");
+            calendar.property("UID", &uid(&event));
+            calendar.property("DTSTAMP", &ics::format_utc(Utc::now()));
+            calendar.property("DTSTART", &ics::format_utc(start.datetime()));
+            calendar.property("DTEND", &ics::format_utc(end));
+            if let Slot::Weekday(..) = start {
+                calendar.property("RRULE", "FREQ=WEEKLY");
+            }
+            let names: Vec<String> = chosen
+                .people
+                .iter()
+                .map(|name| match chosen.if_needed.contains(name) {
+                    true => format!("{} (if needed)", name),
+                    false => name.clone(),
+                })
+                .collect();
+
+            calendar.property("SUMMARY", &ics::escape_text(&event.name));
+            calendar.property(
+                "DESCRIPTION",
+                &ics::escape_text(&format!("Available: {}", names.join(", "))),
+            );
+            calendar.property("END", "VEVENT");
+        }
+        CalendarKind::Freebusy => {
+            let mut starts: Vec<DateTime<Utc>> = event
+                .times
+                .iter()
+                .filter_map(|time| time.parse::<Slot>().ok())
+                .map(|slot| slot.datetime())
+                .collect();
+            starts.sort();
+
+            calendar.property("BEGIN", "VFREEBUSY");
+            calendar.property("UID", &uid(&event));
+            calendar.property("DTSTAMP", &ics::format_utc(Utc::now()));
+            if let (Some(first), Some(last)) = (starts.first(), starts.last()) {
+                calendar.property("DTSTART", &ics::format_utc(*first));
+                calendar.property(
+                    "DTEND",
+                    &ics::format_utc(*last + Duration::minutes(SLOT_MINUTES)),
+                );
+            }
+            for (start, end) in merge_periods(&starts) {
+                calendar.property(
+                    "FREEBUSY;FBTYPE=BUSY-TENTATIVE",
+                    &format!("{}/{}", ics::format_utc(start), ics::format_utc(end)),
+                );
+            }
+            calendar.property("END", "VFREEBUSY");
+        }
+    }
+
+    Ok(calendar_response(&event_id, calendar))
+}
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/people/{person_name}/availability.ics",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", content_type = "text/calendar", body = String),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Event or person not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Export the times a person marked as available as tentative calendar events
+///
+/// Times next to each other are joined into a single block. Days of the week repeat weekly.
+pub async fn get_person_calendar<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    let person = adaptor
+        .get_people(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|p| p.name.to_lowercase() == person_name.to_lowercase())
+        .ok_or(ApiError::NotFound)?;
+
+    // Dates and days of the week are joined into blocks separately, as only the latter repeat
+    let (weekly, dated): (Vec<Slot>, Vec<Slot>) = person
+        .availability
+        .iter()
+        .filter(|time| event.times.contains(time))
+        .filter_map(|time| time.parse::<Slot>().ok())
+        .partition(|slot| matches!(slot, Slot::Weekday(..)));
+    let starts = |slots: Vec<Slot>| {
+        let mut starts: Vec<DateTime<Utc>> = slots.iter().map(|slot| slot.datetime()).collect();
+        starts.sort();
+        starts
+    };
+    let periods = merge_periods(&starts(dated))
+        .into_iter()
+        .map(|period| (period, false))
+        .chain(
+            merge_periods(&starts(weekly))
+                .into_iter()
+                .map(|period| (period, true)),
+        );
+
+    let mut calendar = Calendar::new();
+    calendar.property(
+        "X-WR-CALNAME",
+        &ics::escape_text(&format!("{} ({})", event.name, person.name)),
+    );
+    calendar.property("X-WR-TIMEZONE", &event.timezone);
+
+    for ((start, end), weekly) in periods {
+        calendar.property("BEGIN", "VEVENT");
+        calendar.property(
+            "UID",
+            &format!(
+                "{}-{}-{}",
+                ics::format_utc(start),
+                person.name.to_lowercase().replace(' ', "-"),
+                uid(&event)
+            ),
+        );
+        calendar.property("DTSTAMP", &ics::format_utc(Utc::now()));
+        calendar.property("DTSTART", &ics::format_utc(start));
+        calendar.property("DTEND", &ics::format_utc(end));
+        if weekly {
+            calendar.property("RRULE", "FREQ=WEEKLY");
+        }
+        calendar.property("STATUS", "TENTATIVE");
+        calendar.property(
+            "SUMMARY",
+            &ics::escape_text(&format!("Available for {}", event.name)),
+        );
+        calendar.property("END", "VEVENT");
+    }
+
+    Ok(calendar_response(
+        &format!("{}-{}", event_id, person.name),
+        calendar,
+    ))
+}
+
+/// Respond with a calendar as a downloadable .ics file
+pub fn calendar_response(name: &str, calendar: Calendar) -> Response {
+    (
+        [
+            (CONTENT_TYPE, "text/calendar; charset=utf-8".to_owned()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.ics\"", filename(name)),
+            ),
+        ],
+        calendar.finish(),
+    )
+        .into_response()
+}
+
+/// Join sorted slot start times into continuous periods
+pub fn merge_periods(starts: &[DateTime<Utc>]) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut periods: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for start in starts {
+        let end = *start + Duration::minutes(SLOT_MINUTES);
+        match periods.last_mut() {
+            Some((_, last_end)) if *last_end >= *start => *last_end = end.max(*last_end),
+            _ => periods.push((*start, end)),
+        }
+    }
+    periods
+}
+
+/// Keep filenames to characters that are safe in a header
+pub fn filename(name: &str) -> String {
+    name.chars()
+        .map(|c| if c == ' ' { '-' } else { c })
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+fn uid(event: &Event) -> String {
+    format!("{}@jelli.fit", event.id)
+}
@@ -0,0 +1,128 @@
+use axum::{
+    extract::{self, Path},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use common::{Adaptor, Comment};
+
+use crate::{
+    errors::ApiError,
+    payloads::{ApiResult, CommentInput, CommentResponse},
+    routes::event::get_authorized_event,
+    spam::{check_spam, SpamCheck},
+    State,
+};
+
+// Longest a comment and its author's name can be, in characters
+const MAX_COMMENT_LENGTH: usize = 1000;
+const MAX_AUTHOR_LENGTH: usize = 100;
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/comments",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = [CommentResponse]),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Event not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get the comments left on an event, oldest first
+pub async fn get_comments<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Vec<CommentResponse>, A> {
+    let adaptor = &state.adaptor;
+
+    get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+
+    let comments = adaptor
+        .get_comments(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(comments.into_iter().map(|c| c.into()).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/comments",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    request_body(content = CommentInput, description = "The comment to add"),
+    responses(
+        (status = 201, description = "Created", body = CommentResponse),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 403, description = "Rejected as spam"),
+        (status = 404, description = "Event not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Leave a comment on an event, for context that doesn't fit in the availability grid
+pub async fn create_comment<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    headers: HeaderMap,
+    Json(input): Json<CommentInput>,
+) -> Result<(StatusCode, Json<CommentResponse>), ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+
+    let author = input.author.trim().to_owned();
+    let body = input.body.trim().to_owned();
+    if author.is_empty() || body.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "Comments need an author and a body".to_owned(),
+        ));
+    }
+    if author.chars().count() > MAX_AUTHOR_LENGTH {
+        return Err(ApiError::InvalidInput(format!(
+            "Author names can be at most {} characters",
+            MAX_AUTHOR_LENGTH
+        )));
+    }
+    if body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(ApiError::InvalidInput(format!(
+            "Comments can be at most {} characters",
+            MAX_COMMENT_LENGTH
+        )));
+    }
+
+    check_spam(
+        state.spam_filter.as_ref(),
+        SpamCheck::Comment {
+            event_id: event_id.clone(),
+            author: author.clone(),
+            body: body.clone(),
+        },
+    )
+    .await?;
+
+    let comment = adaptor
+        .create_comment(
+            event_id,
+            Comment {
+                author,
+                body,
+                created_at: chrono::offset::Utc::now(),
+            },
+        )
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok((StatusCode::CREATED, Json(comment.into())))
+}
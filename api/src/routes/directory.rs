@@ -0,0 +1,89 @@
+use std::cmp::Reverse;
+
+use axum::{
+    extract::{self, Query},
+    Json,
+};
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use common::{Adaptor, Event};
+
+use crate::{
+    errors::ApiError,
+    payloads::{ApiResult, DirectoryEntryResponse, DirectoryParams},
+    slots::Slot,
+    State,
+};
+
+// Most events returned from the directory, newest first
+const MAX_DIRECTORY_EVENTS: usize = 50;
+
+#[utoipa::path(
+    get,
+    path = "/directory",
+    params(DirectoryParams),
+    responses(
+        (status = 200, description = "Ok", body = [DirectoryEntryResponse]),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get events that have chosen to be listed in the public directory
+///
+/// Only the name, date range and number of responses are shared, and operators
+/// can remove events from the directory with the admin API.
+pub async fn get_directory<A: Adaptor>(
+    extract::State(state): State<A>,
+    Query(params): Query<DirectoryParams>,
+) -> ApiResult<Vec<DirectoryEntryResponse>, A> {
+    let adaptor = &state.adaptor;
+
+    let mut events = adaptor
+        .get_listed_events(params.tag.map(|tag| tag.trim().to_lowercase()))
+        .await
+        .map_err(ApiError::AdaptorError)?;
+    events.sort_by_key(|e| Reverse(e.created_at));
+    events.truncate(MAX_DIRECTORY_EVENTS);
+
+    let mut entries = Vec::with_capacity(events.len());
+    for event in events {
+        let people_count = adaptor
+            .get_people(event.id.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .unwrap_or_default()
+            .iter()
+            .filter(|p| !p.availability.is_empty())
+            .count();
+        let (start_date, end_date) = date_range(&event);
+
+        entries.push(DirectoryEntryResponse {
+            id: event.id,
+            name: event.name,
+            start_date,
+            end_date,
+            people_count,
+        });
+    }
+
+    Ok(Json(entries))
+}
+
+// The first and last local dates of an event's specific date times
+fn date_range(event: &Event) -> (Option<String>, Option<String>) {
+    let tz = event.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+    let dates: Vec<NaiveDate> = event
+        .times
+        .iter()
+        .filter_map(|time| match time.parse::<Slot>() {
+            Ok(slot @ Slot::Date(_)) => Some(slot.date_in(tz)),
+            _ => None,
+        })
+        .collect();
+
+    let format = |date: &NaiveDate| date.format("%Y-%m-%d").to_string();
+    (
+        dates.iter().min().map(format),
+        dates.iter().max().map(format),
+    )
+}
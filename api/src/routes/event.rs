@@ -1,16 +1,51 @@
+use std::collections::HashMap;
+
 use axum::{
-    extract::{self, Path},
-    http::StatusCode,
-    Json,
+    extract::{self, Path, Query, RawQuery},
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
+    http::{HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json, TypedHeader,
 };
-use common::{Adaptor, Event};
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use chrono::{Duration, Utc};
+use common::{Adaptor, Event, IdempotencyKey, Person};
+use rand::{thread_rng, Rng};
 use regex::Regex;
 
 use crate::{
+    auth::Identity,
+    captcha::check_captcha,
+    chat::{self, ChatNotification, ChatPlatform},
+    cleanup::{event_retention_days, IDEMPOTENCY_KEY_TTL_HOURS},
+    config::config,
     errors::ApiError,
-    payloads::{ApiResult, EventInput, EventResponse},
-    State,
+    etag::ETag,
+    import,
+    middleware::{
+        client_ip::ClientIp,
+        edit_token::{owns_event, OwnedEvent},
+    },
+    msgpack::{Format, JsonOrMsgPack},
+    names::{generate_name, is_offensive, locale_from_headers},
+    payloads::{
+        ActivityKind, ApiResult, ChatWebhookInput, ChatWebhookResponse, DuplicateInput,
+        EventIdsParams, EventInput, EventLookupInput, EventLookupResponse, EventResponse,
+        EventUpdateInput, ExtendParams, ExtendResponse, FieldsQuery, FinalizeInput, ImportInput,
+        ImportResponse, ImportSource, LiveUpdate, LiveUpdateKind, MergeInput, MergeResponse,
+        WebhookInput, WebhookResponse,
+    },
+    routes::{
+        activity::record_activity,
+        analytics::record_view,
+        person::{
+            decode_password, parse_password, verify_edit_token, verify_identity, verify_password,
+        },
+    },
+    slots::{self, Slot},
+    spam::{check_spam, SpamCheck},
+    tokens::{generate_token, hash_token, lookup_hash},
+    validation::{validate_event, validate_event_update},
+    webhooks, AppState, State,
 };
 
 #[utoipa::path(
@@ -18,72 +53,463 @@ use crate::{
     path = "/event/{event_id}",
     params(
         ("event_id", description = "The ID of the event"),
+        FieldsQuery,
     ),
+    security((), ("password" = []), ("event-password" = [])),
     responses(
-        (status = 200, description = "Ok", body = EventResponse),
+        (status = 200, description = "Ok", body = EventResponse, content_type = ["application/json", "application/msgpack"], headers(
+            ("etag" = String, description = "Send as `If-None-Match` to get a 304 if the event hasn't changed"),
+        )),
+        (status = 304, description = "Not modified since the ETag in `If-None-Match`"),
+        (status = 401, description = "Missing or incorrect event password"),
         (status = 404, description = "Not found"),
         (status = 429, description = "Too many requests"),
     ),
     tag = "event",
 )]
 /// Get details about an event
+///
+/// Private events need their password, either as a bearer token or in the `X-Event-Password`
+/// header, base64 encoded like person passwords.
+///
+/// Responses have an ETag, send it back in `If-None-Match` when polling to get a 304 if the
+/// event hasn't changed. Every request counts as a view in the event's analytics.
+///
+/// Send `Accept: application/msgpack` to get the event as MessagePack instead of JSON.
 pub async fn get_event<A: Adaptor>(
     extract::State(state): State<A>,
     Path(event_id): Path<String>,
-) -> ApiResult<EventResponse, A> {
-    let adaptor = &state.lock().await.adaptor;
+    Query(fields): Query<FieldsQuery>,
+    RawQuery(query): RawQuery,
+    client_ip: Option<Extension<ClientIp>>,
+    format: Format,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
 
-    let event = adaptor
-        .get_event(event_id)
-        .await
-        .map_err(ApiError::AdaptorError)?;
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    record_view(
+        &state.jobs,
+        event_id.clone(),
+        client_ip.map(|Extension(ip)| ip),
+    );
+
+    // Counting people needs another query, so only do it if asked
+    let count_people = fields.fields.is_some() && fields.includes("people_count");
+    let people_version = match count_people {
+        true => adaptor
+            .get_people_version(event_id.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?,
+        false => None,
+    };
+    let etag = ETag::new((event.updated_at.timestamp_millis(), people_version, query));
+    if etag.matches(&headers) {
+        return Ok(etag.not_modified());
+    }
 
-    match event {
-        Some(event) => Ok(Json(event.into())),
-        None => Err(ApiError::NotFound),
+    let mut response: EventResponse = event.into();
+    if count_people {
+        let people = adaptor
+            .get_people(event_id)
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .unwrap_or_default();
+        response.people_count = Some(people.iter().filter(|p| !p.availability.is_empty()).count());
     }
+
+    Ok(etag.attach(format.respond(fields.sparse(response))))
+}
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+// Most events that can be looked up in one request
+const MAX_LOOKUP_IDS: usize = 50;
+
+#[utoipa::path(
+    post,
+    path = "/events/lookup",
+    request_body(content = EventLookupInput, description = "IDs of the events to get"),
+    responses(
+        (status = 200, description = "Ok", body = [EventLookupResponse]),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get details about multiple events at once
+///
+/// Results are in the same order as the IDs provided, with a null event for any that weren't
+/// found or are private.
+pub async fn lookup_events<A: Adaptor>(
+    extract::State(state): State<A>,
+    Json(input): Json<EventLookupInput>,
+) -> ApiResult<Vec<EventLookupResponse>, A> {
+    lookup(&state, input.ids).await.map(Json)
+}
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    params(EventIdsParams),
+    responses(
+        (status = 200, description = "Ok", body = [EventLookupResponse]),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get details about multiple events at once, like `/events/lookup`
+///
+/// Results are in the same order as the IDs provided, with a null event for any that weren't
+/// found or are private.
+pub async fn get_events<A: Adaptor>(
+    extract::State(state): State<A>,
+    Query(params): Query<EventIdsParams>,
+) -> ApiResult<Vec<EventLookupResponse>, A> {
+    let ids = params
+        .ids
+        .split(',')
+        .map(|id| id.trim().to_owned())
+        .filter(|id| !id.is_empty())
+        .collect();
+    lookup(&state, ids).await.map(Json)
+}
+
+async fn lookup<A: Adaptor>(
+    state: &AppState<A>,
+    ids: Vec<String>,
+) -> Result<Vec<EventLookupResponse>, ApiError<A>> {
+    if ids.len() > MAX_LOOKUP_IDS {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {} events can be looked up at once",
+            MAX_LOOKUP_IDS
+        )));
+    }
+
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+
+    // Private events are left out, so they're only peeked at first, otherwise looking them
+    // up would count as a visit and keep them from expiring
+    let mut public = Vec::with_capacity(unique.len());
+    for id in unique {
+        let event = state
+            .adaptor
+            .peek_event(id.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?;
+        if event.is_some_and(|event| event.expired_at.is_none() && event.password_hash.is_none()) {
+            public.push(id);
+        }
+    }
+    let events: HashMap<String, Event> = state
+        .adaptor
+        .get_events(public)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .into_iter()
+        .map(|event| (event.id.clone(), event))
+        .collect();
+
+    Ok(ids
+        .into_iter()
+        .map(|id| EventLookupResponse {
+            event: events
+                .get(&id)
+                .filter(|e| e.password_hash.is_none())
+                .map(|e| e.clone().into()),
+            id,
+        })
+        .collect())
 }
 
 #[utoipa::path(
     post,
     path = "/event",
     request_body(content = EventInput, description = "New event details"),
+    params(
+        ("idempotency-key" = Option<String>, Header, description = "Unique key for this event, so retrying the request doesn't create it twice"),
+    ),
     responses(
-        (status = 201, description = "Created", body = EventResponse),
+        (status = 201, description = "Created", body = EventResponse, content_type = ["application/json", "application/msgpack"], headers(
+            ("idempotent-replayed" = bool, description = "Present if this is the response to an earlier request with the same idempotency key"),
+        )),
+        (status = 403, description = "Rejected as spam, or the CAPTCHA token is missing or invalid"),
+        (status = 409, description = "The requested slug is already taken, or the event for the idempotency key is still being created"),
         (status = 415, description = "Unsupported input format"),
-        (status = 422, description = "Invalid input provided"),
+        (status = 422, description = "Invalid input provided, with what's wrong with each field, or the idempotency key was used for a different request", body = ValidationErrorResponse),
         (status = 429, description = "Too many requests"),
     ),
     tag = "event",
 )]
 /// Create a new event
+///
+/// Requests with an `Idempotency-Key` header that was used in the last 24 hours get the
+/// event created by the first request with that key, instead of creating another one. Keys
+/// are scoped to the account or `creator_token` that sent them, and can't be reused with a
+/// different request. Replayed responses don't include the `edit_token` or `creator_token`,
+/// which are only sent once.
+///
+/// Events created without a name get a random one, in the language from `locale` or the
+/// `Accept-Language` header if there are words for it, or English otherwise.
+///
+/// The event can be sent as MessagePack with `Content-Type: application/msgpack`, and the
+/// response is MessagePack too if `Accept` asks for it.
+///
+/// The response includes a `creator_token` to list the event at `/me/events`. Send it as
+/// `creator_token` when creating more events to list them together. When logged in with
+/// an ID token in the `X-Id-Token` header, events are listed with the account instead.
+///
+/// If the instance has CAPTCHAs turned on, the token from the challenge widget has to be
+/// sent in the `X-Captcha-Token` header. Replayed requests don't need a new one.
 pub async fn create_event<A: Adaptor>(
     extract::State(state): State<A>,
-    Json(input): Json<EventInput>,
-) -> Result<(StatusCode, Json<EventResponse>), ApiError<A>> {
-    let adaptor = &state.lock().await.adaptor;
+    identity: Option<Extension<Identity>>,
+    client_ip: Option<Extension<ClientIp>>,
+    format: Format,
+    headers: HeaderMap,
+    JsonOrMsgPack(mut input): JsonOrMsgPack<EventInput>,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
 
-    // Get the current timestamp
-    let now = chrono::offset::Utc::now();
+    let idempotency_key = match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .map(str::trim)
+                .filter(|key| !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH)
+                .ok_or(ApiError::InvalidInput(format!(
+                    "Idempotency keys must be between 1 and {} characters",
+                    MAX_IDEMPOTENCY_KEY_LENGTH
+                )))?
+                .to_owned(),
+        ),
+        None => None,
+    };
+    // Keys are scoped to whoever sent them, so nobody else can replay the request, and the
+    // request is hashed so the key can't be reused for a different event
+    let idempotency_key = match idempotency_key {
+        Some(key) => {
+            let scope = match (&identity, &input.creator_token) {
+                (Some(Extension(identity)), _) => format!("account:{}", identity.subject_id),
+                (None, Some(token)) => format!("creator:{}", lookup_hash(token)),
+                (None, None) => "anonymous".to_owned(),
+            };
+            let request =
+                serde_json::to_string(&input).map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+            Some(IdempotencyKey {
+                key: lookup_hash(&format!("{}\n{}", scope, key)),
+                request_hash: lookup_hash(&request),
+                event_id: None,
+                created_at: Utc::now(),
+            })
+        }
+        None => None,
+    };
+    if let Some(key) = &idempotency_key {
+        let existing = reserve_idempotency_key(adaptor, key.clone()).await?;
+        if let Some(existing) = existing {
+            if existing.request_hash != key.request_hash {
+                return Err(ApiError::InvalidInput(
+                    "This idempotency key was already used to create a different event".to_owned(),
+                ));
+            }
+            let event_id = existing.event_id.ok_or_else(|| {
+                ApiError::Conflict(
+                    "The event for this idempotency key is still being created".to_owned(),
+                )
+            })?;
+            // The tokens were only ever sent with the first response
+            let event = adaptor
+                .peek_event(event_id)
+                .await
+                .map_err(ApiError::AdaptorError)?
+                .filter(|event| event.expired_at.is_none())
+                .ok_or(ApiError::NotFound)?;
+            let replayed = [(HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER), "true")];
+            return Ok((
+                StatusCode::CREATED,
+                replayed,
+                format.respond(EventResponse::from(event)),
+            )
+                .into_response());
+        }
+    }
+
+    input.locale = input.locale.or_else(|| locale_from_headers(&headers));
+    let created = async {
+        check_captcha(
+            state.captcha.as_ref(),
+            &headers,
+            client_ip.map(|Extension(ClientIp(ip))| ip),
+        )
+        .await?;
+        insert_event(&state, identity.as_deref(), input).await
+    }
+    .await
+    // Adaptor errors aren't `Send`, so they can't be held while the key is released
+    .map_err(IntoResponse::into_response);
+
+    if let Some(key) = idempotency_key {
+        // Release the key if the event wasn't created, so it can be retried
+        let stored = match &created {
+            Ok(response) => {
+                adaptor
+                    .set_idempotency_key_event(key.key, response.id.clone())
+                    .await
+            }
+            Err(_) => adaptor.delete_idempotency_key(key.key).await,
+        };
+        if let Err(e) = stored {
+            tracing::warn!("Failed to store idempotency key: {}", e);
+        }
+    }
+
+    let response = match created {
+        Ok(response) => response,
+        Err(error) => return Ok(error),
+    };
+    Ok((StatusCode::CREATED, format.respond(response)).into_response())
+}
+
+// Reserve an idempotency key, or get the unexpired record of the request that already has it
+async fn reserve_idempotency_key<A: Adaptor>(
+    adaptor: &A,
+    key: IdempotencyKey,
+) -> Result<Option<IdempotencyKey>, ApiError<A>> {
+    for _ in 0..2 {
+        if adaptor
+            .create_idempotency_key(key.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?
+        {
+            return Ok(None);
+        }
+        let existing = adaptor
+            .get_idempotency_key(key.key.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?;
+        match existing {
+            Some(existing)
+                if existing.created_at
+                    > Utc::now() - Duration::hours(IDEMPOTENCY_KEY_TTL_HOURS) =>
+            {
+                return Ok(Some(existing))
+            }
+            // Expired keys are only deleted by the cleanup task, so free it up now
+            Some(_) => adaptor
+                .delete_idempotency_key(key.key.clone())
+                .await
+                .map_err(ApiError::AdaptorError)?,
+            None => {}
+        }
+    }
+    Err(ApiError::Conflict(
+        "The event for this idempotency key is still being created".to_owned(),
+    ))
+}
+
+/// Validate and store a new event, returning it along with its edit token
+pub async fn insert_event<A: Adaptor>(
+    state: &AppState<A>,
+    identity: Option<&Identity>,
+    input: EventInput,
+) -> Result<EventResponse, ApiError<A>> {
+    let adaptor = &state.adaptor;
+    let now = Utc::now();
+
+    validate_event(&input)?;
 
     // Generate a name if none provided
     let name = match input.name {
         Some(x) if !x.is_empty() => x.trim().to_string(),
-        _ => generate_name(),
+        _ => generate_name(input.locale.as_deref()),
     };
 
-    // Generate an ID
-    let mut id = generate_id(&name);
+    check_spam(
+        state.spam_filter.as_ref(),
+        SpamCheck::Event { name: name.clone() },
+    )
+    .await?;
 
-    // Check the ID doesn't already exist
-    while (adaptor
-        .get_event(id.clone())
-        .await
-        .map_err(ApiError::AdaptorError)?)
-    .is_some()
-    {
-        id = generate_id(&name);
-    }
+    let id = match input.slug {
+        // Use the requested slug, as long as nobody else has it, including expired events
+        // that haven't been cleaned up yet
+        Some(slug) => {
+            let id = normalize_slug(&slug)?;
+            if RESERVED_SLUGS.contains(&id.as_str())
+                || adaptor
+                    .peek_event(id.clone())
+                    .await
+                    .map_err(ApiError::AdaptorError)?
+                    .is_some()
+            {
+                return Err(ApiError::Conflict(format!(
+                    "The slug \"{}\" is already taken",
+                    id
+                )));
+            }
+            id
+        }
+        None => {
+            // Generate an ID
+            let mut id = generate_id(&name);
+
+            // Check the ID doesn't already exist
+            while (adaptor
+                .peek_event(id.clone())
+                .await
+                .map_err(ApiError::AdaptorError)?)
+            .is_some()
+            {
+                id = generate_id(&name);
+            }
+            id
+        }
+    };
+
+    let times = slots::normalize(&input.times).map_err(ApiError::InvalidInput)?;
+    let group_id = input
+        .group_id
+        .map(|group_id| normalize_slug(&group_id))
+        .transpose()?;
+    let tags = normalize_tags(input.tags.unwrap_or_default())?;
+
+    let invitees: Vec<String> = input
+        .invitees
+        .unwrap_or_default()
+        .into_iter()
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    let password = input.password.filter(|p| !p.is_empty());
+    let listed = input.listed.unwrap_or(false);
+    let chat_webhook_url = input
+        .chat_webhook_url
+        .map(|url| url.trim().to_owned())
+        .filter(|url| !url.is_empty());
+
+    // Generate a token that can be used to delete the event later
+    let edit_token = generate_token();
+    // Logged in creators find their events with their account, and everyone else gets a
+    // token to list them with, unless they already have one
+    let (creator_id, creator_token) = match identity {
+        Some(identity) => (identity.subject_id.clone(), None),
+        None => {
+            let token = input
+                .creator_token
+                .map(|token| token.trim().to_owned())
+                .unwrap_or_else(generate_token);
+            (lookup_hash(&token), Some(token))
+        }
+    };
 
     let event = adaptor
         .create_event(Event {
@@ -91,44 +517,920 @@ pub async fn create_event<A: Adaptor>(
             name,
             created_at: now,
             visited_at: now,
-            times: input.times,
+            updated_at: now,
+            times,
             timezone: input.timezone,
+            edit_token_hash: hash_token(&edit_token),
+            scoring: input
+                .scoring
+                .and_then(|scoring| serde_json::to_string(&scoring).ok()),
+            listed,
+            tags,
+            finalized_time: None,
+            password_hash: password.map(|raw| bcrypt::hash(raw, 10).unwrap_or(String::from(""))),
+            invitees,
+            responses_closed: false,
+            webhook_url: None,
+            webhook_secret: None,
+            removed_people: vec![],
+            retention_days: input.retention_days,
+            max_people: input.max_people,
+            expired_at: None,
+            group_id,
+            creator_id: Some(creator_id),
+            chat_webhook_url,
         })
         .await
         .map_err(ApiError::AdaptorError)?;
 
     // Update stats
+    state.stat_counters.increment_events();
+    state.chat.send(&event, ChatNotification::Created);
+
+    let mut response: EventResponse = event.into();
+    response.edit_token = Some(edit_token);
+    response.creator_token = creator_token;
+    Ok(response)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/event/{event_id}",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Delete an event and everyone who has joined it
+///
+/// Requires the edit token returned when the event was created.
+pub async fn delete_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+) -> Result<StatusCode, ApiError<A>> {
+    state
+        .adaptor
+        .delete_event(event.id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    patch,
+    path = "/event/{event_id}",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    request_body(content = EventUpdateInput, description = "The details to change"),
+    responses(
+        (status = 200, description = "Ok", body = EventResponse),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided, with what's wrong with each field", body = ValidationErrorResponse),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Change an event's name, tags, whether it's listed, how many people can join, or how
+/// long it's kept for
+///
+/// The times can't be changed, as people's availability is for the times the event had when
+/// they responded. Requires the edit token returned when the event was created.
+pub async fn update_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+    Json(input): Json<EventUpdateInput>,
+) -> ApiResult<EventResponse, A> {
+    validate_event_update(&input, &event)?;
+
+    let name = match input.name {
+        Some(name) => {
+            let name = name.trim().to_owned();
+            check_spam(
+                state.spam_filter.as_ref(),
+                SpamCheck::Event { name: name.clone() },
+            )
+            .await?;
+            name
+        }
+        None => event.name.clone(),
+    };
+    let tags = match input.tags {
+        Some(tags) => normalize_tags(tags)?,
+        None => event.tags.clone(),
+    };
+    let max_people = match input.max_people {
+        Some(0) => None,
+        Some(max_people) => Some(max_people),
+        None => event.max_people,
+    };
+    let retention_days = match input.retention_days {
+        Some(0) => None,
+        Some(days) => Some(days),
+        None => event.retention_days,
+    };
+
+    let event = state
+        .adaptor
+        .update_event(Event {
+            name,
+            listed: input.listed.unwrap_or(event.listed),
+            tags,
+            max_people,
+            retention_days,
+            ..event
+        })
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(event.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/finalize",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    request_body(content = FinalizeInput, description = "The chosen time"),
+    responses(
+        (status = 200, description = "Ok", body = EventResponse),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Lock in the time chosen for an event
+///
+/// Once finalized, people can no longer change their availability. Reopening the event also
+/// reopens it for responses if everyone invited had responded. Requires the edit token
+/// returned when the event was created.
+pub async fn finalize_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+    Json(input): Json<FinalizeInput>,
+) -> ApiResult<EventResponse, A> {
+    let adaptor = &state.adaptor;
+
+    if input
+        .time
+        .as_ref()
+        .is_some_and(|t| !event.times.contains(t))
+    {
+        return Err(ApiError::InvalidInput(
+            "Time must be one of the event's times".to_owned(),
+        ));
+    }
+
+    // Reopening lets people respond again, even if everyone invited already has
+    let responses_closed = event.responses_closed && input.time.is_some();
+    let activity_kind = match input.time {
+        Some(_) => Some(ActivityKind::Finalized),
+        // Clearing a time that was never set isn't worth mentioning
+        None => event
+            .finalized_time
+            .is_some()
+            .then_some(ActivityKind::Reopened),
+    };
+    let event = adaptor
+        .update_event(Event {
+            finalized_time: input.time,
+            responses_closed,
+            ..event
+        })
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    if let Some(kind) = activity_kind {
+        record_activity(adaptor, event.id.clone(), kind, None).await;
+    }
+    if let Some(time) = &event.finalized_time {
+        state.chat.send(&event, ChatNotification::Finalized(time));
+    }
+
+    Ok(Json(event.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/event/{event_id}/webhook",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    request_body(content = WebhookInput, description = "Where to send changes"),
+    responses(
+        (status = 200, description = "Ok", body = WebhookResponse),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Register a webhook to be sent changes to an event, replacing any existing one
+///
+/// Whenever a person joins or updates their availability, a JSON `{ event_id, kind, person }`
+/// payload is posted to the URL. The `X-Jellifit-Event` header has the kind of change, and
+/// `X-Jellifit-Signature` has `sha256=` followed by the hex HMAC-SHA256 of the body, keyed
+/// with the secret in the response. Failed deliveries are retried a few times, waiting
+/// longer between each attempt. The URL has to be HTTPS, and its host has to resolve to a
+/// public address. Requires the edit token returned when the event was created.
+pub async fn put_webhook<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+    Json(input): Json<WebhookInput>,
+) -> ApiResult<WebhookResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let url = input.url.trim().to_owned();
+    if !webhooks::is_valid_url(&url) {
+        return Err(ApiError::InvalidInput(
+            "Webhook URLs must be absolute https:// URLs".to_owned(),
+        ));
+    }
+    if !webhooks::is_public_url(&url).await {
+        return Err(ApiError::InvalidInput(
+            "Webhook URLs must resolve to a public address".to_owned(),
+        ));
+    }
+
+    let secret = generate_token();
     adaptor
-        .increment_stat_event_count()
+        .update_event(Event {
+            webhook_url: Some(url.clone()),
+            webhook_secret: Some(secret.clone()),
+            ..event
+        })
         .await
-        .map_err(ApiError::AdaptorError)?;
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
 
-    Ok((StatusCode::CREATED, Json(event.into())))
+    Ok(Json(WebhookResponse { url, secret }))
 }
 
-// Generate a random name based on an adjective and a jelly species
-fn generate_name() -> String {
-    let adjectives: Vec<String> =
-        serde_json::from_slice(include_bytes!("../res/adjectives.json")).unwrap();
-    let jellies: Vec<String> = serde_json::from_slice(include_bytes!("../res/jellies.json")).unwrap();
-
-    format!(
-        "{} {} Jelly",
-        adjectives.choose(&mut thread_rng()).unwrap(),
-        jellies.choose(&mut thread_rng()).unwrap()
+#[utoipa::path(
+    delete,
+    path = "/event/{event_id}/webhook",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    responses(
+        (status = 204, description = "Removed"),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Stop sending changes to an event's webhook
+///
+/// Requires the edit token returned when the event was created.
+pub async fn delete_webhook<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+) -> Result<StatusCode, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    adaptor
+        .update_event(Event {
+            webhook_url: None,
+            webhook_secret: None,
+            ..event
+        })
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/event/{event_id}/chat-webhook",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    request_body(content = ChatWebhookInput, description = "Where to post updates"),
+    responses(
+        (status = 200, description = "Ok", body = ChatWebhookResponse),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Post updates about an event to a Slack or Discord channel, replacing any existing one
+///
+/// A message linking to the event is posted when someone fills in their availability for the
+/// first time, and when a time is chosen, formatted for the platform the incoming webhook
+/// URL belongs to. It can also be set with `chat_webhook_url` when creating the event, to be
+/// told it was created. Requires the edit token returned when the event was created.
+pub async fn put_chat_webhook<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+    Json(input): Json<ChatWebhookInput>,
+) -> ApiResult<ChatWebhookResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let url = input.url.trim().to_owned();
+    let platform = ChatPlatform::from_url(&url)
+        .ok_or_else(|| ApiError::InvalidInput(chat::INVALID_URL_MESSAGE.to_owned()))?;
+
+    adaptor
+        .update_event(Event {
+            chat_webhook_url: Some(url.clone()),
+            ..event
+        })
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(ChatWebhookResponse { url, platform }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/event/{event_id}/chat-webhook",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security(("edit-token" = [])),
+    responses(
+        (status = 204, description = "Removed"),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Stop posting updates about an event to its Slack or Discord channel
+///
+/// Requires the edit token returned when the event was created.
+pub async fn delete_chat_webhook<A: Adaptor>(
+    extract::State(state): State<A>,
+    OwnedEvent(event): OwnedEvent,
+) -> Result<StatusCode, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    adaptor
+        .update_event(Event {
+            chat_webhook_url: None,
+            ..event
+        })
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/merge",
+    request_body(content = MergeInput, description = "The events to merge, with their edit tokens"),
+    responses(
+        (status = 200, description = "Ok", body = MergeResponse),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "Target event has been finalized or closed to responses"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Merge an event that was accidentally created twice into the other copy
+///
+/// The source event's times are added to the target, and its people are copied over, except
+/// for anyone whose name is already taken on the target, who are reported as conflicts. The
+/// source event is left as is, so it can be checked before deleting it. Requires the edit
+/// tokens of both events.
+pub async fn merge_events<A: Adaptor>(
+    extract::State(state): State<A>,
+    Json(input): Json<MergeInput>,
+) -> ApiResult<MergeResponse, A> {
+    let adaptor = &state.adaptor;
+
+    if input.target.id == input.source.id {
+        return Err(ApiError::InvalidInput(
+            "Can't merge an event into itself".to_owned(),
+        ));
+    }
+
+    let mut events = Vec::with_capacity(2);
+    for owner in [&input.target, &input.source] {
+        let event = adaptor
+            .get_event(owner.id.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .ok_or(ApiError::NotFound)?;
+        if !owns_event(&event, &owner.edit_token) {
+            return Err(ApiError::NotAuthorized);
+        }
+        events.push(event);
+    }
+    let source = events.pop().unwrap();
+    let target = events.pop().unwrap();
+
+    if target.finalized_time.is_some() || target.responses_closed {
+        return Err(ApiError::Conflict(
+            "Event is closed to responses".to_owned(),
+        ));
+    }
+
+    let mut times = target.times.clone();
+    for time in &source.times {
+        if !times.contains(time) {
+            times.push(time.clone());
+        }
+    }
+    let event = adaptor
+        .update_event(Event { times, ..target })
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    let target_people = adaptor
+        .get_people(event.id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+    let source_people = adaptor
+        .get_people(source.id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    for person in source_people {
+        if target_people
+            .iter()
+            .any(|p| p.name.to_lowercase() == person.name.to_lowercase())
+        {
+            conflicts.push(person.name);
+            continue;
+        }
+
+        // They're new to this event, so clients syncing it need to pick them up. Merging is up
+        // to the organizer, so it isn't held to `max_people`.
+        let person = adaptor
+            .upsert_person(
+                event.id.clone(),
+                Person {
+                    updated_at: Utc::now(),
+                    ..person
+                },
+            )
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .ok_or(ApiError::NotFound)?;
+        merged.push(person.name.clone());
+        let activity_kind = match person.availability.is_empty() {
+            true => ActivityKind::Joined,
+            false => ActivityKind::Responded,
+        };
+        record_activity(
+            adaptor,
+            event.id.clone(),
+            activity_kind,
+            Some(person.name.clone()),
+        )
+        .await;
+        let update = LiveUpdate::new(event.id.clone(), LiveUpdateKind::PersonAdded, person);
+        state.webhooks.send(&event, &update);
+        state.live.publish(update);
+    }
+
+    Ok(Json(MergeResponse {
+        event: event.into(),
+        merged,
+        conflicts,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/import",
+    request_body(content = ImportInput, description = "The event to import, as exported from another site"),
+    responses(
+        (status = 201, description = "Created", body = ImportResponse),
+        (status = 403, description = "Rejected as spam"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Create an event from one on When2Meet or LettuceMeet
+///
+/// The event's times are copied over along with everyone who responded to it. People who had
+/// the same name as someone before them are left out. Imported people have no password, so
+/// they can be edited by anyone until they set one.
+pub async fn import_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    identity: Option<Extension<Identity>>,
+    Json(input): Json<ImportInput>,
+) -> Result<(StatusCode, Json<ImportResponse>), ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let imported = match input.source {
+        ImportSource::When2meet => import::from_when2meet(&input.payload),
+        ImportSource::Lettucemeet => import::from_lettucemeet(&input.payload),
+    }
+    .map_err(ApiError::InvalidInput)?;
+
+    let timezone = input
+        .timezone
+        .or(imported.timezone)
+        .ok_or(ApiError::InvalidInput(
+            "A timezone is needed, as the imported event doesn't have one".to_owned(),
+        ))?;
+    let event = insert_event(
+        &state,
+        identity.as_deref(),
+        EventInput {
+            name: input.name.or(imported.name),
+            times: imported.times,
+            timezone,
+            scoring: None,
+            listed: None,
+            tags: None,
+            password: None,
+            invitees: None,
+            slug: None,
+            retention_days: None,
+            max_people: None,
+            group_id: None,
+            locale: None,
+            creator_token: None,
+            chat_webhook_url: None,
+        },
     )
+    .await?;
+
+    let mut people: Vec<String> = Vec::new();
+    for person in imported.people {
+        let name = person.name.trim().to_owned();
+        if name.is_empty()
+            || people
+                .iter()
+                .any(|p| p.to_lowercase() == name.to_lowercase())
+        {
+            continue;
+        }
+
+        let now = Utc::now();
+        state.stat_counters.increment_people();
+        let person = adaptor
+            .upsert_person(
+                event.id.clone(),
+                Person {
+                    name,
+                    password_hash: None,
+                    created_at: now,
+                    updated_at: now,
+                    availability: person.availability,
+                    if_needed: vec![],
+                    undecided: vec![],
+                    edit_token_hash: None,
+                    edit_token_expires_at: None,
+                    subject_id: None,
+                    avatar_color: None,
+                    avatar_emoji: None,
+                    timezone: None,
+                    google_refresh_token: None,
+                },
+            )
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .ok_or(ApiError::NotFound)?;
+        let activity_kind = match person.availability.is_empty() {
+            true => ActivityKind::Joined,
+            false => ActivityKind::Responded,
+        };
+        record_activity(
+            adaptor,
+            event.id.clone(),
+            activity_kind,
+            Some(person.name.clone()),
+        )
+        .await;
+        people.push(person.name);
+    }
+
+    Ok((StatusCode::CREATED, Json(ImportResponse { event, people })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/duplicate",
+    params(
+        ("event_id", description = "The ID of the event to copy"),
+    ),
+    request_body(content = DuplicateInput, description = "Changes to make to the copy"),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 201, description = "Created", body = EventResponse),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 403, description = "Rejected as spam"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "The requested slug is already taken"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Create a new event with the same name, times and timezone as another
+///
+/// Nobody who joined the original event is copied, and neither is its chat webhook, so the
+/// channel doesn't hear about an event its creator may not know exists. Its dates can be moved
+/// forward a number of weeks, for when the same meeting is happening again, and the copy is
+/// added to the same group.
+pub async fn duplicate_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    Json(input): Json<DuplicateInput>,
+) -> Result<(StatusCode, Json<EventResponse>), ApiError<A>> {
+    let event = get_authorized_event(&state.adaptor, event_id, &headers).await?;
+
+    let shift_weeks = input.shift_weeks.unwrap_or(0);
+    if !(0..=MAX_DUPLICATE_SHIFT_WEEKS).contains(&shift_weeks) {
+        return Err(ApiError::InvalidInput(format!(
+            "Events can be moved forward by at most {} weeks",
+            MAX_DUPLICATE_SHIFT_WEEKS
+        )));
+    }
+    let times = event
+        .times
+        .into_iter()
+        .map(|time| match time.parse::<Slot>() {
+            Ok(Slot::Date(datetime)) => {
+                Slot::Date(datetime + Duration::weeks(shift_weeks)).to_string()
+            }
+            _ => time,
+        })
+        .collect();
+
+    let response = insert_event(
+        &state,
+        identity.as_deref(),
+        EventInput {
+            name: Some(input.name.unwrap_or(event.name)),
+            times,
+            timezone: event.timezone,
+            scoring: None,
+            listed: None,
+            tags: None,
+            password: None,
+            invitees: None,
+            slug: input.slug,
+            retention_days: None,
+            max_people: event.max_people,
+            group_id: event.group_id,
+            locale: None,
+            creator_token: None,
+            chat_webhook_url: None,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub const EVENT_PASSWORD_HEADER: &str = "x-event-password";
+
+/// Get an event, making sure the password was provided if it's private
+pub async fn get_authorized_event<A: Adaptor>(
+    adaptor: &A,
+    event_id: String,
+    headers: &HeaderMap,
+) -> Result<Event, ApiError<A>> {
+    let event = adaptor
+        .get_event(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    if !verify_event_password(&event, headers) {
+        return Err(ApiError::NotAuthorized);
+    }
+
+    Ok(event)
+}
+
+// The password can be a bearer token or in its own header, for routes
+// where the bearer token is already used for a person's password
+fn verify_event_password(event: &Event, headers: &HeaderMap) -> bool {
+    let Some(hash) = &event.password_hash else {
+        return true;
+    };
+
+    let bearer = parse_password(
+        headers
+            .typed_get::<Authorization<Bearer>>()
+            .map(TypedHeader),
+    );
+    let header = headers
+        .get(EVENT_PASSWORD_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(decode_password);
+
+    [bearer, header]
+        .into_iter()
+        .flatten()
+        .any(|raw| bcrypt::verify(raw, hash).unwrap_or(false))
 }
 
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/extend",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ExtendParams,
+    ),
+    security(("edit-token" = []), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = ExtendResponse),
+        (status = 401, description = "Missing or incorrect edit token, person details or event password"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Push back the date an event will be deleted
+///
+/// Needs the event's edit token as a bearer token, or the `person` who's extending it along
+/// with their password, their `edit_token`, or being logged in as them. People without a
+/// password need one of the others. Private events need their password in the
+/// `X-Event-Password` header too.
+///
+/// If the `EXTEND_REQUIRES_PERSON` environment variable is set to `true`, only people who
+/// have joined the event can extend it, and the event's edit token isn't enough.
+pub async fn extend_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(params): Query<ExtendParams>,
+    identity: Option<Extension<Identity>>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+) -> ApiResult<ExtendResponse, A> {
+    let adaptor = &state.adaptor;
+
+    // Only peeked at, as getting the event marks it as visited, which is what extends it
+    let event = adaptor
+        .peek_event(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .filter(|event| event.expired_at.is_none())
+        .ok_or(ApiError::NotFound)?;
+    if !verify_event_password(&event, &headers) {
+        return Err(ApiError::NotAuthorized);
+    }
+
+    let owner = bearer
+        .as_ref()
+        .is_some_and(|TypedHeader(Authorization(token))| owns_event(&event, token.token()));
+    if extend_requires_person() || !owner {
+        let person_name = params.person.ok_or(ApiError::NotAuthorized)?;
+        let person = adaptor
+            .get_people(event_id.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .ok_or(ApiError::NotFound)?
+            .into_iter()
+            .find(|p| p.name.to_lowercase() == person_name.to_lowercase())
+            .ok_or(ApiError::NotAuthorized)?;
+
+        // Unlike when saving availability, someone without a password has to prove who
+        // they are another way, or anyone could keep the event around forever
+        let password =
+            person.password_hash.is_some() && verify_password(&person, parse_password(bearer));
+        if !password
+            && !verify_edit_token(&person, params.edit_token.as_deref())
+            && !verify_identity(&person, identity.as_deref())
+        {
+            return Err(ApiError::NotAuthorized);
+        }
+    }
+
+    let event = adaptor
+        .get_event(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    let retention_days = event.retention_days.unwrap_or_else(event_retention_days);
+
+    Ok(Json(ExtendResponse {
+        expires_at: (Utc::now() + Duration::days(retention_days)).timestamp(),
+    }))
+}
+
+// Furthest a duplicated event's dates can be moved forward
+const MAX_DUPLICATE_SHIFT_WEEKS: i64 = 52;
+
+// Most tags an event can have, and how long each can be
+const MAX_TAGS: usize = 5;
+const MAX_TAG_LENGTH: usize = 32;
+
+// Lowercase and deduplicate tags, so they match regardless of how they were typed
+fn normalize_tags<A: Adaptor>(tags: Vec<String>) -> Result<Vec<String>, ApiError<A>> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(ApiError::InvalidInput(format!(
+                "Tags can be at most {} characters",
+                MAX_TAG_LENGTH
+            )));
+        }
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+
+    if normalized.len() > MAX_TAGS {
+        return Err(ApiError::InvalidInput(format!(
+            "Events can have at most {} tags",
+            MAX_TAGS
+        )));
+    }
+
+    Ok(normalized)
+}
+
+/// Whether only people who have joined an event can extend it
+pub fn extend_requires_person() -> bool {
+    config().events.extend_requires_person
+}
+
+// Generate a random name based on an adjective and a jelly species
 // Generate a slug for the jelli fit
-fn generate_id(name: &str) -> String {
+pub fn generate_id(name: &str) -> String {
     let mut id = encode_name(name.to_string());
-    if id.replace('-', "").is_empty() {
-        id = encode_name(generate_name());
+    // The name is still shown as it was given, only the ID falls back to a generated name
+    if id.replace('-', "").is_empty() || is_offensive(&id) {
+        id = encode_name(generate_name(None));
     }
     let number = thread_rng().gen_range(100000..=999999);
     format!("{}-{}", id, number)
 }
 
+const MAX_SLUG_LENGTH: usize = 64;
+// Paths under /event that would be shadowed by an event with the same ID
+const RESERVED_SLUGS: [&str; 2] = ["merge", "import"];
+
+// Custom slugs have to look like a name encoded by `encode_name`, so they're
+// made of lowercase letters and numbers separated by single dashes
+fn normalize_slug<A: Adaptor>(slug: &str) -> Result<String, ApiError<A>> {
+    let slug = slug.trim().to_lowercase();
+    if slug.is_empty() || slug.len() > MAX_SLUG_LENGTH {
+        return Err(ApiError::InvalidInput(format!(
+            "Slugs and group IDs must be between 1 and {} characters",
+            MAX_SLUG_LENGTH
+        )));
+    }
+    if encode_name(slug.replace('-', " ")) != slug {
+        return Err(ApiError::InvalidInput(
+            "Slugs and group IDs can only contain letters, numbers and single dashes between words"
+                .to_owned(),
+        ));
+    }
+    if is_offensive(&slug) {
+        return Err(ApiError::InvalidInput(
+            "Slugs and group IDs can't contain offensive words".to_owned(),
+        ));
+    }
+    Ok(slug)
+}
+
 // Use punycode to encode the name
 fn encode_name(name: String) -> String {
     let pc = punycode::encode(&name.trim().to_lowercase())
@@ -0,0 +1,81 @@
+use axum::{
+    extract::{self, Path, Query},
+    http::{header::CONTENT_DISPOSITION, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use common::Adaptor;
+
+use crate::{
+    cleanup::event_retention_days,
+    errors::ApiError,
+    payloads::{ExportParams, ExportResponse},
+    routes::{calendar::filename, event::get_authorized_event},
+    State,
+};
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/export",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ExportParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = ExportResponse),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Event not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Download everything stored about an event as a JSON file
+///
+/// Includes the event and everyone who has joined it along with their availability, so a
+/// copy can be kept after the event is deleted. Passwords and tokens are never included.
+pub async fn get_export<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    let people = adaptor
+        .get_people(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    let comments = match params.comments {
+        true => Some(
+            adaptor
+                .get_comments(event_id.clone())
+                .await
+                .map_err(ApiError::AdaptorError)?
+                .ok_or(ApiError::NotFound)?,
+        ),
+        false => None,
+    };
+
+    // Getting the event just marked it as visited, so it's kept for its full retention again
+    let retention_days = event.retention_days.unwrap_or_else(event_retention_days);
+    let now = Utc::now();
+
+    Ok((
+        [(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.json\"", filename(&event_id)),
+        )],
+        Json(ExportResponse {
+            exported_at: now.timestamp(),
+            expires_at: (now + Duration::days(retention_days)).timestamp(),
+            event: event.into(),
+            people: people.into_iter().map(|p| p.into()).collect(),
+            comments: comments.map(|comments| comments.into_iter().map(|c| c.into()).collect()),
+        }),
+    )
+        .into_response())
+}
@@ -0,0 +1,323 @@
+use axum::{
+    extract::{self, Path, Query},
+    headers::{authorization::Bearer, Authorization},
+    http::{HeaderMap, StatusCode},
+    response::Redirect,
+    Extension, Json, TypedHeader,
+};
+use chrono::Duration;
+use common::{Adaptor, Person};
+
+use crate::{
+    auth::Identity,
+    config::config,
+    cors,
+    errors::ApiError,
+    google::{self, CalendarError, GoogleCalendar},
+    payloads::{
+        ApiResult, EditTokenParams, GoogleCallbackParams, GoogleConnectResponse,
+        SuggestedAvailabilityResponse,
+    },
+    routes::person::find_authorized_person,
+    slots::{Slot, SLOT_MINUTES},
+    State,
+};
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/people/{person_name}/google",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = GoogleConnectResponse),
+        (status = 401, description = "Incorrect password or edit token"),
+        (status = 404, description = "Event or person not found, or Google Calendar isn't set up on this instance"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Start connecting a person's Google Calendar, so their busy times can be suggested
+///
+/// Send the person to the returned `url`. Once they've agreed, Google sends them to
+/// `/google/callback`, which keeps the token on the server and sends them back to the event on
+/// the frontend with `?google=connected`, or `?google=denied` or `?google=failed` if it
+/// didn't work. Only free/busy times are ever read, not what the events are.
+pub async fn connect_google<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> ApiResult<GoogleConnectResponse, A> {
+    let google = state.google.as_ref().ok_or(ApiError::NotFound)?;
+
+    let person = find_authorized_person(
+        &state.adaptor,
+        &event_id,
+        &person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+
+    Ok(Json(GoogleConnectResponse {
+        url: google.authorize_url(&event_id, &person.name),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/google/callback",
+    params(GoogleCallbackParams),
+    responses(
+        (status = 303, description = "Redirect to the event on the frontend"),
+        (status = 404, description = "Event or person not found, or Google Calendar isn't set up on this instance"),
+        (status = 422, description = "Invalid or expired state"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Finish connecting a person's Google Calendar, Google sends them here
+///
+/// This needs to be registered as the OAuth client's redirect URL, and set as
+/// `GOOGLE_REDIRECT_URL`.
+pub async fn google_callback<A: Adaptor>(
+    extract::State(state): State<A>,
+    Query(params): Query<GoogleCallbackParams>,
+) -> Result<Redirect, ApiError<A>> {
+    let google = state.google.as_ref().ok_or(ApiError::NotFound)?;
+    let (event_id, person_name) =
+        google
+            .verify_state(&params.state)
+            .ok_or(ApiError::InvalidInput(
+                "Invalid or expired state, try connecting again".to_owned(),
+            ))?;
+    let redirect = |result: &str| {
+        Redirect::to(&format!(
+            "{}/{}?google={}",
+            cors::frontend_url(&config().cors),
+            event_id,
+            result
+        ))
+    };
+
+    let Some(code) = params.code.filter(|_| params.error.is_none()) else {
+        return Ok(redirect("denied"));
+    };
+    let refresh_token = match google.exchange_code(&code).await {
+        Ok(refresh_token) => refresh_token,
+        Err(e) => {
+            tracing::warn!("Failed to connect Google Calendar: {}", e);
+            return Ok(redirect("failed"));
+        }
+    };
+
+    let person = state
+        .adaptor
+        .get_people(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?
+        .into_iter()
+        .find(|p| p.name.to_lowercase() == person_name.to_lowercase())
+        .ok_or(ApiError::NotFound)?;
+    // Connecting again replaces the old token, which doesn't need to be kept around
+    if let Some(old_token) = &person.google_refresh_token {
+        revoke(google, old_token).await;
+    }
+    state
+        .adaptor
+        .upsert_person(
+            event_id.clone(),
+            Person {
+                google_refresh_token: Some(refresh_token),
+                ..person
+            },
+        )
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(redirect("connected"))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/event/{event_id}/people/{person_name}/google",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 204, description = "Disconnected"),
+        (status = 401, description = "Incorrect password or edit token"),
+        (status = 404, description = "Event or person not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Disconnect a person's Google Calendar, and let Google know the API no longer has access
+pub async fn disconnect_google<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError<A>> {
+    let person = find_authorized_person(
+        &state.adaptor,
+        &event_id,
+        &person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+    let Some(refresh_token) = person.google_refresh_token.clone() else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+
+    state
+        .adaptor
+        .upsert_person(
+            event_id,
+            Person {
+                google_refresh_token: None,
+                ..person
+            },
+        )
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    if let Some(google) = &state.google {
+        revoke(google, &refresh_token).await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/people/{person_name}/suggested",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = SuggestedAvailabilityResponse),
+        (status = 401, description = "Incorrect password or edit token"),
+        (status = 404, description = "Event or person not found, or Google Calendar isn't set up on this instance"),
+        (status = 409, description = "The person hasn't connected their Google Calendar, or has taken back access"),
+        (status = 429, description = "Too many requests"),
+        (status = 502, description = "Google Calendar couldn't be read"),
+    ),
+    tag = "person",
+)]
+/// Suggest a person's availability from their connected Google Calendar
+///
+/// Any of the event's times that overlap something in their calendar are `busy`, and the rest
+/// are suggested as `availability`. Days of the week are checked against the next time they
+/// come around. Nothing is saved, so the person can look over the suggestion before updating
+/// their availability with it.
+pub async fn get_suggested_availability<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> ApiResult<SuggestedAvailabilityResponse, A> {
+    let google = state.google.as_ref().ok_or(ApiError::NotFound)?;
+    let adaptor = &state.adaptor;
+
+    let person = find_authorized_person(
+        adaptor,
+        &event_id,
+        &person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+    let refresh_token = person
+        .google_refresh_token
+        .clone()
+        .ok_or(ApiError::Conflict(
+            "Google Calendar isn't connected".to_owned(),
+        ))?;
+    // Already counted as a visit when the person was authorized
+    let event = adaptor
+        .peek_event(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    let starts: Vec<_> = event
+        .times
+        .iter()
+        .filter_map(|time| time.parse::<Slot>().ok())
+        .map(|slot| google::upcoming_start(&slot))
+        .collect();
+    let (Some(start), Some(end)) = (starts.iter().min(), starts.iter().max()) else {
+        return Ok(Json(SuggestedAvailabilityResponse {
+            availability: vec![],
+            busy: vec![],
+        }));
+    };
+
+    let busy = match google
+        .busy(
+            &refresh_token,
+            *start,
+            *end + Duration::minutes(SLOT_MINUTES),
+        )
+        .await
+    {
+        Ok(busy) => busy,
+        Err(CalendarError::Revoked) => {
+            adaptor
+                .upsert_person(
+                    event_id,
+                    Person {
+                        google_refresh_token: None,
+                        ..person
+                    },
+                )
+                .await
+                .map_err(ApiError::AdaptorError)?;
+            return Err(ApiError::Conflict(
+                "Access to Google Calendar was taken back, connect it again".to_owned(),
+            ));
+        }
+        Err(CalendarError::Failed(e)) => {
+            tracing::warn!("Failed to read Google Calendar: {}", e);
+            return Err(ApiError::Upstream(
+                "Google Calendar couldn't be read".to_owned(),
+            ));
+        }
+    };
+
+    let (availability, busy) = google::split_busy(&event.times, &busy);
+    Ok(Json(SuggestedAvailabilityResponse { availability, busy }))
+}
+
+// Failing to revoke a token isn't worth failing the request over, as it's forgotten either way
+async fn revoke(google: &GoogleCalendar, refresh_token: &str) {
+    if let Err(e) = google.revoke(refresh_token).await {
+        tracing::warn!("Failed to revoke a Google Calendar token: {}", e);
+    }
+}
@@ -0,0 +1,150 @@
+use async_graphql::{
+    parser::types::{OperationType, Selection},
+    Request, Response, ServerError, Variables,
+};
+use axum::{
+    extract::{self, Query},
+    headers::{authorization::Bearer, Authorization},
+    http::HeaderMap,
+    Extension, Json, TypedHeader,
+};
+use common::Adaptor;
+
+use crate::{
+    auth::Identity,
+    errors::ApiError,
+    graphql::RequestContext,
+    middleware::client_ip::ClientIp,
+    payloads::{GraphqlInput, GraphqlParams},
+    AppState, State,
+};
+
+#[utoipa::path(
+    get,
+    path = "/graphql",
+    params(GraphqlParams),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "GraphQL response, with any errors in `errors`"),
+        (status = 422, description = "Invalid variables"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Run a GraphQL query
+///
+/// Mutations have to be sent with POST, which is rate limited like other routes that create
+/// data.
+pub async fn get_graphql<A: Adaptor + 'static>(
+    extract::State(state): State<A>,
+    Query(params): Query<GraphqlParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    client_ip: Option<Extension<ClientIp>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> Result<Json<Response>, ApiError<A>> {
+    let variables = match params.variables {
+        Some(variables) => serde_json::from_str(&variables)
+            .map_err(|e| ApiError::InvalidInput(format!("Invalid variables: {}", e)))?,
+        None => serde_json::Value::Null,
+    };
+    let mut request = Request::new(params.query).variables(Variables::from_json(variables));
+    if let Some(operation_name) = params.operation_name {
+        request = request.operation_name(operation_name);
+    }
+
+    let is_mutation = request.parsed_query().is_ok_and(|document| {
+        document
+            .operations
+            .iter()
+            .any(|(_, operation)| operation.node.ty == OperationType::Mutation)
+    });
+    if is_mutation {
+        return Ok(Json(Response::from_errors(vec![ServerError::new(
+            "Mutations have to be sent with POST",
+            None,
+        )])));
+    }
+
+    Ok(Json(
+        execute(state, request, bearer, client_ip, identity, headers).await,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    request_body(content = GraphqlInput, description = "GraphQL query or mutation"),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "GraphQL response, with any errors in `errors`"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Run a GraphQL query or mutation
+///
+/// The schema mirrors the REST routes, so an event and its people can be fetched in one
+/// request, with only the fields that are needed. Events and people are authenticated the
+/// same way too, with the bearer token as the person's password and the `X-Event-Password`
+/// header for private events.
+///
+/// The schema can be fetched with an introspection query. A mutation can only have one field,
+/// so each one is rate limited like the REST route it mirrors.
+pub async fn post_graphql<A: Adaptor + 'static>(
+    extract::State(state): State<A>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    client_ip: Option<Extension<ClientIp>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    Json(input): Json<GraphqlInput>,
+) -> Json<Response> {
+    let mut request = Request::new(input.query)
+        .variables(Variables::from_json(input.variables.unwrap_or_default()));
+    if let Some(operation_name) = input.operation_name {
+        request = request.operation_name(operation_name);
+    }
+
+    // The rate limit counts requests, so several mutations in one request would get around it.
+    // Fragments are rejected too, rather than counting the fields they'd expand to.
+    let is_batched = request.parsed_query().is_ok_and(|document| {
+        document.operations.iter().any(|(_, operation)| {
+            let items = &operation.node.selection_set.node.items;
+            operation.node.ty == OperationType::Mutation
+                && (items.len() > 1
+                    || items
+                        .iter()
+                        .any(|item| !matches!(item.node, Selection::Field(_))))
+        })
+    });
+    if is_batched {
+        return Json(Response::from_errors(vec![ServerError::new(
+            "A mutation can only have one field, send each one in its own request",
+            None,
+        )]));
+    }
+
+    Json(execute(state, request, bearer, client_ip, identity, headers).await)
+}
+
+async fn execute<A: Adaptor + 'static>(
+    state: AppState<A>,
+    request: Request,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    client_ip: Option<Extension<ClientIp>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> Response {
+    let schema = state.graphql.clone();
+    schema
+        .execute(request.data(RequestContext {
+            state,
+            headers,
+            bearer,
+            client_ip: client_ip.map(|Extension(ip)| ip),
+            identity: identity.map(|Extension(identity)| identity),
+        }))
+        .await
+}
@@ -0,0 +1,296 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{self, Path, Query},
+    headers::{authorization::Bearer, Authorization},
+    http::HeaderMap,
+    Extension, Json, TypedHeader,
+};
+use chrono::{Datelike, NaiveTime, Utc};
+use chrono_tz::Tz;
+use common::{Adaptor, Event, Person, PersonInsert};
+
+use crate::{
+    auth::Identity,
+    chat::ChatNotification,
+    errors::ApiError,
+    payloads::{
+        ActivityKind, ApiResult, EditTokenParams, GroupAvailabilityInput,
+        GroupAvailabilityResponse, GroupResponse, LiveUpdate, LiveUpdateKind,
+    },
+    routes::{
+        activity::record_activity,
+        event::get_authorized_event,
+        person::{
+            close_responses_if_complete, find_authorized_person, parse_password, verify_password,
+        },
+    },
+    slots::Slot,
+    ApiState, State,
+};
+
+#[utoipa::path(
+    get,
+    path = "/group/{group_id}",
+    params(
+        ("group_id", description = "The ID of the group"),
+    ),
+    responses(
+        (status = 200, description = "Ok", body = GroupResponse),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get the events in a group, oldest first
+///
+/// Private events in the group are left out, as they need their own password to view.
+pub async fn get_group<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(group_id): Path<String>,
+) -> ApiResult<GroupResponse, A> {
+    let events = state
+        .adaptor
+        .get_group_events(group_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?;
+    if events.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Json(GroupResponse {
+        id: group_id,
+        events: events
+            .into_iter()
+            .filter(|event| event.password_hash.is_none())
+            .map(|event| event.into())
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/group/{group_id}/people/{person_name}",
+    params(
+        ("group_id", description = "The ID of the group"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    request_body(content = GroupAvailabilityInput, description = "The event to copy availability from"),
+    responses(
+        (status = 200, description = "Ok", body = GroupAvailabilityResponse),
+        (status = 401, description = "Incorrect password"),
+        (status = 404, description = "Group, event or person not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Copy a person's availability from one event in a group to every other event in it
+///
+/// Times line up across events when they're on the same day of the week at the same local
+/// time, so availability for one week of a series carries over to the others. The person
+/// joins any events they aren't on yet, with the same password.
+///
+/// Events are skipped if they're finalized or closed to responses, if someone else has the
+/// person's name there, if they're full, or if they're private and the `X-Event-Password`
+/// header doesn't match.
+pub async fn copy_group_availability<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((group_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    Json(input): Json<GroupAvailabilityInput>,
+) -> ApiResult<GroupAvailabilityResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let events = adaptor
+        .get_group_events(group_id)
+        .await
+        .map_err(ApiError::AdaptorError)?;
+    let source_event = events
+        .iter()
+        .find(|event| event.id == input.from)
+        .ok_or(ApiError::NotFound)?;
+    let source = find_authorized_person(
+        adaptor,
+        &source_event.id,
+        &person_name,
+        params,
+        bearer.clone(),
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+    let source_tz = event_tz(source_event);
+    let password = parse_password(bearer);
+
+    let mut updated = Vec::new();
+    let mut skipped = Vec::new();
+    for event in events.iter().filter(|event| event.id != source_event.id) {
+        let event = match get_authorized_event(adaptor, event.id.clone(), &headers).await {
+            Ok(event) => event,
+            Err(ApiError::NotAuthorized | ApiError::NotFound) => {
+                skipped.push(event.id.clone());
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let id = event.id.clone();
+        let existing_person = find_person(adaptor, &id, &source.name).await?;
+        if existing_person
+            .as_ref()
+            .is_some_and(|p| !verify_password(p, password.clone()))
+        {
+            skipped.push(id);
+            continue;
+        }
+        match copy_availability(&state, event, &source, source_tz, existing_person).await? {
+            Some(_) => updated.push(id),
+            None => skipped.push(id),
+        }
+    }
+
+    Ok(Json(GroupAvailabilityResponse { updated, skipped }))
+}
+
+/// Find someone on an event by name, if they've joined it
+pub async fn find_person<A: Adaptor>(
+    adaptor: &A,
+    event_id: &str,
+    name: &str,
+) -> Result<Option<Person>, ApiError<A>> {
+    Ok(adaptor
+        .get_people(event_id.to_owned())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|p| p.name.to_lowercase() == name.to_lowercase()))
+}
+
+/// Set a person's availability on an event to match their availability on another, adding
+/// them if they're not on it yet. The caller checks they're allowed to change
+/// `existing_person`.
+///
+/// Returns None if they can't respond to the event, because it's finalized, closed or full.
+pub async fn copy_availability<A: Adaptor>(
+    state: &ApiState<A>,
+    event: Event,
+    source: &Person,
+    source_tz: Tz,
+    existing_person: Option<Person>,
+) -> Result<Option<Person>, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    if event.finalized_time.is_some() || event.responses_closed {
+        return Ok(None);
+    }
+
+    let tz = event_tz(&event);
+    let copy = |times: &[String]| -> Vec<String> {
+        let keys: HashSet<(u32, NaiveTime)> = times
+            .iter()
+            .filter_map(|time| weekly_key(time, source_tz))
+            .collect();
+        event
+            .times
+            .iter()
+            .filter(|time| weekly_key(time, tz).is_some_and(|key| keys.contains(&key)))
+            .cloned()
+            .collect()
+    };
+    let availability = copy(&source.availability);
+    let if_needed = copy(&source.if_needed);
+    let undecided = copy(&source.undecided);
+
+    let now = Utc::now();
+    let is_new = existing_person.is_none();
+    let (person, activity_kind, update_kind) = match existing_person {
+        Some(person) => {
+            let activity_kind = match person.availability.is_empty() {
+                true => ActivityKind::Responded,
+                false => ActivityKind::UpdatedAvailability,
+            };
+            (person, activity_kind, LiveUpdateKind::PersonUpdated)
+        }
+        None => {
+            let person = Person {
+                name: source.name.clone(),
+                password_hash: source.password_hash.clone(),
+                created_at: now,
+                updated_at: now,
+                availability: vec![],
+                if_needed: vec![],
+                undecided: vec![],
+                edit_token_hash: None,
+                edit_token_expires_at: None,
+                subject_id: source.subject_id.clone(),
+                avatar_color: source.avatar_color.clone(),
+                avatar_emoji: source.avatar_emoji.clone(),
+                timezone: source.timezone.clone(),
+                // Connected calendars are only for the event they were connected on
+                google_refresh_token: None,
+            };
+            (person, ActivityKind::Responded, LiveUpdateKind::PersonAdded)
+        }
+    };
+
+    // Joining an event that's already full skips it
+    let person = match adaptor
+        .insert_person(
+            event.id.clone(),
+            Person {
+                updated_at: now,
+                availability,
+                if_needed,
+                undecided,
+                ..person
+            },
+        )
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?
+    {
+        PersonInsert::Inserted(person) => *person,
+        PersonInsert::Full => return Ok(None),
+    };
+    if is_new {
+        state.stat_counters.increment_people();
+    }
+
+    record_activity(
+        adaptor,
+        event.id.clone(),
+        activity_kind,
+        Some(person.name.clone()),
+    )
+    .await;
+    if activity_kind == ActivityKind::Responded {
+        state
+            .chat
+            .send(&event, ChatNotification::Responded(&person.name));
+    }
+    let update = LiveUpdate::new(event.id.clone(), update_kind, person.clone());
+    state.webhooks.send(&event, &update);
+    state.live.publish(update);
+
+    close_responses_if_complete(state, event, &person).await?;
+
+    Ok(Some(person))
+}
+
+pub fn event_tz(event: &Event) -> Tz {
+    event.timezone.parse().unwrap_or(Tz::UTC)
+}
+
+// Times in different events line up when they're on the same day of the week at the same
+// local time, so a series of weekly events can share availability
+fn weekly_key(time: &str, tz: Tz) -> Option<(u32, NaiveTime)> {
+    let local = time.parse::<Slot>().ok()?.datetime_in(tz);
+    Some((local.weekday().num_days_from_sunday(), local.time()))
+}
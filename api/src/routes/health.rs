@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use axum::{extract, http::StatusCode, Json};
+use common::Adaptor;
+
+use crate::{
+    payloads::{HealthResponse, HealthStatus},
+    State,
+};
+
+// How long storage has to respond before the API counts as not ready
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    responses(
+        (status = 200, description = "Ok", body = HealthResponse),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Check the API is running, for liveness probes
+///
+/// This doesn't touch storage, so a database outage won't get the API restarted.
+pub async fn get_health() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: HealthStatus::Ok,
+        detail: None,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "Ok", body = HealthResponse),
+        (status = 429, description = "Too many requests"),
+        (status = 503, description = "Storage can't be reached", body = HealthResponse),
+    ),
+    tag = "info",
+)]
+/// Check the API can reach its storage, for readiness probes and load balancers
+pub async fn get_ready<A: Adaptor>(
+    extract::State(state): State<A>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let detail = match tokio::time::timeout(PING_TIMEOUT, state.adaptor.ping()).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!(
+            "Storage didn't respond within {} seconds",
+            PING_TIMEOUT.as_secs()
+        )),
+    };
+
+    match detail {
+        None => (
+            StatusCode::OK,
+            Json(HealthResponse {
+                status: HealthStatus::Ok,
+                detail: None,
+            }),
+        ),
+        Some(detail) => {
+            tracing::warn!("Readiness check failed: {}", detail);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(HealthResponse {
+                    status: HealthStatus::Unavailable,
+                    detail: Some(detail),
+                }),
+            )
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use axum::{
+    extract::{self, Path, Query},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+};
+use chrono_tz::Tz;
+use common::Adaptor;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    errors::ApiError,
+    etag::{self, ETag},
+    heatmap::Heatmap,
+    routes::event::get_authorized_event,
+    routes::meta::branding,
+    State,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HeatmapQuery {
+    /// The IANA timezone to lay out the times in, like `Europe/London`, defaults to the
+    /// event's timezone
+    timezone: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/heatmap.svg",
+    params(
+        ("event_id", description = "The ID of the event"),
+        HeatmapQuery,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", content_type = "image/svg+xml", body = String),
+        (status = 304, description = "Not modified"),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Not found"),
+        (status = 422, description = "Invalid timezone"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get an SVG of an event's times shaded by how many people are available, with each day
+/// and hour labelled, for embedding in other pages
+pub async fn get_heatmap_svg<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(query): Query<HeatmapQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let (heatmap, cache_control) = build_heatmap(&state.adaptor, event_id, query, &headers).await?;
+    let svg = heatmap.to_svg(&branding().color);
+
+    Ok(respond(
+        &headers,
+        cache_control,
+        "image/svg+xml",
+        svg.into_bytes(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/heatmap.png",
+    params(
+        ("event_id", description = "The ID of the event"),
+        HeatmapQuery,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", content_type = "image/png", body = Vec<u8>),
+        (status = 304, description = "Not modified"),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Not found"),
+        (status = 422, description = "Invalid timezone"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get a 1200×630 PNG of an event's times shaded by how many people are available, sized for
+/// link previews like an Open Graph image
+///
+/// Chat apps and social sites don't show SVGs in previews, so this draws the same grid
+/// without the labels.
+pub async fn get_heatmap_png<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(query): Query<HeatmapQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let (heatmap, cache_control) = build_heatmap(&state.adaptor, event_id, query, &headers).await?;
+    let png = heatmap.to_png(&branding().color);
+
+    Ok(respond(&headers, cache_control, "image/png", png))
+}
+
+async fn build_heatmap<A: Adaptor>(
+    adaptor: &A,
+    event_id: String,
+    query: HeatmapQuery,
+    headers: &HeaderMap,
+) -> Result<(Heatmap, &'static str), ApiError<A>> {
+    let timezone = query
+        .timezone
+        .map(|timezone| {
+            timezone
+                .parse::<Tz>()
+                .map_err(|_| ApiError::InvalidInput(format!("Unknown timezone \"{}\"", timezone)))
+        })
+        .transpose()?;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), headers).await?;
+    let people = adaptor
+        .get_people(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+    let timezone = timezone.unwrap_or_else(|| event.timezone.parse::<Tz>().unwrap_or(Tz::UTC));
+
+    Ok((
+        Heatmap::new(&event.name, &event.times, &people, timezone),
+        etag::cache_control(&event),
+    ))
+}
+
+// Tagged with the rendered image, so it only changes when the image does
+fn respond(
+    headers: &HeaderMap,
+    cache_control: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+) -> Response {
+    let etag = ETag::new(&body);
+    if etag.matches(headers) {
+        return ([(CACHE_CONTROL, cache_control)], etag.not_modified()).into_response();
+    }
+
+    etag.attach((
+        [(CACHE_CONTROL, cache_control), (CONTENT_TYPE, content_type)],
+        body,
+    ))
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{self, Path},
+    http::HeaderMap,
+    Json,
+};
+use chrono_tz::Tz;
+use common::{Adaptor, Person};
+
+use crate::{
+    errors::ApiError,
+    payloads::{ApiResult, InterviewInput, InterviewResponse},
+    routes::event::get_authorized_event,
+    scheduling::{self, Candidate, Interviewer, Pool},
+    State,
+};
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/interviews",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    request_body(content = InterviewInput, description = "Candidates and interviewer pools"),
+    responses(
+        (status = 200, description = "Ok", body = InterviewResponse),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Event not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Assign interviewers to the times picked by candidates
+///
+/// Candidates and interviewers are people on the event, and their availability is used
+/// as the times they picked and the times they're free to interview respectively.
+pub async fn assign_interviews<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    headers: HeaderMap,
+    Json(input): Json<InterviewInput>,
+) -> ApiResult<InterviewResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    let people = adaptor
+        .get_people(event_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+
+    // Only consider times that are part of the event
+    let availability = |name: &str| -> Vec<String> {
+        find_person(&people, name)
+            .map(|p| {
+                p.availability
+                    .iter()
+                    .filter(|t| event.times.contains(t))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let candidates: Vec<Candidate> = input
+        .candidates
+        .iter()
+        .map(|name| Candidate {
+            name: name.clone(),
+            times: availability(name),
+        })
+        .collect();
+    let pools: Vec<Pool> = input
+        .pools
+        .into_iter()
+        .map(|pool| Pool {
+            name: pool.name,
+            interviewers: pool
+                .interviewers
+                .into_iter()
+                .map(|name| Interviewer {
+                    times: HashSet::from_iter(availability(&name)),
+                    name,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let tz = event.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+    let assignments = scheduling::assign(&candidates, &pools, input.max_per_day, tz);
+
+    let unassigned = input
+        .candidates
+        .into_iter()
+        .filter(|name| !assignments.iter().any(|a| &a.candidate == name))
+        .collect();
+
+    Ok(Json(InterviewResponse {
+        assignments: assignments.into_iter().map(|a| a.into()).collect(),
+        unassigned,
+    }))
+}
+
+fn find_person<'a>(people: &'a [Person], name: &str) -> Option<&'a Person> {
+    people
+        .iter()
+        .find(|p| p.name.to_lowercase() == name.to_lowercase())
+}
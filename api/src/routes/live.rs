@@ -0,0 +1,43 @@
+use axum::{
+    extract::{self, Path},
+    http::HeaderMap,
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use common::Adaptor;
+
+use crate::{errors::ApiError, routes::event::get_authorized_event, State};
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/events",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Stream of LiveUpdate objects", content_type = "text/event-stream", body = LiveUpdate),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Stream changes to the people on an event as server-sent events
+///
+/// Each message's event type is the kind of change, and its data is a JSON `LiveUpdate`.
+/// Updates are only shared within a single instance of the API, so when running more than
+/// one, clients will only see changes made through the instance they're connected to.
+pub async fn get_live_events<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    get_authorized_event(&state.adaptor, event_id.clone(), &headers).await?;
+
+    Ok(Sse::new(state.live.subscribe(event_id))
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
@@ -0,0 +1,98 @@
+use axum::{
+    extract,
+    headers::{authorization::Bearer, Authorization},
+    Extension, Json, TypedHeader,
+};
+use common::Adaptor;
+
+use crate::{
+    auth::Identity,
+    errors::ApiError,
+    payloads::{ApiResult, EventResponse, MeResponse},
+    tokens::lookup_hash,
+    State,
+};
+
+#[utoipa::path(
+    get,
+    path = "/me",
+    security(("id-token" = [])),
+    responses(
+        (status = 200, description = "Ok", body = MeResponse),
+        (status = 401, description = "Missing or invalid ID token"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Get who is logged in
+///
+/// Requires an ID token from the instance's OIDC issuer in the `X-Id-Token` header. The
+/// name can be used to fill in who someone is when they join an event.
+pub async fn get_me<A: Adaptor>(identity: Option<Extension<Identity>>) -> ApiResult<MeResponse, A> {
+    let Extension(identity) = identity.ok_or(ApiError::NotAuthorized)?;
+
+    Ok(Json(MeResponse {
+        subject_id: identity.subject_id,
+        name: identity.name,
+        email: identity.email,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/events",
+    security(("creator-token" = []), ("id-token" = [])),
+    responses(
+        (status = 200, description = "Ok", body = [EventResponse]),
+        (status = 401, description = "Missing creator token or ID token"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get the events you've created, newest first
+///
+/// Requires the creator token returned when an event was created. Pass the same token
+/// when creating more events to have them all listed here. When logged in, send the ID
+/// token in the `X-Id-Token` header instead to list the events created with the account
+/// on any device.
+///
+/// Expired events are left out, and each event includes `people_count`.
+pub async fn get_my_events<A: Adaptor>(
+    extract::State(state): State<A>,
+    identity: Option<Extension<Identity>>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+) -> ApiResult<Vec<EventResponse>, A> {
+    let adaptor = &state.adaptor;
+
+    let creator_id = match identity {
+        Some(Extension(identity)) => identity.subject_id,
+        None => bearer
+            .map(|TypedHeader(Authorization(bearer))| bearer.token().trim().to_owned())
+            .filter(|token| !token.is_empty())
+            .map(|token| lookup_hash(&token))
+            .ok_or(ApiError::NotAuthorized)?,
+    };
+
+    let events = adaptor
+        .get_creator_events(creator_id)
+        .await
+        .map_err(ApiError::AdaptorError)?;
+
+    let mut responses = Vec::with_capacity(events.len());
+    for event in events {
+        let people_count = adaptor
+            .get_people(event.id.clone())
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .unwrap_or_default()
+            .iter()
+            .filter(|p| !p.availability.is_empty())
+            .count();
+
+        let mut response = EventResponse::from(event);
+        response.people_count = Some(people_count);
+        responses.push(response);
+    }
+
+    Ok(Json(responses))
+}
@@ -0,0 +1,66 @@
+use axum::Json;
+
+use crate::{
+    config::config,
+    middleware::terms::terms_version,
+    payloads::{BrandingResponse, ChangelogVersionResponse, MetaResponse},
+};
+
+#[utoipa::path(
+    get,
+    path = "/meta",
+    responses(
+        (status = 200, description = "Ok", body = MetaResponse),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Get information about this instance
+pub async fn get_meta() -> Json<MetaResponse> {
+    Json(MetaResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        terms_version: terms_version(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/meta/branding",
+    responses(
+        (status = 200, description = "Ok", body = BrandingResponse),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Get the branding of this instance
+pub async fn get_branding() -> Json<BrandingResponse> {
+    Json(branding())
+}
+
+#[utoipa::path(
+    get,
+    path = "/meta/changelog",
+    responses(
+        (status = 200, description = "Ok", body = [ChangelogVersionResponse]),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Get the changes made to the API in each version, newest first
+///
+/// Clients can compare this with the `version` from `/meta` to check which features an
+/// instance supports.
+pub async fn get_changelog() -> Json<Vec<ChangelogVersionResponse>> {
+    Json(serde_json::from_slice(include_bytes!("../res/changelog.json")).unwrap())
+}
+
+/// Branding set by the operator of this instance, falling back to Jelli Fit's own
+pub fn branding() -> BrandingResponse {
+    let branding = &config().branding;
+    BrandingResponse {
+        name: branding.name.clone(),
+        logo_url: branding.logo_url.clone(),
+        color: branding.color.clone(),
+        support_contact: branding.support_contact.clone(),
+    }
+}
@@ -1,4 +1,581 @@
+use axum::{
+    body::Body,
+    handler::Handler,
+    http::Method,
+    middleware::from_extractor,
+    routing::{on, MethodFilter, MethodRouter},
+    Router,
+};
+use common::Adaptor;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{middleware::admin_key::AdminKey, rate_limit::RateLimits, AppState};
+
+pub mod activity;
+pub mod admin;
+pub mod analytics;
+pub mod availability;
+pub mod badge;
+pub mod calendar;
+pub mod comment;
+pub mod directory;
 pub mod event;
+pub mod export;
+pub mod google;
+pub mod graphql;
+pub mod group;
+pub mod health;
+pub mod heatmap;
+pub mod interview;
+pub mod live;
+pub mod me;
+pub mod meta;
 pub mod person;
+pub mod short_link;
 pub mod stats;
+pub mod sync;
 pub mod tasks;
+pub mod template;
+
+/// How a route authenticates whoever is calling it
+#[derive(Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Auth {
+    Anonymous,
+    /// The event's password, if it's private
+    EventPassword,
+    /// The password of a person on the event (if they set one), or their edit token,
+    /// as well as the event's password if it's private
+    PersonPassword,
+    /// The edit token returned when the event or template was created
+    OwnerToken,
+    /// The creator token returned when an event was created, or an ID token
+    CreatorToken,
+    /// An ID token from the configured OIDC issuer, in the `X-Id-Token` header
+    IdToken,
+    /// The `X-Admin-Key` header, matching the configured `ADMIN_KEY`
+    Admin,
+}
+
+/// Which rate limit a route falls under
+#[derive(Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimit {
+    Standard,
+    /// For routes that create data, like new events
+    Strict,
+}
+
+/// A route served by the API, along with the information needed to audit it
+pub struct RouteSpec<A: Adaptor> {
+    pub method: Method,
+    pub path: &'static str,
+    pub auth: Auth,
+    pub rate_limit: RateLimit,
+    pub handler: MethodRouter<AppState<A>>,
+}
+
+fn route<A, H, T>(
+    method: Method,
+    path: &'static str,
+    auth: Auth,
+    rate_limit: RateLimit,
+    handler: H,
+) -> RouteSpec<A>
+where
+    A: Adaptor + 'static,
+    H: Handler<T, AppState<A>, Body>,
+    T: 'static,
+{
+    let filter = MethodFilter::try_from(method.clone()).expect("Unsupported route method");
+    RouteSpec {
+        method,
+        path,
+        auth,
+        rate_limit,
+        handler: on(filter, handler),
+    }
+}
+
+/// Every route in the API. The router is built from this list, so it's also
+/// the source of truth for auditing which routes exist and how they're protected.
+pub fn registry<A: Adaptor + 'static>() -> Vec<RouteSpec<A>> {
+    use Auth::*;
+    use RateLimit::*;
+
+    vec![
+        route(Method::GET, "/", Anonymous, Standard, crate::get_root),
+        route(Method::GET, "/stats", Anonymous, Standard, stats::get_stats),
+        route(
+            Method::GET,
+            "/stats/timeseries",
+            Anonymous,
+            Standard,
+            stats::get_stats_timeseries,
+        ),
+        route(
+            Method::GET,
+            "/healthz",
+            Anonymous,
+            Standard,
+            health::get_health,
+        ),
+        route(
+            Method::GET,
+            "/readyz",
+            Anonymous,
+            Standard,
+            health::get_ready,
+        ),
+        route(Method::GET, "/meta", Anonymous, Standard, meta::get_meta),
+        route(
+            Method::GET,
+            "/meta/branding",
+            Anonymous,
+            Standard,
+            meta::get_branding,
+        ),
+        route(
+            Method::GET,
+            "/meta/changelog",
+            Anonymous,
+            Standard,
+            meta::get_changelog,
+        ),
+        route(
+            Method::POST,
+            "/event",
+            Anonymous,
+            Strict,
+            event::create_event,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id",
+            EventPassword,
+            Standard,
+            event::get_event,
+        ),
+        route(
+            Method::PATCH,
+            "/event/:event_id",
+            OwnerToken,
+            Standard,
+            event::update_event,
+        ),
+        route(
+            Method::DELETE,
+            "/event/:event_id",
+            OwnerToken,
+            Standard,
+            event::delete_event,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/duplicate",
+            EventPassword,
+            Strict,
+            event::duplicate_event,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/finalize",
+            OwnerToken,
+            Standard,
+            event::finalize_event,
+        ),
+        route(
+            Method::PUT,
+            "/event/:event_id/webhook",
+            OwnerToken,
+            Standard,
+            event::put_webhook,
+        ),
+        route(
+            Method::DELETE,
+            "/event/:event_id/webhook",
+            OwnerToken,
+            Standard,
+            event::delete_webhook,
+        ),
+        route(
+            Method::PUT,
+            "/event/:event_id/chat-webhook",
+            OwnerToken,
+            Standard,
+            event::put_chat_webhook,
+        ),
+        route(
+            Method::DELETE,
+            "/event/:event_id/chat-webhook",
+            OwnerToken,
+            Standard,
+            event::delete_chat_webhook,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/extend",
+            if event::extend_requires_person() {
+                PersonPassword
+            } else {
+                OwnerToken
+            },
+            Standard,
+            event::extend_event,
+        ),
+        route(
+            Method::GET,
+            "/graphql",
+            EventPassword,
+            Standard,
+            graphql::get_graphql,
+        ),
+        route(
+            Method::POST,
+            "/graphql",
+            PersonPassword,
+            Strict,
+            graphql::post_graphql,
+        ),
+        route(
+            Method::GET,
+            "/group/:group_id",
+            Anonymous,
+            Standard,
+            group::get_group,
+        ),
+        route(
+            Method::PATCH,
+            "/group/:group_id/people/:person_name",
+            PersonPassword,
+            Standard,
+            group::copy_group_availability,
+        ),
+        route(
+            Method::POST,
+            "/templates",
+            Anonymous,
+            Strict,
+            template::create_template,
+        ),
+        route(
+            Method::POST,
+            "/templates/:template_id/events",
+            OwnerToken,
+            Strict,
+            template::create_template_event,
+        ),
+        route(
+            Method::POST,
+            "/event/import",
+            Anonymous,
+            Strict,
+            event::import_event,
+        ),
+        route(
+            Method::POST,
+            "/event/merge",
+            OwnerToken,
+            Standard,
+            event::merge_events,
+        ),
+        route(
+            Method::POST,
+            "/events/lookup",
+            Anonymous,
+            Standard,
+            event::lookup_events,
+        ),
+        route(
+            Method::GET,
+            "/events",
+            Anonymous,
+            Standard,
+            event::get_events,
+        ),
+        route(
+            Method::GET,
+            "/directory",
+            Anonymous,
+            Standard,
+            directory::get_directory,
+        ),
+        route(Method::GET, "/me", IdToken, Standard, me::get_me::<A>),
+        route(
+            Method::GET,
+            "/me/events",
+            CreatorToken,
+            Standard,
+            me::get_my_events,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/availability",
+            EventPassword,
+            Standard,
+            availability::get_availability,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/events",
+            EventPassword,
+            Standard,
+            live::get_live_events,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/sync",
+            EventPassword,
+            Standard,
+            sync::get_sync,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/sync",
+            PersonPassword,
+            Standard,
+            sync::post_sync,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/calendar.ics",
+            EventPassword,
+            Standard,
+            calendar::get_calendar,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/badge.svg",
+            EventPassword,
+            Standard,
+            badge::get_badge,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/heatmap.svg",
+            EventPassword,
+            Standard,
+            heatmap::get_heatmap_svg,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/heatmap.png",
+            EventPassword,
+            Standard,
+            heatmap::get_heatmap_png,
+        ),
+        route(
+            Method::GET,
+            "/e/:event_id",
+            Anonymous,
+            Standard,
+            short_link::get_short_link,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/interviews",
+            EventPassword,
+            Standard,
+            interview::assign_interviews,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/activity",
+            EventPassword,
+            Standard,
+            activity::get_activity,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/export",
+            EventPassword,
+            Standard,
+            export::get_export,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/analytics",
+            OwnerToken,
+            Standard,
+            analytics::get_analytics,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/comments",
+            EventPassword,
+            Standard,
+            comment::get_comments,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/comments",
+            EventPassword,
+            Standard,
+            comment::create_comment,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/people",
+            EventPassword,
+            Standard,
+            person::get_people,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/people/:person_name",
+            PersonPassword,
+            Standard,
+            person::get_person,
+        ),
+        route(
+            Method::PATCH,
+            "/event/:event_id/people/:person_name",
+            PersonPassword,
+            Standard,
+            person::update_person,
+        ),
+        route(
+            Method::DELETE,
+            "/event/:event_id/people/:person_name",
+            PersonPassword,
+            Standard,
+            person::delete_person,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/people/:person_name/availability.ics",
+            EventPassword,
+            Standard,
+            calendar::get_person_calendar,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/people/:person_name/edit-token",
+            PersonPassword,
+            Standard,
+            person::rotate_edit_token,
+        ),
+        route(
+            Method::DELETE,
+            "/event/:event_id/people/:person_name/edit-token",
+            PersonPassword,
+            Standard,
+            person::revoke_edit_token,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/people/:person_name/copy",
+            PersonPassword,
+            Standard,
+            person::copy_person_availability,
+        ),
+        route(
+            Method::POST,
+            "/event/:event_id/people/:person_name/google",
+            PersonPassword,
+            Standard,
+            google::connect_google,
+        ),
+        route(
+            Method::DELETE,
+            "/event/:event_id/people/:person_name/google",
+            PersonPassword,
+            Standard,
+            google::disconnect_google,
+        ),
+        route(
+            Method::GET,
+            "/event/:event_id/people/:person_name/suggested",
+            PersonPassword,
+            Standard,
+            google::get_suggested_availability,
+        ),
+        route(
+            Method::GET,
+            "/google/callback",
+            Anonymous,
+            Standard,
+            google::google_callback,
+        ),
+        route(
+            Method::GET,
+            "/tasks/cleanup",
+            Admin,
+            Standard,
+            tasks::cleanup,
+        ),
+        route(Method::POST, "/tasks/seed", Admin, Strict, tasks::seed),
+        route(
+            Method::GET,
+            "/admin/route-matrix",
+            Admin,
+            Standard,
+            admin::get_route_matrix::<A>,
+        ),
+        route(
+            Method::GET,
+            "/admin/cache",
+            Admin,
+            Standard,
+            admin::get_cache_stats,
+        ),
+        route(
+            Method::GET,
+            "/admin/adaptor",
+            Admin,
+            Standard,
+            admin::get_adaptor_metrics,
+        ),
+        route(
+            Method::DELETE,
+            "/admin/directory/:event_id",
+            Admin,
+            Standard,
+            admin::delist_event,
+        ),
+        route(
+            Method::GET,
+            "/admin/events",
+            Admin,
+            Standard,
+            admin::list_events,
+        ),
+        route(
+            Method::GET,
+            "/admin/events/:event_id",
+            Admin,
+            Standard,
+            admin::get_event_details,
+        ),
+        route(
+            Method::DELETE,
+            "/admin/events/:event_id",
+            Admin,
+            Standard,
+            admin::purge_event,
+        ),
+        route(
+            Method::POST,
+            "/admin/events/:event_id/restore",
+            Admin,
+            Standard,
+            admin::restore_event,
+        ),
+    ]
+}
+
+/// Build a router serving every route in the registry, each limited by its group's rate limit
+/// and requiring the admin key if it's an admin route
+pub fn router<A: Adaptor + 'static>(state: AppState<A>, rate_limits: &RateLimits) -> Router {
+    registry::<A>()
+        .into_iter()
+        .fold(Router::new(), |router, spec| {
+            let handler = match spec.auth {
+                Auth::Admin => spec.handler.layer(from_extractor::<AdminKey>()),
+                _ => spec.handler,
+            };
+            router.route(spec.path, rate_limits.apply(spec.rate_limit, handler))
+        })
+        .with_state(state)
+}
@@ -1,57 +1,116 @@
 use axum::{
-    extract::{self, Path},
+    extract::{self, Path, Query, RawQuery},
     headers::{authorization::Bearer, Authorization},
-    Json, TypedHeader,
+    http::{header::IF_MATCH, HeaderMap, HeaderName, StatusCode},
+    response::Response,
+    Extension, Json, TypedHeader,
 };
 use base64::{engine::general_purpose, Engine};
-use common::{Adaptor, Person};
+use chrono::{DateTime, Duration, Utc};
+use common::{Adaptor, Event, PeopleQuery, Person, PersonInsert, PersonUpdate, RemovedPerson};
 
 use crate::{
+    auth::Identity,
+    chat::ChatNotification,
     errors::ApiError,
-    payloads::{ApiResult, PersonInput, PersonResponse},
-    State,
+    etag::{self, ETag},
+    msgpack::{Format, JsonOrMsgPack},
+    payloads::{
+        ActivityKind, ApiResult, CopyAvailabilityInput, EditTokenParams, EditTokenResponse,
+        FieldsQuery, LiveUpdate, LiveUpdateKind, PeopleParams, PersonInput, PersonResponse,
+    },
+    routes::{
+        activity::record_activity,
+        event::get_authorized_event,
+        group::{copy_availability, event_tz, find_person},
+    },
+    spam::{check_spam, SpamCheck},
+    tokens::{generate_token, hash_token, verify_token},
+    validation::{validate_person, validate_person_name},
+    ApiState, State,
 };
 
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+// How long a person's private edit link works for
+const EDIT_TOKEN_LIFETIME_DAYS: i64 = 30;
+
 #[utoipa::path(
     get,
     path = "/event/{event_id}/people",
     params(
         ("event_id", description = "The ID of the event"),
+        PeopleParams,
+        FieldsQuery,
     ),
+    security((), ("password" = []), ("event-password" = [])),
     responses(
-        (status = 200, description = "Ok", body = [PersonResponse]),
+        (status = 200, description = "Ok", body = [PersonResponse], content_type = ["application/json", "application/msgpack"], headers(
+            ("x-total-count" = usize, description = "How many people matched, before `limit` and `offset` were applied"),
+            ("etag" = String, description = "Send as `If-None-Match` to get a 304 if nobody has changed"),
+        )),
+        (status = 304, description = "Not modified since the ETag in `If-None-Match`"),
+        (status = 401, description = "Missing or incorrect event password"),
         (status = 404, description = "Event not found"),
         (status = 429, description = "Too many requests"),
     ),
     tag = "person",
 )]
 /// Get availabilities for an event
+///
+/// Large events can be fetched a page at a time with `limit` and `offset`, the total number
+/// of matching people is in the `X-Total-Count` header.
+///
+/// Responses have an ETag, send it back in `If-None-Match` when polling to get a 304 if
+/// nobody has joined, changed or been removed.
+///
+/// Send `Accept: application/msgpack` to get people as MessagePack instead of JSON, which is
+/// smaller and quicker to parse for events with lots of availability.
 pub async fn get_people<A: Adaptor>(
     extract::State(state): State<A>,
     Path(event_id): Path<String>,
-) -> ApiResult<Vec<PersonResponse>, A> {
-    let adaptor = &state.lock().await.adaptor;
+    Query(params): Query<PeopleParams>,
+    Query(fields): Query<FieldsQuery>,
+    RawQuery(query): RawQuery,
+    format: Format,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
 
-    let people = adaptor
-        .get_people(event_id)
-        .await
-        .map_err(ApiError::AdaptorError)?;
+    get_authorized_event(adaptor, event_id.clone(), &headers).await?;
 
-    match people {
-        Some(people) => Ok(Json(
-            people
-                .into_iter()
-                .filter_map(|p| {
-                    if !p.availability.is_empty() {
-                        Some(p.into())
-                    } else {
-                        None
-                    }
-                })
-                .collect(),
-        )),
-        None => Err(ApiError::NotFound),
+    // Check for changes before fetching everyone
+    let version = adaptor
+        .get_people_version(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    let etag = ETag::new((version, query));
+    if etag.matches(&headers) {
+        return Ok(etag.not_modified());
     }
+
+    let range = params.range();
+    let page = adaptor
+        .query_people_page(event_id, PeopleQuery::from(params), range)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(etag.attach((
+        [(
+            HeaderName::from_static(TOTAL_COUNT_HEADER),
+            page.total.to_string(),
+        )],
+        format.respond(
+            fields.sparse(
+                page.items
+                    .into_iter()
+                    .map(PersonResponse::from)
+                    .collect::<Vec<_>>(),
+            ),
+        ),
+    )))
 }
 
 #[utoipa::path(
@@ -60,25 +119,67 @@ pub async fn get_people<A: Adaptor>(
     params(
         ("event_id", description = "The ID of the event"),
         ("person_name", description = "The name of the person"),
+        EditTokenParams,
     ),
-    security((), ("password" = [])),
+    security((), ("password" = []), ("event-password" = [])),
     responses(
-        (status = 200, description = "Ok", body = PersonResponse),
+        (status = 200, description = "Ok", body = PersonResponse, content_type = ["application/json", "application/msgpack"]),
         (status = 401, description = "Incorrect password"),
+        (status = 403, description = "Rejected as spam"),
         (status = 404, description = "Event not found"),
+        (status = 409, description = "The event already has as many people as it allows"),
         (status = 415, description = "Unsupported input format"),
-        (status = 422, description = "Invalid input provided"),
+        (status = 422, description = "The name is too long for a new person", body = ValidationErrorResponse),
         (status = 429, description = "Too many requests"),
     ),
     tag = "person",
 )]
 /// Login or create a person for an event
+///
+/// On private events, the event's password goes in the `X-Event-Password` header, as the
+/// bearer token is the person's own password.
+///
+/// New people can't join events that already have their `max_people`.
+///
+/// When logged in with an ID token in the `X-Id-Token` header, new people are linked to
+/// the account, and it can be used instead of their password from then on.
+///
+/// Send `Accept: application/msgpack` to get the person as MessagePack instead of JSON.
 pub async fn get_person<A: Adaptor>(
     extract::State(state): State<A>,
     Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
     bearer: Option<TypedHeader<Authorization<Bearer>>>,
-) -> ApiResult<PersonResponse, A> {
-    let adaptor = &state.lock().await.adaptor;
+    identity: Option<Extension<Identity>>,
+    format: Format,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<A>> {
+    let person = login_or_create_person(
+        &state,
+        event_id,
+        person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+    Ok(format.respond(PersonResponse::from(person)))
+}
+
+/// Log in as a person on an event, or add them if nobody has their name yet
+pub async fn login_or_create_person<A: Adaptor>(
+    state: &ApiState<A>,
+    event_id: String,
+    person_name: String,
+    params: EditTokenParams,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<&Identity>,
+    headers: &HeaderMap,
+) -> Result<Person, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), headers).await?;
 
     // Get inputted password
     let password = parse_password(bearer);
@@ -102,38 +203,80 @@ pub async fn get_person<A: Adaptor>(
     match existing_person {
         // Login
         Some(p) => {
-            // Verify password (if set)
-            if verify_password(&p, password) {
-                Ok(Json(p.into()))
+            // Verify password (if set), edit token or who's logged in
+            if verify_edit_token(&p, params.edit_token.as_deref())
+                || verify_password(&p, password)
+                || verify_identity(&p, identity)
+            {
+                Ok(p)
             } else {
                 Err(ApiError::NotAuthorized)
             }
         }
         // Signup
         None => {
-            // Update stats
-            adaptor
-                .increment_stat_person_count()
+            validate_person_name(&person_name)?;
+            check_spam(
+                state.spam_filter.as_ref(),
+                SpamCheck::Person {
+                    event_id: event_id.clone(),
+                    name: person_name.clone(),
+                    availability: vec![],
+                },
+            )
+            .await?;
+
+            let now = chrono::offset::Utc::now();
+
+            let person = match adaptor
+                .insert_person(
+                    event_id.clone(),
+                    Person {
+                        name: person_name,
+                        password_hash: password
+                            .map(|raw| bcrypt::hash(raw, 10).unwrap_or(String::from(""))),
+                        created_at: now,
+                        updated_at: now,
+                        availability: vec![],
+                        if_needed: vec![],
+                        undecided: vec![],
+                        edit_token_hash: None,
+                        edit_token_expires_at: None,
+                        subject_id: identity.map(|identity| identity.subject_id.clone()),
+                        avatar_color: None,
+                        avatar_emoji: None,
+                        timezone: None,
+                        google_refresh_token: None,
+                    },
+                )
                 .await
-                .map_err(ApiError::AdaptorError)?;
-
-            Ok(Json(
-                adaptor
-                    .upsert_person(
-                        event_id,
-                        Person {
-                            name: person_name,
-                            password_hash: password
-                                .map(|raw| bcrypt::hash(raw, 10).unwrap_or(String::from(""))),
-                            created_at: chrono::offset::Utc::now(),
-                            availability: vec![],
-                        },
-                    )
-                    .await
-                    .map_err(ApiError::AdaptorError)?
-                    .unwrap()
-                    .into(),
-            ))
+                .map_err(ApiError::AdaptorError)?
+                .ok_or(ApiError::NotFound)?
+            {
+                PersonInsert::Inserted(person) => *person,
+                PersonInsert::Full => {
+                    return Err(ApiError::Conflict(format!(
+                        "Event is full, it allows at most {} people",
+                        event.max_people.unwrap_or_default()
+                    )))
+                }
+            };
+
+            // Update stats
+            state.stat_counters.increment_people();
+
+            record_activity(
+                adaptor,
+                event_id.clone(),
+                ActivityKind::Joined,
+                Some(person.name.clone()),
+            )
+            .await;
+            let update = LiveUpdate::new(event_id, LiveUpdateKind::PersonAdded, person.clone());
+            state.webhooks.send(&event, &update);
+            state.live.publish(update);
+
+            Ok(person)
         }
     }
 }
@@ -144,77 +287,657 @@ pub async fn get_person<A: Adaptor>(
     params(
         ("event_id", description = "The ID of the event"),
         ("person_name", description = "The name of the person"),
+        EditTokenParams,
+        ("if-match" = Option<String>, Header, description = "Only update the person if their `version` still matches"),
     ),
-    security((), ("password" = [])),
+    security((), ("password" = []), ("event-password" = [])),
     request_body(content = PersonInput, description = "Person details"),
     responses(
-        (status = 200, description = "Ok", body = PersonResponse),
+        (status = 200, description = "Ok", body = PersonResponse, content_type = ["application/json", "application/msgpack"]),
         (status = 401, description = "Incorrect password"),
+        (status = 403, description = "Rejected as spam"),
         (status = 404, description = "Event or person not found"),
+        (status = 409, description = "Event has been finalized or closed to responses"),
+        (status = 412, description = "The person has changed since the version in `If-Match`"),
         (status = 415, description = "Unsupported input format"),
-        (status = 422, description = "Invalid input provided"),
+        (status = 422, description = "Invalid input provided, with what's wrong with each field", body = ValidationErrorResponse),
         (status = 429, description = "Too many requests"),
     ),
     tag = "person",
 )]
 /// Update a person's availabilities
+///
+/// The first time a person fills in their availability, the response includes a token for
+/// a private edit link, which can be used instead of their password until it expires.
+///
+/// An avatar color and emoji can be set at the same time, so the person is shown the same
+/// way on every device, along with their own timezone if it's different to the event's.
+///
+/// If the event has invitees, responses are closed once they've all filled in their
+/// availability, and a `responses_closed` live update is sent.
+///
+/// On private events, the event's password goes in the `X-Event-Password` header, as the
+/// bearer token is the person's own password.
+///
+/// To avoid overwriting changes made somewhere else, like another tab, send the `version`
+/// the changes were based on as `If-Match`.
+///
+/// Availability can be sent as MessagePack with `Content-Type: application/msgpack`, and the
+/// response is MessagePack too if `Accept` asks for it.
+#[allow(clippy::too_many_arguments)]
 pub async fn update_person<A: Adaptor>(
     extract::State(state): State<A>,
     Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
     bearer: Option<TypedHeader<Authorization<Bearer>>>,
-    Json(input): Json<PersonInput>,
-) -> ApiResult<PersonResponse, A> {
-    let adaptor = &state.lock().await.adaptor;
+    identity: Option<Extension<Identity>>,
+    format: Format,
+    headers: HeaderMap,
+    JsonOrMsgPack(input): JsonOrMsgPack<PersonInput>,
+) -> Result<Response, ApiError<A>> {
+    let response = save_availability(
+        &state,
+        event_id,
+        person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+        input,
+    )
+    .await?;
+    Ok(format.respond(response))
+}
 
-    let existing_people = adaptor
-        .get_people(event_id.clone())
+/// Update a person's availability, after checking they're allowed to
+#[allow(clippy::too_many_arguments)]
+pub async fn save_availability<A: Adaptor>(
+    state: &ApiState<A>,
+    event_id: String,
+    person_name: String,
+    params: EditTokenParams,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<&Identity>,
+    headers: &HeaderMap,
+    input: PersonInput,
+) -> Result<PersonResponse, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let existing_person = find_authorized_person(
+        adaptor,
+        &event_id,
+        &person_name,
+        params,
+        bearer,
+        identity,
+        headers,
+    )
+    .await?;
+    let expected_updated_at = existing_person.updated_at;
+    let version = expected_updated_at.timestamp_millis().to_string();
+    if !etag::if_match(headers, &version) {
+        return Err(ApiError::PreconditionFailed(format!(
+            "{} has been changed since, the current version is {}",
+            existing_person.name, version
+        )));
+    }
+
+    // Availability is locked in once the organizer picks a time, or everyone invited has responded
+    let event = adaptor
+        .get_event(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    if event.finalized_time.is_some() {
+        return Err(ApiError::Conflict("Event has been finalized".to_owned()));
+    }
+    if event.responses_closed {
+        return Err(ApiError::Conflict(
+            "Event is closed to responses".to_owned(),
+        ));
+    }
+    validate_person(&input, &event)?;
+
+    check_spam(
+        state.spam_filter.as_ref(),
+        SpamCheck::Person {
+            event_id: event_id.clone(),
+            name: existing_person.name.clone(),
+            availability: input.availability.clone(),
+        },
+    )
+    .await?;
+
+    // Only keep levels for times the person is actually available
+    let if_needed: Vec<String> = input
+        .if_needed
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|time| input.availability.contains(time))
+        .collect();
+    let undecided: Vec<String> = input
+        .undecided
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|time| !input.availability.contains(time))
+        .collect();
+
+    // Colors are stored lowercase so clients can compare them, and empty strings clear these
+    let avatar_color = match input.avatar_color {
+        Some(color) => Some(color.trim().to_lowercase()).filter(|color| !color.is_empty()),
+        None => existing_person.avatar_color,
+    };
+    let avatar_emoji = match input.avatar_emoji {
+        Some(emoji) => Some(emoji.trim().to_owned()).filter(|emoji| !emoji.is_empty()),
+        None => existing_person.avatar_emoji,
+    };
+    let timezone = match input.timezone {
+        Some(timezone) => Some(timezone.trim().to_owned()).filter(|tz| !tz.is_empty()),
+        None => existing_person.timezone,
+    };
+
+    let activity_kind = match existing_person.availability.is_empty() {
+        true => ActivityKind::Responded,
+        false => ActivityKind::UpdatedAvailability,
+    };
+
+    // Issue an edit token the first time availability is filled in
+    let edit_token = (existing_person.availability.is_empty()
+        && existing_person.edit_token_hash.is_none()
+        && !input.availability.is_empty())
+    .then(|| (generate_token(), edit_token_expiry()));
+
+    let person = Person {
+        name: existing_person.name,
+        password_hash: existing_person.password_hash,
+        created_at: existing_person.created_at,
+        updated_at: chrono::offset::Utc::now(),
+        availability: input.availability,
+        if_needed,
+        undecided,
+        edit_token_hash: match &edit_token {
+            Some((token, _)) => hash_token(token),
+            None => existing_person.edit_token_hash,
+        },
+        edit_token_expires_at: match &edit_token {
+            Some((_, expires_at)) => Some(*expires_at),
+            None => existing_person.edit_token_expires_at,
+        },
+        subject_id: existing_person.subject_id,
+        avatar_color,
+        avatar_emoji,
+        timezone,
+        google_refresh_token: existing_person.google_refresh_token,
+    };
+    // With `If-Match`, the version is checked again as it's written, in case someone else
+    // saved in the meantime
+    let person =
+        match headers.contains_key(IF_MATCH) {
+            true => match adaptor
+                .update_person(event_id.clone(), person, expected_updated_at)
+                .await
+                .map_err(ApiError::AdaptorError)?
+                .ok_or(ApiError::NotFound)?
+            {
+                PersonUpdate::Updated(person) => *person,
+                PersonUpdate::Changed => return Err(ApiError::PreconditionFailed(
+                    "This person has been changed since, fetch them again for the current version"
+                        .to_owned(),
+                )),
+            },
+            false => adaptor
+                .upsert_person(event_id.clone(), person)
+                .await
+                .map_err(ApiError::AdaptorError)?
+                .ok_or(ApiError::NotFound)?,
+        };
+
+    record_activity(
+        adaptor,
+        event_id.clone(),
+        activity_kind,
+        Some(person.name.clone()),
+    )
+    .await;
+    if activity_kind == ActivityKind::Responded {
+        state
+            .chat
+            .send(&event, ChatNotification::Responded(&person.name));
+    }
+    let update = LiveUpdate::new(
+        event_id.clone(),
+        LiveUpdateKind::PersonUpdated,
+        person.clone(),
+    );
+    state.webhooks.send(&event, &update);
+    state.live.publish(update);
+
+    close_responses_if_complete(state, event, &person).await?;
+
+    let mut response: PersonResponse = person.into();
+    if let Some((token, expires_at)) = edit_token {
+        response.edit_token = Some(token);
+        response.edit_token_expires_at = Some(expires_at.timestamp());
+    }
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/event/{event_id}/people/{person_name}",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Incorrect password"),
+        (status = 404, description = "Event or person not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Remove a person from an event
+///
+/// The person count in the stats is a running total of everyone who has joined an event,
+/// so it isn't decremented.
+pub async fn delete_person<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let existing_person = find_authorized_person(
+        adaptor,
+        &event_id,
+        &person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+
+    let person = adaptor
+        .delete_person(event_id.clone(), existing_person.name)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    // Leave a record of the removal, so clients syncing the event can remove them too
+    let event = adaptor
+        .get_event(event_id.clone())
         .await
         .map_err(ApiError::AdaptorError)?;
+    if let Some(mut event) = event {
+        event.removed_people.push(RemovedPerson {
+            name: person.name.clone(),
+            removed_at: Utc::now(),
+        });
+        adaptor
+            .update_event(event)
+            .await
+            .map_err(ApiError::AdaptorError)?;
+    }
 
-    // Event not found
-    if existing_people.is_none() {
-        return Err(ApiError::NotFound);
+    record_activity(
+        adaptor,
+        event_id.clone(),
+        ActivityKind::Removed,
+        Some(person.name.clone()),
+    )
+    .await;
+    state.live.publish(LiveUpdate::new(
+        event_id,
+        LiveUpdateKind::PersonRemoved,
+        person,
+    ));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/people/{person_name}/edit-token",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = EditTokenResponse),
+        (status = 401, description = "Incorrect password or edit token"),
+        (status = 404, description = "Event or person not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Issue a new edit token for a person, replacing any existing one
+pub async fn rotate_edit_token<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> ApiResult<EditTokenResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let existing_person = find_authorized_person(
+        adaptor,
+        &event_id,
+        &person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+
+    let edit_token = generate_token();
+    let expires_at = edit_token_expiry();
+    adaptor
+        .upsert_person(
+            event_id,
+            Person {
+                edit_token_hash: hash_token(&edit_token),
+                edit_token_expires_at: Some(expires_at),
+                ..existing_person
+            },
+        )
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(EditTokenResponse {
+        edit_token,
+        expires_at: expires_at.timestamp(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/event/{event_id}/people/{person_name}/edit-token",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 204, description = "Revoked"),
+        (status = 401, description = "Incorrect password or edit token"),
+        (status = 404, description = "Event or person not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Revoke a person's edit token, so their edit link stops working
+pub async fn revoke_edit_token<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let existing_person = find_authorized_person(
+        adaptor,
+        &event_id,
+        &person_name,
+        params,
+        bearer,
+        identity.as_deref(),
+        &headers,
+    )
+    .await?;
+
+    adaptor
+        .upsert_person(
+            event_id,
+            Person {
+                edit_token_hash: None,
+                edit_token_expires_at: None,
+                ..existing_person
+            },
+        )
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/people/{person_name}/copy",
+    params(
+        ("event_id", description = "The ID of the event"),
+        ("person_name", description = "The name of the person"),
+        EditTokenParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    request_body(content = CopyAvailabilityInput, description = "The event to copy availability from"),
+    responses(
+        (status = 200, description = "Ok", body = PersonResponse),
+        (status = 401, description = "Incorrect password"),
+        (status = 404, description = "Either event, or the person on the other event, not found"),
+        (status = 409, description = "Event has been finalized, is closed to responses, or is full"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Copying from the same event"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "person",
+)]
+/// Copy a person's availability from another event they responded to
+///
+/// The person is matched by name on the other event, and needs the same password there, or to
+/// have been added by whoever is logged in. Times line up across events when they're on the
+/// same day of the week at the same local time, so a weekly pattern carries over without
+/// painting it again. The person is added to this event with the same password if they
+/// haven't joined it yet, and anything they'd already filled in here is replaced.
+///
+/// Availability can only be copied without a password from people who didn't set one on
+/// events that aren't private.
+pub async fn copy_person_availability<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path((event_id, person_name)): Path<(String, String)>,
+    Query(params): Query<EditTokenParams>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    Json(input): Json<CopyAvailabilityInput>,
+) -> ApiResult<PersonResponse, A> {
+    let adaptor = &state.adaptor;
+
+    if input.from == event_id {
+        return Err(ApiError::InvalidInput(
+            "Availability can't be copied from the same event".to_owned(),
+        ));
+    }
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    if event.finalized_time.is_some() {
+        return Err(ApiError::Conflict("Event has been finalized".to_owned()));
+    }
+    if event.responses_closed {
+        return Err(ApiError::Conflict(
+            "Event is closed to responses".to_owned(),
+        ));
     }
 
-    // Check if the user exists
-    let existing_person = existing_people
-        .unwrap()
+    // Reading the other event isn't a visit, as only the person's own availability is used,
+    // but expired events are as good as deleted
+    let source_event = adaptor
+        .peek_event(input.from.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .filter(|event| event.expired_at.is_none())
+        .ok_or(ApiError::NotFound)?;
+    let source = find_person(adaptor, &source_event.id, &person_name)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let password = parse_password(bearer);
+    let source_authorized = match source.password_hash {
+        Some(_) => verify_password(&source, password.clone()),
+        None => source_event.password_hash.is_none(),
+    };
+    if !source_authorized && !verify_identity(&source, identity.as_deref()) {
+        return Err(ApiError::NotAuthorized);
+    }
+
+    let existing_person = find_person(adaptor, &event_id, &person_name).await?;
+    if let Some(person) = &existing_person {
+        if !verify_edit_token(person, params.edit_token.as_deref())
+            && !verify_password(person, password)
+            && !verify_identity(person, identity.as_deref())
+        {
+            return Err(ApiError::NotAuthorized);
+        }
+    }
+
+    let max_people = event.max_people;
+    let person = copy_availability(
+        &state,
+        event,
+        &source,
+        event_tz(&source_event),
+        existing_person,
+    )
+    .await?
+    .ok_or_else(|| {
+        ApiError::Conflict(format!(
+            "Event is full, it allows at most {} people",
+            max_people.unwrap_or_default()
+        ))
+    })?;
+
+    Ok(Json(person.into()))
+}
+
+/// Close responses once everyone invited has filled in their availability,
+/// letting live clients know with the person whose update completed it
+pub async fn close_responses_if_complete<A: Adaptor>(
+    state: &ApiState<A>,
+    event: Event,
+    person: &Person,
+) -> Result<(), ApiError<A>> {
+    if event.invitees.is_empty() {
+        return Ok(());
+    }
+
+    let adaptor = &state.adaptor;
+    let people = adaptor
+        .get_people(event.id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .unwrap_or_default();
+    let all_responded = event.invitees.iter().all(|name| {
+        people
+            .iter()
+            .any(|p| p.name.to_lowercase() == name.to_lowercase() && !p.availability.is_empty())
+    });
+
+    if all_responded {
+        let event_id = event.id.clone();
+        adaptor
+            .update_event(Event {
+                responses_closed: true,
+                ..event
+            })
+            .await
+            .map_err(ApiError::AdaptorError)?;
+        record_activity(
+            adaptor,
+            event_id.clone(),
+            ActivityKind::ResponsesClosed,
+            None,
+        )
+        .await;
+        state.live.publish(LiveUpdate::new(
+            event_id,
+            LiveUpdateKind::ResponsesClosed,
+            person.clone(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Find a person on an event, and check the password or edit token provided lets them make changes
+pub async fn find_authorized_person<A: Adaptor>(
+    adaptor: &A,
+    event_id: &str,
+    person_name: &str,
+    params: EditTokenParams,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<&Identity>,
+    headers: &HeaderMap,
+) -> Result<Person, ApiError<A>> {
+    get_authorized_event(adaptor, event_id.to_owned(), headers).await?;
+
+    let existing_person = adaptor
+        .get_people(event_id.to_owned())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?
         .into_iter()
         .find(|p| p.name.to_lowercase() == person_name.to_lowercase())
         .ok_or(ApiError::NotFound)?;
 
-    // Verify password (if set)
-    if !verify_password(&existing_person, parse_password(bearer)) {
+    // Verify password (if set), edit token or who's logged in
+    if !verify_edit_token(&existing_person, params.edit_token.as_deref())
+        && !verify_password(&existing_person, parse_password(bearer))
+        && !verify_identity(&existing_person, identity)
+    {
         return Err(ApiError::NotAuthorized);
     }
 
-    Ok(Json(
-        adaptor
-            .upsert_person(
-                event_id,
-                Person {
-                    name: existing_person.name,
-                    password_hash: existing_person.password_hash,
-                    created_at: existing_person.created_at,
-                    availability: input.availability,
-                },
-            )
-            .await
-            .map_err(ApiError::AdaptorError)?
-            .unwrap()
-            .into(),
-    ))
+    Ok(existing_person)
+}
+
+fn edit_token_expiry() -> DateTime<Utc> {
+    Utc::now() + Duration::days(EDIT_TOKEN_LIFETIME_DAYS)
+}
+
+pub fn verify_edit_token(person: &Person, token: Option<&str>) -> bool {
+    match (&person.edit_token_hash, person.edit_token_expires_at, token) {
+        (Some(hash), Some(expires_at), Some(token)) => {
+            expires_at > Utc::now() && verify_token(token, hash)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the person was added by whoever is logged in
+pub fn verify_identity(person: &Person, identity: Option<&Identity>) -> bool {
+    match (&person.subject_id, identity) {
+        (Some(subject_id), Some(identity)) => *subject_id == identity.subject_id,
+        _ => false,
+    }
 }
 
 pub fn parse_password(bearer: Option<TypedHeader<Authorization<Bearer>>>) -> Option<String> {
-    bearer.map(|TypedHeader(Authorization(b))| {
-        String::from_utf8(
-            general_purpose::STANDARD
-                .decode(b.token().trim())
-                .unwrap_or(vec![]),
-        )
-        .unwrap_or("".to_owned())
-    })
+    bearer.map(|TypedHeader(Authorization(b))| decode_password(b.token()))
+}
+
+/// Passwords are sent base64 encoded, so they can contain any characters
+pub fn decode_password(encoded: &str) -> String {
+    String::from_utf8(
+        general_purpose::STANDARD
+            .decode(encoded.trim())
+            .unwrap_or(vec![]),
+    )
+    .unwrap_or("".to_owned())
 }
 
 pub fn verify_password(person: &Person, raw: Option<String>) -> bool {
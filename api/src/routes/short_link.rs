@@ -0,0 +1,103 @@
+use axum::{
+    extract::{self, Path},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, LOCATION},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use common::Adaptor;
+
+use crate::{
+    config::config,
+    cors,
+    errors::ApiError,
+    routes::{badge::escape_xml, meta::branding},
+    State,
+};
+
+// Unfurls are fetched once per link, so it's fine if the count is a little out of date
+const SHORT_LINK_CACHE_CONTROL: &str = "public, max-age=300";
+
+#[utoipa::path(
+    get,
+    path = "/e/{event_id}",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    responses(
+        (status = 302, description = "Redirect to the event on the frontend", content_type = "text/html", body = String),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Redirect a short link to the event's page on the frontend, from `FRONTEND_URL`
+///
+/// The redirect has a small HTML page with Open Graph tags, so links shared from the API's
+/// domain unfurl with the event's name and how many people have responded in apps like Slack
+/// and Discord. Private events are described without either, as the link doesn't include the
+/// password.
+pub async fn get_short_link<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+) -> Result<Response, ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    // Unfurling a link isn't a visit, so the event is only peeked at, which includes expired
+    // events that haven't been cleaned up yet
+    let event = adaptor
+        .peek_event(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .filter(|event| event.expired_at.is_none())
+        .ok_or(ApiError::NotFound)?;
+    let url = format!("{}/{}", cors::frontend_url(&config().cors), event.id);
+    let site_name = branding().name;
+
+    let (title, description) = match event.password_hash {
+        Some(_) => (
+            "Private event".to_owned(),
+            "Enter the event's password to see it and add your availability".to_owned(),
+        ),
+        None => {
+            let responses = adaptor
+                .get_people(event_id)
+                .await
+                .map_err(ApiError::AdaptorError)?
+                .unwrap_or_default()
+                .iter()
+                .filter(|p| !p.availability.is_empty())
+                .count();
+            let title = match event.name.is_empty() {
+                true => format!("Event on {}", site_name),
+                false => event.name,
+            };
+            let description = match responses {
+                0 => "Nobody has responded yet, add your availability".to_owned(),
+                1 => "1 person has responded, add your availability".to_owned(),
+                n => format!("{} people have responded, add your availability", n),
+            };
+            (title, description)
+        }
+    };
+
+    let link = escape_xml(&url);
+    let title = escape_xml(&title);
+    let description = escape_xml(&description);
+    let site_name = escape_xml(&site_name);
+    let html = format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>{title}</title><meta name="description" content="{description}"><meta property="og:type" content="website"><meta property="og:site_name" content="{site_name}"><meta property="og:title" content="{title}"><meta property="og:description" content="{description}"><meta property="og:url" content="{link}"><meta name="twitter:card" content="summary"><meta http-equiv="refresh" content="0; url={link}"></head><body><a href="{link}">{title}</a></body></html>"#
+    );
+
+    Ok((
+        StatusCode::FOUND,
+        [
+            (LOCATION, url),
+            (CONTENT_TYPE, "text/html; charset=utf-8".to_owned()),
+            (CACHE_CONTROL, SHORT_LINK_CACHE_CONTROL.to_owned()),
+        ],
+        html,
+    )
+        .into_response())
+}
@@ -1,12 +1,22 @@
-use axum::{extract, Json};
-use common::Adaptor;
+use axum::{
+    extract::{self, Query},
+    Json,
+};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use common::{Adaptor, Stats};
 
 use crate::{
     errors::ApiError,
-    payloads::{ApiResult, StatsResponse},
+    payloads::{
+        ApiResult, StatsBucketResponse, StatsGranularity, StatsResponse, StatsTimeseriesParams,
+        StatsTimeseriesResponse,
+    },
     State,
 };
 
+// Furthest back the time series can go
+const MAX_TIMESERIES_DAYS: u32 = 365;
+
 #[utoipa::path(
     get,
     path = "/stats",
@@ -17,10 +27,89 @@ use crate::{
     tag = "info",
 )]
 /// Get current stats
+///
+/// Includes new events and people that haven't been written to storage yet.
 pub async fn get_stats<A: Adaptor>(extract::State(state): State<A>) -> ApiResult<StatsResponse, A> {
-    let adaptor = &state.lock().await.adaptor;
+    let adaptor = &state.adaptor;
 
     let stats = adaptor.get_stats().await.map_err(ApiError::AdaptorError)?;
+    let pending = state.stat_counters.pending();
+
+    Ok(Json(
+        Stats {
+            event_count: stats.event_count + pending.event_count,
+            person_count: stats.person_count + pending.person_count,
+        }
+        .into(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/timeseries",
+    params(StatsTimeseriesParams),
+    responses(
+        (status = 200, description = "Ok", body = StatsTimeseriesResponse),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "info",
+)]
+/// Get how many events and people were created each day or week
+///
+/// Days are in UTC. Events and people created in the last few seconds may not be counted yet,
+/// and nothing from before this was tracked is included.
+pub async fn get_stats_timeseries<A: Adaptor>(
+    extract::State(state): State<A>,
+    Query(params): Query<StatsTimeseriesParams>,
+) -> ApiResult<StatsTimeseriesResponse, A> {
+    let days = params.days.unwrap_or(30);
+    if !(1..=MAX_TIMESERIES_DAYS).contains(&days) {
+        return Err(ApiError::InvalidInput(format!(
+            "Days must be between 1 and {}",
+            MAX_TIMESERIES_DAYS
+        )));
+    }
+
+    let today = Utc::now().date_naive();
+    let first_day = today - Duration::days(days as i64 - 1);
+    let daily = state
+        .adaptor
+        .get_daily_stats(first_day)
+        .await
+        .map_err(ApiError::AdaptorError)?;
+
+    // Start with an empty bucket for every period, so gaps show up as zeroes
+    let bucket_start = |date: NaiveDate| match params.granularity {
+        StatsGranularity::Day => date,
+        StatsGranularity::Week => {
+            date - Duration::days(date.weekday().num_days_from_monday() as i64)
+        }
+    };
+    let mut buckets: Vec<StatsBucketResponse> = Vec::new();
+    let mut date = first_day;
+    while date <= today {
+        let start = bucket_start(date).to_string();
+        if buckets.last().is_none_or(|last| last.start != start) {
+            buckets.push(StatsBucketResponse {
+                start,
+                event_count: 0,
+                person_count: 0,
+            });
+        }
+        date += Duration::days(1);
+    }
+
+    for day in daily.into_iter().filter(|day| day.date <= today) {
+        let start = bucket_start(day.date).to_string();
+        if let Some(bucket) = buckets.iter_mut().find(|bucket| bucket.start == start) {
+            bucket.event_count += day.event_count;
+            bucket.person_count += day.person_count;
+        }
+    }
 
-    Ok(Json(stats.into()))
+    Ok(Json(StatsTimeseriesResponse {
+        granularity: params.granularity,
+        buckets,
+    }))
 }
@@ -0,0 +1,274 @@
+use axum::{
+    extract::{self, Path, Query},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{TimeZone, Utc};
+use common::{Adaptor, PeopleQuery, Person};
+
+use crate::{
+    chat::ChatNotification,
+    errors::ApiError,
+    payloads::{
+        ActivityKind, ApiResult, LiveUpdate, LiveUpdateKind, PersonResponse, SyncInput, SyncParams,
+        SyncResponse, SyncResultResponse,
+    },
+    routes::{
+        activity::record_activity,
+        event::get_authorized_event,
+        person::{close_responses_if_complete, verify_edit_token},
+    },
+    spam::{check_spam, SpamCheck},
+    validation::validate_availability,
+    State,
+};
+
+// Most changes that can be sent in one sync
+const MAX_SYNC_MUTATIONS: usize = 100;
+
+#[utoipa::path(
+    get,
+    path = "/event/{event_id}/sync",
+    params(
+        ("event_id", description = "The ID of the event"),
+        SyncParams,
+    ),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = SyncResponse),
+        (status = 401, description = "Missing or incorrect event password"),
+        (status = 404, description = "Event not found"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Get what's changed on an event since the last sync
+///
+/// For clients that keep a copy of the event offline. Versions are unix timestamps in
+/// milliseconds, taken from the latest change included.
+pub async fn get_sync<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    Query(params): Query<SyncParams>,
+    headers: HeaderMap,
+) -> ApiResult<SyncResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    let since = params
+        .since_version
+        .and_then(|version| Utc.timestamp_millis_opt(version).single());
+    let changed_since = |time| since.is_none_or(|since| time >= since);
+
+    let people = adaptor
+        .query_people(
+            event_id,
+            PeopleQuery {
+                updated_since: since,
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+    // Someone who was removed and then joined again only shows up as changed
+    let removed: Vec<_> = event
+        .removed_people
+        .iter()
+        .filter(|removed| changed_since(removed.removed_at))
+        .filter(|removed| {
+            !people
+                .iter()
+                .any(|p| p.name.to_lowercase() == removed.name.to_lowercase())
+        })
+        .collect();
+
+    let version = people
+        .iter()
+        .map(|p| p.updated_at)
+        .chain(removed.iter().map(|removed| removed.removed_at))
+        .chain(since)
+        .chain([event.updated_at])
+        .max()
+        .unwrap_or(event.updated_at)
+        .timestamp_millis();
+
+    Ok(Json(SyncResponse {
+        version,
+        removed: removed.iter().map(|removed| removed.name.clone()).collect(),
+        people: people.into_iter().map(|p| p.into()).collect(),
+        event: changed_since(event.updated_at).then(|| event.into()),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/event/{event_id}/sync",
+    params(
+        ("event_id", description = "The ID of the event"),
+    ),
+    request_body(content = SyncInput, description = "Changes made while offline"),
+    security((), ("password" = []), ("event-password" = [])),
+    responses(
+        (status = 200, description = "Ok", body = SyncResultResponse),
+        (status = 401, description = "Missing or incorrect event password, or edit token"),
+        (status = 403, description = "Rejected as spam"),
+        (status = 404, description = "Event or person not found"),
+        (status = 409, description = "Event has been finalized or closed to responses"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "event",
+)]
+/// Send availability changes made while offline
+///
+/// Each change replaces the person's availability, unless they were changed on the server
+/// after the client made its edit, in which case the server's copy wins and is returned as
+/// a conflict. People with a password need their edit token, and people can't join an
+/// event through a sync. Nothing is saved if any change is unauthorized or for someone who
+/// isn't on the event.
+pub async fn post_sync<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(event_id): Path<String>,
+    headers: HeaderMap,
+    Json(input): Json<SyncInput>,
+) -> ApiResult<SyncResultResponse, A> {
+    let adaptor = &state.adaptor;
+
+    let event = get_authorized_event(adaptor, event_id.clone(), &headers).await?;
+    if event.finalized_time.is_some() {
+        return Err(ApiError::Conflict("Event has been finalized".to_owned()));
+    }
+    if event.responses_closed {
+        return Err(ApiError::Conflict(
+            "Event is closed to responses".to_owned(),
+        ));
+    }
+    if input.mutations.len() > MAX_SYNC_MUTATIONS {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {} changes can be synced at once",
+            MAX_SYNC_MUTATIONS
+        )));
+    }
+
+    let people = adaptor
+        .get_people(event_id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    // Check every change before saving any of them
+    let mut mutations = Vec::new();
+    for mutation in input.mutations {
+        let person = people
+            .iter()
+            .find(|p| p.name.to_lowercase() == mutation.name.to_lowercase())
+            .ok_or(ApiError::NotFound)?;
+        if person.password_hash.is_some()
+            && !verify_edit_token(person, mutation.edit_token.as_deref())
+        {
+            return Err(ApiError::NotAuthorized);
+        }
+        validate_availability(
+            &event,
+            &mutation.availability,
+            mutation.if_needed.as_deref(),
+            mutation.undecided.as_deref(),
+        )?;
+        mutations.push((person, mutation));
+    }
+    mutations.sort_by_key(|(_, mutation)| mutation.edited_at);
+
+    let mut applied: Vec<Person> = Vec::new();
+    let mut conflicts: Vec<Person> = Vec::new();
+    for (existing_person, mutation) in mutations {
+        // Compare against the server's copy from before this sync, so earlier changes in
+        // the same batch don't count as conflicts
+        if existing_person.updated_at.timestamp_millis() > mutation.edited_at {
+            if !conflicts.iter().any(|p| p.name == existing_person.name) {
+                conflicts.push(existing_person.clone());
+            }
+            continue;
+        }
+
+        check_spam(
+            state.spam_filter.as_ref(),
+            SpamCheck::Person {
+                event_id: event_id.clone(),
+                name: existing_person.name.clone(),
+                availability: mutation.availability.clone(),
+            },
+        )
+        .await?;
+
+        // Only keep levels for times the person is actually available
+        let if_needed: Vec<String> = mutation
+            .if_needed
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|time| mutation.availability.contains(time))
+            .collect();
+        let undecided: Vec<String> = mutation
+            .undecided
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|time| !mutation.availability.contains(time))
+            .collect();
+
+        let person = adaptor
+            .upsert_person(
+                event_id.clone(),
+                Person {
+                    updated_at: Utc::now(),
+                    availability: mutation.availability,
+                    if_needed,
+                    undecided,
+                    ..existing_person.clone()
+                },
+            )
+            .await
+            .map_err(ApiError::AdaptorError)?
+            .ok_or(ApiError::NotFound)?;
+
+        // Earlier changes in the same batch count as the person having responded
+        let activity_kind = match existing_person.availability.is_empty()
+            && !applied.iter().any(|p| p.name == person.name)
+        {
+            true => ActivityKind::Responded,
+            false => ActivityKind::UpdatedAvailability,
+        };
+        record_activity(
+            adaptor,
+            event_id.clone(),
+            activity_kind,
+            Some(person.name.clone()),
+        )
+        .await;
+        if activity_kind == ActivityKind::Responded {
+            state
+                .chat
+                .send(&event, ChatNotification::Responded(&person.name));
+        }
+        let update = LiveUpdate::new(
+            event_id.clone(),
+            LiveUpdateKind::PersonUpdated,
+            person.clone(),
+        );
+        state.webhooks.send(&event, &update);
+        state.live.publish(update);
+
+        conflicts.retain(|p| p.name != person.name);
+        applied.retain(|p| p.name != person.name);
+        applied.push(person);
+    }
+
+    if let Some(person) = applied.last() {
+        close_responses_if_complete(&state, event, person).await?;
+    }
+
+    Ok(Json(SyncResultResponse {
+        applied: applied.into_iter().map(PersonResponse::from).collect(),
+        conflicts: conflicts.into_iter().map(PersonResponse::from).collect(),
+    }))
+}
@@ -1,51 +1,107 @@
-use std::env;
-
-use axum::{extract, http::HeaderMap};
-use chrono::{Duration, Utc};
+use axum::{
+    extract::{self, Query},
+    Json,
+};
 use common::Adaptor;
-use tracing::info;
 
-use crate::{errors::ApiError, State};
+use crate::{
+    cleanup,
+    errors::ApiError,
+    payloads::{ApiResult, CleanupParams, CleanupResponse, SeedParams, SeedResponse},
+    seed::{self, MAX_SEED_EVENTS},
+    State,
+};
+
+// Enough to look through without making it slow to respond
+const DEFAULT_SEED_EVENTS: usize = 10;
 
 #[utoipa::path(
     get,
     path = "/tasks/cleanup",
+    params(CleanupParams),
     responses(
-        (status = 200, description = "Cleanup complete"),
-        (status = 401, description = "Missing or incorrect X-Cron-Key header"),
+        (status = 200, description = "Cleanup complete, with what was removed", body = CleanupResponse),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 409, description = "Cleanup is already running"),
         (status = 429, description = "Too many requests"),
     ),
-    security((), ("cron-key" = [])),
+    security(("admin-key" = [])),
     tag = "tasks",
 )]
-/// Delete events older than 3 months
+/// Expire events that haven't been visited recently, and delete old expired events and
+/// idempotency keys
+///
+/// Events expire once they haven't been visited for their retention period, which is set by
+/// the `EVENT_RETENTION_DAYS` environment variable (90 days by default) unless the event
+/// chose its own. Expired events can't be viewed, but can be restored by an admin until
+/// they're deleted, `EVENT_GRACE_DAYS` (7 by default) after they expired.
+///
+/// With `dry_run=true`, the events that would be expired and deleted are listed instead,
+/// and nothing is changed. Dry runs can happen while a cleanup is running.
+///
+/// With `ARCHIVE_BUCKET` set, events are uploaded to that S3-compatible bucket as
+/// newline-delimited JSON, along with their people, before they're deleted. The response has
+/// the archive's key, which is also logged beside each event's ID. If the upload fails, the
+/// events are kept until the next cleanup.
+///
+/// The cleanup can also run in the background by setting `CLEANUP_INTERVAL_MINUTES`.
 pub async fn cleanup<A: Adaptor>(
     extract::State(state): State<A>,
-    headers: HeaderMap,
-) -> Result<(), ApiError<A>> {
-    // Check cron key
-    let cron_key_header: String = headers
-        .get("X-Cron-Key")
-        .map(|k| k.to_str().unwrap_or_default().into())
-        .unwrap_or_default();
-    let env_key = env::var("CRON_KEY").unwrap_or_default();
-    if !env_key.is_empty() && cron_key_header != env_key {
-        return Err(ApiError::NotAuthorized);
+    Query(params): Query<CleanupParams>,
+) -> ApiResult<CleanupResponse, A> {
+    if params.dry_run {
+        let preview = cleanup::preview(&state.adaptor)
+            .await
+            .map_err(ApiError::AdaptorError)?;
+        return Ok(Json(preview.into()));
     }
 
-    info!("Running cleanup task");
+    let report = cleanup::run(&state.adaptor, state.archive.as_ref())
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::Conflict("Cleanup is already running".to_owned()))?;
 
-    let adaptor = &state.lock().await.adaptor;
+    Ok(Json(report.into()))
+}
 
-    let result = adaptor
-        .delete_events(Utc::now() - Duration::days(90))
+#[utoipa::path(
+    post,
+    path = "/tasks/seed",
+    params(SeedParams),
+    responses(
+        (status = 200, description = "Events created, with their IDs and tokens", body = SeedResponse),
+        (status = 422, description = "Too many events requested"),
+        (status = 401, description = "Missing or incorrect X-Admin-Key header"),
+        (status = 404, description = "Seeding isn't allowed on this instance"),
+        (status = 429, description = "Too many requests"),
+    ),
+    security(("admin-key" = [])),
+    tag = "tasks",
+)]
+/// Create events with generated names, times and responses, for developing against and load
+/// testing
+///
+/// Only allowed in debug builds, or in release builds with `ALLOW_SEEDING=true`. Every event
+/// created in one call shares an edit token and a creator token, so they can all be edited
+/// and listed at `/me/events`. Seeded events don't count towards the stats.
+pub async fn seed<A: Adaptor>(
+    extract::State(state): State<A>,
+    Query(params): Query<SeedParams>,
+) -> ApiResult<SeedResponse, A> {
+    if !seed::enabled() {
+        return Err(ApiError::NotFound);
+    }
+    let count = params.count.unwrap_or(DEFAULT_SEED_EVENTS);
+    if count > MAX_SEED_EVENTS {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {} events can be seeded at once",
+            MAX_SEED_EVENTS
+        )));
+    }
+
+    let report = seed::seed(&state.adaptor, count)
         .await
         .map_err(ApiError::AdaptorError)?;
 
-    info!(
-        "Cleanup successful: {} events and {} people removed",
-        result.event_count, result.person_count
-    );
-
-    Ok(())
+    Ok(Json(report.into()))
 }
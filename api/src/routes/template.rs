@@ -0,0 +1,212 @@
+use axum::{
+    extract::{self, Path},
+    headers::{authorization::Bearer, Authorization},
+    http::StatusCode,
+    Extension, Json, TypedHeader,
+};
+use chrono::{Datelike, Duration, TimeZone, Utc};
+use chrono_tz::Tz;
+use common::{Adaptor, Template};
+
+use crate::{
+    auth::Identity,
+    errors::ApiError,
+    payloads::{EventInput, EventResponse, TemplateEventInput, TemplateInput, TemplateResponse},
+    routes::event::{generate_id, insert_event},
+    slots::{self, Slot},
+    tokens::{generate_token, hash_token, verify_token},
+    State,
+};
+
+// Longest a template's name can be, in characters
+const MAX_TEMPLATE_NAME_LENGTH: usize = 100;
+// Replaced in a template's name with the first day of each event's week
+const DATE_PLACEHOLDER: &str = "{date}";
+
+#[utoipa::path(
+    post,
+    path = "/templates",
+    request_body(content = TemplateInput, description = "The template to save"),
+    responses(
+        (status = 201, description = "Created", body = TemplateResponse),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "template",
+)]
+/// Save a template for an event that happens every week
+///
+/// The template's times use days of the week, and an event for a specific week can be
+/// created from it whenever it's needed. Templates that haven't been used for a while are
+/// deleted by the cleanup task.
+pub async fn create_template<A: Adaptor>(
+    extract::State(state): State<A>,
+    Json(input): Json<TemplateInput>,
+) -> Result<(StatusCode, Json<TemplateResponse>), ApiError<A>> {
+    let adaptor = &state.adaptor;
+
+    let name = input.name.trim().to_owned();
+    if name.is_empty() || name.chars().count() > MAX_TEMPLATE_NAME_LENGTH {
+        return Err(ApiError::InvalidInput(format!(
+            "Template names must be between 1 and {} characters",
+            MAX_TEMPLATE_NAME_LENGTH
+        )));
+    }
+    let times = slots::normalize(&input.times).map_err(ApiError::InvalidInput)?;
+    if !times
+        .iter()
+        .all(|time| matches!(time.parse::<Slot>(), Ok(Slot::Weekday(..))))
+    {
+        return Err(ApiError::InvalidInput(
+            "Every time in a template has to be a day of the week".to_owned(),
+        ));
+    }
+    if input.timezone.parse::<Tz>().is_err() {
+        return Err(ApiError::InvalidInput(format!(
+            "Unknown timezone \"{}\"",
+            input.timezone
+        )));
+    }
+
+    let now = Utc::now();
+    let edit_token = generate_token();
+
+    // Generate an ID that isn't taken by another template
+    let id_name = name.replace(DATE_PLACEHOLDER, "");
+    let mut id = generate_id(&id_name);
+    while (adaptor
+        .get_template(id.clone())
+        .await
+        .map_err(ApiError::AdaptorError)?)
+    .is_some()
+    {
+        id = generate_id(&id_name);
+    }
+
+    let template = adaptor
+        .create_template(Template {
+            id,
+            name,
+            times,
+            timezone: input.timezone,
+            edit_token_hash: hash_token(&edit_token),
+            created_at: now,
+            used_at: now,
+        })
+        .await
+        .map_err(ApiError::AdaptorError)?;
+
+    let mut response: TemplateResponse = template.into();
+    response.edit_token = Some(edit_token);
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/templates/{template_id}/events",
+    params(
+        ("template_id", description = "The ID of the template"),
+    ),
+    request_body(content = TemplateEventInput, description = "Which week to create the event for"),
+    security(("edit-token" = [])),
+    responses(
+        (status = 201, description = "Created", body = EventResponse),
+        (status = 401, description = "Missing or incorrect edit token"),
+        (status = 403, description = "Rejected as spam"),
+        (status = 404, description = "Not found"),
+        (status = 415, description = "Unsupported input format"),
+        (status = 422, description = "Invalid input provided"),
+        (status = 429, description = "Too many requests"),
+    ),
+    tag = "template",
+)]
+/// Create an event from a template
+///
+/// The template's days of the week are turned into the dates of the requested week, in the
+/// template's timezone. Requires the edit token returned when the template was created.
+pub async fn create_template_event<A: Adaptor>(
+    extract::State(state): State<A>,
+    Path(template_id): Path<String>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    identity: Option<Extension<Identity>>,
+    Json(input): Json<TemplateEventInput>,
+) -> Result<(StatusCode, Json<EventResponse>), ApiError<A>> {
+    let template = state
+        .adaptor
+        .get_template(template_id)
+        .await
+        .map_err(ApiError::AdaptorError)?
+        .ok_or(ApiError::NotFound)?;
+
+    let owns_template = match (&bearer, &template.edit_token_hash) {
+        (Some(TypedHeader(Authorization(bearer))), Some(hash)) => {
+            verify_token(bearer.token(), hash)
+        }
+        _ => false,
+    };
+    if !owns_template {
+        return Err(ApiError::NotAuthorized);
+    }
+
+    let tz: Tz = template.timezone.parse().unwrap_or(Tz::UTC);
+    let week_of = match input.week_of {
+        Some(timestamp) => {
+            Utc.timestamp_opt(timestamp, 0)
+                .single()
+                .ok_or(ApiError::InvalidInput(
+                    "Invalid week_of timestamp".to_owned(),
+                ))?
+        }
+        None => Utc::now(),
+    }
+    .with_timezone(&tz)
+    .date_naive();
+    let week_start = week_of - Duration::days(week_of.weekday().num_days_from_sunday() as i64);
+
+    let mut times = Vec::with_capacity(template.times.len());
+    for time in &template.times {
+        let Ok(slot) = time.parse::<Slot>() else {
+            continue;
+        };
+        // Slots are stored in UTC, so find the local day and time they stand for first
+        let local = slot.datetime_in(tz);
+        let date = week_start + Duration::days(local.weekday().num_days_from_sunday() as i64);
+        if let Some(datetime) = tz
+            .from_local_datetime(&date.and_time(local.time()))
+            .earliest()
+        {
+            times.push(Slot::Date(datetime.naive_utc()).to_string());
+        }
+    }
+
+    let name = template.name.replace(
+        DATE_PLACEHOLDER,
+        &week_start.format("%-d %b %Y").to_string(),
+    );
+    let response = insert_event(
+        &state,
+        identity.as_deref(),
+        EventInput {
+            name: Some(name),
+            times,
+            timezone: template.timezone,
+            scoring: None,
+            listed: None,
+            tags: None,
+            password: None,
+            invitees: None,
+            slug: None,
+            retention_days: None,
+            max_people: None,
+            group_id: None,
+            locale: None,
+            creator_token: None,
+            chat_webhook_url: None,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
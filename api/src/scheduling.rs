@@ -0,0 +1,170 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+
+use crate::slots::Slot;
+
+// Upper bound on search steps, so a large or unsatisfiable problem
+// returns the best assignment found so far instead of running forever
+const SEARCH_LIMIT: usize = 100_000;
+
+/// Someone being interviewed, and the times they picked
+pub struct Candidate {
+    pub name: String,
+    pub times: Vec<String>,
+}
+
+/// A group of interviewers, one of which has to be present at each interview
+pub struct Pool {
+    pub name: String,
+    pub interviewers: Vec<Interviewer>,
+}
+
+/// Someone conducting interviews, and the times they're free
+pub struct Interviewer {
+    pub name: String,
+    pub times: HashSet<String>,
+}
+
+#[derive(Clone)]
+pub struct Assignment {
+    pub candidate: String,
+    pub time: String,
+    /// Pairs of pool name and interviewer name
+    pub interviewers: Vec<(String, String)>,
+}
+
+/// Assign each candidate one of their picked times, with an interviewer from every pool.
+/// An interviewer can only attend one interview per time, and at most `max_per_day`
+/// interviews on each day (in the event's timezone). Candidates that can't be fit in are
+/// left out of the result.
+pub fn assign(
+    candidates: &[Candidate],
+    pools: &[Pool],
+    max_per_day: Option<usize>,
+    tz: Tz,
+) -> Vec<Assignment> {
+    // Try the most constrained candidates first
+    let mut ordered: Vec<&Candidate> = candidates.iter().collect();
+    ordered.sort_by_key(|c| c.times.len());
+
+    let mut solver = Solver {
+        candidates: ordered,
+        pools,
+        max_per_day: max_per_day.unwrap_or(usize::MAX),
+        tz,
+        booked: HashSet::new(),
+        load: HashMap::new(),
+        current: Vec::new(),
+        best: Vec::new(),
+        steps: 0,
+    };
+    solver.search(0);
+
+    solver.best
+}
+
+struct Solver<'a> {
+    candidates: Vec<&'a Candidate>,
+    pools: &'a [Pool],
+    max_per_day: usize,
+    tz: Tz,
+    /// Interviewer and time pairs that are already taken
+    booked: HashSet<(&'a str, &'a str)>,
+    /// Interviews per interviewer per day
+    load: HashMap<(&'a str, NaiveDate), usize>,
+    current: Vec<Assignment>,
+    best: Vec<Assignment>,
+    steps: usize,
+}
+
+impl<'a> Solver<'a> {
+    fn search(&mut self, index: usize) {
+        self.steps += 1;
+        if self.steps > SEARCH_LIMIT || self.best.len() == self.candidates.len() {
+            return;
+        }
+
+        // Stop if this branch can't beat the best assignment found so far
+        if self.current.len() + (self.candidates.len() - index) <= self.best.len() {
+            return;
+        }
+
+        if index == self.candidates.len() {
+            self.best = self.current.clone();
+            return;
+        }
+
+        let candidate = self.candidates[index];
+        for time in candidate.times.iter() {
+            if let Ok(slot) = time.parse::<Slot>() {
+                let day = slot.date_in(self.tz);
+                self.choose(index, time, day, &mut Vec::new());
+            }
+        }
+
+        // Try leaving this candidate out
+        self.search(index + 1);
+    }
+
+    // Pick an interviewer from each pool in turn, then move on to the next candidate
+    fn choose(
+        &mut self,
+        index: usize,
+        time: &'a str,
+        day: NaiveDate,
+        chosen: &mut Vec<(&'a str, &'a str)>,
+    ) {
+        let pools = self.pools;
+
+        let Some(pool) = pools.get(chosen.len()) else {
+            for (_, name) in chosen.iter() {
+                self.booked.insert((*name, time));
+                *self.load.entry((*name, day)).or_default() += 1;
+            }
+            self.current.push(Assignment {
+                candidate: self.candidates[index].name.clone(),
+                time: time.to_owned(),
+                interviewers: chosen
+                    .iter()
+                    .map(|(pool, name)| (pool.to_string(), name.to_string()))
+                    .collect(),
+            });
+
+            self.search(index + 1);
+
+            self.current.pop();
+            for (_, name) in chosen.iter() {
+                self.booked.remove(&(*name, time));
+                if let Some(load) = self.load.get_mut(&(*name, day)) {
+                    *load -= 1;
+                }
+            }
+            return;
+        };
+
+        // Prefer whoever has the fewest interviews that day
+        let mut options: Vec<&'a Interviewer> = pool
+            .interviewers
+            .iter()
+            .filter(|i| {
+                i.times.contains(time)
+                    && !self.booked.contains(&(i.name.as_str(), time))
+                    && self.load_on(&i.name, day) < self.max_per_day
+                    && !chosen.iter().any(|(_, name)| *name == i.name)
+            })
+            .collect();
+        options.sort_by_key(|i| self.load_on(&i.name, day));
+
+        for interviewer in options {
+            chosen.push((pool.name.as_str(), interviewer.name.as_str()));
+            self.choose(index, time, day, chosen);
+            chosen.pop();
+        }
+    }
+
+    fn load_on(&self, name: &'a str, day: NaiveDate) -> usize {
+        self.load.get(&(name, day)).copied().unwrap_or(0)
+    }
+}
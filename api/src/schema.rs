@@ -0,0 +1,58 @@
+use common::{Adaptor, SchemaVersion};
+use tracing::info;
+
+use crate::config::config;
+
+/// Get the adaptor's stored data ready to serve, migrating it unless `adaptor.auto_migrate`
+/// is `false`, then check it's at the schema version this build needs
+///
+/// Panics with how to fix it if the schema is behind, or is from a newer build, as serving
+/// requests against the wrong schema could lose data.
+pub async fn prepare<A: Adaptor>(adaptor: &A) {
+    let version = match auto_migrate() {
+        true => migrate(adaptor).await,
+        false => adaptor
+            .get_schema_version()
+            .await
+            .unwrap_or_else(|e| panic!("Failed to get the schema version: {}", e)),
+    };
+
+    if version.current > version.expected {
+        panic!(
+            "The stored data is at schema version {}, which is newer than this build supports (version {}). Upgrade the API, or restore a backup from before it was migrated.",
+            version.current, version.expected
+        );
+    }
+    if version.current < version.expected {
+        panic!(
+            "The stored data is at schema version {}, but this build needs version {}. Run `jellifit-api migrate`, or turn on auto_migrate to migrate on startup.",
+            version.current, version.expected
+        );
+    }
+}
+
+/// Bring the adaptor's schema up to date, for the `migrate` command or on startup
+pub async fn migrate<A: Adaptor>(adaptor: &A) -> SchemaVersion {
+    let before = adaptor
+        .get_schema_version()
+        .await
+        .unwrap_or_else(|e| panic!("Failed to get the schema version: {}", e));
+    let after = adaptor
+        .migrate()
+        .await
+        .unwrap_or_else(|e| panic!("Failed to migrate the stored data: {}", e));
+
+    if after.current != before.current {
+        info!(
+            "Migrated the stored data from schema version {} to {}",
+            before.current, after.current
+        );
+    }
+    after
+}
+
+// Deployments that migrate as a separate step, like a release job, can turn this off so
+// instances starting at the same time don't all try to migrate
+fn auto_migrate() -> bool {
+    config().adaptor.auto_migrate
+}
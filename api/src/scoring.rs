@@ -0,0 +1,118 @@
+use chrono::{Datelike, Timelike, Weekday};
+use chrono_tz::Tz;
+use common::{Event, Person};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::slots::{self, Slot, SlotAvailability};
+
+/// How an event's times are ranked when suggesting the best one
+#[derive(Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum Scoring {
+    /// Rank times by the number of people available
+    #[default]
+    MaximizeAttendance,
+    /// Only suggest times where at least `quorum` people are available
+    RequireQuorum { quorum: usize },
+    /// Rank by the number of people available, preferring mornings when tied
+    PreferMornings,
+    /// Score each time by adding up weights for the people available and the time of day
+    Custom {
+        #[serde(default = "default_attendance_weight")]
+        attendance: f64,
+        #[serde(default)]
+        morning: f64,
+        #[serde(default)]
+        afternoon: f64,
+        #[serde(default)]
+        evening: f64,
+        #[serde(default)]
+        weekend: f64,
+    },
+}
+
+fn default_attendance_weight() -> f64 {
+    1.0
+}
+
+/// A time of an event, with the score it was given
+pub struct ScoredSlot {
+    pub availability: SlotAvailability,
+    pub score: f64,
+}
+
+impl Scoring {
+    /// The scoring strategy picked for an event, or the default if it didn't pick one
+    pub fn of(event: &Event) -> Self {
+        event
+            .scoring
+            .as_ref()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default()
+    }
+
+    /// Rank an event's times from best to worst. Ties are broken by the number of people
+    /// available, then by the earliest slot. Times that don't meet a quorum are left out.
+    pub fn rank(&self, times: &[String], people: &[Person], tz: Tz) -> Vec<ScoredSlot> {
+        let mut scored: Vec<ScoredSlot> = slots::rank(times, people)
+            .into_iter()
+            .filter(|s| match self {
+                Scoring::RequireQuorum { quorum } => s.people.len() >= *quorum,
+                _ => true,
+            })
+            .map(|availability| ScoredSlot {
+                score: self.score(&availability, tz),
+                availability,
+            })
+            .collect();
+
+        // Stable, so slots with the same score keep the order from `slots::rank`
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        scored
+    }
+
+    fn score(&self, availability: &SlotAvailability, tz: Tz) -> f64 {
+        let count = availability.attendance();
+        let local = availability
+            .time
+            .parse::<Slot>()
+            .ok()
+            .map(|slot| slot.datetime_in(tz));
+
+        match self {
+            Scoring::MaximizeAttendance | Scoring::RequireQuorum { .. } => count,
+            // Less than a person who can only make it if needed, so a morning never
+            // beats a time more people can make
+            Scoring::PreferMornings => {
+                count
+                    + if local.is_some_and(|t| t.hour() < 12) {
+                        0.25
+                    } else {
+                        0.0
+                    }
+            }
+            Scoring::Custom {
+                attendance,
+                morning,
+                afternoon,
+                evening,
+                weekend,
+            } => {
+                let mut score = count * attendance;
+                if let Some(local) = local {
+                    score += match local.hour() {
+                        0..=11 => morning,
+                        12..=16 => afternoon,
+                        _ => evening,
+                    };
+                    if matches!(local.weekday(), Weekday::Sat | Weekday::Sun) {
+                        score += weekend;
+                    }
+                }
+                score
+            }
+        }
+    }
+}
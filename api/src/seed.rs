@@ -0,0 +1,214 @@
+use chrono::{Duration, NaiveTime, Utc};
+use common::{Adaptor, Event, Person};
+use rand::{rngs::ThreadRng, seq::SliceRandom, thread_rng, Rng};
+
+use crate::{
+    config::config,
+    names::generate_name,
+    routes::event::generate_id,
+    slots::{Slot, SLOT_MINUTES},
+    tokens::{generate_token, hash_token, lookup_hash},
+};
+
+/// Most events that can be seeded at once
+pub const MAX_SEED_EVENTS: usize = 1000;
+
+const PERSON_NAMES: [&str; 40] = [
+    "Ana", "Ben", "Chiara", "Dev", "Emeka", "Fatima", "Gus", "Hana", "Ines", "Jonah", "Kemal",
+    "Lena", "Mateo", "Nadia", "Oscar", "Priya", "Quinn", "Rosa", "Sami", "Tomás", "Uma", "Vik",
+    "Wren", "Xiu", "Yusuf", "Zoe", "Aroha", "Bilal", "Cleo", "Dara", "Elif", "Finn", "Gwen",
+    "Hiro", "Ivy", "Jae", "Kofi", "Lucía", "Mika", "Noor",
+];
+const TIMEZONES: [&str; 8] = [
+    "UTC",
+    "Europe/London",
+    "Europe/Berlin",
+    "America/New_York",
+    "America/Los_Angeles",
+    "Asia/Tokyo",
+    "Australia/Sydney",
+    "Pacific/Auckland",
+];
+const TAGS: [&str; 8] = [
+    "meetup",
+    "study",
+    "games",
+    "running",
+    "music",
+    "volunteering",
+    "book-club",
+    "coding",
+];
+const AVATAR_COLORS: [&str; 6] = [
+    "#f59e0b", "#10b981", "#3b82f6", "#8b5cf6", "#ec4899", "#ef4444",
+];
+
+/// The events stored by [`seed`], which all share one edit token and creator token so they
+/// can be edited and listed at `/me/events`
+pub struct SeedReport {
+    pub event_ids: Vec<String>,
+    pub person_count: usize,
+    pub edit_token: String,
+    pub creator_token: String,
+}
+
+/// Whether fake data can be seeded, which is always allowed in debug builds, but only with
+/// `admin.allow_seeding` in release builds, like for load testing a staging instance
+pub fn enabled() -> bool {
+    cfg!(debug_assertions) || config().admin.allow_seeding
+}
+
+/// Store events with generated names, times and people who've responded, for developing
+/// against and load testing without making them all by hand. They don't count towards
+/// the stats.
+pub async fn seed<A: Adaptor>(adaptor: &A, count: usize) -> Result<SeedReport, A::Error> {
+    // Hashing is slow on purpose, so it's only done once and every event shares the token
+    let edit_token = generate_token();
+    let edit_token_hash = hash_token(&edit_token);
+    let creator_token = generate_token();
+    let creator_id = lookup_hash(&creator_token);
+
+    let mut event_ids = Vec::with_capacity(count);
+    let mut person_count = 0;
+    for _ in 0..count {
+        let (event, people) = {
+            let mut rng = thread_rng();
+            let mut event = fake_event(&mut rng);
+            event.edit_token_hash = edit_token_hash.clone();
+            event.creator_id = Some(creator_id.clone());
+            let people = fake_people(&mut rng, &mut event);
+            (event, people)
+        };
+
+        let event = adaptor.create_event(event).await?;
+        for person in people {
+            adaptor.upsert_person(event.id.clone(), person).await?;
+            person_count += 1;
+        }
+        event_ids.push(event.id);
+    }
+
+    Ok(SeedReport {
+        event_ids,
+        person_count,
+        edit_token,
+        creator_token,
+    })
+}
+
+fn fake_event(rng: &mut ThreadRng) -> Event {
+    let now = Utc::now();
+    let name = generate_name(None);
+    let created_at = now - Duration::minutes(rng.gen_range(0..30 * 24 * 60));
+    let listed = rng.gen_bool(0.3);
+    let tag_count = if listed { rng.gen_range(1..=2) } else { 0 };
+
+    Event {
+        id: generate_id(&name),
+        name,
+        created_at,
+        visited_at: now,
+        updated_at: created_at,
+        times: fake_times(rng),
+        timezone: TIMEZONES.choose(rng).unwrap().to_string(),
+        edit_token_hash: None,
+        scoring: None,
+        listed,
+        tags: TAGS
+            .choose_multiple(rng, tag_count)
+            .map(|tag| tag.to_string())
+            .collect(),
+        finalized_time: None,
+        password_hash: None,
+        invitees: Vec::new(),
+        responses_closed: false,
+        webhook_url: None,
+        webhook_secret: None,
+        removed_people: Vec::new(),
+        retention_days: None,
+        expired_at: None,
+        group_id: None,
+        creator_id: None,
+        max_people: None,
+        chat_webhook_url: None,
+    }
+}
+
+// A block of the day on a few dates in the next couple of weeks, or on weekdays for a
+// recurring event
+fn fake_times(rng: &mut ThreadRng) -> Vec<String> {
+    let start_hour = rng.gen_range(7..=14);
+    let slot_count = rng.gen_range(2..=8) * (60 / SLOT_MINUTES);
+    let start = NaiveTime::from_hms_opt(start_hour, 0, 0).unwrap();
+
+    let days: Vec<Slot> = match rng.gen_bool(0.2) {
+        true => (1..=5).map(|day| Slot::Weekday(day, start)).collect(),
+        false => {
+            let first = Utc::now().date_naive() + Duration::days(rng.gen_range(1..=14));
+            (0..rng.gen_range(1..=5))
+                .map(|offset| Slot::Date((first + Duration::days(offset)).and_time(start)))
+                .collect()
+        }
+    };
+
+    days.into_iter()
+        .flat_map(|day| (0..slot_count).map(move |i| day.add_minutes(i * SLOT_MINUTES)))
+        .map(|slot| slot.to_string())
+        .collect()
+}
+
+// Everyone is available for a run of the event's times, some only if needed, and a few
+// events are capped or invite the people who responded
+fn fake_people(rng: &mut ThreadRng, event: &mut Event) -> Vec<Person> {
+    let count = rng.gen_range(0..=12);
+    let names: Vec<&str> = PERSON_NAMES.choose_multiple(rng, count).copied().collect();
+
+    let people: Vec<Person> = names
+        .iter()
+        .map(|name| {
+            let start = rng.gen_range(0..event.times.len());
+            let end = rng.gen_range(start..=event.times.len());
+            let (availability, if_needed) = event.times[start..end]
+                .iter()
+                .cloned()
+                .partition(|_| rng.gen_bool(0.8));
+            let responded_at = event.created_at
+                + Duration::minutes(
+                    rng.gen_range(0..=(Utc::now() - event.created_at).num_minutes().max(0)),
+                );
+
+            Person {
+                name: name.to_string(),
+                password_hash: None,
+                created_at: responded_at,
+                updated_at: responded_at,
+                availability,
+                if_needed,
+                undecided: Vec::new(),
+                edit_token_hash: None,
+                edit_token_expires_at: None,
+                subject_id: None,
+                avatar_color: rng
+                    .gen_bool(0.5)
+                    .then(|| AVATAR_COLORS.choose(rng).unwrap().to_string()),
+                avatar_emoji: None,
+                timezone: Some(event.timezone.clone()),
+                google_refresh_token: None,
+            }
+        })
+        .collect();
+
+    if rng.gen_bool(0.1) {
+        event.max_people = Some(people.len() as i64 + rng.gen_range(0..=4));
+    } else if rng.gen_bool(0.15) {
+        // Someone invited hasn't responded yet, so responses stay open
+        let missing = PERSON_NAMES.iter().find(|name| !names.contains(name));
+        event.invitees = names
+            .iter()
+            .chain(missing)
+            .map(|name| name.to_string())
+            .collect();
+    }
+
+    people
+}
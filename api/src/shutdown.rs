@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+};
+
+use crate::config::config;
+
+/// Tells the servers to stop once the process gets a SIGTERM or Ctrl+C, and how long they get
+/// to finish the requests they're in the middle of, from `server.shutdown_timeout_secs`
+#[derive(Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+    pub timeout: Duration,
+}
+
+impl Shutdown {
+    pub fn listen() -> Self {
+        let timeout = Duration::from_secs(config().server.shutdown_timeout_secs);
+
+        let (sender, receiver) = watch::channel(false);
+        tokio::spawn(async move {
+            let mut terminate =
+                signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+            tokio::select! {
+                result = tokio::signal::ctrl_c() => result.expect("Failed to install Ctrl+C handler"),
+                _ = terminate.recv() => {}
+            }
+            tracing::info!(
+                "Shutting down, waiting up to {:?} for requests to finish",
+                timeout
+            );
+            let _ = sender.send(true);
+        });
+
+        Self { receiver, timeout }
+    }
+
+    /// Resolves once shutdown has been requested
+    pub async fn signalled(mut self) {
+        // An error means the signal task is gone, which only happens after it's sent
+        let _ = self.receiver.wait_for(|stopping| *stopping).await;
+    }
+
+    /// Resolves once requests have had `timeout` to finish after shutdown was requested, so
+    /// long-lived connections like live update streams don't hold up the exit forever
+    pub async fn deadline(self) {
+        let timeout = self.timeout;
+        self.signalled().await;
+        tokio::time::sleep(timeout).await;
+        tracing::warn!("Gave up waiting for requests to finish");
+    }
+}
@@ -0,0 +1,209 @@
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
+};
+use chrono_tz::Tz;
+use common::Person;
+
+/// How long each of an event's times lasts
+pub const SLOT_MINUTES: i64 = 15;
+
+/// A candidate time of an event, parsed from the UTC `HHmm-DDMMYYYY` (specific dates)
+/// or `HHmm-d` (days of the week, where 0 is Sunday) strings stored in `Event::times`.
+/// An event can have both kinds of times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Slot {
+    Date(NaiveDateTime),
+    Weekday(u32, NaiveTime),
+}
+
+impl FromStr for Slot {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (time, date) = s.split_once('-').ok_or(())?;
+        if time.len() != 4 {
+            return Err(());
+        }
+        let time = NaiveTime::parse_from_str(time, "%H%M").map_err(|_| ())?;
+
+        match date.len() {
+            8 => NaiveDate::parse_from_str(date, "%d%m%Y")
+                .map(|date| Slot::Date(date.and_time(time)))
+                .map_err(|_| ()),
+            1 => match date.parse::<u32>() {
+                Ok(day) if day < 7 => Ok(Slot::Weekday(day, time)),
+                _ => Err(()),
+            },
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Slot::Date(datetime) => write!(f, "{}", datetime.format("%H%M-%d%m%Y")),
+            Slot::Weekday(day, time) => write!(f, "{}-{}", time.format("%H%M"), day),
+        }
+    }
+}
+
+impl Slot {
+    /// Human readable representation of this slot in a specific timezone, e.g. "Tue 14:00"
+    pub fn format_in(&self, tz: Tz) -> String {
+        let local = self.datetime_in(tz);
+        match self {
+            Slot::Date(_) => local.format("%a %-d %b %H:%M").to_string(),
+            Slot::Weekday(..) => local.format("%a %H:%M").to_string(),
+        }
+    }
+
+    /// The local date this slot falls on in a specific timezone
+    pub fn date_in(&self, tz: Tz) -> NaiveDate {
+        self.datetime_in(tz).date_naive()
+    }
+
+    /// The local date and time of this slot in a specific timezone
+    pub fn datetime_in(&self, tz: Tz) -> DateTime<Tz> {
+        self.datetime().with_timezone(&tz)
+    }
+
+    /// The slot a number of minutes after this one, weekday slots wrap around the end of the week
+    pub fn add_minutes(&self, minutes: i64) -> Slot {
+        match self {
+            Slot::Date(datetime) => Slot::Date(*datetime + Duration::minutes(minutes)),
+            Slot::Weekday(day, time) => {
+                let minute_of_week = (*day as i64 * MINUTES_PER_DAY
+                    + time.num_seconds_from_midnight() as i64 / 60
+                    + minutes)
+                    .rem_euclid(7 * MINUTES_PER_DAY);
+                let minute_of_day = (minute_of_week % MINUTES_PER_DAY) as u32;
+                Slot::Weekday(
+                    (minute_of_week / MINUTES_PER_DAY) as u32,
+                    NaiveTime::from_hms_opt(minute_of_day / 60, minute_of_day % 60, 0).unwrap(),
+                )
+            }
+        }
+    }
+
+    /// When this slot starts, weekday slots are resolved to the current week
+    pub fn datetime(&self) -> DateTime<Utc> {
+        match self {
+            Slot::Date(datetime) => Utc.from_utc_datetime(datetime),
+            Slot::Weekday(day, time) => {
+                Utc.from_utc_datetime(&weekday_reference(*day).and_time(*time))
+            }
+        }
+    }
+}
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+/// Check an event's times are all valid slots that start on a multiple of
+/// [`SLOT_MINUTES`], returning them in their canonical form with duplicates removed
+pub fn normalize(times: &[String]) -> Result<Vec<String>, String> {
+    if times.is_empty() {
+        return Err("Events need at least one time".to_owned());
+    }
+
+    let mut seen = HashSet::new();
+    let mut normalized = Vec::with_capacity(times.len());
+    for time in times {
+        let slot = time
+            .trim()
+            .parse::<Slot>()
+            .ok()
+            .filter(|slot| {
+                let minute = match slot {
+                    Slot::Date(datetime) => datetime.minute(),
+                    Slot::Weekday(_, time) => time.minute(),
+                };
+                minute as i64 % SLOT_MINUTES == 0
+            })
+            .ok_or(format!(
+                "Invalid time \"{}\", times have to look like HHmm-DDMMYYYY or HHmm-d and start on a multiple of {} minutes",
+                time, SLOT_MINUTES
+            ))?;
+        if seen.insert(slot) {
+            normalized.push(slot.to_string());
+        }
+    }
+
+    Ok(normalized)
+}
+
+// Weekday slots aren't tied to a date, so use the current week to resolve
+// them, matching how the frontend displays them
+fn weekday_reference(day: u32) -> NaiveDate {
+    let today = Utc::now().date_naive();
+    let days_from_sunday = today.weekday().num_days_from_sunday();
+    today - Duration::days(days_from_sunday as i64) + Duration::days(day as i64)
+}
+
+/// How much someone who can only make a time if needed counts towards it,
+/// compared to someone who is available
+pub const IF_NEEDED_WEIGHT: f64 = 0.5;
+
+/// The people available at one of an event's times
+pub struct SlotAvailability {
+    pub time: String,
+    pub people: Vec<String>,
+    /// The people in `people` who can only make it if needed
+    pub if_needed: Vec<String>,
+    /// The people who haven't decided whether they can make it
+    pub undecided: Vec<String>,
+}
+
+impl SlotAvailability {
+    /// The number of people available, with those who can only make it if needed counting for less
+    pub fn attendance(&self) -> f64 {
+        (self.people.len() - self.if_needed.len()) as f64
+            + self.if_needed.len() as f64 * IF_NEEDED_WEIGHT
+    }
+}
+
+/// Rank an event's times by the number of people available, weighted by whether they can
+/// only make it if needed, with ties broken by the earliest slot
+pub fn rank(times: &[String], people: &[Person]) -> Vec<SlotAvailability> {
+    let mut ranked: Vec<SlotAvailability> = times
+        .iter()
+        .map(|time| {
+            let available: Vec<&Person> = people
+                .iter()
+                .filter(|p| p.availability.contains(time))
+                .collect();
+            SlotAvailability {
+                time: time.clone(),
+                people: available.iter().map(|p| p.name.clone()).collect(),
+                if_needed: available
+                    .iter()
+                    .filter(|p| p.if_needed.contains(time))
+                    .map(|p| p.name.clone())
+                    .collect(),
+                undecided: people
+                    .iter()
+                    .filter(|p| p.undecided.contains(time))
+                    .map(|p| p.name.clone())
+                    .collect(),
+            }
+        })
+        .collect();
+
+    // Unparseable times sort after all valid ones
+    ranked.sort_by(|a, b| {
+        b.attendance()
+            .total_cmp(&a.attendance())
+            .then_with(|| slot_order(a).cmp(&slot_order(b)))
+    });
+
+    ranked
+}
+
+fn slot_order(availability: &SlotAvailability) -> Result<Slot, String> {
+    availability
+        .time
+        .parse::<Slot>()
+        .map_err(|_| availability.time.clone())
+}
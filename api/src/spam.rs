@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::http::{header::CONTENT_TYPE, Request};
+use common::Adaptor;
+use hyper::{body, client::HttpConnector, Body, Client};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SpamConfig, errors::ApiError};
+
+// How long to wait for a scoring service before letting the content through
+const SCORER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Content submitted by a user that should be checked for spam
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpamCheck {
+    Event {
+        name: String,
+    },
+    Person {
+        event_id: String,
+        name: String,
+        availability: Vec<String>,
+    },
+    Comment {
+        event_id: String,
+        author: String,
+        body: String,
+    },
+}
+
+/// A service that scores content from 0 (definitely not spam) to 1 (definitely spam)
+#[async_trait]
+pub trait SpamScorer: Send + Sync {
+    async fn score(&self, check: &SpamCheck) -> Result<f64, String>;
+}
+
+/// Sends content to an external service as JSON, and expects a `{ "score": number }` response
+pub struct HttpScorer {
+    url: String,
+    client: Client<HttpConnector>,
+}
+
+#[derive(Deserialize)]
+struct ScoreResponse {
+    score: f64,
+}
+
+#[async_trait]
+impl SpamScorer for HttpScorer {
+    async fn score(&self, check: &SpamCheck) -> Result<f64, String> {
+        let request = Request::post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::to_vec(check).map_err(|e| e.to_string())?,
+            ))
+            .map_err(|e| e.to_string())?;
+
+        let response = tokio::time::timeout(SCORER_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| "Timed out".to_owned())?
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Responded with {}", response.status()));
+        }
+
+        let bytes = body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| e.to_string())?;
+        let ScoreResponse { score } = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+
+        Ok(score)
+    }
+}
+
+pub struct SpamFilter {
+    scorer: Box<dyn SpamScorer>,
+    threshold: f64,
+}
+
+impl SpamFilter {
+    /// Set up a filter if there's a scorer URL, content scoring above the threshold
+    /// (defaults to 0.9) is rejected
+    pub fn from_config(config: &SpamConfig) -> Option<Self> {
+        let url = config.scorer_url.clone()?;
+
+        Some(Self::new(
+            Box::new(HttpScorer {
+                url,
+                client: Client::new(),
+            }),
+            config.threshold,
+        ))
+    }
+
+    pub fn new(scorer: Box<dyn SpamScorer>, threshold: f64) -> Self {
+        Self { scorer, threshold }
+    }
+
+    /// Whether the content should be rejected. If the scorer fails, the content is
+    /// allowed so an outage of the scoring service doesn't take down the API.
+    pub async fn is_spam(&self, check: SpamCheck) -> bool {
+        match self.scorer.score(&check).await {
+            Ok(score) => score > self.threshold,
+            Err(e) => {
+                tracing::warn!("Spam scorer failed: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// Reject content that the filter considers spam, if a filter is configured
+pub async fn check_spam<A: Adaptor>(
+    filter: Option<&SpamFilter>,
+    check: SpamCheck,
+) -> Result<(), ApiError<A>> {
+    match filter {
+        Some(filter) if filter.is_spam(check).await => Err(ApiError::Spam),
+        _ => Ok(()),
+    }
+}
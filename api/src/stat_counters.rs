@@ -0,0 +1,72 @@
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::Duration,
+};
+
+use chrono::Utc;
+use common::{Adaptor, Stats};
+
+use crate::{
+    jobs::{Job, Jobs},
+    AppState,
+};
+
+// How often buffered increments are written to the adaptor
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Counts new events and people in memory, so a burst of signups doesn't
+/// turn into a burst of writes to the same stats row
+#[derive(Default)]
+pub struct StatCounters {
+    events: AtomicI64,
+    people: AtomicI64,
+}
+
+impl StatCounters {
+    pub fn increment_events(&self) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn increment_people(&self) {
+        self.people.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Increments that haven't been written to the adaptor yet
+    pub fn pending(&self) -> Stats {
+        Stats {
+            event_count: self.events.load(Ordering::Relaxed),
+            person_count: self.people.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Queue any buffered increments to be written to the totals and today's counts as
+    /// separate jobs, so each is retried on its own, keeping them for the next flush if the
+    /// queue is full
+    pub fn flush(&self, jobs: &Jobs) {
+        let pending = Stats {
+            event_count: self.events.swap(0, Ordering::Relaxed),
+            person_count: self.people.swap(0, Ordering::Relaxed),
+        };
+        if pending.event_count == 0 && pending.person_count == 0 {
+            return;
+        }
+
+        if !jobs.push(Job::AddStats(pending.clone())) {
+            self.events
+                .fetch_add(pending.event_count, Ordering::Relaxed);
+            self.people
+                .fetch_add(pending.person_count, Ordering::Relaxed);
+            return;
+        }
+        jobs.push(Job::AddDailyStats(Utc::now().date_naive(), pending));
+    }
+}
+
+/// Flush buffered stats on an interval, for as long as the API is running
+pub async fn flush_periodically<A: Adaptor>(state: AppState<A>) {
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        state.stat_counters.flush(&state.jobs);
+    }
+}
@@ -0,0 +1,66 @@
+use std::{path::PathBuf, time::Duration, time::SystemTime};
+
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::config::ServerConfig;
+
+// How often to check whether the certificate or key has been replaced on disk
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Paths to a PEM certificate chain and private key to serve HTTPS with, from
+/// `server.tls_cert_path` and `server.tls_key_path`. The server uses plain HTTP if neither
+/// is set.
+pub struct TlsPaths {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+impl TlsPaths {
+    /// The config has already checked they're set together
+    pub fn from_config(config: &ServerConfig) -> Option<Self> {
+        Some(Self {
+            cert: config.tls_cert_path.clone()?,
+            key: config.tls_key_path.clone()?,
+        })
+    }
+
+    pub async fn load(&self) -> RustlsConfig {
+        RustlsConfig::from_pem_file(&self.cert, &self.key)
+            .await
+            .expect("Failed to load TLS certificate and key")
+    }
+
+    // Either file changing means the pair should be reloaded, renewals usually replace both
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Some((modified(&self.cert)?, modified(&self.key)?))
+    }
+}
+
+/// Reload the certificate and key whenever the process gets a SIGHUP or either file changes,
+/// so renewed certificates are picked up without a restart. New connections use the new
+/// certificate, and if it can't be loaded the old one is kept.
+pub async fn reload_on_change(config: RustlsConfig, paths: TlsPaths) {
+    let mut hangup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+    let mut interval = tokio::time::interval(RELOAD_CHECK_INTERVAL);
+    let mut last_modified = paths.modified();
+
+    loop {
+        tokio::select! {
+            _ = hangup.recv() => {}
+            _ = interval.tick() => {
+                let modified = paths.modified();
+                if modified.is_none() || modified == last_modified {
+                    continue;
+                }
+            }
+        }
+
+        last_modified = paths.modified();
+        match config.reload_from_pem_file(&paths.cert, &paths.key).await {
+            Ok(()) => tracing::info!("Reloaded TLS certificate"),
+            Err(e) => tracing::error!("Failed to reload TLS certificate: {}", e),
+        }
+    }
+}
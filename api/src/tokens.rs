@@ -0,0 +1,26 @@
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+/// Generate a random token that can be used instead of a password
+pub fn generate_token() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Hash a token so it can be stored
+pub fn hash_token(token: &str) -> Option<String> {
+    bcrypt::hash(token, 10).ok()
+}
+
+pub fn verify_token(token: &str, hash: &str) -> bool {
+    bcrypt::verify(token.trim(), hash).unwrap_or(false)
+}
+
+/// Hash a token so it can be stored and looked up, unlike [`hash_token`] which is salted.
+/// Only for generated tokens, which are too long to guess without a slow hash.
+pub fn lookup_hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.trim().as_bytes()))
+}
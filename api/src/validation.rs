@@ -0,0 +1,325 @@
+use std::collections::HashSet;
+
+use chrono_tz::Tz;
+use common::{Adaptor, Event};
+
+use crate::{
+    chat::{self, ChatPlatform},
+    cleanup::MAX_EVENT_RETENTION_DAYS,
+    errors::ApiError,
+    payloads::{EventInput, EventUpdateInput, FieldErrorResponse, PersonInput},
+    slots,
+};
+
+pub const MAX_EVENT_NAME_LENGTH: usize = 100;
+pub const MAX_PERSON_NAME_LENGTH: usize = 64;
+// Far more than anyone selects in the frontend, but stops events being used to store junk
+const MAX_EVENT_TIMES: usize = 10_000;
+// Most people that can be invited to an event
+const MAX_INVITEES: usize = 100;
+// Enough for a family or flag, which are several characters joined together
+const MAX_AVATAR_EMOJI_CHARS: usize = 8;
+// As long as generated tokens, so they can't be guessed
+const MIN_CREATOR_TOKEN_LENGTH: usize = 32;
+
+/// Collects everything wrong with an input, so it can all be reported at once with the
+/// field it's about, instead of only the first problem
+#[derive(Default)]
+struct Validator {
+    errors: Vec<FieldErrorResponse>,
+}
+
+impl Validator {
+    fn error(&mut self, field: &str, message: String) {
+        self.errors.push(FieldErrorResponse {
+            field: field.to_owned(),
+            message,
+        });
+    }
+
+    fn check(&mut self, valid: bool, field: &str, message: impl FnOnce() -> String) {
+        if !valid {
+            self.error(field, message());
+        }
+    }
+
+    fn finish<A: Adaptor>(self) -> Result<(), ApiError<A>> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(ApiError::InvalidFields(self.errors)),
+        }
+    }
+}
+
+/// Check a new event before anything is stored, names and times are normalized afterwards
+pub fn validate_event<A: Adaptor>(input: &EventInput) -> Result<(), ApiError<A>> {
+    let mut v = Validator::default();
+
+    if let Some(name) = &input.name {
+        v.check(
+            name.trim().chars().count() <= MAX_EVENT_NAME_LENGTH,
+            "name",
+            || {
+                format!(
+                    "Event names can be at most {} characters",
+                    MAX_EVENT_NAME_LENGTH
+                )
+            },
+        );
+    }
+
+    v.check(input.times.len() <= MAX_EVENT_TIMES, "times", || {
+        format!("Events can have at most {} times", MAX_EVENT_TIMES)
+    });
+    if input.times.len() <= MAX_EVENT_TIMES {
+        if let Err(message) = slots::normalize(&input.times) {
+            v.error("times", message);
+        }
+    }
+
+    v.check(input.timezone.parse::<Tz>().is_ok(), "timezone", || {
+        format!(
+            "Unknown timezone \"{}\", it has to be from the IANA database, like Europe/London",
+            input.timezone
+        )
+    });
+
+    let invitees = input.invitees.as_deref().unwrap_or_default();
+    v.check(invitees.len() <= MAX_INVITEES, "invitees", || {
+        format!("Events can have at most {} invitees", MAX_INVITEES)
+    });
+    v.check(
+        invitees
+            .iter()
+            .all(|name| name.trim().chars().count() <= MAX_PERSON_NAME_LENGTH),
+        "invitees",
+        || format!("Names can be at most {} characters", MAX_PERSON_NAME_LENGTH),
+    );
+
+    if let Some(max_people) = input.max_people {
+        v.check(max_people >= 1, "max_people", || {
+            "Events have to allow at least 1 person".to_owned()
+        });
+        v.check(
+            max_people < 1 || max_people >= invitees.len() as i64,
+            "max_people",
+            || "Events have to allow at least as many people as are invited".to_owned(),
+        );
+    }
+
+    v.check(
+        !(input.listed == Some(true) && input.password.as_ref().is_some_and(|p| !p.is_empty())),
+        "listed",
+        || "Private events can't be listed in the directory".to_owned(),
+    );
+
+    v.check(
+        input.creator_token.as_ref().is_none_or(|token| {
+            let token = token.trim();
+            token.len() >= MIN_CREATOR_TOKEN_LENGTH
+                && token.chars().all(|c| c.is_ascii_alphanumeric())
+        }),
+        "creator_token",
+        || {
+            format!(
+                "Creator tokens have to be at least {} letters and numbers, like the ones returned with events",
+                MIN_CREATOR_TOKEN_LENGTH
+            )
+        },
+    );
+
+    v.check(
+        input
+            .retention_days
+            .is_none_or(|days| (1..=MAX_EVENT_RETENTION_DAYS).contains(&days)),
+        "retention_days",
+        || {
+            format!(
+                "Retention must be between 1 and {} days",
+                MAX_EVENT_RETENTION_DAYS
+            )
+        },
+    );
+
+    v.check(
+        input
+            .chat_webhook_url
+            .as_ref()
+            .is_none_or(|url| ChatPlatform::from_url(url.trim()).is_some()),
+        "chat_webhook_url",
+        || chat::INVALID_URL_MESSAGE.to_owned(),
+    );
+
+    v.finish()
+}
+
+/// Check changes to an existing event, which has to stay consistent with what isn't changing
+pub fn validate_event_update<A: Adaptor>(
+    input: &EventUpdateInput,
+    event: &Event,
+) -> Result<(), ApiError<A>> {
+    let mut v = Validator::default();
+
+    if let Some(name) = &input.name {
+        let length = name.trim().chars().count();
+        v.check(
+            length > 0 && length <= MAX_EVENT_NAME_LENGTH,
+            "name",
+            || {
+                format!(
+                    "Event names must be between 1 and {} characters",
+                    MAX_EVENT_NAME_LENGTH
+                )
+            },
+        );
+    }
+
+    if let Some(max_people) = input.max_people.filter(|max| *max != 0) {
+        v.check(max_people >= 1, "max_people", || {
+            "Events have to allow at least 1 person".to_owned()
+        });
+        v.check(
+            max_people < 1 || max_people >= event.invitees.len() as i64,
+            "max_people",
+            || "Events have to allow at least as many people as are invited".to_owned(),
+        );
+    }
+
+    v.check(
+        input
+            .retention_days
+            .is_none_or(|days| (0..=MAX_EVENT_RETENTION_DAYS).contains(&days)),
+        "retention_days",
+        || {
+            format!(
+                "Retention must be between 1 and {} days, or 0 for the default",
+                MAX_EVENT_RETENTION_DAYS
+            )
+        },
+    );
+
+    v.check(
+        !(input.listed == Some(true) && event.password_hash.is_some()),
+        "listed",
+        || "Private events can't be listed in the directory".to_owned(),
+    );
+
+    v.finish()
+}
+
+/// Check the name of someone joining an event
+pub fn validate_person_name<A: Adaptor>(name: &str) -> Result<(), ApiError<A>> {
+    let mut v = Validator::default();
+    let length = name.trim().chars().count();
+    v.check(
+        length > 0 && length <= MAX_PERSON_NAME_LENGTH,
+        "name",
+        || {
+            format!(
+                "Names must be between 1 and {} characters",
+                MAX_PERSON_NAME_LENGTH
+            )
+        },
+    );
+    v.finish()
+}
+
+/// Check a person's availability only has times the event actually has, and their
+/// avatar and timezone are something that can be shown
+pub fn validate_person<A: Adaptor>(input: &PersonInput, event: &Event) -> Result<(), ApiError<A>> {
+    let mut v = Validator::default();
+    check_availability(
+        &mut v,
+        event,
+        &input.availability,
+        input.if_needed.as_deref(),
+        input.undecided.as_deref(),
+    );
+
+    // Empty strings clear them
+    if let Some(color) = input.avatar_color.as_deref().map(str::trim) {
+        v.check(
+            color.is_empty() || is_hex_color(color),
+            "avatar_color",
+            || "Avatar colors have to be a hex color like #ff8800".to_owned(),
+        );
+    }
+    if let Some(emoji) = input.avatar_emoji.as_deref().map(str::trim) {
+        v.check(emoji.is_empty() || is_emoji(emoji), "avatar_emoji", || {
+            "Avatars can only be a single emoji".to_owned()
+        });
+    }
+    if let Some(timezone) = input.timezone.as_deref().map(str::trim) {
+        v.check(
+            timezone.is_empty() || timezone.parse::<Tz>().is_ok(),
+            "timezone",
+            || {
+                format!(
+                    "Unknown timezone \"{}\", it has to be from the IANA database, like Europe/London",
+                    timezone
+                )
+            },
+        );
+    }
+
+    v.finish()
+}
+
+/// Like [`validate_person`], for routes with availability in another shape
+pub fn validate_availability<A: Adaptor>(
+    event: &Event,
+    availability: &[String],
+    if_needed: Option<&[String]>,
+    undecided: Option<&[String]>,
+) -> Result<(), ApiError<A>> {
+    let mut v = Validator::default();
+    check_availability(&mut v, event, availability, if_needed, undecided);
+    v.finish()
+}
+
+fn check_availability(
+    v: &mut Validator,
+    event: &Event,
+    availability: &[String],
+    if_needed: Option<&[String]>,
+    undecided: Option<&[String]>,
+) {
+    let times: HashSet<&str> = event.times.iter().map(String::as_str).collect();
+    let fields = [
+        ("availability", Some(availability)),
+        ("if_needed", if_needed),
+        ("undecided", undecided),
+    ];
+
+    for (field, list) in fields {
+        let Some(list) = list else { continue };
+        if let Some(time) = list.iter().find(|time| !times.contains(time.as_str())) {
+            v.error(
+                field,
+                format!("\"{}\" isn't one of the event's times", time),
+            );
+        }
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Not a full check, as emoji sequences change every year, but enough to keep out text
+fn is_emoji(emoji: &str) -> bool {
+    emoji.chars().count() <= MAX_AVATAR_EMOJI_CHARS
+        && !emoji
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_ascii_alphabetic())
+        && !emoji.is_ascii()
+}
+
+/// The field errors as one line, for APIs that can only return a message
+pub fn summary(errors: &[FieldErrorResponse]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,44 @@
+use std::net::IpAddr;
+
+use sha2::{Digest, Sha256};
+
+// Registers in each event's HyperLogLog sketch, giving estimates within about 6.5%
+const REGISTERS: usize = 256;
+const INDEX_BITS: u32 = REGISTERS.trailing_zeros();
+
+/// Which register of an event's visitor sketch a visitor falls into, and the rank to raise it to
+///
+/// Only the hash is used, so the sketch can't be traced back to anyone's IP address.
+pub fn register_for(event_id: &str, ip: IpAddr) -> (usize, u8) {
+    let digest = Sha256::new()
+        .chain_update(event_id.as_bytes())
+        .chain_update([0])
+        .chain_update(ip.to_string().as_bytes())
+        .finalize();
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    let hash = u64::from_be_bytes(bytes);
+
+    let register = (hash >> (64 - INDEX_BITS)) as usize;
+    let rest = hash << INDEX_BITS;
+    let rank = (rest.leading_zeros() + 1).min(64 - INDEX_BITS + 1) as u8;
+    (register, rank)
+}
+
+/// Estimate how many different visitors a sketch has seen
+pub fn estimate(registers: &[u8]) -> i64 {
+    let m = REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+
+    let ranks = (0..REGISTERS).map(|i| registers.get(i).copied().unwrap_or(0));
+    let sum: f64 = ranks.clone().map(|rank| 2f64.powi(-(rank as i32))).sum();
+    let zeros = ranks.filter(|rank| *rank == 0).count();
+
+    let raw = alpha * m * m / sum;
+    // The raw estimate is biased for small counts, which are more accurate counted by empty registers
+    let estimate = match raw <= 2.5 * m && zeros > 0 {
+        true => m * (m / zeros as f64).ln(),
+        false => raw,
+    };
+    estimate.round() as i64
+}
@@ -0,0 +1,191 @@
+use std::{net::IpAddr, time::Duration};
+
+use axum::http::{header::CONTENT_TYPE, Request, Uri};
+use common::Event;
+use hmac::{Hmac, Mac};
+use hyper::{client::HttpConnector, Body, Client};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::{
+    jobs::{Job, Jobs},
+    payloads::{LiveUpdate, LiveUpdateKind, PersonResponse},
+};
+
+// How long to wait for a webhook to respond before counting the attempt as failed
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const SIGNATURE_HEADER: &str = "x-jellifit-signature";
+pub const KIND_HEADER: &str = "x-jellifit-event";
+
+/// The body posted to a webhook
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    event_id: &'a str,
+    kind: LiveUpdateKind,
+    person: &'a PersonResponse,
+}
+
+pub struct Delivery {
+    url: String,
+    kind: LiveUpdateKind,
+    body: Vec<u8>,
+    signature: String,
+}
+
+impl Delivery {
+    /// Where the delivery is going, without the rest of the URL, which can hold a secret token
+    pub fn host(&self) -> String {
+        self.url
+            .parse::<Uri>()
+            .ok()
+            .and_then(|uri| uri.host().map(str::to_owned))
+            .unwrap_or_default()
+    }
+}
+
+/// Sends changes to events to the webhooks their creators registered, as background jobs
+pub struct Webhooks {
+    jobs: Jobs,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl Webhooks {
+    pub fn new(jobs: Jobs) -> Self {
+        // Plain HTTP is only let through `is_valid_url` in debug builds
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Self {
+            jobs,
+            client: Client::builder().build(connector),
+        }
+    }
+
+    /// Queue an update to be sent to the event's webhook, if it has one
+    pub fn send(&self, event: &Event, update: &LiveUpdate) {
+        let (Some(url), Some(secret)) = (&event.webhook_url, &event.webhook_secret) else {
+            return;
+        };
+
+        let body = match serde_json::to_vec(&WebhookPayload {
+            event_id: &event.id,
+            kind: update.kind,
+            person: &update.person,
+        }) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+
+        let delivery = Delivery {
+            url: url.clone(),
+            kind: update.kind,
+            signature: sign(secret, &body),
+            body,
+        };
+        self.jobs.push(Job::Webhook(delivery));
+    }
+
+    /// Make one attempt at a delivery, the job queue retries it if this fails
+    pub async fn deliver(&self, delivery: &Delivery) -> Result<(), String> {
+        // The host is checked again, as where it points can change after it was registered
+        if !is_public_url(&delivery.url).await {
+            return Err("Doesn't resolve to a public address".to_owned());
+        }
+
+        let request = Request::post(&delivery.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(KIND_HEADER, delivery.kind.as_str())
+            .header(SIGNATURE_HEADER, &delivery.signature)
+            .body(Body::from(delivery.body.clone()))
+            .map_err(|e| e.to_string())?;
+
+        let response = tokio::time::timeout(DELIVERY_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| "Timed out".to_owned())?
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Responded with {}", response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+// HMAC-SHA256 of the body, formatted like `sha256=<hex digest>`
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+/// Whether a webhook URL can be delivered to, which has to be HTTPS, as the payloads have
+/// people's availability in them. Plain HTTP is allowed in debug builds, for local testing.
+pub fn is_valid_url(url: &str) -> bool {
+    url.parse::<Uri>().is_ok_and(|uri| {
+        let scheme = uri.scheme_str();
+        (scheme == Some("https") || (cfg!(debug_assertions) && scheme == Some("http")))
+            && uri.host().is_some()
+    })
+}
+
+/// Whether every address a webhook URL's host resolves to is public, so the server can't be
+/// used to reach loopback, private network or cloud metadata addresses
+pub async fn is_public_url(url: &str) -> bool {
+    let Ok(uri) = url.parse::<Uri>() else {
+        return false;
+    };
+    let Some(host) = uri.host() else {
+        return false;
+    };
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("http") {
+            80
+        } else {
+            443
+        });
+    // IPv6 literals are bracketed in URLs, but not when they're looked up
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let Ok(addresses) = tokio::net::lookup_host((host, port)).await else {
+        return false;
+    };
+    let addresses: Vec<_> = addresses.map(|address| address.ip()).collect();
+    !addresses.is_empty() && addresses.into_iter().all(is_public)
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
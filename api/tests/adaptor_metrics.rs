@@ -0,0 +1,42 @@
+use std::env;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use jellifit_api::middleware::admin_key::ADMIN_KEY_HEADER;
+use serde_json::json;
+
+mod common;
+
+const ADMIN_KEY: &str = "test-admin-key";
+
+#[tokio::test]
+async fn adaptor_calls_are_counted() {
+    env::set_var("ADMIN_KEY", ADMIN_KEY);
+    let app = TestApp::new().await;
+
+    let response = app.get("/admin/adaptor", &[]).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["1200-01022023"], "timezone": "UTC" }),
+        )
+        .await;
+    let id = created.body["id"].as_str().unwrap();
+    app.get(&format!("/event/{}", id), &[]).await;
+
+    let response = app
+        .get("/admin/adaptor", &[(ADMIN_KEY_HEADER, ADMIN_KEY)])
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let methods = response.body.as_array().unwrap();
+    let create_event = methods
+        .iter()
+        .find(|method| method["method"] == "create_event")
+        .unwrap();
+    assert_eq!(create_event["calls"], 1);
+    assert_eq!(create_event["errors"], 0);
+    assert!(create_event["mean_ms"].as_f64().unwrap() >= 0.0);
+}
@@ -0,0 +1,136 @@
+use std::{
+    env,
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, Method, StatusCode, Uri},
+    Router, Server,
+};
+use chrono::{Duration, Utc};
+use common::TestApp;
+use jellifit_api::middleware::admin_key::ADMIN_KEY_HEADER;
+use memory_adaptor::MemoryAdaptor;
+
+mod common;
+
+use ::common::{
+    conformance::{event, person},
+    Adaptor, Event,
+};
+
+const ADMIN_KEY: &str = "test-admin-key";
+
+// What the fake bucket was sent: the method, path, authorization header and body
+type Uploads = Arc<Mutex<Vec<(Method, String, String, String)>>>;
+
+#[derive(Clone)]
+struct Bucket {
+    uploads: Uploads,
+    failing: Arc<AtomicBool>,
+}
+
+async fn upload(
+    State(bucket): State<Bucket>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    if bucket.failing.load(Ordering::Relaxed) {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    let authorization = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    bucket.uploads.lock().unwrap().push((
+        method,
+        uri.path().to_owned(),
+        authorization,
+        String::from_utf8_lossy(&body).into_owned(),
+    ));
+    StatusCode::OK
+}
+
+// A bucket on a local port, which the archive config points at
+fn serve_bucket() -> Bucket {
+    let bucket = Bucket {
+        uploads: Uploads::default(),
+        failing: Arc::new(AtomicBool::new(true)),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let router = Router::new().fallback(upload).with_state(bucket.clone());
+    tokio::spawn(
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service()),
+    );
+
+    env::set_var("ADMIN_KEY", ADMIN_KEY);
+    env::set_var("ARCHIVE_BUCKET", "jellifit");
+    env::set_var("ARCHIVE_ENDPOINT", format!("http://127.0.0.1:{}", port));
+    env::set_var("ARCHIVE_ACCESS_KEY_ID", "test-key-id");
+    env::set_var("ARCHIVE_SECRET_ACCESS_KEY", "test-secret");
+    env::set_var("ARCHIVE_PREFIX", "archives/");
+    bucket
+}
+
+#[tokio::test]
+async fn cleanup_archives_events_before_deleting_them() {
+    let bucket = serve_bucket();
+    let adaptor = MemoryAdaptor::new().await;
+    adaptor
+        .create_event(Event {
+            expired_at: Some(Utc::now() - Duration::days(400)),
+            ..event("expired")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person("expired".to_owned(), person("Ana"))
+        .await
+        .unwrap();
+    let app = TestApp::with_adaptor(adaptor);
+
+    // Nothing is deleted while the bucket can't be written to
+    let response = app
+        .get("/tasks/cleanup", &[(ADMIN_KEY_HEADER, ADMIN_KEY)])
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["deleted_event_count"], 0);
+    assert!(response.body["archive_key"].is_null());
+
+    bucket.failing.store(false, Ordering::Relaxed);
+    let response = app
+        .get("/tasks/cleanup", &[(ADMIN_KEY_HEADER, ADMIN_KEY)])
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["deleted_event_count"], 1);
+    assert_eq!(response.body["deleted_person_count"], 1);
+    let key = response.body["archive_key"].as_str().unwrap();
+    assert!(key.starts_with("archives/") && key.ends_with(".ndjson"));
+
+    let uploads = bucket.uploads.lock().unwrap();
+    assert_eq!(uploads.len(), 1);
+    let (method, path, authorization, body) = &uploads[0];
+    assert_eq!(method, Method::PUT);
+    assert_eq!(path, &format!("/jellifit/{}", key));
+    assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=test-key-id/"));
+
+    let lines: Vec<serde_json::Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["id"], "expired");
+    assert_eq!(lines[0]["people"][0]["name"], "Ana");
+}
@@ -0,0 +1,64 @@
+use axum::http::{
+    header::{CACHE_CONTROL, ETAG},
+    StatusCode,
+};
+use base64::{engine::general_purpose, Engine};
+use serde_json::json;
+
+mod common;
+
+use common::TestApp;
+
+const PASSWORD: &str = "hunter2";
+
+async fn create_event(app: &TestApp, password: Option<&str>) -> String {
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["1200-01022023"], "timezone": "UTC", "password": password }),
+        )
+        .await;
+    created.body["id"].as_str().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn badges_of_public_events_can_be_cached() {
+    let app = TestApp::new().await;
+    let uri = format!("/event/{}/badge.svg", create_event(&app, None).await);
+
+    let badge = app.get(&uri, &[]).await;
+    assert_eq!(badge.status, StatusCode::OK);
+    assert!(badge.headers[CACHE_CONTROL]
+        .to_str()
+        .unwrap()
+        .starts_with("public"));
+
+    let etag = badge.headers[ETAG].to_str().unwrap();
+    let unchanged = app.get(&uri, &[("if-none-match", etag)]).await;
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn badges_of_private_events_arent_stored() {
+    let app = TestApp::new().await;
+    let uri = format!(
+        "/event/{}/badge.svg",
+        create_event(&app, Some(PASSWORD)).await
+    );
+    let password = general_purpose::STANDARD.encode(PASSWORD);
+
+    let badge = app.get(&uri, &[("x-event-password", &password)]).await;
+    assert_eq!(badge.status, StatusCode::OK);
+    assert_eq!(badge.headers[CACHE_CONTROL], "private, no-store");
+
+    let etag = badge.headers[ETAG].to_str().unwrap();
+    let unchanged = app
+        .get(
+            &uri,
+            &[("x-event-password", &password), ("if-none-match", etag)],
+        )
+        .await;
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(unchanged.headers[CACHE_CONTROL], "private, no-store");
+}
@@ -0,0 +1,161 @@
+use std::env;
+
+use axum::http::{Method, StatusCode};
+use jellifit_api::middleware::admin_key::ADMIN_KEY_HEADER;
+use serde_json::json;
+
+mod common;
+
+use common::{bearer, TestApp};
+
+const SLACK_URL: &str = "https://hooks.slack.com/services/T000/B000/XXXX";
+const DISCORD_URL: &str = "https://discord.com/api/webhooks/1234/abcd";
+const ADMIN_KEY: &str = "test-admin-key";
+
+// The config is loaded once for every test here, so they all set the admin key
+async fn app() -> TestApp {
+    env::set_var("ADMIN_KEY", ADMIN_KEY);
+    TestApp::new().await
+}
+
+async fn create_event(app: &TestApp) -> (String, String) {
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["1200-01022023"], "timezone": "UTC" }),
+        )
+        .await;
+    (
+        created.body["id"].as_str().unwrap().to_owned(),
+        created.body["edit_token"].as_str().unwrap().to_owned(),
+    )
+}
+
+#[tokio::test]
+async fn chat_webhooks_are_matched_to_their_platform() {
+    let app = app().await;
+    let (id, edit_token) = create_event(&app).await;
+    let auth = bearer(&edit_token);
+    let uri = format!("/event/{}/chat-webhook", id);
+
+    let slack = app
+        .request(
+            Method::PUT,
+            &uri,
+            &[("authorization", &auth)],
+            Some(json!({ "url": SLACK_URL })),
+        )
+        .await;
+    assert_eq!(slack.status, StatusCode::OK);
+    assert_eq!(slack.body["platform"], "slack");
+
+    let discord = app
+        .request(
+            Method::PUT,
+            &uri,
+            &[("authorization", &auth)],
+            Some(json!({ "url": format!(" {} ", DISCORD_URL) })),
+        )
+        .await;
+    assert_eq!(discord.status, StatusCode::OK);
+    assert_eq!(discord.body["platform"], "discord");
+    assert_eq!(discord.body["url"], DISCORD_URL);
+
+    let removed = app
+        .request(Method::DELETE, &uri, &[("authorization", &auth)], None)
+        .await;
+    assert_eq!(removed.status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn other_urls_are_rejected() {
+    let app = app().await;
+    let (id, edit_token) = create_event(&app).await;
+    let uri = format!("/event/{}/chat-webhook", id);
+
+    for url in [
+        "https://example.com/hook",
+        "http://hooks.slack.com/services/T000/B000/XXXX",
+        "https://discord.com/channels/1234",
+    ] {
+        let response = app
+            .request(
+                Method::PUT,
+                &uri,
+                &[("authorization", &bearer(&edit_token))],
+                Some(json!({ "url": url })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", url);
+    }
+
+    let unauthorized = app
+        .request(Method::PUT, &uri, &[], Some(json!({ "url": SLACK_URL })))
+        .await;
+    assert_eq!(unauthorized.status, StatusCode::UNAUTHORIZED);
+
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "times": ["1200-01022023"],
+                "timezone": "UTC",
+                "chat_webhook_url": "https://example.com/hook",
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(created.body["errors"][0]["field"], "chat_webhook_url");
+}
+
+#[tokio::test]
+async fn events_can_be_created_with_a_chat_webhook() {
+    let app = app().await;
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "times": ["1200-01022023"],
+                "timezone": "UTC",
+                "chat_webhook_url": SLACK_URL,
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn duplicates_dont_post_to_the_original_chat() {
+    let app = app().await;
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "times": ["1200-01022023"],
+                "timezone": "UTC",
+                "chat_webhook_url": SLACK_URL,
+            }),
+        )
+        .await;
+    let id = created.body["id"].as_str().unwrap();
+
+    let duplicate = app
+        .post(&format!("/event/{}/duplicate", id), &[], json!({}))
+        .await;
+    assert_eq!(duplicate.status, StatusCode::CREATED);
+
+    // Messages are only queued for events with a chat webhook, so without one on the copy,
+    // nothing is posted about it
+    let copy = app
+        .get(
+            &format!("/admin/events/{}", duplicate.body["id"].as_str().unwrap()),
+            &[(ADMIN_KEY_HEADER, ADMIN_KEY)],
+        )
+        .await;
+    assert_eq!(copy.status, StatusCode::OK);
+    assert_eq!(copy.body["has_chat_webhook"], false);
+}
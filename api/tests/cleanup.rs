@@ -0,0 +1,128 @@
+use std::env;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use common::TestApp;
+use jellifit_api::middleware::admin_key::ADMIN_KEY_HEADER;
+use memory_adaptor::MemoryAdaptor;
+
+mod common;
+
+use ::common::{
+    conformance::{event, person},
+    Adaptor, Event,
+};
+
+const ADMIN_KEY: &str = "test-admin-key";
+
+// An event nobody has visited in a long time, and one that expired long enough ago to delete
+async fn seeded_app() -> TestApp {
+    env::set_var("ADMIN_KEY", ADMIN_KEY);
+    let adaptor = MemoryAdaptor::new().await;
+    adaptor
+        .create_event(Event {
+            visited_at: Utc::now() - Duration::days(400),
+            ..event("stale")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .create_event(Event {
+            expired_at: Some(Utc::now() - Duration::days(400)),
+            ..event("expired")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person("expired".to_owned(), person("Ana"))
+        .await
+        .unwrap();
+    adaptor.create_event(event("fresh")).await.unwrap();
+
+    TestApp::with_adaptor(adaptor)
+}
+
+#[tokio::test]
+async fn cleanup_needs_the_admin_key() {
+    let app = seeded_app().await;
+    let response = app.get("/tasks/cleanup", &[]).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn dry_run_reports_without_removing_anything() {
+    let app = seeded_app().await;
+    let response = app
+        .get(
+            "/tasks/cleanup?dry_run=true",
+            &[(ADMIN_KEY_HEADER, ADMIN_KEY)],
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["dry_run"], true);
+    assert_eq!(response.body["expired_event_count"], 1);
+    assert_eq!(response.body["deleted_event_count"], 1);
+    assert_eq!(response.body["deleted_person_count"], 1);
+    assert_eq!(response.body["expired_events"][0]["id"], "stale");
+    assert_eq!(response.body["deleted_events"][0]["id"], "expired");
+
+    let stale = app.get("/event/stale", &[]).await;
+    assert_eq!(stale.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn cleanup_expires_and_deletes_events() {
+    let app = seeded_app().await;
+    let response = app
+        .get("/tasks/cleanup", &[(ADMIN_KEY_HEADER, ADMIN_KEY)])
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["dry_run"], false);
+    assert_eq!(response.body["expired_event_count"], 1);
+    assert_eq!(response.body["deleted_event_count"], 1);
+    assert_eq!(response.body["deleted_person_count"], 1);
+
+    assert_eq!(
+        app.get("/event/stale", &[]).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.get("/event/expired", &[]).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(app.get("/event/fresh", &[]).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn looking_up_private_events_doesnt_keep_them_around() {
+    env::set_var("ADMIN_KEY", ADMIN_KEY);
+    let adaptor = MemoryAdaptor::new().await;
+    for (id, password_hash) in [("stale", None), ("stale-private", Some("hash".to_owned()))] {
+        adaptor
+            .create_event(Event {
+                visited_at: Utc::now() - Duration::days(400),
+                password_hash,
+                ..event(id)
+            })
+            .await
+            .unwrap();
+    }
+    let app = TestApp::with_adaptor(adaptor);
+
+    let lookup = app.get("/events?ids=stale,stale-private", &[]).await;
+    assert_eq!(lookup.status, StatusCode::OK);
+    assert_eq!(lookup.body[0]["event"]["id"], "stale");
+    assert!(lookup.body[1]["event"].is_null());
+
+    // Only the public event was visited by looking it up
+    let response = app
+        .get(
+            "/tasks/cleanup?dry_run=true",
+            &[(ADMIN_KEY_HEADER, ADMIN_KEY)],
+        )
+        .await;
+    assert_eq!(response.body["expired_event_count"], 1);
+    assert_eq!(response.body["expired_events"][0]["id"], "stale-private");
+}
@@ -0,0 +1,104 @@
+//! Boots the whole API against an adaptor, so requests go through the same routes and
+//! middleware as when it's deployed
+
+// Each test file only uses some of the helpers
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header::CONTENT_TYPE, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use common::Adaptor;
+use jellifit_api::{config::config, rate_limit::RateLimits};
+use memory_adaptor::MemoryAdaptor;
+use serde_json::Value;
+use tower::ServiceExt;
+
+pub struct TestApp {
+    router: Router,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// The body as JSON, or null if it was empty or wasn't JSON
+    pub body: Value,
+    /// The body as it was sent, for responses that aren't JSON
+    pub bytes: Vec<u8>,
+}
+
+impl TestApp {
+    /// An app with nothing stored
+    pub async fn new() -> Self {
+        Self::with_adaptor(MemoryAdaptor::new().await)
+    }
+
+    /// An app using another adaptor, or one that's already had data stored in it
+    pub fn with_adaptor<A: Adaptor + 'static>(adaptor: A) -> Self
+    where
+        A::Error: Send,
+    {
+        let state = jellifit_api::state(adaptor);
+        Self {
+            router: jellifit_api::app(state, &RateLimits::from_config(&config().rate_limits), None),
+        }
+    }
+
+    pub async fn get(&self, uri: &str, headers: &[(&str, &str)]) -> TestResponse {
+        self.request(Method::GET, uri, headers, None).await
+    }
+
+    pub async fn post(&self, uri: &str, headers: &[(&str, &str)], body: Value) -> TestResponse {
+        self.request(Method::POST, uri, headers, Some(body)).await
+    }
+
+    pub async fn patch(&self, uri: &str, headers: &[(&str, &str)], body: Value) -> TestResponse {
+        self.request(Method::PATCH, uri, headers, Some(body)).await
+    }
+
+    /// Send a request from 127.0.0.1, as the rate limits need the client's IP
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let body = match body {
+            Some(body) => {
+                request = request.header(CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        let mut request = request.body(body).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+        TestResponse {
+            status,
+            headers,
+            body: serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+            bytes: bytes.to_vec(),
+        }
+    }
+}
+
+/// The bearer header for an event's edit token
+pub fn bearer(token: &str) -> String {
+    format!("Bearer {}", token)
+}
@@ -0,0 +1,137 @@
+use std::{env, fs, path::PathBuf};
+
+use jellifit_api::config::{CaptchaProvider, Config, LogFormat, ProxyHeader};
+
+// A config file in the temp directory, unique to the test that writes it
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("jellifit-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn load_errors(name: &str, contents: &str) -> Vec<String> {
+    let path = write_config(name, contents);
+    let Err(errors) = Config::load(Some(&path)) else {
+        panic!("Expected {} to be invalid", name);
+    };
+    errors
+}
+
+#[test]
+fn toml_files_are_loaded() {
+    let path = write_config(
+        "full.toml",
+        r#"
+            [server]
+            port = 8080
+            trusted_proxies = ["10.0.0.0/8"]
+            trusted_proxy_header = "forwarded"
+
+            [cors]
+            origins = ["https://jelli.fit"]
+
+            [rate_limits.strict]
+            burst_size = 2
+            period_ms = 1000
+
+            [retention]
+            event_days = 30
+
+            [captcha]
+            secret = "secret"
+            provider = "hcaptcha"
+
+            [logging]
+            format = "json"
+        "#,
+    );
+    let config = Config::load(Some(&path)).unwrap();
+
+    assert_eq!(config.server.port, 8080);
+    assert_eq!(config.server.trusted_proxies, vec!["10.0.0.0/8"]);
+    assert!(config.server.trusted_proxy_header == ProxyHeader::Forwarded);
+    assert_eq!(config.cors.origins, vec!["https://jelli.fit"]);
+    assert_eq!(config.rate_limits.strict.burst_size, 2);
+    assert_eq!(config.retention.event_days, 30);
+    assert!(config.captcha.provider == CaptchaProvider::Hcaptcha);
+    assert!(config.logging.format == LogFormat::Json);
+
+    // Anything left out uses its default
+    assert_eq!(config.rate_limits.standard.burst_size, 20);
+    assert_eq!(config.retention.event_grace_days, 7);
+    assert_eq!(config.branding.name, "Jelli Fit");
+}
+
+#[test]
+fn yaml_files_are_loaded() {
+    let path = write_config(
+        "full.yaml",
+        "server:\n  port: 8081\nevents:\n  extend_requires_person: true\n",
+    );
+    let config = Config::load(Some(&path)).unwrap();
+
+    assert_eq!(config.server.port, 8081);
+    assert!(config.events.extend_requires_person);
+}
+
+#[test]
+fn environment_variables_override_the_file() {
+    // Only this test reads the branding, so setting it doesn't affect the others
+    env::set_var("BRAND_COLOR", "#ff0000");
+    let path = write_config(
+        "branding.toml",
+        "[branding]\nname = \"Fitness club\"\ncolor = \"#000000\"\n",
+    );
+    let config = Config::load(Some(&path)).unwrap();
+
+    assert_eq!(config.branding.name, "Fitness club");
+    assert_eq!(config.branding.color, "#ff0000");
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    let errors = load_errors("unknown.toml", "[server]\nprot = 8080\n");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("prot"), "{}", errors[0]);
+}
+
+#[test]
+fn every_invalid_setting_is_reported() {
+    let errors = load_errors(
+        "invalid.toml",
+        r#"
+            [server]
+            tls_cert_path = "cert.pem"
+            trusted_proxies = ["not an address"]
+
+            [rate_limits.standard]
+            burst_size = 0
+            period_ms = 500
+
+            [retention]
+            event_days = 0
+
+            [oidc]
+            issuer = "https://accounts.google.com"
+
+            [spam]
+            threshold = 2.0
+        "#,
+    );
+
+    for key in [
+        "server.tls_cert_path",
+        "server.trusted_proxies",
+        "rate_limits.standard",
+        "retention.event_days",
+        "oidc.audience",
+        "spam.threshold",
+    ] {
+        assert!(
+            errors.iter().any(|error| error.contains(key)),
+            "Expected an error about {} in {:?}",
+            key,
+            errors
+        );
+    }
+}
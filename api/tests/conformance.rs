@@ -0,0 +1,21 @@
+use std::env;
+
+use common::Adaptor;
+use memory_adaptor::MemoryAdaptor;
+use sql_adaptor::SqlAdaptor;
+
+#[tokio::test]
+async fn memory_adaptor_conforms() {
+    common::conformance::run(&MemoryAdaptor::new().await).await;
+}
+
+#[tokio::test]
+async fn sql_adaptor_conforms() {
+    let path = env::temp_dir().join(format!("jellifit-conformance-{}.db", std::process::id()));
+    env::set_var("SQLITE_PATH", &path);
+    let adaptor = SqlAdaptor::new().await;
+    adaptor.migrate().await.unwrap();
+    common::conformance::run(&adaptor).await;
+    adaptor.close().await.unwrap();
+    std::fs::remove_file(path).ok();
+}
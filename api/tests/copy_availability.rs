@@ -0,0 +1,139 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use common::{bearer, TestApp};
+use memory_adaptor::MemoryAdaptor;
+use serde_json::json;
+
+mod common;
+
+use ::common::{
+    conformance::{event, person},
+    Adaptor, Event, Person,
+};
+
+// "secret", base64 encoded as passwords are sent
+const PASSWORD: &str = "c2VjcmV0";
+
+// Two Monday morning events a week apart, with Ana on the first, and on an expired event
+async fn seeded_app() -> TestApp {
+    let adaptor = MemoryAdaptor::new().await;
+    adaptor
+        .create_event(Event {
+            times: vec!["0900-07012030".to_owned(), "0915-07012030".to_owned()],
+            ..event("last-week")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .create_event(Event {
+            times: vec![
+                "0900-14012030".to_owned(),
+                "0915-14012030".to_owned(),
+                "0930-14012030".to_owned(),
+            ],
+            ..event("this-week")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person(
+            "last-week".to_owned(),
+            Person {
+                password_hash: Some(bcrypt::hash("secret", 4).unwrap()),
+                availability: vec!["0900-07012030".to_owned()],
+                ..person("Ana")
+            },
+        )
+        .await
+        .unwrap();
+    adaptor
+        .create_event(Event {
+            times: vec!["0900-31122029".to_owned()],
+            expired_at: Some(Utc::now()),
+            ..event("expired-week")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person(
+            "expired-week".to_owned(),
+            Person {
+                availability: vec!["0900-31122029".to_owned()],
+                ..person("Ana")
+            },
+        )
+        .await
+        .unwrap();
+    TestApp::with_adaptor(adaptor)
+}
+
+#[tokio::test]
+async fn availability_is_copied_onto_the_same_weekly_times() {
+    let app = seeded_app().await;
+    let response = app
+        .post(
+            "/event/this-week/people/Ana/copy",
+            &[("authorization", &bearer(PASSWORD))],
+            json!({ "from": "last-week" }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["availability"], json!(["0900-14012030"]));
+
+    // Ana joined with the same password
+    let response = app.get("/event/this-week/people/Ana", &[]).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn copying_needs_the_person_password() {
+    let app = seeded_app().await;
+    let response = app
+        .post(
+            "/event/this-week/people/Ana/copy",
+            &[("authorization", &bearer("d3Jvbmc="))],
+            json!({ "from": "last-week" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .get(
+            "/event/this-week/people/Ana",
+            &[("authorization", &bearer(PASSWORD))],
+        )
+        .await;
+    assert_eq!(response.body["availability"], json!([]));
+}
+
+#[tokio::test]
+async fn copying_needs_another_event_with_the_person() {
+    let app = seeded_app().await;
+    let response = app
+        .post(
+            "/event/this-week/people/Bo/copy",
+            &[],
+            json!({ "from": "last-week" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .post(
+            "/event/this-week/people/Ana/copy",
+            &[("authorization", &bearer(PASSWORD))],
+            json!({ "from": "this-week" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .post(
+            "/event/this-week/people/Ana/copy",
+            &[("authorization", &bearer(PASSWORD))],
+            json!({ "from": "expired-week" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
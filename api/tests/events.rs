@@ -0,0 +1,180 @@
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use memory_adaptor::MemoryAdaptor;
+use serde_json::json;
+
+mod common;
+
+use ::common::{conformance::event, Adaptor, Event};
+use common::{bearer, TestApp};
+
+#[tokio::test]
+async fn create_get_and_update_an_event() {
+    let app = TestApp::new().await;
+
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "name": "Team lunch",
+                "times": ["1200-01022023", "1215-01022023"],
+                "timezone": "Europe/London",
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let id = created.body["id"].as_str().unwrap().to_owned();
+    let edit_token = created.body["edit_token"].as_str().unwrap().to_owned();
+
+    let fetched = app.get(&format!("/event/{}", id), &[]).await;
+    assert_eq!(fetched.status, StatusCode::OK);
+    assert_eq!(fetched.body["name"], "Team lunch");
+    assert_eq!(fetched.body["times"].as_array().unwrap().len(), 2);
+    assert!(
+        fetched.body.get("edit_token").is_none(),
+        "the edit token is only returned when the event is created"
+    );
+
+    let updated = app
+        .patch(
+            &format!("/event/{}", id),
+            &[("authorization", &bearer(&edit_token))],
+            json!({ "name": "Team dinner", "max_people": 4 }),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK);
+    assert_eq!(updated.body["name"], "Team dinner");
+    assert_eq!(updated.body["max_people"], 4);
+
+    let fetched = app.get(&format!("/event/{}", id), &[]).await;
+    assert_eq!(fetched.body["name"], "Team dinner");
+}
+
+#[tokio::test]
+async fn updating_needs_the_edit_token() {
+    let app = TestApp::new().await;
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["1200-01022023"], "timezone": "UTC" }),
+        )
+        .await;
+    let id = created.body["id"].as_str().unwrap();
+
+    let missing = app
+        .patch(
+            &format!("/event/{}", id),
+            &[],
+            json!({ "name": "Mine now" }),
+        )
+        .await;
+    assert_eq!(missing.status, StatusCode::UNAUTHORIZED);
+
+    let wrong = app
+        .patch(
+            &format!("/event/{}", id),
+            &[("authorization", &bearer("not-the-token"))],
+            json!({ "name": "Mine now" }),
+        )
+        .await;
+    assert_eq!(wrong.status, StatusCode::UNAUTHORIZED);
+
+    let unknown = app
+        .patch(
+            "/event/no-such-event",
+            &[("authorization", &bearer("not-the-token"))],
+            json!({ "name": "Mine now" }),
+        )
+        .await;
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn invalid_events_report_each_field() {
+    let app = TestApp::new().await;
+    let response = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["1207-01022023"], "timezone": "Mars/Olympus" }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let fields: Vec<&str> = response.body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert!(fields.contains(&"times"));
+    assert!(fields.contains(&"timezone"));
+}
+
+#[tokio::test]
+async fn slugs_of_expired_events_stay_taken() {
+    let adaptor = MemoryAdaptor::new().await;
+    adaptor
+        .create_event(Event {
+            expired_at: Some(Utc::now() - Duration::days(1)),
+            ..event("team-lunch")
+        })
+        .await
+        .unwrap();
+    let app = TestApp::with_adaptor(adaptor);
+
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "name": "Team lunch",
+                "slug": "team-lunch",
+                "times": ["1200-01022023"],
+                "timezone": "Europe/London",
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn weak_creator_tokens_are_rejected() {
+    let app = TestApp::new().await;
+    for token in [
+        "",
+        "a",
+        "short-token",
+        "!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!",
+    ] {
+        let created = app
+            .post(
+                "/event",
+                &[],
+                json!({ "times": ["1200-01022023"], "timezone": "UTC", "creator_token": token }),
+            )
+            .await;
+        assert_eq!(
+            created.status,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{:?}",
+            token
+        );
+        assert_eq!(created.body["errors"][0]["field"], "creator_token");
+    }
+
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "times": ["1200-01022023"],
+                "timezone": "UTC",
+                "creator_token": "Zq3xW8rT5yU1iO9pA2sD4fG6hJ7kL0mN",
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+}
@@ -0,0 +1,122 @@
+use axum::http::StatusCode;
+use common::{bearer, TestApp};
+use memory_adaptor::MemoryAdaptor;
+use serde_json::json;
+
+mod common;
+
+use ::common::{
+    conformance::{event, person},
+    Adaptor, Event, Person,
+};
+
+const EDIT_TOKEN: &str = "organizer-edit-token";
+// "secret" and "hunter2", base64 encoded as passwords are sent
+const PERSON_PASSWORD: &str = "c2VjcmV0";
+const EVENT_PASSWORD: &str = "aHVudGVyMg==";
+
+// A public event with Ana, who has no password, and Bo, who does, and a private event
+async fn seeded_app() -> TestApp {
+    let adaptor = MemoryAdaptor::new().await;
+    let edit_token_hash = Some(bcrypt::hash(EDIT_TOKEN, 4).unwrap());
+    adaptor
+        .create_event(Event {
+            edit_token_hash: edit_token_hash.clone(),
+            ..event("public")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .create_event(Event {
+            edit_token_hash,
+            password_hash: Some(bcrypt::hash("hunter2", 4).unwrap()),
+            ..event("private")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person("public".to_owned(), person("Ana"))
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person(
+            "public".to_owned(),
+            Person {
+                password_hash: Some(bcrypt::hash("secret", 4).unwrap()),
+                ..person("Bo")
+            },
+        )
+        .await
+        .unwrap();
+    TestApp::with_adaptor(adaptor)
+}
+
+async fn extend(app: &TestApp, uri: &str, token: Option<&str>) -> StatusCode {
+    let auth = token.map(bearer);
+    let headers: Vec<_> = auth
+        .iter()
+        .map(|auth| ("authorization", auth.as_str()))
+        .collect();
+    app.post(uri, &headers, json!({})).await.status
+}
+
+#[tokio::test]
+async fn extending_needs_the_edit_token_or_a_person() {
+    let app = seeded_app().await;
+
+    assert_eq!(
+        extend(&app, "/event/public/extend", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    // Ana doesn't have a password, so her name alone proves nothing
+    assert_eq!(
+        extend(&app, "/event/public/extend?person=Ana", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        extend(&app, "/event/public/extend?person=Bo", Some("d3Jvbmc=")).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    assert_eq!(
+        extend(
+            &app,
+            "/event/public/extend?person=Bo",
+            Some(PERSON_PASSWORD)
+        )
+        .await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        extend(&app, "/event/public/extend", Some(EDIT_TOKEN)).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn private_events_need_their_password_to_be_extended() {
+    let app = seeded_app().await;
+    let edit_token = bearer(EDIT_TOKEN);
+
+    let response = app
+        .post(
+            "/event/private/extend",
+            &[("authorization", &edit_token)],
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post(
+            "/event/private/extend",
+            &[
+                ("authorization", &edit_token),
+                ("x-event-password", EVENT_PASSWORD),
+            ],
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body["expires_at"].is_i64());
+}
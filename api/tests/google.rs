@@ -0,0 +1,210 @@
+use std::{collections::HashMap, env, net::TcpListener, sync::OnceLock, thread};
+
+use axum::{http::StatusCode, routing::post, Form, Json, Router, Server};
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use common::TestApp;
+use memory_adaptor::MemoryAdaptor;
+use serde_json::{json, Value};
+
+mod common;
+
+use ::common::{
+    conformance::{event, person},
+    Adaptor, Event, Person,
+};
+
+const REFRESH_TOKEN: &str = "test-refresh-token";
+const REVOKED_REFRESH_TOKEN: &str = "test-revoked-token";
+
+async fn token(Form(form): Form<HashMap<String, String>>) -> (StatusCode, Json<Value>) {
+    match (
+        form["grant_type"].as_str(),
+        form.get("code").or(form.get("refresh_token")),
+    ) {
+        ("authorization_code", Some(code)) if code == "test-code" => (
+            StatusCode::OK,
+            Json(json!({ "access_token": "test-access-token", "refresh_token": REFRESH_TOKEN })),
+        ),
+        ("refresh_token", Some(token)) if token == REFRESH_TOKEN => (
+            StatusCode::OK,
+            Json(json!({ "access_token": "test-access-token" })),
+        ),
+        _ => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "invalid_grant" })),
+        ),
+    }
+}
+
+// Busy for 10 minutes from 09:10 tomorrow
+async fn free_busy(Json(body): Json<Value>) -> Json<Value> {
+    assert!(body["timeMin"].is_string() && body["timeMax"].is_string());
+    let start = tomorrow_at(9, 10);
+    Json(json!({
+        "calendars": {
+            "primary": {
+                "busy": [{
+                    "start": start.to_rfc3339(),
+                    "end": (start + Duration::minutes(10)).to_rfc3339(),
+                }],
+            },
+        },
+    }))
+}
+
+// Google's token and Calendar APIs on a local port, running for as long as the tests do,
+// which the config points at
+fn serve_google() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let router = Router::new()
+                .route("/token", post(token))
+                .route("/calendar/freeBusy", post(free_busy));
+            tokio::runtime::Runtime::new().unwrap().block_on(async {
+                Server::from_tcp(listener)
+                    .unwrap()
+                    .serve(router.into_make_service())
+                    .await
+            })
+        });
+
+        env::set_var("GOOGLE_CLIENT_ID", "test-client");
+        env::set_var("GOOGLE_CLIENT_SECRET", "test-secret");
+        env::set_var(
+            "GOOGLE_REDIRECT_URL",
+            "http://localhost:3000/google/callback",
+        );
+        env::set_var(
+            "GOOGLE_TOKEN_URL",
+            format!("http://127.0.0.1:{}/token", port),
+        );
+        env::set_var(
+            "GOOGLE_CALENDAR_URL",
+            format!("http://127.0.0.1:{}/calendar", port),
+        );
+    });
+}
+
+fn tomorrow_at(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.from_utc_datetime(
+        &(Utc::now().date_naive() + Duration::days(1))
+            .and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap()),
+    )
+}
+
+fn slot(hour: u32, minute: u32) -> String {
+    tomorrow_at(hour, minute).format("%H%M-%d%m%Y").to_string()
+}
+
+// An event tomorrow morning with one person on it, who might have connected their calendar
+async fn seeded_app(refresh_token: Option<&str>) -> TestApp {
+    serve_google();
+    let adaptor = MemoryAdaptor::new().await;
+    adaptor
+        .create_event(Event {
+            times: vec![slot(9, 0), slot(9, 15), slot(9, 30)],
+            ..event("meeting")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person(
+            "meeting".to_owned(),
+            Person {
+                google_refresh_token: refresh_token.map(str::to_owned),
+                ..person("Ana")
+            },
+        )
+        .await
+        .unwrap();
+    TestApp::with_adaptor(adaptor)
+}
+
+// The `state` Google is given, which it passes back to the callback
+fn state_from(url: &str) -> String {
+    let query = url.split_once('?').unwrap().1;
+    serde_urlencoded::from_str::<HashMap<String, String>>(query).unwrap()["state"].clone()
+}
+
+#[tokio::test]
+async fn connected_calendars_suggest_availability() {
+    let app = seeded_app(None).await;
+
+    let response = app
+        .post("/event/meeting/people/Ana/google", &[], json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let url = response.body["url"].as_str().unwrap();
+    assert!(url.starts_with("https://accounts.google.com/"));
+    assert!(url.contains("calendar.freebusy"));
+
+    let response = app
+        .get(
+            &format!("/google/callback?code=test-code&state={}", state_from(url)),
+            &[],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    let location = response.headers["location"].to_str().unwrap();
+    assert!(location.ends_with("/meeting?google=connected"));
+
+    // The token never leaves the server
+    let person = app.get("/event/meeting/people/Ana", &[]).await;
+    assert!(!String::from_utf8_lossy(&person.bytes).contains(REFRESH_TOKEN));
+
+    let response = app.get("/event/meeting/people/Ana/suggested", &[]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["availability"], json!([slot(9, 30)]));
+    assert_eq!(response.body["busy"], json!([slot(9, 0), slot(9, 15)]));
+}
+
+#[tokio::test]
+async fn suggestions_need_a_connected_calendar() {
+    let app = seeded_app(None).await;
+    let response = app.get("/event/meeting/people/Ana/suggested", &[]).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn revoked_calendars_are_disconnected() {
+    let app = seeded_app(Some(REVOKED_REFRESH_TOKEN)).await;
+    let response = app.get("/event/meeting/people/Ana/suggested", &[]).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    // The token was forgotten, so Google isn't asked again
+    let response = app.get("/event/meeting/people/Ana/suggested", &[]).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.bytes, b"Google Calendar isn't connected");
+}
+
+#[tokio::test]
+async fn callbacks_need_a_valid_state() {
+    let app = seeded_app(None).await;
+    let response = app
+        .get("/google/callback?code=test-code&state=forged.state", &[])
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let url = app
+        .post("/event/meeting/people/Ana/google", &[], json!({}))
+        .await
+        .body["url"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    let response = app
+        .get(
+            &format!(
+                "/google/callback?error=access_denied&state={}",
+                state_from(&url)
+            ),
+            &[],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    let location = response.headers["location"].to_str().unwrap();
+    assert!(location.ends_with("/meeting?google=denied"));
+}
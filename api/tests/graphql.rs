@@ -0,0 +1,90 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+mod common;
+
+use common::TestApp;
+
+async fn graphql(app: &TestApp, query: &str) -> Value {
+    let response = app.post("/graphql", &[], json!({ "query": query })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    response.body
+}
+
+fn error(body: &Value) -> &str {
+    body["errors"][0]["message"].as_str().unwrap_or_default()
+}
+
+// The query GraphiQL and most code generators fetch the schema with
+const INTROSPECTION: &str = r#"
+query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives { name description locations args { ...InputValue } }
+  }
+}
+fragment FullType on __Type {
+  kind name description
+  fields(includeDeprecated: true) {
+    name description args { ...InputValue } type { ...TypeRef } isDeprecated deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) { name description isDeprecated deprecationReason }
+  possibleTypes { ...TypeRef }
+}
+fragment InputValue on __InputValue {
+  name description type { ...TypeRef } defaultValue
+}
+fragment TypeRef on __Type {
+  kind name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name
+    ofType { kind name ofType { kind name ofType { kind name } } } } } } }
+}
+"#;
+
+#[tokio::test]
+async fn the_schema_can_be_introspected() {
+    let app = TestApp::new().await;
+    let body = graphql(&app, INTROSPECTION).await;
+    assert!(body.get("errors").is_none(), "{}", body);
+    assert_eq!(body["data"]["__schema"]["queryType"]["name"], "QueryRoot");
+}
+
+#[tokio::test]
+async fn complex_queries_are_rejected() {
+    let app = TestApp::new().await;
+    let fields: String = (0..200)
+        .map(|i| format!("e{}: event(id: \"missing\") {{ id name timezone }} ", i))
+        .collect();
+    let body = graphql(&app, &format!("{{ {} }}", fields)).await;
+    assert!(body["data"].is_null());
+    assert!(error(&body).contains("complex"), "{}", body);
+}
+
+#[tokio::test]
+async fn mutations_are_one_field_per_request() {
+    let app = TestApp::new().await;
+    let create = r#"createEvent(input: { times: ["0900-01012030"], timezone: "UTC" }) { id }"#;
+
+    let body = graphql(&app, &format!("mutation {{ a: {} b: {} }}", create, create)).await;
+    assert!(body["data"].is_null());
+    assert!(error(&body).contains("one field"), "{}", body);
+
+    let body = graphql(
+        &app,
+        &format!(
+            "mutation {{ ...Create }} fragment Create on MutationRoot {{ {} }}",
+            create
+        ),
+    )
+    .await;
+    assert!(error(&body).contains("one field"), "{}", body);
+
+    let body = graphql(&app, &format!("mutation {{ {} }}", create)).await;
+    assert!(body.get("errors").is_none(), "{}", body);
+    assert!(body["data"]["createEvent"]["id"].is_string());
+}
@@ -0,0 +1,128 @@
+use axum::http::{
+    header::{CACHE_CONTROL, CONTENT_TYPE},
+    StatusCode,
+};
+use base64::{engine::general_purpose, Engine};
+use common::TestApp;
+use memory_adaptor::MemoryAdaptor;
+use serde_json::json;
+
+mod common;
+
+use ::common::{
+    conformance::{event, person},
+    Adaptor, Event, Person,
+};
+
+// Ana can make both times and Ben only the first, on an event shown in New York
+async fn app() -> TestApp {
+    let adaptor = MemoryAdaptor::new().await;
+    adaptor
+        .create_event(Event {
+            name: "Board games & snacks".to_owned(),
+            timezone: "America/New_York".to_owned(),
+            ..event("heatmap")
+        })
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person(
+            "heatmap".to_owned(),
+            Person {
+                availability: vec!["0900-01022023".to_owned(), "0915-01022023".to_owned()],
+                ..person("Ana")
+            },
+        )
+        .await
+        .unwrap();
+    adaptor
+        .upsert_person("heatmap".to_owned(), person("Ben"))
+        .await
+        .unwrap();
+    TestApp::with_adaptor(adaptor)
+}
+
+#[tokio::test]
+async fn svg_heatmaps_are_laid_out_in_the_events_timezone() {
+    let app = app().await;
+
+    let response = app.get("/event/heatmap/heatmap.svg", &[]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers[CONTENT_TYPE], "image/svg+xml");
+    let svg = String::from_utf8(response.bytes).unwrap();
+    assert!(svg.contains("Board games &amp; snacks"));
+    assert!(svg.contains("2 responses · America/New_York"));
+    // 09:00 UTC is 04:00 in New York
+    assert!(svg.contains(">04:00<"));
+    assert!(svg.contains(">Wed 1 Feb<"));
+
+    let utc = app
+        .get("/event/heatmap/heatmap.svg?timezone=UTC", &[])
+        .await;
+    let svg = String::from_utf8(utc.bytes).unwrap();
+    assert!(svg.contains(">09:00<"));
+}
+
+#[tokio::test]
+async fn png_heatmaps_are_open_graph_sized() {
+    let app = app().await;
+
+    let response = app.get("/event/heatmap/heatmap.png", &[]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers[CONTENT_TYPE], "image/png");
+    assert_eq!(&response.bytes[..8], b"\x89PNG\r\n\x1a\n");
+    // The IHDR chunk comes first, with the width and height
+    assert_eq!(&response.bytes[16..24], &[0, 0, 4, 176, 0, 0, 2, 118]);
+
+    let etag = response.headers["etag"].to_str().unwrap();
+    let cached = app
+        .get("/event/heatmap/heatmap.png", &[("if-none-match", etag)])
+        .await;
+    assert_eq!(cached.status, StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn unknown_timezones_are_rejected() {
+    let app = app().await;
+
+    let response = app
+        .get("/event/heatmap/heatmap.svg?timezone=Mars/Olympus_Mons", &[])
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let missing = app.get("/event/missing/heatmap.svg", &[]).await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn heatmaps_of_private_events_arent_stored() {
+    let app = app().await;
+    let public = app.get("/event/heatmap/heatmap.png", &[]).await;
+    assert!(public.headers[CACHE_CONTROL]
+        .to_str()
+        .unwrap()
+        .starts_with("public"));
+
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["0900-01022023"], "timezone": "UTC", "password": "hunter2" }),
+        )
+        .await;
+    let password = general_purpose::STANDARD.encode("hunter2");
+    for format in ["svg", "png"] {
+        let private = app
+            .get(
+                &format!(
+                    "/event/{}/heatmap.{}",
+                    created.body["id"].as_str().unwrap(),
+                    format
+                ),
+                &[("x-event-password", &password)],
+            )
+            .await;
+        assert_eq!(private.status, StatusCode::OK);
+        assert_eq!(private.headers[CACHE_CONTROL], "private, no-store");
+    }
+}
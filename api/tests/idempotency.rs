@@ -0,0 +1,65 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{TestApp, TestResponse};
+
+const KEY: &str = "retry-me";
+
+fn new_event(creator_token: Option<&str>) -> Value {
+    json!({
+        "name": "Retried",
+        "times": ["1200-01022023"],
+        "timezone": "UTC",
+        "creator_token": creator_token,
+    })
+}
+
+async fn create(app: &TestApp, body: Value) -> TestResponse {
+    app.post("/event", &[("idempotency-key", KEY)], body).await
+}
+
+#[tokio::test]
+async fn retries_replay_the_event_without_its_tokens() {
+    let app = TestApp::new().await;
+    let first = create(&app, new_event(None)).await;
+    assert_eq!(first.status, StatusCode::CREATED);
+    assert!(first.body["edit_token"].is_string());
+
+    let retried = create(&app, new_event(None)).await;
+    assert_eq!(retried.status, StatusCode::CREATED);
+    assert_eq!(retried.headers["idempotent-replayed"], "true");
+    assert_eq!(retried.body["id"], first.body["id"]);
+    assert!(retried.body.get("edit_token").is_none());
+    assert!(retried.body.get("creator_token").is_none());
+}
+
+#[tokio::test]
+async fn keys_are_scoped_to_their_creator() {
+    let app = TestApp::new().await;
+    let mine = create(&app, new_event(Some("Zq3xW8rT5yU1iO9pA2sD4fG6hJ7kL0mN"))).await;
+    assert_eq!(mine.status, StatusCode::CREATED);
+
+    // Someone else using the same key gets their own event
+    let theirs = create(&app, new_event(Some("Pw9eR4tY7uI2oP5aS8dF1gH3jK6lZ0xC"))).await;
+    assert_eq!(theirs.status, StatusCode::CREATED);
+    assert!(theirs.headers.get("idempotent-replayed").is_none());
+    assert_ne!(theirs.body["id"], mine.body["id"]);
+}
+
+#[tokio::test]
+async fn keys_cant_be_reused_for_a_different_event() {
+    let app = TestApp::new().await;
+    assert_eq!(
+        create(&app, new_event(None)).await.status,
+        StatusCode::CREATED
+    );
+
+    let different = create(
+        &app,
+        json!({ "name": "Something else", "times": ["1200-01022023"], "timezone": "UTC" }),
+    )
+    .await;
+    assert_eq!(different.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
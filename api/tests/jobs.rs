@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{bearer, TestApp};
+
+#[tokio::test]
+async fn views_are_recorded_in_the_background() {
+    let app = TestApp::new().await;
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["1200-01022023"], "timezone": "UTC" }),
+        )
+        .await;
+    let id = created.body["id"].as_str().unwrap().to_owned();
+    let edit_token = created.body["edit_token"].as_str().unwrap().to_owned();
+
+    for _ in 0..3 {
+        let fetched = app.get(&format!("/event/{}", id), &[]).await;
+        assert_eq!(fetched.status, StatusCode::OK);
+    }
+
+    // The responses don't wait for the views to be written, so give the queue a moment
+    let mut view_count = 0;
+    for _ in 0..50 {
+        let analytics = app
+            .get(
+                &format!("/event/{}/analytics", id),
+                &[("authorization", &bearer(&edit_token))],
+            )
+            .await;
+        assert_eq!(analytics.status, StatusCode::OK);
+        view_count = analytics.body["view_count"].as_i64().unwrap();
+        if view_count == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(view_count, 3);
+}
@@ -0,0 +1,28 @@
+use std::env;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn strict_routes_are_limited_per_client() {
+    // Each test file is its own process, so this doesn't change the other tests' limits
+    env::set_var("RATE_LIMIT_STRICT_BURST", "2");
+    env::set_var("RATE_LIMIT_STRICT_PERIOD_MS", "60000");
+    let app = TestApp::new().await;
+
+    let input = json!({ "times": ["1200-01022023"], "timezone": "UTC" });
+    for _ in 0..2 {
+        let response = app.post("/event", &[], input.clone()).await;
+        assert_eq!(response.status, StatusCode::CREATED);
+    }
+    let limited = app.post("/event", &[], input).await;
+    assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Routes in other groups have their own limit
+    let root = app.get("/", &[]).await;
+    assert_eq!(root.status, StatusCode::OK);
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use ::common::{
+    conformance::{event, person},
+    Adaptor, Event,
+};
+use jellifit_api::replicas::ReplicatedAdaptor;
+use memory_adaptor::MemoryAdaptor;
+
+const LAG: Duration = Duration::from_secs(60);
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// Separate stores stand in for replicas that haven't caught up, so it's clear where each
+// read went
+async fn replicated(replica_count: usize) -> ReplicatedAdaptor<MemoryAdaptor> {
+    let mut replicas = Vec::new();
+    for _ in 0..replica_count {
+        replicas.push(MemoryAdaptor::new().await);
+    }
+    ReplicatedAdaptor::new(
+        MemoryAdaptor::new().await,
+        replicas,
+        LAG,
+        HEALTH_CHECK_INTERVAL,
+    )
+}
+
+#[tokio::test]
+async fn reads_go_to_the_replicas_and_writes_to_the_primary() {
+    let adaptor = replicated(1).await;
+    adaptor.create_event(event("written")).await.unwrap();
+
+    // The replica doesn't have it, but it was just written so the primary is read
+    assert!(adaptor
+        .peek_event("written".to_owned())
+        .await
+        .unwrap()
+        .is_some());
+    assert!(adaptor
+        .get_people("written".to_owned())
+        .await
+        .unwrap()
+        .is_some());
+
+    // Events that weren't written through the adaptor are read from the replica
+    assert!(adaptor
+        .peek_event("elsewhere".to_owned())
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(adaptor.get_stats().await.unwrap().event_count, 0);
+}
+
+#[tokio::test]
+async fn without_replicas_everything_uses_the_primary() {
+    let adaptor = replicated(0).await;
+    adaptor.create_event(event("only")).await.unwrap();
+    adaptor
+        .upsert_person("only".to_owned(), person("Ana"))
+        .await
+        .unwrap();
+    adaptor.increment_stat_event_count().await.unwrap();
+
+    assert!(adaptor
+        .get_event("only".to_owned())
+        .await
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        adaptor
+            .get_people("only".to_owned())
+            .await
+            .unwrap()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(adaptor.get_stats().await.unwrap().event_count, 1);
+}
+
+#[tokio::test]
+async fn visits_are_recorded_on_the_primary() {
+    let adaptor = replicated(1).await;
+    adaptor
+        .create_event(Event {
+            visited_at: chrono::Utc::now() - chrono::Duration::days(30),
+            ..event("visited")
+        })
+        .await
+        .unwrap();
+
+    // The first get is from the primary, whatever the replicas have, which records the visit
+    assert!(adaptor
+        .get_event("visited".to_owned())
+        .await
+        .unwrap()
+        .is_some());
+    let visited = adaptor
+        .peek_event("visited".to_owned())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(visited.visited_at > chrono::Utc::now() - chrono::Duration::days(1));
+}
+
+#[tokio::test]
+async fn the_adaptor_conforms_without_replicas() {
+    // With replicas the checks would see each store's own stats, which don't add up
+    ::common::conformance::run(&replicated(0).await).await;
+}
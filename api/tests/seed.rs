@@ -0,0 +1,57 @@
+use std::env;
+
+use axum::http::StatusCode;
+
+mod common;
+
+use common::{bearer, TestApp};
+use jellifit_api::middleware::admin_key::ADMIN_KEY_HEADER;
+use serde_json::json;
+
+const ADMIN_KEY: &str = "test-admin-key";
+
+#[tokio::test]
+async fn seeded_events_can_be_viewed_and_edited() {
+    env::set_var("ADMIN_KEY", ADMIN_KEY);
+    let app = TestApp::new().await;
+
+    let seeded = app
+        .post(
+            "/tasks/seed?count=3",
+            &[(ADMIN_KEY_HEADER, ADMIN_KEY)],
+            json!({}),
+        )
+        .await;
+    assert_eq!(seeded.status, StatusCode::OK);
+    let event_ids = seeded.body["event_ids"].as_array().unwrap();
+    assert_eq!(event_ids.len(), 3);
+    let edit_token = seeded.body["edit_token"].as_str().unwrap();
+
+    for id in event_ids {
+        let id = id.as_str().unwrap();
+        let event = app.get(&format!("/event/{}", id), &[]).await;
+        assert_eq!(event.status, StatusCode::OK);
+        assert!(!event.body["times"].as_array().unwrap().is_empty());
+
+        let people = app.get(&format!("/event/{}/people", id), &[]).await;
+        assert_eq!(people.status, StatusCode::OK);
+
+        let updated = app
+            .patch(
+                &format!("/event/{}", id),
+                &[("authorization", &bearer(edit_token))],
+                json!({ "name": "Renamed" }),
+            )
+            .await;
+        assert_eq!(updated.status, StatusCode::OK);
+    }
+
+    let too_many = app
+        .post(
+            "/tasks/seed?count=100000",
+            &[(ADMIN_KEY_HEADER, ADMIN_KEY)],
+            json!({}),
+        )
+        .await;
+    assert_eq!(too_many.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,88 @@
+use axum::http::{
+    header::{CONTENT_TYPE, LOCATION},
+    StatusCode,
+};
+use chrono::{Duration, Utc};
+use memory_adaptor::MemoryAdaptor;
+use serde_json::json;
+
+mod common;
+
+use ::common::{conformance::event, Adaptor, Event};
+use common::TestApp;
+
+#[tokio::test]
+async fn short_links_redirect_to_the_frontend() {
+    let app = TestApp::new().await;
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "name": "Pizza <3",
+                "times": ["1200-01022023"],
+                "timezone": "UTC",
+            }),
+        )
+        .await;
+    let id = created.body["id"].as_str().unwrap().to_owned();
+
+    let response = app.get(&format!("/e/{}", id), &[]).await;
+    assert_eq!(response.status, StatusCode::FOUND);
+    // Tests run without a `FRONTEND_URL`, so it's the frontend's dev server
+    assert_eq!(
+        response.headers[LOCATION],
+        format!("http://localhost:1234/{}", id).as_str()
+    );
+    assert!(response.headers[CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+
+    let html = String::from_utf8(response.bytes).unwrap();
+    assert!(html.contains(r#"<meta property="og:title" content="Pizza &lt;3">"#));
+    assert!(html.contains("Nobody has responded yet"));
+}
+
+#[tokio::test]
+async fn private_events_are_not_described() {
+    let app = TestApp::new().await;
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({
+                "name": "Surprise party",
+                "times": ["1200-01022023"],
+                "timezone": "UTC",
+                "password": "hunter2",
+            }),
+        )
+        .await;
+    let id = created.body["id"].as_str().unwrap().to_owned();
+
+    let response = app.get(&format!("/e/{}", id), &[]).await;
+    assert_eq!(response.status, StatusCode::FOUND);
+    let html = String::from_utf8(response.bytes).unwrap();
+    assert!(!html.contains("Surprise party"));
+    assert!(html.contains("Private event"));
+
+    let missing = app.get("/e/missing", &[]).await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn expired_events_dont_unfurl() {
+    let adaptor = MemoryAdaptor::new().await;
+    adaptor
+        .create_event(Event {
+            expired_at: Some(Utc::now() - Duration::days(1)),
+            ..event("expired")
+        })
+        .await
+        .unwrap();
+    let app = TestApp::with_adaptor(adaptor);
+
+    let response = app.get("/e/expired", &[]).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,117 @@
+use std::{env, sync::OnceLock};
+
+use axum::http::StatusCode;
+use base64::{engine::general_purpose, Engine};
+use chrono::Utc;
+use common::TestApp;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+mod common;
+
+const KEY_ID: &str = "billing";
+const SECRET: &str = "test-signing-secret";
+
+fn register_key() {
+    static REGISTERED: OnceLock<()> = OnceLock::new();
+    REGISTERED.get_or_init(|| env::set_var("SIGNATURE_KEYS", format!("{}:{}", KEY_ID, SECRET)));
+}
+
+// The Content-Digest, Signature-Input and Signature headers a client would send for a POST,
+// signed with `secret` at `created`
+fn sign(path: &str, body: &Value, secret: &str, created: i64) -> Vec<(&'static str, String)> {
+    let digest = format!(
+        "sha-256=:{}:",
+        general_purpose::STANDARD.encode(Sha256::digest(body.to_string().as_bytes()))
+    );
+    let params = format!(
+        "(\"@method\" \"@path\" \"content-digest\");created={};keyid=\"{}\";alg=\"hmac-sha256\"",
+        created, KEY_ID
+    );
+    let base = format!(
+        "\"@method\": POST\n\"@path\": {}\n\"content-digest\": {}\n\"@signature-params\": {}",
+        path, digest, params
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(base.as_bytes());
+    let signature = general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+
+    vec![
+        ("content-digest", digest),
+        ("signature-input", format!("sig1={}", params)),
+        ("signature", format!("sig1=:{}:", signature)),
+    ]
+}
+
+async fn post(app: &TestApp, headers: &[(&str, String)], body: Value) -> StatusCode {
+    let headers: Vec<_> = headers
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+    app.post("/event", &headers, body).await.status
+}
+
+fn new_event() -> Value {
+    json!({ "name": "Signed", "times": ["0900-01012030"], "timezone": "UTC" })
+}
+
+#[tokio::test]
+async fn signed_requests_are_verified() {
+    register_key();
+    let app = TestApp::new().await;
+    let body = new_event();
+    let headers = sign("/event", &body, SECRET, Utc::now().timestamp());
+    assert_eq!(post(&app, &headers, body).await, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn signing_is_optional() {
+    register_key();
+    let app = TestApp::new().await;
+    assert_eq!(post(&app, &[], new_event()).await, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn invalid_signatures_are_rejected() {
+    register_key();
+    let app = TestApp::new().await;
+    let now = Utc::now().timestamp();
+
+    // Signed with the wrong secret
+    let body = new_event();
+    let headers = sign("/event", &body, "not-the-secret", now);
+    assert_eq!(post(&app, &headers, body).await, StatusCode::UNAUTHORIZED);
+
+    // A body that doesn't match its digest
+    let headers = sign("/event", &new_event(), SECRET, now);
+    let tampered = json!({ "name": "Tampered", "times": ["0900-01012030"], "timezone": "UTC" });
+    assert_eq!(
+        post(&app, &headers, tampered).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // Too old to be trusted
+    let body = new_event();
+    let headers = sign("/event", &body, SECRET, now - 3600);
+    assert_eq!(post(&app, &headers, body).await, StatusCode::UNAUTHORIZED);
+
+    // From a key that isn't registered
+    let body = new_event();
+    let mut headers = sign("/event", &body, SECRET, now);
+    headers[1].1 = headers[1].1.replace(KEY_ID, "unknown");
+    assert_eq!(post(&app, &headers, body).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn signed_bodies_are_size_limited() {
+    register_key();
+    let app = TestApp::new().await;
+    // Over the 2MB the JSON extractor allows
+    let body = json!({ "name": "x".repeat(3 * 1024 * 1024), "times": ["0900-01012030"], "timezone": "UTC" });
+    let headers = sign("/event", &body, SECRET, Utc::now().timestamp());
+    assert_eq!(
+        post(&app, &headers, body).await,
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+}
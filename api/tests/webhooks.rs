@@ -0,0 +1,67 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+mod common;
+
+use common::{bearer, TestApp};
+
+// An event, and the path and auth header to set its webhook with
+async fn create_event(app: &TestApp) -> (String, String) {
+    let created = app
+        .post(
+            "/event",
+            &[],
+            json!({ "times": ["1200-01022023"], "timezone": "UTC" }),
+        )
+        .await;
+    (
+        format!("/event/{}/webhook", created.body["id"].as_str().unwrap()),
+        bearer(created.body["edit_token"].as_str().unwrap()),
+    )
+}
+
+async fn put_webhook(app: &TestApp, (uri, auth): &(String, String), url: &str) -> StatusCode {
+    app.request(
+        Method::PUT,
+        uri,
+        &[("authorization", auth)],
+        Some(json!({ "url": url })),
+    )
+    .await
+    .status
+}
+
+#[tokio::test]
+async fn webhooks_go_to_public_addresses() {
+    let app = TestApp::new().await;
+    let event = create_event(&app).await;
+    assert_eq!(
+        put_webhook(&app, &event, "https://93.184.216.34/hook").await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn internal_addresses_are_rejected() {
+    let app = TestApp::new().await;
+    let event = create_event(&app).await;
+    for url in [
+        "https://127.0.0.1/hook",
+        "http://localhost:8080/hook",
+        "https://10.0.0.5/hook",
+        "https://192.168.1.1/hook",
+        "https://169.254.169.254/latest/meta-data",
+        "https://0.0.0.0/hook",
+        "https://[::1]/hook",
+        "https://[fd00::1]/hook",
+        "https://[::ffff:127.0.0.1]/hook",
+        "ftp://93.184.216.34/hook",
+    ] {
+        assert_eq!(
+            put_webhook(&app, &event, url).await,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            url
+        );
+    }
+}